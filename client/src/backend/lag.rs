@@ -0,0 +1,125 @@
+use std::time::Duration;
+
+use common::message::{ReliableMessageFromServer, UnreliableMessageFromServer};
+use rand::{Rng, SeedableRng, rngs::StdRng};
+
+/// Network trouble faked on messages from an instance, for trying prediction and
+/// interpolation on a bad connection without external tools.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct NetworkConditions {
+    /// Added to every message.
+    pub latency: Duration,
+    /// Up to this much more is added to each message at random.
+    pub jitter: Duration,
+    /// Chance, from 0 to 1, that an unreliable message is dropped. Reliable messages are
+    /// only ever delayed.
+    pub loss: f32,
+}
+
+impl NetworkConditions {
+    pub fn is_perfect(&self) -> bool {
+        self.latency.is_zero() && self.jitter.is_zero() && self.loss <= 0.0
+    }
+}
+
+/// Holds messages back and drops some, as described by [`NetworkConditions`].
+///
+/// Time only moves through [`LagSimulator::advance`], so a run with the same seed and the
+/// same steps delivers the same messages at the same points.
+#[derive(Debug)]
+pub struct LagSimulator {
+    conditions: NetworkConditions,
+    rng: StdRng,
+    now: Duration,
+    /// Due time of each held message, in arrival order.
+    reliable: Vec<(Duration, ReliableMessageFromServer)>,
+    unreliable: Vec<(Duration, UnreliableMessageFromServer)>,
+    /// When the newest reliable message is due, so jitter never reorders them.
+    last_reliable_due: Duration,
+    dropped: u64,
+}
+
+impl LagSimulator {
+    pub fn new(conditions: NetworkConditions) -> LagSimulator {
+        LagSimulator::with_rng(conditions, StdRng::from_os_rng())
+    }
+
+    pub fn with_seed(conditions: NetworkConditions, seed: u64) -> LagSimulator {
+        LagSimulator::with_rng(conditions, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(conditions: NetworkConditions, rng: StdRng) -> LagSimulator {
+        LagSimulator {
+            conditions,
+            rng,
+            now: Duration::ZERO,
+            reliable: Vec::new(),
+            unreliable: Vec::new(),
+            last_reliable_due: Duration::ZERO,
+            dropped: 0,
+        }
+    }
+
+    pub fn get_conditions(&self) -> NetworkConditions {
+        self.conditions
+    }
+
+    /// Unreliable messages dropped so far.
+    pub fn get_dropped_count(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Messages still held back.
+    pub fn len(&self) -> usize {
+        self.reliable.len() + self.unreliable.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn advance(&mut self, elapsed: Duration) {
+        self.now += elapsed;
+    }
+
+    fn delay(&mut self) -> Duration {
+        let jitter = self.conditions.jitter.mul_f32(self.rng.random::<f32>());
+        self.conditions.latency + jitter
+    }
+
+    pub fn push_reliable(&mut self, message: ReliableMessageFromServer) {
+        let due = (self.now + self.delay()).max(self.last_reliable_due);
+        self.last_reliable_due = due;
+        self.reliable.push((due, message));
+    }
+
+    pub fn push_unreliable(&mut self, message: UnreliableMessageFromServer) {
+        if self.rng.random::<f32>() < self.conditions.loss {
+            self.dropped += 1;
+            return;
+        }
+
+        let due = self.now + self.delay();
+        self.unreliable.push((due, message));
+    }
+
+    /// Reliable messages that are due, in the order they arrived.
+    pub fn take_reliable(&mut self) -> Vec<ReliableMessageFromServer> {
+        take_due(&mut self.reliable, self.now)
+    }
+
+    /// Unreliable messages that are due, which jitter may have reordered.
+    pub fn take_unreliable(&mut self) -> Vec<UnreliableMessageFromServer> {
+        self.unreliable.sort_by_key(|(due, _)| *due);
+        take_due(&mut self.unreliable, self.now)
+    }
+}
+
+fn take_due<T>(held: &mut Vec<(Duration, T)>, now: Duration) -> Vec<T> {
+    let (due, waiting) = std::mem::take(held)
+        .into_iter()
+        .partition::<Vec<_>, _>(|(due, _)| *due <= now);
+    *held = waiting;
+
+    due.into_iter().map(|(_, message)| message).collect()
+}
@@ -1,35 +1,340 @@
 use std::{
     collections::HashMap,
-    io::{BufRead, BufReader, Write},
+    fs::{File, OpenOptions},
+    io::{BufRead as _, BufReader, ErrorKind, Read as _, Seek as _, Write},
     net::{Ipv4Addr, SocketAddr, UdpSocket},
     os::fd::IntoRawFd as _,
+    path::{Path, PathBuf},
     process::{Child, Command},
     str::FromStr as _,
-    time::{SystemTime, UNIX_EPOCH},
+    sync::mpsc::{Receiver, channel},
+    time::{Duration, SystemTime, UNIX_EPOCH},
 };
 
 use common::{
     Error, Result,
-    game::character::{Character, CharacterKind},
+    chunk::{PayloadAssembler, decode_payload, split_message},
+    game::character::{Character, CharacterKind, cap_name, name_to_user_data},
+    handoff::PlayerHandoff,
     message::{
         ReliableMessageFromClient, ReliableMessageFromServer, UnreliableMessageFromClient,
-        UnreliableMessageFromServer,
+        UnreliableMessageFromServer, decode, encode,
     },
+    ready::{InstanceReport, read_ready},
 };
+use nix::{errno::Errno, sys::signal::kill, unistd::Pid};
 use renet::{ConnectionConfig, DefaultChannel, RenetClient};
 use renet_netcode::{ClientAuthentication, ConnectToken, NetcodeClientTransport};
-use tracing::info;
+use tracing::{debug, info, warn};
 use uuid::Uuid;
 
+use super::{
+    DisconnectReason,
+    lag::{LagSimulator, NetworkConditions},
+    monitor::{REPORT_INTERVAL, TickMonitor},
+};
+
+/// Where clients that share their home, see [`LocalBackend::share_home`], advertise it unless
+/// told otherwise. Relative to the working directory like the instance program's path, so
+/// every client has to be started from the repository root to find the same file.
+pub const SHARED_HOME_PATH: &str = "./target/local-home";
+
+/// Longest a handoff waits on the source instance's [`PlayerHandoff`] before it is called
+/// off, so a source that never answers doesn't leave the player stuck.
+pub const HANDOFF_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Times in a row a connection that timed out or failed is opened again before giving up.
+/// Reconnecting keeps the client id, so the instance hands back the player it held.
+pub const MAX_RECONNECTS: u32 = 3;
+
 #[derive(Debug)]
-struct LocalInstance {
+struct SharedHome {
     id: Uuid,
+    addr: SocketAddr,
+    key: [u8; 32],
+    /// The instance process, to tell whether it is still running.
+    pid: u32,
+}
+
+impl SharedHome {
+    /// Opens the shared home file at `path` and waits for the lock on it, which is released
+    /// when the file is dropped, even if the client crashes. Clients hold it while they look
+    /// for the home and spawn one, so two of them never both spawn.
+    fn lock(path: &Path) -> Result<File> {
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        file.lock()?;
+        Ok(file)
+    }
+
+    /// The home recorded in the locked `file`, if there is one. An empty file means none.
+    fn read(file: &mut File, path: &Path) -> Result<Option<SharedHome>> {
+        let mut content = String::new();
+        file.rewind()?;
+        file.read_to_string(&mut content)?;
+        if content.trim().is_empty() {
+            return Ok(None);
+        }
+
+        let mut parts = content.split_whitespace();
+        let (Some(id), Some(addr), Some(key), Some(pid)) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            warn!("Ignoring malformed {}", path.display());
+            return Ok(None);
+        };
+
+        Ok(Some(SharedHome {
+            id: Uuid::from_str(id)?,
+            addr: SocketAddr::from_str(addr)?,
+            key: hex::decode(key)?
+                .try_into()
+                .map_err(|_| Error::InvalidKeyLength)?,
+            pid: pid.parse()?,
+        }))
+    }
+
+    fn write(&self, file: &mut File) -> Result<()> {
+        let content = format!(
+            "{} {} {} {}\n",
+            self.id.as_simple(),
+            self.addr,
+            hex::encode(self.key),
+            self.pid
+        );
+        file.set_len(0)?;
+        file.rewind()?;
+        file.write_all(content.as_bytes())?;
+        Ok(())
+    }
+
+    /// Whether the recorded instance process is still running and holds its port. Either
+    /// one alone could be another process that reused the pid or the port after the instance
+    /// exited and left the file behind.
+    fn is_alive(&self) -> bool {
+        let Ok(pid) = i32::try_from(self.pid) else {
+            return false;
+        };
+        // Signal 0 only checks the process exists; EPERM means it does but isn't ours.
+        let running = matches!(kill(Pid::from_raw(pid), None), Ok(()) | Err(Errno::EPERM));
+
+        running
+            && match UdpSocket::bind(self.addr) {
+                Ok(_) => false,
+                Err(err) => err.kind() == ErrorKind::AddrInUse,
+            }
+    }
+}
+
+/// An instance process started by this backend, which it is responsible for shutting down.
+#[derive(Debug)]
+struct OwnedProcess {
     process: Child,
+    tx: interprocess::unnamed_pipe::Sender,
+    /// Reports the instance wrote back on its pipe since the last poll.
+    reports: Receiver<InstanceReport>,
+    /// Players the instance handed off in answer to the `handoff` command.
+    handoffs: Receiver<PlayerHandoff>,
+    monitor: TickMonitor,
+}
+
+impl OwnedProcess {
+    /// Keeps reading the instance's pipe after the ready line, passing on its reports and
+    /// handoffs.
+    fn new(
+        process: Child,
+        tx: interprocess::unnamed_pipe::Sender,
+        mut rx: BufReader<interprocess::unnamed_pipe::Recver>,
+    ) -> OwnedProcess {
+        let (report_tx, reports) = channel();
+        let (handoff_tx, handoffs) = channel();
+
+        std::thread::spawn(move || {
+            let mut line = String::new();
+            loop {
+                line.clear();
+                match rx.read_line(&mut line) {
+                    Ok(0) | Err(_) => return,
+                    Ok(_) => {}
+                }
+
+                if let Some(report) = InstanceReport::parse(&line) {
+                    if report_tx.send(report).is_err() {
+                        return;
+                    }
+                } else if let Some(handoff) = PlayerHandoff::parse(&line) {
+                    if handoff_tx.send(handoff).is_err() {
+                        return;
+                    }
+                } else {
+                    warn!("Ignoring line from instance: {:?}", line.trim());
+                }
+            }
+        });
+
+        OwnedProcess {
+            process,
+            tx,
+            reports,
+            handoffs,
+            monitor: TickMonitor::default(),
+        }
+    }
+
+    /// Checks the report answering the last poll, then asks for the next one.
+    fn poll(&mut self, id: Uuid) {
+        let was_hung = self.monitor.is_hung();
+        self.monitor.record(self.reports.try_iter().last());
+
+        match (was_hung, self.monitor.is_hung()) {
+            (false, true) => warn!(
+                "Instance {id} looks hung, its tick hasn't advanced in {} polls",
+                self.monitor.get_stalled_polls()
+            ),
+            (true, false) => info!("Instance {id} is advancing again"),
+            _ => {}
+        }
+
+        // An instance that exited can't be written to, but that shows up as a disconnect.
+        if let Err(err) = self.tx.write_all(b"report\n") {
+            warn!("Failed to poll instance {id}: {err}");
+        }
+    }
+}
+
+#[derive(Debug)]
+struct LocalInstance {
+    id: Uuid,
+    /// The id this backend connected with, which the instance knows its player by.
+    client_id: u64,
+    server_addr: SocketAddr,
+    key: [u8; 32],
+    /// Character the connect token was issued for, to issue another when reconnecting.
+    character_name: String,
+    /// Reconnects tried since the connection was last up.
+    reconnects: u32,
+    /// `None` when the instance was joined through another client's shared home.
+    owned: Option<OwnedProcess>,
     client: RenetClient,
     transport: NetcodeClientTransport,
-    tx: interprocess::unnamed_pipe::Sender,
+    assembler: PayloadAssembler,
+    /// Set once the transport reports the connection closed; the instance is not polled or
+    /// sent to after that.
+    disconnect_reason: Option<DisconnectReason>,
     unreliable_message_queue: Vec<UnreliableMessageFromServer>,
     reliable_message_queue: Vec<ReliableMessageFromServer>,
+    /// Holds received messages back before they are queued. `None` on a perfect network.
+    lag: Option<LagSimulator>,
+}
+
+impl LocalInstance {
+    fn receive_reliable(&mut self, message: ReliableMessageFromServer) {
+        match &mut self.lag {
+            Some(lag) => lag.push_reliable(message),
+            None => self.reliable_message_queue.push(message),
+        }
+    }
+
+    fn receive_unreliable(&mut self, message: UnreliableMessageFromServer) {
+        match &mut self.lag {
+            Some(lag) => lag.push_unreliable(message),
+            None => self.unreliable_message_queue.push(message),
+        }
+    }
+
+    /// Returns the next message the instance sent with `send_large` once all of its chunks
+    /// have arrived.
+    fn poll_large(&mut self) -> Option<Result<ReliableMessageFromServer>> {
+        while let Some(bytes) = self.client.receive_message(DefaultChannel::ReliableOrdered) {
+            match decode(&bytes).and_then(|chunk| self.assembler.push(chunk)) {
+                Ok(Some(payload)) => return Some(decode_payload(&payload)),
+                Ok(None) => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        None
+    }
+
+    /// Opens a fresh connection with the same client id after the last one was lost.
+    fn reconnect(&mut self) -> Result<()> {
+        self.reconnects += 1;
+        info!(
+            "Reconnecting to instance {}, attempt {} of {MAX_RECONNECTS}",
+            self.id, self.reconnects
+        );
+
+        let (client, transport) = open_connection(
+            self.server_addr,
+            &self.key,
+            self.client_id,
+            &self.character_name,
+        )?;
+        self.client = client;
+        self.transport = transport;
+        self.assembler = PayloadAssembler::new();
+
+        Ok(())
+    }
+
+    /// Tells the instance this client is gone for good, so it removes the player instead of
+    /// holding it for a reconnect. The instance closes the connection once it reads this.
+    fn leave(&mut self) -> Result<()> {
+        if self.disconnect_reason.is_some() {
+            return Ok(());
+        }
+
+        let message = encode(&ReliableMessageFromClient::Leave)?;
+        self.client
+            .send_message(DefaultChannel::ReliableUnordered, message);
+        self.transport.send_packets(&mut self.client)?;
+
+        Ok(())
+    }
+}
+
+/// Starts connecting to the instance at `server_addr` as `client_id`, playing
+/// `character_name`.
+fn open_connection(
+    server_addr: SocketAddr,
+    key: &[u8; 32],
+    client_id: u64,
+    character_name: &str,
+) -> Result<(RenetClient, NetcodeClientTransport)> {
+    let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?;
+
+    let connect_token = ConnectToken::generate(
+        current_time,
+        0,
+        30 * 60,
+        client_id,
+        30 * 60,
+        vec![server_addr],
+        Some(&name_to_user_data(character_name)),
+        key,
+    )?;
+
+    let local_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
+    let socket = UdpSocket::bind(local_addr)?;
+
+    let client = RenetClient::new(ConnectionConfig::default());
+
+    let transport = NetcodeClientTransport::new(
+        current_time,
+        ClientAuthentication::Secure { connect_token },
+        socket,
+    )?;
+
+    Ok((client, transport))
+}
+
+/// Several local clients may share one instance, so each needs its own id.
+fn new_client_id() -> u64 {
+    u64::from_le_bytes(renet_netcode::generate_random_bytes::<8>())
 }
 
 #[derive(Debug)]
@@ -42,12 +347,30 @@ enum State {
     },
 }
 
+/// A handoff waiting on the source instance's [`PlayerHandoff`].
+#[derive(Debug)]
+struct PendingHandoff {
+    destination: Uuid,
+    /// The source's connection, to call the handoff off with.
+    client_id: u64,
+    waited: Duration,
+}
+
 #[derive(Debug)]
 pub struct LocalBackend {
     instances: HashMap<Uuid, LocalInstance>,
     home_instances: HashMap<u32, Uuid>,
+    /// Where the home is shared with other local clients, `None` to keep it to this backend.
+    shared_home: Option<PathBuf>,
+    owns_shared_home: bool,
     characters: Vec<Character>,
     state: State,
+    /// Faked on every instance connection, see [`LagSimulator`].
+    conditions: NetworkConditions,
+    /// Time since the spawned instances were last polled for reports.
+    since_poll: Duration,
+    /// Handoffs asked of a source instance, keyed by the source.
+    handoffs: HashMap<Uuid, PendingHandoff>,
 }
 
 impl Default for LocalBackend {
@@ -58,22 +381,59 @@ impl Default for LocalBackend {
 
 impl LocalBackend {
     pub fn new() -> LocalBackend {
+        LocalBackend::with_conditions(NetworkConditions::default())
+    }
+
+    /// A backend whose messages from instances are delayed and dropped as in `conditions`.
+    pub fn with_conditions(conditions: NetworkConditions) -> LocalBackend {
         info!("Starting local backend");
+        if !conditions.is_perfect() {
+            info!("Simulating network conditions: {conditions:?}");
+        }
 
         LocalBackend {
             instances: HashMap::new(),
             home_instances: HashMap::new(),
+            shared_home: None,
+            owns_shared_home: false,
             characters: Vec::new(),
             state: State::Inactive,
+            conditions,
+            since_poll: Duration::ZERO,
+            handoffs: HashMap::new(),
         }
     }
 
-    fn create_and_connect_to_instance(&mut self, character_id: u32) -> Result<Uuid> {
+    /// Joins the home instance advertised at `path`, or spawns one and advertises it there,
+    /// instead of always spawning a home of its own. Every local client sharing the same
+    /// `path` plays in the same instance, which is how two players are tested locally.
+    /// Takes effect the next time a character without a home enters the game.
+    pub fn share_home(&mut self, path: impl Into<PathBuf>) {
+        self.shared_home = Some(path.into());
+    }
+
+    /// How many instance processes this backend spawned, rather than joined.
+    pub fn get_spawned_instance_count(&self) -> usize {
+        self.instances
+            .values()
+            .filter(|instance| instance.owned.is_some())
+            .count()
+    }
+
+    /// Spawns an instance. With `home_file`, the locked shared home file, it is recorded
+    /// there for other local clients to join.
+    fn create_and_connect_to_instance(
+        &mut self,
+        character_name: &str,
+        home_file: Option<&mut File>,
+    ) -> Result<Uuid> {
         let id = Uuid::now_v7();
 
         info!("Creating local instance {id}");
 
         let key = renet_netcode::generate_random_bytes::<32>();
+        // Whoever creates the instance owns it, so its connection may send admin commands.
+        let client_id = new_client_id();
 
         #[cfg(debug_assertions)]
         let program = "./target/debug/instance";
@@ -92,57 +452,74 @@ impl LocalBackend {
             .args([
                 id.as_simple().to_string(),
                 hex::encode(key),
+                client_id.to_string(),
                 format!("{tx_handle};{rx_handle}"),
             ])
             .spawn()?;
 
-        let mut reader = BufReader::new(rx);
-
-        let mut server_addr = String::with_capacity(16);
+        let mut rx = BufReader::new(rx);
+        let server_addr = read_ready(&mut rx)?;
 
-        reader.read_line(&mut server_addr)?;
+        let pid = process.id();
+        let mut owned = OwnedProcess::new(process, tx, rx);
+        // Ask straight away, so the first poll already has an answer to check.
+        owned.tx.write_all(b"report\n")?;
 
-        let server_addr = SocketAddr::from_str(server_addr.trim())?;
-
-        let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?;
-
-        let connect_token = ConnectToken::generate(
-            current_time,
-            0,
-            30 * 60,
-            0,
-            30 * 60,
-            vec![server_addr],
-            None,
+        self.connect_to_instance(
+            id,
+            server_addr,
             &key,
+            client_id,
+            character_name,
+            Some(owned),
         )?;
 
-        let server_addr = SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0);
-        let socket = UdpSocket::bind(server_addr)?;
+        if let Some(home_file) = home_file {
+            SharedHome {
+                id,
+                addr: server_addr,
+                key,
+                pid,
+            }
+            .write(home_file)?;
+            self.owns_shared_home = true;
+        }
 
-        let client = RenetClient::new(ConnectionConfig::default());
+        Ok(id)
+    }
 
-        let transport = NetcodeClientTransport::new(
-            current_time,
-            ClientAuthentication::Secure { connect_token },
-            socket,
-        )?;
+    fn connect_to_instance(
+        &mut self,
+        id: Uuid,
+        server_addr: SocketAddr,
+        key: &[u8; 32],
+        client_id: u64,
+        character_name: &str,
+        owned: Option<OwnedProcess>,
+    ) -> Result<()> {
+        let (client, transport) = open_connection(server_addr, key, client_id, character_name)?;
 
         self.instances.insert(
             id,
             LocalInstance {
                 id,
-                process,
+                client_id,
+                server_addr,
+                key: *key,
+                character_name: character_name.to_string(),
+                reconnects: 0,
+                owned,
                 client,
                 transport,
-                tx,
+                assembler: PayloadAssembler::new(),
+                disconnect_reason: None,
                 reliable_message_queue: Vec::new(),
                 unreliable_message_queue: Vec::new(),
+                lag: (!self.conditions.is_perfect()).then(|| LagSimulator::new(self.conditions)),
             },
         );
-        self.home_instances.insert(character_id, id);
 
-        Ok(id)
+        Ok(())
     }
 
     pub fn create_character(&mut self, name: &str, kind: CharacterKind) -> Result<Character> {
@@ -153,7 +530,7 @@ impl LocalBackend {
         let char = Character {
             account_id: 0,
             character_id: self.characters.len() as u32,
-            name: name.into(),
+            name: cap_name(name),
             kind,
         };
 
@@ -168,13 +545,9 @@ impl LocalBackend {
             .get(character_id as usize)
             .ok_or(Error::InvalidCharacterId)?;
 
-        _ = character;
+        let character_name = character.name.clone();
 
-        let home = if let Some(home) = self.home_instances.get(&character_id).copied() {
-            home
-        } else {
-            self.create_and_connect_to_instance(character_id)?
-        };
+        let home = self.home_instance(character_id, &character_name)?;
 
         self.state = State::LoggedIn {
             character_id,
@@ -185,31 +558,313 @@ impl LocalBackend {
         Ok(home)
     }
 
+    /// Switches to the character `character_id` in the active instance without reconnecting.
+    /// The instance swaps the connection's player for the new character's, so this needs an
+    /// instance this backend started. Enters the game as usual when not in it yet.
+    pub fn switch_character(&mut self, character_id: u32) -> Result<Uuid> {
+        let character = self
+            .characters
+            .get(character_id as usize)
+            .ok_or(Error::InvalidCharacterId)?;
+        let character_name = character.name.clone();
+
+        let State::LoggedIn {
+            character_id: current,
+            active_instance,
+            ..
+        } = &mut self.state
+        else {
+            return self.enter_game(character_id);
+        };
+        let active_instance = *active_instance;
+        *current = character_id;
+
+        self.home_instances
+            .entry(character_id)
+            .or_insert(active_instance);
+
+        let Some(client_id) = self.instances.get(&active_instance).map(|i| i.client_id) else {
+            warn!("Not connected to the active instance {active_instance}");
+            return Ok(active_instance);
+        };
+        self.send_command(
+            active_instance,
+            &format!("switch-character {client_id} {character_name}"),
+        )?;
+
+        Ok(active_instance)
+    }
+
+    /// Moves the current character from the active instance to `destination`, carrying its
+    /// position and cooldowns, and makes `destination` the active instance once it has taken
+    /// the player. Both instances must have been started by this backend, and the destination
+    /// must be connected and advancing, otherwise it is [`Error::InstanceUnavailable`] and the
+    /// player stays where it is.
+    pub fn hand_off(&mut self, destination: Uuid) -> Result<()> {
+        let State::LoggedIn {
+            character_id,
+            active_instance: source,
+            ..
+        } = self.state
+        else {
+            return Err(Error::NotLoggedIn);
+        };
+
+        if source == destination {
+            warn!("Already in instance {destination}");
+            return Ok(());
+        }
+
+        if !self.can_accept_handoff(destination) {
+            return Err(Error::InstanceUnavailable(destination));
+        }
+
+        let Some(client_id) = self.instances.get(&source).map(|i| i.client_id) else {
+            return Err(Error::InstanceUnavailable(source));
+        };
+
+        info!("Handing character {character_id} off from {source} to {destination}");
+        self.send_command(source, &format!("handoff {client_id} {character_id}"))?;
+        self.handoffs.insert(
+            source,
+            PendingHandoff {
+                destination,
+                client_id,
+                waited: Duration::ZERO,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Whether `id` is an instance this backend started that is connected and not hung.
+    fn can_accept_handoff(&self, id: Uuid) -> bool {
+        self.instances.get(&id).is_some_and(|instance| {
+            instance.disconnect_reason.is_none()
+                && instance.client.is_connected()
+                && instance
+                    .owned
+                    .as_ref()
+                    .is_some_and(|owned| !owned.monitor.is_hung())
+        })
+    }
+
+    /// Passes the players source instances handed off on to their destination, and calls
+    /// off handoffs that waited too long.
+    fn forward_handoffs(&mut self, elapsed: Duration) -> Result<()> {
+        let handed_off: Vec<_> = self
+            .instances
+            .values()
+            .filter_map(|instance| Some((instance.id, instance.owned.as_ref()?)))
+            .flat_map(|(id, owned)| owned.handoffs.try_iter().map(move |handoff| (id, handoff)))
+            .collect();
+
+        for (source, mut handoff) in handed_off {
+            let Some(pending) = self.handoffs.remove(&source) else {
+                warn!("Instance {source} handed off a player nobody asked for");
+                self.send_command(source, &format!("handoff-failed {}", handoff.client_id))?;
+                continue;
+            };
+
+            let destination = pending.destination;
+            let destination_client = self.instances.get(&destination).map(|i| i.client_id);
+            let accepted = match destination_client {
+                Some(client_id) if self.can_accept_handoff(destination) => {
+                    handoff.client_id = client_id;
+                    handoff.to_hex().and_then(|payload| {
+                        self.send_command(destination, &format!("accept-handoff {payload}"))
+                    })
+                }
+                _ => Err(Error::InstanceUnavailable(destination)),
+            };
+
+            if let Err(err) = accepted {
+                warn!("Handoff from {source} to {destination} failed: {err}");
+                self.send_command(source, &format!("handoff-failed {}", pending.client_id))?;
+                continue;
+            }
+
+            self.send_command(source, &format!("handoff-done {}", pending.client_id))?;
+            info!("Handed off from {source} to {destination}");
+
+            if let State::LoggedIn {
+                active_instance,
+                connected_instances,
+                ..
+            } = &mut self.state
+            {
+                *active_instance = destination;
+                if !connected_instances.contains(&destination) {
+                    connected_instances.push(destination);
+                }
+            }
+        }
+
+        let mut timed_out = Vec::new();
+        self.handoffs.retain(|source, pending| {
+            pending.waited += elapsed;
+            if pending.waited >= HANDOFF_TIMEOUT {
+                timed_out.push((*source, pending.client_id));
+            }
+            pending.waited < HANDOFF_TIMEOUT
+        });
+
+        for (source, client_id) in timed_out {
+            warn!("Instance {source} didn't hand off in time, calling the handoff off");
+            self.send_command(source, &format!("handoff-failed {client_id}"))?;
+        }
+
+        Ok(())
+    }
+
+    /// The character's home instance, joining or spawning one the first time. The home is
+    /// recorded in the same step it is found, so a character never ends up with two.
+    fn home_instance(&mut self, character_id: u32, character_name: &str) -> Result<Uuid> {
+        if let Some(home) = self.home_instances.get(&character_id) {
+            return Ok(*home);
+        }
+
+        let Some(path) = self.shared_home.clone() else {
+            let home = self.create_and_connect_to_instance(character_name, None)?;
+            self.home_instances.insert(character_id, home);
+            return Ok(home);
+        };
+
+        // Held until the home is recorded, so no other client spawns one meanwhile.
+        let mut home_file = SharedHome::lock(&path)?;
+        let home = match SharedHome::read(&mut home_file, &path)? {
+            Some(shared) if shared.is_alive() => {
+                if !self.instances.contains_key(&shared.id) {
+                    info!("Joining shared local instance {}", shared.id);
+                    self.connect_to_instance(
+                        shared.id,
+                        shared.addr,
+                        &shared.key,
+                        new_client_id(),
+                        character_name,
+                        None,
+                    )?;
+                }
+                shared.id
+            }
+            shared => {
+                if let Some(shared) = shared {
+                    warn!(
+                        "Shared local instance {} is gone, spawning a new one",
+                        shared.id
+                    );
+                }
+                self.create_and_connect_to_instance(character_name, Some(&mut home_file))?
+            }
+        };
+
+        self.home_instances.insert(character_id, home);
+
+        Ok(home)
+    }
+
+    /// How many instances this backend is connected to, whether it spawned them or not.
+    pub fn get_instance_count(&self) -> usize {
+        self.instances.len()
+    }
+
     pub fn pre_update(&mut self, elapsed: std::time::Duration) -> Result<()> {
         for instance in self.instances.values_mut() {
+            if instance.disconnect_reason.is_some() {
+                continue;
+            }
+
             instance.client.update(elapsed);
-            instance.transport.update(elapsed, &mut instance.client)?;
+            if let Err(err) = instance.transport.update(elapsed, &mut instance.client) {
+                let reason = DisconnectReason::from(&err);
+                if reason.is_transient() && instance.reconnects < MAX_RECONNECTS {
+                    warn!("Lost connection to instance {}: {reason}", instance.id);
+                    match instance.reconnect() {
+                        Ok(()) => continue,
+                        Err(err) => warn!("Failed to reconnect to {}: {err}", instance.id),
+                    }
+                }
+
+                warn!("Disconnected from instance {}: {reason}", instance.id);
+                instance.disconnect_reason = Some(reason);
+                continue;
+            }
+
+            if instance.client.is_connected() {
+                instance.reconnects = 0;
+            }
 
             while let Some(unreliable) = instance.client.receive_message(DefaultChannel::Unreliable)
             {
-                let (unreliable, _) =
-                    bincode::decode_from_slice(&unreliable, bincode::config::standard())?;
-                instance.unreliable_message_queue.push(unreliable);
+                match decode(&unreliable) {
+                    Ok(unreliable) => instance.receive_unreliable(unreliable),
+                    Err(err) => warn!("Dropping unreliable message from {}: {err}", instance.id),
+                }
             }
 
             while let Some(reliable) = instance
                 .client
                 .receive_message(DefaultChannel::ReliableUnordered)
             {
-                let (reliable, _) =
-                    bincode::decode_from_slice(&reliable, bincode::config::standard())?;
-                instance.reliable_message_queue.push(reliable);
+                match decode(&reliable) {
+                    Ok(reliable) => instance.receive_reliable(reliable),
+                    Err(err) => warn!("Dropping reliable message from {}: {err}", instance.id),
+                }
             }
+
+            while let Some(large) = instance.poll_large() {
+                match large {
+                    Ok(large) => instance.receive_reliable(large),
+                    Err(err) => warn!("Dropping large message from {}: {err}", instance.id),
+                }
+            }
+
+            if let Some(lag) = &mut instance.lag {
+                lag.advance(elapsed);
+                instance.reliable_message_queue.extend(lag.take_reliable());
+                instance
+                    .unreliable_message_queue
+                    .extend(lag.take_unreliable());
+            }
+        }
+
+        self.since_poll += elapsed;
+        if self.since_poll >= REPORT_INTERVAL {
+            self.since_poll = Duration::ZERO;
+            self.poll_instances();
         }
 
+        self.forward_handoffs(elapsed)?;
+
         Ok(())
     }
 
+    fn poll_instances(&mut self) {
+        for instance in self.instances.values_mut() {
+            if instance.disconnect_reason.is_some() {
+                continue;
+            }
+
+            if let Some(owned) = &mut instance.owned {
+                owned.poll(instance.id);
+            }
+        }
+
+        let (mut players, mut hung) = (0, 0);
+        for (_, monitor) in self.iter_instance_monitors() {
+            players += monitor.get_last_report().map_or(0, |report| report.players);
+            hung += monitor.is_hung() as usize;
+        }
+        debug!("Spawned instances have {players} players, {hung} hung");
+    }
+
+    /// The health of every instance this backend spawned, as of its last poll.
+    pub fn iter_instance_monitors(&self) -> impl Iterator<Item = (Uuid, &TickMonitor)> {
+        self.instances
+            .values()
+            .filter_map(|instance| Some((instance.id, &instance.owned.as_ref()?.monitor)))
+    }
+
     pub fn is_instance_connected(&self, id: Uuid) -> bool {
         if let Some(instance) = self.instances.get(&id) {
             instance.client.is_connected()
@@ -218,6 +873,25 @@ impl LocalBackend {
         }
     }
 
+    /// Whether the connection to the instance dropped and is being opened again.
+    pub fn is_instance_reconnecting(&self, id: Uuid) -> bool {
+        self.instances
+            .get(&id)
+            .is_some_and(|instance| instance.reconnects > 0 && !instance.client.is_connected())
+    }
+
+    /// Unreliable messages from the instance dropped by the simulated network.
+    pub fn get_simulated_drops(&self, id: Uuid) -> u64 {
+        self.instances
+            .get(&id)
+            .and_then(|instance| instance.lag.as_ref())
+            .map_or(0, LagSimulator::get_dropped_count)
+    }
+
+    pub fn get_disconnect_reason(&self, id: Uuid) -> Option<&DisconnectReason> {
+        self.instances.get(&id)?.disconnect_reason.as_ref()
+    }
+
     pub fn get_unreliable_messages(&self, id: Uuid) -> &[UnreliableMessageFromServer] {
         if let Some(instance) = self.instances.get(&id) {
             &instance.unreliable_message_queue
@@ -240,10 +914,9 @@ impl LocalBackend {
         message: UnreliableMessageFromClient,
     ) -> Result<()> {
         if let Some(instance) = self.instances.get_mut(&id) {
-            instance.client.send_message(
-                DefaultChannel::Unreliable,
-                bincode::encode_to_vec(message, bincode::config::standard())?,
-            );
+            instance
+                .client
+                .send_message(DefaultChannel::Unreliable, encode(&message)?);
         }
 
         Ok(())
@@ -255,18 +928,54 @@ impl LocalBackend {
         message: ReliableMessageFromClient,
     ) -> Result<()> {
         if let Some(instance) = self.instances.get_mut(&id) {
-            instance.client.send_message(
-                DefaultChannel::ReliableUnordered,
-                bincode::encode_to_vec(message, bincode::config::standard())?,
-            );
+            instance
+                .client
+                .send_message(DefaultChannel::ReliableUnordered, encode(&message)?);
         }
 
         Ok(())
     }
 
+    /// Sends a message too big for a single network message, as chunks on the ordered
+    /// reliable channel.
+    pub fn send_large(&mut self, id: Uuid, message: ReliableMessageFromClient) -> Result<()> {
+        if let Some(instance) = self.instances.get_mut(&id) {
+            for chunk in split_message(&message)? {
+                instance
+                    .client
+                    .send_message(DefaultChannel::ReliableOrdered, chunk);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Asks an instance this backend started to kick the player called `name`.
+    pub fn kick(&mut self, id: Uuid, name: &str, reason: &str) -> Result<()> {
+        self.send_command(id, &format!("kick {name}\t{reason}"))
+    }
+
+    /// Sends one line of the pipe protocol, e.g. `status`, `pause`, `resume`,
+    /// `dump-positions`, `set-tickrate 30` or `set-tick-broadcast 2000`, to an instance this
+    /// backend started.
+    pub fn send_command(&mut self, id: Uuid, command: &str) -> Result<()> {
+        let Some(owned) = self.instances.get_mut(&id).and_then(|i| i.owned.as_mut()) else {
+            warn!("Can't send {command:?} to instance {id}, it isn't owned by this backend");
+            return Ok(());
+        };
+
+        // The pipe protocol is line based.
+        let command = command.replace('\n', " ");
+        owned.tx.write_all(format!("{command}\n").as_bytes())?;
+
+        Ok(())
+    }
+
     pub fn post_update(&mut self) -> Result<()> {
         for instance in self.instances.values_mut() {
-            instance.transport.send_packets(&mut instance.client)?;
+            if instance.disconnect_reason.is_none() {
+                instance.transport.send_packets(&mut instance.client)?;
+            }
             instance.unreliable_message_queue.clear();
             instance.reliable_message_queue.clear();
         }
@@ -302,15 +1011,54 @@ impl LocalBackend {
         }
     }
 
+    /// Clears the shared home file if it still names the instance this backend spawned. The
+    /// file itself stays, since other clients may be waiting on its lock.
+    fn remove_shared_home(&mut self) {
+        let Some(path) = self.shared_home.as_deref() else {
+            return;
+        };
+        if !self.owns_shared_home {
+            return;
+        }
+        self.owns_shared_home = false;
+
+        let result = SharedHome::lock(path).and_then(|mut file| {
+            let ours = SharedHome::read(&mut file, path)?.is_some_and(|shared| {
+                self.instances
+                    .get(&shared.id)
+                    .is_some_and(|instance| instance.owned.is_some())
+            });
+            if ours {
+                file.set_len(0)?;
+            }
+            Ok(())
+        });
+        if let Err(err) = result {
+            warn!("Failed to clear {}: {err}", path.display());
+        }
+    }
+
     pub fn shutdown(&mut self) -> common::Result<()> {
+        self.remove_shared_home();
+
+        for instance in self.instances.values_mut() {
+            if let Err(err) = instance.leave() {
+                warn!("Failed to leave instance {}: {err}", instance.id);
+            }
+        }
+
         for instance in self.instances.values_mut() {
-            instance.tx.write_all(b"shutdown\n")?;
-            info!("Sent shutdown to {}", instance.id);
+            if let Some(owned) = &mut instance.owned {
+                owned.tx.write_all(b"shutdown\n")?;
+                info!("Sent shutdown to {}", instance.id);
+            }
         }
 
-        for (_, mut instance) in self.instances.drain() {
-            let exit_status = instance.process.wait()?;
-            info!("Instance {} exited with status {exit_status}", instance.id);
+        for (_, instance) in self.instances.drain() {
+            if let Some(mut owned) = instance.owned {
+                let exit_status = owned.process.wait()?;
+                info!("Instance {} exited with status {exit_status}", instance.id);
+            }
         }
 
         Ok(())
@@ -319,12 +1067,20 @@ impl LocalBackend {
 
 impl std::ops::Drop for LocalBackend {
     fn drop(&mut self) {
+        self.remove_shared_home();
+
         for instance in self.instances.values_mut() {
-            instance.process.kill().unwrap();
+            let _ = instance.leave();
         }
 
-        for (_, mut instance) in self.instances.drain() {
-            instance.process.wait().unwrap();
+        for owned in self.instances.values_mut().filter_map(|i| i.owned.as_mut()) {
+            owned.process.kill().unwrap();
+        }
+
+        for (_, instance) in self.instances.drain() {
+            if let Some(mut owned) = instance.owned {
+                owned.process.wait().unwrap();
+            }
         }
     }
 }
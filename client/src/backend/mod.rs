@@ -1,4 +1,4 @@
-use std::time::Duration;
+use std::{fmt, time::Duration};
 
 use common::{
     Result,
@@ -8,9 +8,89 @@ use common::{
         UnreliableMessageFromServer,
     },
 };
+use renet_netcode::{NetcodeDisconnectReason, NetcodeError, NetcodeTransportError};
 use uuid::Uuid;
 
+pub mod lag;
 pub mod local;
+pub mod monitor;
+
+/// Why the connection to an instance ended, for telling the player.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DisconnectReason {
+    /// The instance stopped answering, or never answered the connection request.
+    TimedOut,
+    /// The instance has no free slots, either said so or refused the connection outright.
+    ServerFull,
+    VersionMismatch {
+        client_version: u32,
+        server_version: u32,
+    },
+    /// Removed by the instance, with the reason it gave.
+    Kicked(String),
+    /// The instance closed the connection without giving a reason.
+    DisconnectedByServer,
+    /// The instance shut down.
+    ServerClosed,
+    /// The client closed the connection itself.
+    DisconnectedByClient,
+    /// The transport failed in a way that isn't one of the above.
+    Transport(String),
+}
+
+impl DisconnectReason {
+    /// Whether the connection was lost rather than closed, so opening it again may work.
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            DisconnectReason::TimedOut | DisconnectReason::Transport(_)
+        )
+    }
+}
+
+impl From<NetcodeDisconnectReason> for DisconnectReason {
+    fn from(reason: NetcodeDisconnectReason) -> Self {
+        match reason {
+            NetcodeDisconnectReason::ConnectTokenExpired
+            | NetcodeDisconnectReason::ConnectionTimedOut
+            | NetcodeDisconnectReason::ConnectionResponseTimedOut
+            | NetcodeDisconnectReason::ConnectionRequestTimedOut => DisconnectReason::TimedOut,
+            NetcodeDisconnectReason::ConnectionDenied => DisconnectReason::ServerFull,
+            NetcodeDisconnectReason::DisconnectedByServer => DisconnectReason::DisconnectedByServer,
+            NetcodeDisconnectReason::DisconnectedByClient => DisconnectReason::DisconnectedByClient,
+        }
+    }
+}
+
+impl From<&NetcodeTransportError> for DisconnectReason {
+    fn from(err: &NetcodeTransportError) -> Self {
+        match err {
+            NetcodeTransportError::Netcode(NetcodeError::Disconnected(reason)) => (*reason).into(),
+            err => DisconnectReason::Transport(err.to_string()),
+        }
+    }
+}
+
+impl fmt::Display for DisconnectReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DisconnectReason::TimedOut => write!(f, "Connection timed out"),
+            DisconnectReason::ServerFull => write!(f, "Server is full"),
+            DisconnectReason::VersionMismatch {
+                client_version,
+                server_version,
+            } => write!(
+                f,
+                "Version mismatch: client {client_version}, server {server_version}"
+            ),
+            DisconnectReason::Kicked(reason) => write!(f, "Kicked: {reason}"),
+            DisconnectReason::DisconnectedByServer => write!(f, "Disconnected by server"),
+            DisconnectReason::ServerClosed => write!(f, "Server closed"),
+            DisconnectReason::DisconnectedByClient => write!(f, "Disconnected"),
+            DisconnectReason::Transport(err) => write!(f, "Connection error: {err}"),
+        }
+    }
+}
 
 enum BackendInner {
     Local(local::LocalBackend),
@@ -23,6 +103,20 @@ impl BackendConnection {
         BackendConnection(BackendInner::Local(local::LocalBackend::new()))
     }
 
+    /// A local backend faking `conditions` on everything instances send it.
+    pub fn local_with_conditions(conditions: lag::NetworkConditions) -> BackendConnection {
+        BackendConnection(BackendInner::Local(local::LocalBackend::with_conditions(
+            conditions,
+        )))
+    }
+
+    /// Shares the home instance with other local clients, see [`local::LocalBackend::share_home`].
+    pub fn share_home(&mut self, path: impl Into<std::path::PathBuf>) {
+        match &mut self.0 {
+            BackendInner::Local(local_backend) => local_backend.share_home(path),
+        }
+    }
+
     pub fn create_character(&mut self, name: &str, kind: CharacterKind) -> Result<Character> {
         match &mut self.0 {
             BackendInner::Local(local_backend) => local_backend.create_character(name, kind),
@@ -35,6 +129,20 @@ impl BackendConnection {
         }
     }
 
+    /// Switches to another character in the current instance, keeping the connection.
+    pub fn switch_character(&mut self, character_id: u32) -> Result<Uuid> {
+        match &mut self.0 {
+            BackendInner::Local(local_backend) => local_backend.switch_character(character_id),
+        }
+    }
+
+    /// Moves the current character to another instance, see [`local::LocalBackend::hand_off`].
+    pub fn hand_off(&mut self, destination: Uuid) -> Result<()> {
+        match &mut self.0 {
+            BackendInner::Local(local_backend) => local_backend.hand_off(destination),
+        }
+    }
+
     pub fn pre_update(&mut self, elapsed: Duration) -> Result<()> {
         match &mut self.0 {
             BackendInner::Local(local_backend) => local_backend.pre_update(elapsed),
@@ -47,6 +155,34 @@ impl BackendConnection {
         }
     }
 
+    /// Whether the connection to the instance dropped and is being opened again.
+    pub fn is_instance_reconnecting(&self, id: Uuid) -> bool {
+        match &self.0 {
+            BackendInner::Local(local_backend) => local_backend.is_instance_reconnecting(id),
+        }
+    }
+
+    /// Unreliable messages from the instance dropped by the simulated network.
+    pub fn get_simulated_drops(&self, id: Uuid) -> u64 {
+        match &self.0 {
+            BackendInner::Local(local_backend) => local_backend.get_simulated_drops(id),
+        }
+    }
+
+    /// The health of every instance this backend spawned, as of its last poll.
+    pub fn iter_instance_monitors(&self) -> impl Iterator<Item = (Uuid, &monitor::TickMonitor)> {
+        match &self.0 {
+            BackendInner::Local(local_backend) => local_backend.iter_instance_monitors(),
+        }
+    }
+
+    /// Why the connection to the instance ended, if it has.
+    pub fn get_disconnect_reason(&self, id: Uuid) -> Option<&DisconnectReason> {
+        match &self.0 {
+            BackendInner::Local(local_backend) => local_backend.get_disconnect_reason(id),
+        }
+    }
+
     pub fn get_unreliable_messages(&self, id: Uuid) -> &[UnreliableMessageFromServer] {
         match &self.0 {
             BackendInner::Local(local_backend) => local_backend.get_unreliable_messages(id),
@@ -81,6 +217,26 @@ impl BackendConnection {
         }
     }
 
+    pub fn send_large(&mut self, id: Uuid, message: ReliableMessageFromClient) -> Result<()> {
+        match &mut self.0 {
+            BackendInner::Local(local_backend) => local_backend.send_large(id, message),
+        }
+    }
+
+    /// Removes the player called `name` from the instance, showing them `reason`.
+    pub fn kick(&mut self, id: Uuid, name: &str, reason: &str) -> Result<()> {
+        match &mut self.0 {
+            BackendInner::Local(local_backend) => local_backend.kick(id, name, reason),
+        }
+    }
+
+    /// Sends an admin command such as `status` or `set-tickrate 30` to the instance.
+    pub fn send_command(&mut self, id: Uuid, command: &str) -> Result<()> {
+        match &mut self.0 {
+            BackendInner::Local(local_backend) => local_backend.send_command(id, command),
+        }
+    }
+
     pub fn post_update(&mut self) -> Result<()> {
         match &mut self.0 {
             BackendInner::Local(local_backend) => local_backend.post_update(),
@@ -0,0 +1,76 @@
+use std::time::Duration;
+
+use common::ready::InstanceReport;
+
+/// How often the backend asks each instance it spawned for an [`InstanceReport`].
+pub const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Polls in a row without the tick advancing before an instance counts as hung.
+pub const HUNG_AFTER_POLLS: u32 = 3;
+
+/// Tracks one instance's reports across polls to notice when its simulation stops.
+///
+/// A poll counts against the instance when its tick is the same as at the last poll or when
+/// no report arrived at all, since a stuck main loop can't answer either. Paused instances
+/// don't advance their tick on purpose, so a paused report never counts.
+#[derive(Debug, Clone)]
+pub struct TickMonitor {
+    hung_after: u32,
+    last_report: Option<InstanceReport>,
+    stalled_polls: u32,
+}
+
+impl Default for TickMonitor {
+    fn default() -> Self {
+        TickMonitor::new(HUNG_AFTER_POLLS)
+    }
+}
+
+impl TickMonitor {
+    pub fn new(hung_after: u32) -> TickMonitor {
+        TickMonitor {
+            hung_after,
+            last_report: None,
+            stalled_polls: 0,
+        }
+    }
+
+    pub fn get_hung_after(&self) -> u32 {
+        self.hung_after
+    }
+
+    pub fn set_hung_after(&mut self, hung_after: u32) {
+        self.hung_after = hung_after;
+    }
+
+    /// The newest report the instance answered with.
+    pub fn get_last_report(&self) -> Option<InstanceReport> {
+        self.last_report
+    }
+
+    /// Polls in a row the instance's tick hasn't moved.
+    pub fn get_stalled_polls(&self) -> u32 {
+        self.stalled_polls
+    }
+
+    pub fn is_hung(&self) -> bool {
+        self.stalled_polls >= self.hung_after
+    }
+
+    /// Records one poll, with the newest report received since the last one, if any.
+    pub fn record(&mut self, report: Option<InstanceReport>) {
+        let Some(report) = report else {
+            self.stalled_polls += 1;
+            return;
+        };
+
+        let advanced = self.last_report.is_none_or(|last| report.tick != last.tick);
+        if advanced || report.paused {
+            self.stalled_polls = 0;
+        } else {
+            self.stalled_polls += 1;
+        }
+
+        self.last_report = Some(report);
+    }
+}
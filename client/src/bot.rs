@@ -0,0 +1,128 @@
+use std::{
+    sync::{
+        Arc,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::{Duration, Instant},
+};
+
+use common::{DT, FixedTimestep, Result, game::character::CharacterKind};
+use rand::{Rng, rngs::ThreadRng};
+use tracing::{Level, info, span};
+
+use crate::{backend::BackendConnection, game::Game, instance::InstanceStatus};
+
+/// How long a bot keeps holding the same movement keys.
+const DIRECTION_CHANGE_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often the number of bots still playing is logged.
+const REPORT_INTERVAL: Duration = Duration::from_secs(5);
+
+const MOVEMENT_KEYS: [glfw::Key; 4] = [glfw::Key::W, glfw::Key::A, glfw::Key::S, glfw::Key::D];
+
+/// A headless client that joins like a player and wanders around at random.
+///
+/// It runs a [`Game::headless`], pressing keys on it instead of reading a window.
+struct Bot {
+    game: Game,
+    until_direction_change: Duration,
+}
+
+impl Bot {
+    fn new(index: usize) -> Result<Bot> {
+        let mut backend = BackendConnection::local();
+
+        let character =
+            backend.create_character(&format!("bot {index}"), CharacterKind::SoloAccount)?;
+        let instance_id = backend.enter_game(character.character_id)?;
+
+        Ok(Bot {
+            game: Game::headless(backend, instance_id, false),
+            until_direction_change: Duration::ZERO,
+        })
+    }
+
+    fn update(&mut self, rng: &mut ThreadRng, dt: Duration) -> Result<()> {
+        self.until_direction_change = self.until_direction_change.saturating_sub(dt);
+        if self.until_direction_change.is_zero() {
+            self.until_direction_change = DIRECTION_CHANGE_INTERVAL;
+            let keyboard_state = self.game.get_keyboard_state_mut();
+            for key in MOVEMENT_KEYS {
+                if rng.random_bool(0.5) {
+                    keyboard_state.press(key, glfw::Modifiers::empty());
+                } else {
+                    keyboard_state.release(key, glfw::Modifiers::empty());
+                }
+            }
+        }
+
+        self.game.update(dt)
+    }
+
+    fn get_status(&self) -> Option<InstanceStatus> {
+        self.game.get_current_instance_status()
+    }
+}
+
+/// Runs `count` headless bots against the local home instance until CTRL-C, for load
+/// testing. The first bot spawns the instance unless a local client already has one.
+pub fn run_bot(count: usize) -> Result<()> {
+    let span = span!(Level::INFO, "bots");
+    let _enter = span.enter();
+
+    let got_ctrl_c = Arc::new(AtomicBool::new(false));
+    ctrlc::set_handler({
+        let got_ctrl_c = got_ctrl_c.clone();
+        move || got_ctrl_c.store(true, Ordering::SeqCst)
+    })
+    .unwrap();
+
+    let mut bots = Vec::with_capacity(count);
+    for index in 0..count {
+        bots.push(Bot::new(index)?);
+    }
+    info!("Started {count} bots");
+
+    let mut rng = rand::rng();
+    let mut last_report = Instant::now();
+    let mut last_update = Instant::now();
+    let mut timestep = FixedTimestep::new(DT);
+
+    while !got_ctrl_c.load(Ordering::SeqCst) {
+        let elapsed = last_update.elapsed();
+        last_update = Instant::now();
+
+        for _ in 0..timestep.tick(elapsed) {
+            for bot in &mut bots {
+                bot.update(&mut rng, DT)?;
+            }
+        }
+
+        if last_report.elapsed() >= REPORT_INTERVAL {
+            last_report = Instant::now();
+
+            let active = bots
+                .iter()
+                .filter(|bot| bot.get_status() == Some(InstanceStatus::Active))
+                .count();
+            info!("{active}/{count} bots active");
+
+            if bots
+                .iter()
+                .all(|bot| bot.get_status() == Some(InstanceStatus::Disconnected))
+            {
+                info!("Every bot was disconnected. Exiting...");
+                break;
+            }
+        }
+
+        std::thread::sleep(DT.saturating_sub(last_update.elapsed()));
+    }
+
+    // The first bot owns the instance, so it shuts down last.
+    for bot in bots.into_iter().rev() {
+        bot.game.into_backend().shutdown()?;
+    }
+
+    Ok(())
+}
@@ -7,25 +7,43 @@ use std::{
     time::{Duration, Instant},
 };
 
-use common::{DT, Error, Result, Vec2, instance::Instance};
+use common::{
+    DT, Error, FixedTimestep, Result, Vec2, game::character::CharacterKind, instance::Instance,
+    message::ReliableMessageFromClient,
+};
 use glfw::PWindow;
 use tracing::{info, warn};
 use uuid::Uuid;
 
 use crate::{
-    backend::BackendConnection, graphics::Graphics, input::KeyboardState, instance::InstanceData,
+    backend::{BackendConnection, DisconnectReason},
+    graphics::{Graphics, grid::Grid},
+    input::KeyboardState,
+    instance::{InstanceData, InstanceStatus},
+    overlay::DebugOverlay,
 };
 
 pub struct Game {
-    graphics: Graphics,
+    /// `None` for a [`Game::headless`] game, which runs the same logic without a window.
+    graphics: Option<Graphics>,
     last_redraw: Instant,
-    accumulator: Duration,
+    timestep: FixedTimestep,
     backend: BackendConnection,
     instances: HashMap<Uuid, InstanceData>,
     got_ctrl_c: Arc<AtomicBool>,
     keyboard_state: KeyboardState,
+    overlay: DebugOverlay,
+    /// Whether players are also outlined where the server last put them, toggled with F5.
+    show_synced: bool,
+    /// Where the camera looks while spectating, moved with the arrow keys.
+    spectator_camera: Vec2,
+    /// Whether the window had focus at the last focus event, so repeats are ignored.
+    focused: bool,
 }
 
+/// How fast the spectator camera pans, in world units per second.
+const SPECTATOR_CAMERA_SPEED: f32 = 800.0;
+
 impl std::fmt::Debug for Game {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.debug_struct("Game").finish_non_exhaustive()
@@ -37,38 +55,163 @@ impl Game {
         backend: BackendConnection,
         window: Arc<PWindow>,
         instance_id: Uuid,
+        spectate: bool,
     ) -> Result<Game> {
+        let mut game = Game::headless(backend, instance_id, spectate);
+        game.graphics = Some(pollster::block_on(Graphics::new(window.clone()))?);
+
+        ctrlc::set_handler({
+            let got_ctrl_c = game.got_ctrl_c.clone();
+            move || got_ctrl_c.store(true, Ordering::SeqCst)
+        })
+        .unwrap();
+
+        Ok(game)
+    }
+
+    /// A game with no window or GPU, for bots and tests. It is driven by calling
+    /// [`Game::update`] and pressing keys through [`Game::get_keyboard_state_mut`], and
+    /// leaves CTRL-C handling to the caller.
+    pub fn headless(backend: BackendConnection, instance_id: Uuid, spectate: bool) -> Game {
         let mut game = Game {
-            graphics: pollster::block_on(Graphics::new(window.clone()))?,
+            graphics: None,
             last_redraw: Instant::now(),
-            accumulator: Duration::ZERO,
+            timestep: FixedTimestep::new(DT),
             backend,
             instances: HashMap::new(),
             got_ctrl_c: Arc::new(std::sync::atomic::AtomicBool::new(false)),
             keyboard_state: KeyboardState::default(),
+            overlay: DebugOverlay::new(),
+            show_synced: false,
+            spectator_camera: Vec2::zeros(),
+            focused: true,
         };
 
-        game.instances
-            .insert(instance_id, InstanceData::new(Instance::new(instance_id)));
+        let instance = Instance::new(instance_id);
+        let instance = if spectate {
+            InstanceData::new_spectator(instance)
+        } else {
+            InstanceData::new(instance)
+        };
+        game.instances.insert(instance_id, instance);
 
-        ctrlc::set_handler({
-            let got_ctrl_c = game.got_ctrl_c.clone();
-            move || got_ctrl_c.store(true, Ordering::SeqCst)
-        })
-        .unwrap();
+        game
+    }
 
-        Ok(game)
+    pub fn get_keyboard_state_mut(&mut self) -> &mut KeyboardState {
+        &mut self.keyboard_state
     }
 
-    fn get_current_player_position(&mut self) -> Option<Vec2> {
+    /// Whether players are outlined at their synced positions as well as drawn where they
+    /// are shown.
+    pub fn is_showing_synced(&self) -> bool {
+        self.show_synced
+    }
+
+    /// The active instance, once the backend has one.
+    pub fn get_current_instance(&self) -> Option<&InstanceData> {
+        let current_instance = self.backend.get_current_instance()?;
+        self.instances.get(&current_instance)
+    }
+
+    pub fn get_current_player_position(&mut self) -> Option<Vec2> {
         let current_instance = self.backend.get_current_instance()?;
         let current_instance = self.instances.get_mut(&current_instance)?;
         current_instance.get_current_player_position()
     }
 
+    /// How far along joining the active instance is, for a loading screen.
+    pub fn get_current_instance_status(&self) -> Option<InstanceStatus> {
+        let current_instance = self.backend.get_current_instance()?;
+        Some(self.instances.get(&current_instance)?.get_status())
+    }
+
+    /// Why the active instance was left, for showing once its status is
+    /// [`InstanceStatus::Disconnected`].
+    pub fn get_current_disconnect_reason(&self) -> Option<&DisconnectReason> {
+        let current_instance = self.backend.get_current_instance()?;
+        self.instances
+            .get(&current_instance)?
+            .get_disconnect_reason()
+    }
+
+    /// Pans the spectator camera with the arrow keys, or leaves spectating on F4.
+    fn update_spectator(&mut self, dt: Duration) -> Result<()> {
+        let Some(current_instance) = self.backend.get_current_instance() else {
+            return Ok(());
+        };
+        let Some(instance) = self.instances.get_mut(&current_instance) else {
+            return Ok(());
+        };
+
+        if !instance.is_spectating() {
+            return Ok(());
+        }
+
+        if self.keyboard_state.is_just_pressed(glfw::Key::F4, None) {
+            return instance.join_as_player(&mut self.backend);
+        }
+
+        let mut direction = Vec2::zeros();
+        if self.keyboard_state.is_pressed(glfw::Key::Up, None) {
+            direction += Vec2::y();
+        }
+        if self.keyboard_state.is_pressed(glfw::Key::Down, None) {
+            direction -= Vec2::y();
+        }
+        if self.keyboard_state.is_pressed(glfw::Key::Right, None) {
+            direction += Vec2::x();
+        }
+        if self.keyboard_state.is_pressed(glfw::Key::Left, None) {
+            direction -= Vec2::x();
+        }
+
+        if direction != Vec2::zeros() {
+            self.spectator_camera +=
+                direction.normalize() * SPECTATOR_CAMERA_SPEED * dt.as_secs_f32();
+        }
+
+        Ok(())
+    }
+
+    /// Pauses solo instances while the window is in the background. Instances shared with
+    /// other players are skipped, since the server would ignore the request anyway.
+    fn set_paused(&mut self, paused: bool) -> Result<()> {
+        if self.focused != paused {
+            return Ok(());
+        }
+        self.focused = !paused;
+
+        let Some(character) = self.backend.get_current_character() else {
+            return Ok(());
+        };
+
+        if character.kind == CharacterKind::Normal {
+            return Ok(());
+        }
+
+        for id in self.backend.get_connected_instances().to_vec() {
+            let message = if paused {
+                let shared = self
+                    .instances
+                    .get(&id)
+                    .is_some_and(|instance| instance.get_player_list().len() > 1);
+                if shared {
+                    continue;
+                }
+                ReliableMessageFromClient::Pause
+            } else {
+                ReliableMessageFromClient::Resume
+            };
+            self.backend.send_reliable_message(id, message)?;
+        }
+
+        Ok(())
+    }
+
     #[tracing::instrument(skip(self))]
     #[profiling::function]
-    fn update(&mut self, dt: Duration) -> Result<()> {
+    pub fn update(&mut self, dt: Duration) -> Result<()> {
         self.backend.pre_update(dt)?;
 
         for instance in self.instances.values_mut() {
@@ -77,10 +220,35 @@ impl Game {
 
         self.backend.post_update()?;
 
-        self.keyboard_state.post_update();
+        if self.keyboard_state.is_just_pressed(glfw::Key::F3, None) {
+            self.overlay.toggle();
+        }
+
+        if self.keyboard_state.is_just_pressed(glfw::Key::F5, None) {
+            self.show_synced = !self.show_synced;
+        }
+
+        if let Some(graphics) = &mut self.graphics
+            && self.keyboard_state.is_just_pressed(glfw::Key::F2, None)
+        {
+            let grid = match graphics.get_grid() {
+                Some(_) => None,
+                None => Some(Grid::default()),
+            };
+            graphics.set_grid(grid);
+        }
+
+        self.update_spectator(dt)?;
+
+        self.keyboard_state.post_update(dt);
 
         if let Some(position) = self.get_current_player_position() {
-            self.graphics.post_update(position);
+            // Carry on from the player once it leaves again, rather than jumping back.
+            self.spectator_camera = position;
+        }
+
+        if let Some(graphics) = &mut self.graphics {
+            graphics.post_update(self.spectator_camera);
         }
 
         Ok(())
@@ -89,8 +257,31 @@ impl Game {
     #[tracing::instrument(skip(self))]
     #[profiling::function]
     fn draw(&mut self) -> Result<()> {
-        let player_position = self.get_current_player_position().unwrap_or_default();
-        self.graphics.render(player_position)?;
+        let current = self
+            .backend
+            .get_current_instance()
+            .and_then(|id| self.instances.get(&id));
+        let players = current
+            .map(|instance| instance.get_player_bodies())
+            .unwrap_or_default();
+        let synced = current
+            .filter(|_| self.show_synced)
+            .map(|instance| instance.get_synced_bodies())
+            .unwrap_or_default();
+        if let Some(graphics) = &mut self.graphics {
+            graphics.render(&players, &synced)?;
+        }
+
+        if self.overlay.is_visible() {
+            let current = self
+                .backend
+                .get_current_instance()
+                .and_then(|id| self.instances.get(&id));
+            let tick = current.map(InstanceData::get_tick);
+            let player_count = current.map_or(0, |instance| instance.get_player_list().len());
+            let prediction = current.map(InstanceData::get_prediction_stats);
+            self.overlay.report(tick, player_count, prediction);
+        }
 
         profiling::finish_frame!();
 
@@ -106,16 +297,32 @@ impl Game {
     ) -> Result<()> {
         while !window.should_close() {
             let elapsed = self.last_redraw.elapsed();
-            self.accumulator += elapsed;
             self.last_redraw = Instant::now();
+            self.overlay.record_frame(elapsed);
 
             glfw.poll_events();
             for (_, event) in glfw::flush_messages(&events) {
-                match event {
-                    glfw::WindowEvent::FramebufferSize(w, h) => {
-                        self.graphics.resize(Some((w, h)));
+                match (event, &mut self.graphics) {
+                    (glfw::WindowEvent::FramebufferSize(w, h), Some(graphics))
+                        if w <= 0 || h <= 0 =>
+                    {
+                        info!("Window minimized, pausing rendering");
+                        graphics.resize(Some((w, h)));
+                    }
+                    (glfw::WindowEvent::FramebufferSize(w, h), Some(graphics)) => {
+                        if graphics.is_minimized() {
+                            info!("Window restored, resuming rendering");
+                        }
+                        graphics.resize(Some((w, h)));
                     }
-                    glfw::WindowEvent::Key(key, _, action, mods) => match action {
+                    (glfw::WindowEvent::ContentScale(x, _), Some(graphics)) => {
+                        info!("Content scale changed to {x}");
+                        graphics.set_content_scale(x);
+                    }
+                    (glfw::WindowEvent::Focus(focused), _) => {
+                        self.set_paused(!focused)?;
+                    }
+                    (glfw::WindowEvent::Key(key, _, action, mods), _) => match action {
                         glfw::Action::Press => {
                             self.keyboard_state.press(key, mods);
                         }
@@ -128,9 +335,7 @@ impl Game {
                 }
             }
 
-            while self.accumulator >= DT {
-                self.accumulator -= DT;
-
+            for _ in 0..self.timestep.tick(elapsed) {
                 self.update(DT)?;
             }
 
@@ -142,13 +347,14 @@ impl Game {
             if let Err(err) = self.draw() {
                 match err {
                     Error::Surface(wgpu::SurfaceError::Lost | wgpu::SurfaceError::Outdated) => {
-                        self.graphics.resize(None);
+                        if let Some(graphics) = &mut self.graphics {
+                            graphics.resize(None);
+                        }
                     }
                     Error::Surface(wgpu::SurfaceError::Timeout) => {
                         warn!("Surface timeout");
                     }
-                    Error::Surface(wgpu::SurfaceError::OutOfMemory | wgpu::SurfaceError::Other)
-                    | _ => {
+                    _ => {
                         return Err(err);
                     }
                 }
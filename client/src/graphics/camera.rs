@@ -1,4 +1,4 @@
-use common::Vec2;
+use common::{Rect, Vec2};
 use nalgebra_glm as glm;
 
 pub trait Camera {
@@ -16,9 +16,26 @@ impl Camera2D {
         Camera2D { position, size }
     }
 
+    pub fn get_position(&self) -> Vec2 {
+        self.position
+    }
+
     pub fn set_position(&mut self, new_position: Vec2) {
         self.position = new_position;
     }
+
+    /// The part of the world in view.
+    pub fn get_bounds(&self) -> Rect {
+        Rect::new(
+            self.position - self.size * 0.5,
+            self.position + self.size * 0.5,
+        )
+    }
+
+    /// Sets how much of the world is visible, in world units.
+    pub fn set_size(&mut self, new_size: Vec2) {
+        self.size = new_size;
+    }
 }
 
 impl Camera for Camera2D {
@@ -26,9 +43,7 @@ impl Camera for Camera2D {
         let min = self.position - self.size * 0.5;
         let max = self.position + self.size * 0.5;
 
-        let proj = glm::ortho_zo(min.x, max.x, min.y, max.y, 0.0, 1.0);
-
-        proj
+        glm::ortho_zo(min.x, max.x, min.y, max.y, 0.0, 1.0)
     }
 }
 
@@ -38,6 +53,12 @@ pub struct CameraUniform {
     view_proj: glm::Mat4,
 }
 
+impl Default for CameraUniform {
+    fn default() -> Self {
+        CameraUniform::new()
+    }
+}
+
 impl CameraUniform {
     pub fn new() -> CameraUniform {
         CameraUniform {
@@ -0,0 +1,64 @@
+use common::{Rect, Vec2, Vec4};
+
+/// Lines per axis above which the grid spacing is doubled, so zooming out doesn't flood the
+/// sprite batch.
+pub const MAX_GRID_LINES: usize = 128;
+
+/// World-aligned background lines, drawn behind everything else so movement is visible even
+/// without any world art.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Grid {
+    /// World units between lines.
+    pub spacing: f32,
+    /// Line thickness in world units.
+    pub thickness: f32,
+    pub colour: Vec4,
+}
+
+impl Default for Grid {
+    fn default() -> Self {
+        Grid {
+            spacing: 100.0,
+            thickness: 2.0,
+            colour: Vec4::new(0.2, 0.3, 0.4, 1.0),
+        }
+    }
+}
+
+impl Grid {
+    /// The lines crossing `view`, as rectangles in world space.
+    pub fn lines(&self, view: Rect) -> Vec<Rect> {
+        if self.spacing <= 0.0 || self.thickness <= 0.0 {
+            return Vec::new();
+        }
+
+        let longest = view.width().max(view.height());
+        let mut spacing = self.spacing;
+        while longest / spacing > MAX_GRID_LINES as f32 {
+            spacing *= 2.0;
+        }
+
+        let half = self.thickness * 0.5;
+        let mut lines = Vec::new();
+
+        let mut x = (view.min.x / spacing).floor() * spacing;
+        while x <= view.max.x {
+            lines.push(Rect::new(
+                Vec2::new(x - half, view.min.y),
+                Vec2::new(x + half, view.max.y),
+            ));
+            x += spacing;
+        }
+
+        let mut y = (view.min.y / spacing).floor() * spacing;
+        while y <= view.max.y {
+            lines.push(Rect::new(
+                Vec2::new(view.min.x, y - half),
+                Vec2::new(view.max.x, y + half),
+            ));
+            y += spacing;
+        }
+
+        lines
+    }
+}
@@ -0,0 +1,65 @@
+use common::{Rect, Vec2, Vec4};
+
+/// Dots for the players around the camera, drawn over the world in the top right corner of
+/// the screen.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Minimap {
+    /// World units from the middle of the minimap to its edge.
+    pub range: f32,
+    /// Side of the minimap in screen coordinates.
+    pub size: f32,
+    /// Gap to the edges of the screen in screen coordinates.
+    pub margin: f32,
+    /// Side of a dot in screen coordinates.
+    pub dot_size: f32,
+    pub background: Vec4,
+    pub colour: Vec4,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Minimap {
+            range: 3000.0,
+            size: 160.0,
+            margin: 16.0,
+            dot_size: 6.0,
+            background: Vec4::new(0.05, 0.1, 0.15, 1.0),
+            colour: Vec4::new(1.0, 1.0, 1.0, 1.0),
+        }
+    }
+}
+
+impl Minimap {
+    /// Where the minimap sits on a screen of `screen` size, in screen coordinates with y up.
+    pub fn bounds(&self, screen: Vec2) -> Rect {
+        let max = screen - Vec2::new(self.margin, self.margin);
+        Rect::new(max - Vec2::new(self.size, self.size), max)
+    }
+
+    /// Where to centre a dot for each of `players`, seen from `centre`. Players out of range
+    /// are pinned to the edge in their direction.
+    pub fn dots(&self, centre: Vec2, players: &[Vec2], screen: Vec2) -> Vec<Vec2> {
+        if self.range <= 0.0 {
+            return Vec::new();
+        }
+
+        let bounds = self.bounds(screen);
+        let middle = (bounds.min + bounds.max) * 0.5;
+        // Keep whole dots inside the minimap.
+        let half = ((self.size - self.dot_size) * 0.5).max(0.0);
+        let scale = half / self.range;
+
+        players
+            .iter()
+            .map(|position| {
+                let offset = (position - centre) * scale;
+                let furthest = offset.x.abs().max(offset.y.abs());
+                if furthest > half {
+                    middle + offset * (half / furthest)
+                } else {
+                    middle + offset
+                }
+            })
+            .collect()
+    }
+}
@@ -1,39 +1,151 @@
 use std::sync::Arc;
 
 use camera::{Camera2D, CameraUniform};
-use common::{Result, Vec2};
+use common::{Rect, Result, Vec2, Vec4};
 use glfw::PWindow;
+use grid::Grid;
+use minimap::Minimap;
 use nalgebra_glm as glm;
-use sprite_batch::{SpriteBatch, Vertex};
+use sprite_batch::{CULL_MODE, FRONT_FACE, SpriteBatch, Vertex};
 use texture::{TextureId, TextureRegistry};
-use tracing::instrument;
+use tracing::{error, info, instrument, warn};
 use wgpu::util::DeviceExt;
 
 pub mod camera;
+pub mod grid;
+pub mod minimap;
 pub mod sprite_batch;
 pub mod texture;
 
+/// Adapter requests tried in order, as (power preference, force fallback adapter). Some
+/// integrated GPUs are only offered for one of the preferences, and the fallback adapter is a
+/// software renderer for machines without a usable GPU at all.
+const ADAPTER_REQUESTS: [(wgpu::PowerPreference, bool); 4] = [
+    (wgpu::PowerPreference::None, false),
+    (wgpu::PowerPreference::LowPower, false),
+    (wgpu::PowerPreference::HighPerformance, false),
+    (wgpu::PowerPreference::None, true),
+];
+
+async fn request_adapter(
+    instance: &wgpu::Instance,
+    surface: &wgpu::Surface<'_>,
+) -> Result<wgpu::Adapter> {
+    let mut last_err = None;
+
+    for (power_preference, force_fallback_adapter) in ADAPTER_REQUESTS {
+        let request = instance.request_adapter(&wgpu::RequestAdapterOptions {
+            power_preference,
+            force_fallback_adapter,
+            compatible_surface: Some(surface),
+        });
+
+        match request.await {
+            Ok(adapter) => {
+                let info = adapter.get_info();
+                info!(
+                    "Using {:?} adapter {} ({:?})",
+                    info.device_type, info.name, info.backend
+                );
+                return Ok(adapter);
+            }
+            Err(err) => {
+                warn!(
+                    "No adapter for {power_preference:?} (fallback: {force_fallback_adapter}): {err}"
+                );
+                last_err = Some(err);
+            }
+        }
+    }
+
+    error!(
+        "No graphics adapter can draw to the game window. The game needs Vulkan, Metal or \
+         DirectX 12 support; updating your graphics drivers usually fixes this."
+    );
+    Err(last_err.expect("ADAPTER_REQUESTS is not empty").into())
+}
+
+async fn request_device(adapter: &wgpu::Adapter) -> Result<(wgpu::Device, wgpu::Queue)> {
+    // Older and integrated GPUs may not meet the default limits, so retry with the downlevel
+    // ones before giving up.
+    let limits = [
+        wgpu::Limits::default(),
+        wgpu::Limits::downlevel_defaults().using_resolution(adapter.limits()),
+    ];
+
+    let mut last_err = None;
+
+    for required_limits in limits {
+        let request = adapter.request_device(&wgpu::DeviceDescriptor {
+            required_features: wgpu::Features::empty(),
+            required_limits,
+            label: None,
+            memory_hints: Default::default(),
+            trace: wgpu::Trace::Off,
+        });
+
+        match request.await {
+            Ok(device) => return Ok(device),
+            Err(err) => {
+                warn!("Device request failed: {err}");
+                last_err = Some(err);
+            }
+        }
+    }
+
+    error!(
+        "The graphics adapter {} doesn't support the limits the game needs. Updating your \
+         graphics drivers may help.",
+        adapter.get_info().name
+    );
+    Err(last_err
+        .expect("at least one set of limits is tried")
+        .into())
+}
+
+/// Thickness of the outline drawn where the server last put each player.
+const SYNCED_OUTLINE_WIDTH: f32 = 3.0;
+
+const SYNCED_OUTLINE_COLOUR: Vec4 = Vec4::new(1.0, 0.9, 0.2, 1.0);
+
 #[derive(Debug)]
 pub struct Graphics {
     surface: wgpu::Surface<'static>,
     device: wgpu::Device,
     queue: wgpu::Queue,
     config: wgpu::SurfaceConfiguration,
+    /// Framebuffer size in pixels.
     size: (i32, i32),
+    /// Pixels per screen coordinate, e.g. 2.0 on a HiDPI display.
+    content_scale: f32,
+    /// Set while the framebuffer has no area, e.g. when the window is minimized.
+    minimized: bool,
     render_pipeline: wgpu::RenderPipeline,
     camera: Camera2D,
     camera_uniform: CameraUniform,
     camera_buffer: wgpu::Buffer,
     camera_bind_group: wgpu::BindGroup,
+    /// Maps screen coordinates onto the framebuffer, with the origin in the bottom left.
+    ui_camera: Camera2D,
+    ui_camera_buffer: wgpu::Buffer,
+    ui_camera_bind_group: wgpu::BindGroup,
     texture_registry: TextureRegistry,
     sprite_batch: SpriteBatch,
+    /// Batches screen space sprites separately, since a batch's vertices are only uploaded
+    /// once per frame.
+    ui_batch: SpriteBatch,
     tid: TextureId,
+    grid: Option<Grid>,
+    minimap: Option<Minimap>,
 }
 
 impl Graphics {
     #[instrument(skip(window))]
     pub async fn new(window: Arc<PWindow>) -> Result<Graphics> {
-        let size = window.get_size();
+        // The surface is sized in pixels, which on a HiDPI display is more than the window's
+        // screen coordinates.
+        let size = window.get_framebuffer_size();
+        let content_scale = window.get_content_scale().0;
 
         let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor {
             backends: wgpu::Backends::PRIMARY,
@@ -42,23 +154,9 @@ impl Graphics {
 
         let surface = instance.create_surface(window)?;
 
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                force_fallback_adapter: false,
-                compatible_surface: Some(&surface),
-            })
-            .await?;
-
-        let (device, queue) = adapter
-            .request_device(&wgpu::DeviceDescriptor {
-                required_features: wgpu::Features::empty(),
-                required_limits: wgpu::Limits::default(),
-                label: None,
-                memory_hints: Default::default(),
-                trace: wgpu::Trace::Off,
-            })
-            .await?;
+        let adapter = request_adapter(&instance, &surface).await?;
+
+        let (device, queue) = request_device(&adapter).await?;
 
         let surface_caps = surface.get_capabilities(&adapter);
 
@@ -72,20 +170,15 @@ impl Graphics {
         let config = wgpu::SurfaceConfiguration {
             usage: wgpu::TextureUsages::RENDER_ATTACHMENT,
             format: surface_format,
-            width: size.0 as u32,
-            height: size.1 as u32,
+            width: size.0.max(1) as u32,
+            height: size.1.max(1) as u32,
             present_mode: surface_caps.present_modes[0],
             alpha_mode: surface_caps.alpha_modes[0],
             view_formats: vec![],
             desired_maximum_frame_latency: 2,
         };
 
-        let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
-            label: Some("Shader"),
-            source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
-        });
-
-        let camera = Camera2D::new(glm::zero(), glm::vec2(1920.0, 1080.0));
+        let camera = Camera2D::new(glm::zero(), view_size(size, content_scale));
 
         let mut camera_uniform = CameraUniform::new();
         camera_uniform.update_view_proj(&camera);
@@ -96,20 +189,7 @@ impl Graphics {
             usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
         });
 
-        let camera_bind_group_layout =
-            device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-                entries: &[wgpu::BindGroupLayoutEntry {
-                    binding: 0,
-                    visibility: wgpu::ShaderStages::VERTEX,
-                    ty: wgpu::BindingType::Buffer {
-                        ty: wgpu::BufferBindingType::Uniform,
-                        has_dynamic_offset: false,
-                        min_binding_size: None,
-                    },
-                    count: None,
-                }],
-                label: Some("camera_bind_group_layout"),
-            });
+        let camera_bind_group_layout = create_camera_bind_group_layout(&device);
 
         let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
             label: Some("camera_bind_group"),
@@ -120,57 +200,36 @@ impl Graphics {
             }],
         });
 
-        let mut texture_registry = TextureRegistry::new(&device);
+        let ui_camera = ui_camera(view_size(size, content_scale));
+        let mut ui_camera_uniform = CameraUniform::new();
+        ui_camera_uniform.update_view_proj(&ui_camera);
 
-        let render_pipeline_layout =
-            device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
-                label: Some("Render Pipeline Layout"),
-                bind_group_layouts: &[
-                    &texture_registry.bind_group_layout,
-                    &camera_bind_group_layout,
-                ],
-                push_constant_ranges: &[],
-            });
+        let ui_camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("UI Camera Buffer"),
+            contents: bytemuck::cast_slice(&[ui_camera_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+        });
 
-        let render_pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
-            label: Some("Render Pipeline"),
-            layout: Some(&render_pipeline_layout),
-            vertex: wgpu::VertexState {
-                module: &shader,
-                entry_point: Some("vs_main"),
-                buffers: &[Vertex::desc()],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            },
-            fragment: Some(wgpu::FragmentState {
-                module: &shader,
-                entry_point: Some("fs_main"),
-                targets: &[Some(wgpu::ColorTargetState {
-                    format: config.format,
-                    blend: Some(wgpu::BlendState::REPLACE),
-                    write_mask: wgpu::ColorWrites::ALL,
-                })],
-                compilation_options: wgpu::PipelineCompilationOptions::default(),
-            }),
-            primitive: wgpu::PrimitiveState {
-                topology: wgpu::PrimitiveTopology::TriangleList,
-                strip_index_format: None,
-                front_face: wgpu::FrontFace::Ccw,
-                cull_mode: Some(wgpu::Face::Back),
-                polygon_mode: wgpu::PolygonMode::Fill,
-                unclipped_depth: false,
-                conservative: false,
-            },
-            depth_stencil: None,
-            multisample: wgpu::MultisampleState {
-                count: 1,
-                mask: !0,
-                alpha_to_coverage_enabled: false,
-            },
-            multiview: None,
-            cache: None,
+        let ui_camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("ui_camera_bind_group"),
+            layout: &camera_bind_group_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: ui_camera_buffer.as_entire_binding(),
+            }],
         });
 
+        let mut texture_registry = TextureRegistry::new(&device, &queue);
+
+        let render_pipeline = create_render_pipeline(
+            &device,
+            config.format,
+            &texture_registry.bind_group_layout,
+            &camera_bind_group_layout,
+        );
+
         let sprite_batch = SpriteBatch::new(&device);
+        let ui_batch = SpriteBatch::new(&device);
 
         let tid = texture_registry.load(
             &device,
@@ -185,39 +244,104 @@ impl Graphics {
             queue,
             config,
             size,
+            content_scale,
+            minimized: size.0 <= 0 || size.1 <= 0,
             render_pipeline,
             camera,
             camera_uniform,
             camera_buffer,
             camera_bind_group,
+            ui_camera,
+            ui_camera_buffer,
+            ui_camera_bind_group,
             texture_registry,
             sprite_batch,
+            ui_batch,
             tid,
+            grid: Some(Grid::default()),
+            minimap: Some(Minimap::default()),
         })
     }
 
     pub fn resize(&mut self, new_size: Option<(i32, i32)>) {
         let new_size = new_size.unwrap_or(self.size);
 
-        if new_size.0 > 0 && new_size.0 > 0 {
+        // wgpu can't configure a zero-sized surface, so keep the old configuration until the
+        // window is restored.
+        self.minimized = new_size.0 <= 0 || new_size.1 <= 0;
+
+        if !self.minimized {
             self.size = new_size;
             self.config.width = new_size.0 as u32;
             self.config.height = new_size.1 as u32;
             self.surface.configure(&self.device, &self.config);
+            self.camera
+                .set_size(view_size(self.size, self.content_scale));
+            self.ui_camera = ui_camera(view_size(self.size, self.content_scale));
         }
     }
 
-    pub fn post_update(&mut self, player_position: Vec2) {
-        self.camera.set_position(player_position);
+    /// Called when the window moves to a monitor with a different scale.
+    pub fn set_content_scale(&mut self, content_scale: f32) {
+        self.content_scale = content_scale;
+        self.camera
+            .set_size(view_size(self.size, self.content_scale));
+        self.ui_camera = ui_camera(view_size(self.size, self.content_scale));
+    }
+
+    /// Sets the background grid, or hides it with `None`.
+    pub fn set_grid(&mut self, grid: Option<Grid>) {
+        self.grid = grid;
+    }
+
+    pub fn get_grid(&self) -> Option<&Grid> {
+        self.grid.as_ref()
+    }
+
+    /// Sets the minimap, or hides it with `None`.
+    pub fn set_minimap(&mut self, minimap: Option<Minimap>) {
+        self.minimap = minimap;
+    }
+
+    pub fn get_minimap(&self) -> Option<&Minimap> {
+        self.minimap.as_ref()
+    }
+
+    /// Pixels per screen coordinate, for scaling UI and text.
+    pub fn get_content_scale(&self) -> f32 {
+        self.content_scale
+    }
+
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+
+    pub fn post_update(&mut self, camera_position: Vec2) {
+        self.camera.set_position(camera_position);
         self.camera_uniform.update_view_proj(&self.camera);
         self.queue.write_buffer(
             &self.camera_buffer,
             0,
             bytemuck::cast_slice(&[self.camera_uniform]),
         );
+
+        let mut ui_camera_uniform = CameraUniform::new();
+        ui_camera_uniform.update_view_proj(&self.ui_camera);
+        self.queue.write_buffer(
+            &self.ui_camera_buffer,
+            0,
+            bytemuck::cast_slice(&[ui_camera_uniform]),
+        );
     }
 
-    pub fn render(&mut self, player_position: Vec2) -> Result<()> {
+    /// Draws the frame. `players` are the position, collider radius and alpha of each player,
+    /// and `synced` the position and radius of where the server last put them, outlined for
+    /// debugging.
+    pub fn render(&mut self, players: &[(Vec2, f32, f32)], synced: &[(Vec2, f32)]) -> Result<()> {
+        if self.minimized {
+            return Ok(());
+        }
+
         let output = self.surface.get_current_texture()?;
 
         let view = output
@@ -254,16 +378,44 @@ impl Graphics {
             render_pass.set_pipeline(&self.render_pipeline);
             render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
 
+            if let Some(grid) = &self.grid {
+                for line in grid.lines(self.camera.get_bounds()) {
+                    self.sprite_batch
+                        .draw(self.texture_registry.white(), line.min)
+                        .scale(Vec2::new(line.width(), line.height()))
+                        .colour(grid.colour)
+                        .draw(&mut self.sprite_batch, &self.texture_registry);
+                }
+            }
+
             self.sprite_batch
                 .draw(self.tid, Vec2::new(256.0, 256.0))
                 .scale(Vec2::new(2.0, 1.0))
                 .draw(&mut self.sprite_batch, &self.texture_registry);
 
-            self.sprite_batch
-                .draw(self.tid, player_position)
-                .origin(Vec2::new(128.0, 128.0))
-                .scale_uniform(100.0 / 256.0)
-                .draw(&mut self.sprite_batch, &self.texture_registry);
+            if let Some(texture) = self.texture_registry.get(self.tid) {
+                let size = Vec2::new(texture.get_width_f32(), texture.get_height_f32());
+                for &(position, radius, alpha) in players {
+                    self.sprite_batch
+                        .draw(self.tid, position)
+                        .origin(size / 2.0)
+                        .scale(sprite_scale(size, radius))
+                        .colour(Vec4::new(1.0, 1.0, 1.0, alpha))
+                        .draw(&mut self.sprite_batch, &self.texture_registry);
+                }
+            }
+
+            for &(position, radius) in synced {
+                let half = Vec2::new(radius, radius);
+                let bounds = Rect::new(position - half, position + half);
+                for edge in outline(bounds, SYNCED_OUTLINE_WIDTH) {
+                    self.sprite_batch
+                        .draw(self.texture_registry.white(), edge.min)
+                        .scale(Vec2::new(edge.width(), edge.height()))
+                        .colour(SYNCED_OUTLINE_COLOUR)
+                        .draw(&mut self.sprite_batch, &self.texture_registry);
+                }
+            }
 
             self.sprite_batch.end(
                 &self.device,
@@ -271,6 +423,35 @@ impl Graphics {
                 &self.texture_registry,
                 &mut render_pass,
             );
+
+            if let Some(minimap) = &self.minimap {
+                let screen = self.ui_camera.get_bounds().max;
+                let bounds = minimap.bounds(screen);
+                self.ui_batch
+                    .draw(self.texture_registry.white(), bounds.min)
+                    .scale(Vec2::new(bounds.width(), bounds.height()))
+                    .colour(minimap.background)
+                    .draw(&mut self.ui_batch, &self.texture_registry);
+
+                let positions: Vec<Vec2> = players.iter().map(|(position, ..)| *position).collect();
+                let centre = self.camera.get_position();
+                let half_dot = Vec2::new(minimap.dot_size, minimap.dot_size) * 0.5;
+                for dot in minimap.dots(centre, &positions, screen) {
+                    self.ui_batch
+                        .draw(self.texture_registry.white(), dot - half_dot)
+                        .scale_uniform(minimap.dot_size)
+                        .colour(minimap.colour)
+                        .draw(&mut self.ui_batch, &self.texture_registry);
+                }
+
+                render_pass.set_bind_group(1, &self.ui_camera_bind_group, &[]);
+                self.ui_batch.end(
+                    &self.device,
+                    &self.queue,
+                    &self.texture_registry,
+                    &mut render_pass,
+                );
+            }
         }
 
         self.queue.submit(std::iter::once(encoder.finish()));
@@ -279,3 +460,125 @@ impl Graphics {
         Ok(())
     }
 }
+
+/// Layout of the camera's view projection uniform, bound as group 1.
+pub fn create_camera_bind_group_layout(device: &wgpu::Device) -> wgpu::BindGroupLayout {
+    device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        entries: &[wgpu::BindGroupLayoutEntry {
+            binding: 0,
+            visibility: wgpu::ShaderStages::VERTEX,
+            ty: wgpu::BindingType::Buffer {
+                ty: wgpu::BufferBindingType::Uniform,
+                has_dynamic_offset: false,
+                min_binding_size: None,
+            },
+            count: None,
+        }],
+        label: Some("camera_bind_group_layout"),
+    })
+}
+
+/// The sprite pipeline drawing into targets of `format`.
+pub fn create_render_pipeline(
+    device: &wgpu::Device,
+    format: wgpu::TextureFormat,
+    texture_bind_group_layout: &wgpu::BindGroupLayout,
+    camera_bind_group_layout: &wgpu::BindGroupLayout,
+) -> wgpu::RenderPipeline {
+    let shader = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+    });
+
+    let render_pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Render Pipeline Layout"),
+        bind_group_layouts: &[texture_bind_group_layout, camera_bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+        label: Some("Render Pipeline"),
+        layout: Some(&render_pipeline_layout),
+        vertex: wgpu::VertexState {
+            module: &shader,
+            entry_point: Some("vs_main"),
+            buffers: &[Vertex::desc()],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        },
+        fragment: Some(wgpu::FragmentState {
+            module: &shader,
+            entry_point: Some("fs_main"),
+            targets: &[Some(wgpu::ColorTargetState {
+                format,
+                blend: Some(wgpu::BlendState::ALPHA_BLENDING),
+                write_mask: wgpu::ColorWrites::ALL,
+            })],
+            compilation_options: wgpu::PipelineCompilationOptions::default(),
+        }),
+        primitive: wgpu::PrimitiveState {
+            topology: wgpu::PrimitiveTopology::TriangleList,
+            strip_index_format: None,
+            front_face: FRONT_FACE,
+            cull_mode: CULL_MODE,
+            polygon_mode: wgpu::PolygonMode::Fill,
+            unclipped_depth: false,
+            conservative: false,
+        },
+        depth_stencil: None,
+        multisample: wgpu::MultisampleState {
+            count: 1,
+            mask: !0,
+            alpha_to_coverage_enabled: false,
+        },
+        multiview: None,
+        cache: None,
+    })
+}
+
+/// Four bars of `width` along the inside of `bounds`.
+pub fn outline(bounds: Rect, width: f32) -> [Rect; 4] {
+    let width = width.min(bounds.width() / 2.0).min(bounds.height() / 2.0);
+    let (min, max) = (bounds.min, bounds.max);
+
+    [
+        Rect::new(min, Vec2::new(max.x, min.y + width)),
+        Rect::new(Vec2::new(min.x, max.y - width), max),
+        Rect::new(
+            Vec2::new(min.x, min.y + width),
+            Vec2::new(min.x + width, max.y - width),
+        ),
+        Rect::new(
+            Vec2::new(max.x - width, min.y + width),
+            Vec2::new(max.x, max.y - width),
+        ),
+    ]
+}
+
+/// A camera showing `size` screen coordinates with the origin in the bottom left.
+fn ui_camera(size: Vec2) -> Camera2D {
+    Camera2D::new(size * 0.5, size)
+}
+
+/// World units visible for a framebuffer of `size` pixels. One world unit is one screen
+/// coordinate, so the world looks the same size on a HiDPI display, just sharper.
+pub fn view_size(size: (i32, i32), content_scale: f32) -> Vec2 {
+    let content_scale = if content_scale > 0.0 {
+        content_scale
+    } else {
+        1.0
+    };
+
+    Vec2::new(
+        size.0.max(1) as f32 / content_scale,
+        size.1.max(1) as f32 / content_scale,
+    )
+}
+
+/// Scale that stretches a sprite of `size` pixels over a collider of `radius`, so what is
+/// drawn is what collides.
+pub fn sprite_scale(size: Vec2, radius: f32) -> Vec2 {
+    Vec2::new(
+        2.0 * radius / size.x.max(1.0),
+        2.0 * radius / size.y.max(1.0),
+    )
+}
@@ -35,5 +35,5 @@ var s_diffuse: sampler;
 
 @fragment
 fn fs_main(in: VertexOutput) -> @location(0) vec4<f32> {
-    return textureSample(t_diffuse, s_diffuse, in.tex);
+    return textureSample(t_diffuse, s_diffuse, in.tex) * in.colour;
 }
\ No newline at end of file
@@ -71,17 +71,23 @@ pub struct SpriteBatch {
 
 const MAXIMUM_BATCH_SIZE: u16 = 256;
 
+/// Triangles of one quad, indexing its vertices in the order [`SpriteBatch::end`] writes
+/// them: top left, top right, bottom left, bottom right. With y pointing up, both triangles
+/// are counter-clockwise, which is [`FRONT_FACE`].
+pub const QUAD_INDICES: [u16; 6] = [0, 3, 1, 0, 2, 3];
+
+pub const FRONT_FACE: wgpu::FrontFace = wgpu::FrontFace::Ccw;
+
+/// Sprites are never culled. A negative scale flips a quad's winding, and a flipped sprite
+/// must still be drawn.
+pub const CULL_MODE: Option<wgpu::Face> = None;
+
 impl SpriteBatch {
     pub fn new(device: &wgpu::Device) -> SpriteBatch {
         let mut indices = Vec::with_capacity(MAXIMUM_BATCH_SIZE as usize * 6);
 
         for i in 0..MAXIMUM_BATCH_SIZE {
-            indices.push(i * 4);
-            indices.push(i * 4 + 3);
-            indices.push(i * 4 + 1);
-            indices.push(i * 4);
-            indices.push(i * 4 + 2);
-            indices.push(i * 4 + 3);
+            indices.extend(QUAD_INDICES.map(|index| i * 4 + index));
         }
 
         let index_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
@@ -116,6 +122,7 @@ impl SpriteBatch {
         }
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn draw_detailed(
         &mut self,
         texture_registry: &TextureRegistry,
@@ -177,7 +184,8 @@ impl SpriteBatch {
         );
 
         let tr = Vertex::new(
-            position - scaled_origin + Vec2::new(source.width() * scale.x, source.height() * scale.y),
+            position - scaled_origin
+                + Vec2::new(source.width() * scale.x, source.height() * scale.y),
             colour,
             Vec2::new(
                 source.max.x / texture.get_width_f32(),
@@ -241,6 +249,15 @@ impl SpriteBatch {
             self.vertices.push(item.br);
         }
 
+        while item_count - current_batch_start > MAXIMUM_BATCH_SIZE as u64 {
+            batches.push((
+                current_texture,
+                current_batch_start,
+                current_batch_start + MAXIMUM_BATCH_SIZE as u64,
+            ));
+            current_batch_start += MAXIMUM_BATCH_SIZE as u64;
+        }
+
         batches.push((current_texture, current_batch_start, item_count));
 
         if (self.vertices.len() * std::mem::size_of::<Vertex>()) as u64 <= self.vertex_buffer_size {
@@ -1,4 +1,7 @@
-use std::collections::HashMap;
+use std::{
+    collections::HashMap,
+    sync::atomic::{AtomicU32, Ordering},
+};
 
 use common::Result;
 use image::GenericImageView;
@@ -60,18 +63,29 @@ pub fn sampler_bind_group_layout_entry(binding: u32) -> wgpu::BindGroupLayoutEnt
     }
 }
 
+/// Hands every [`TextureRegistry`] its own id.
+static NEXT_REGISTRY_ID: AtomicU32 = AtomicU32::new(0);
+
+/// A texture in one [`TextureRegistry`]. Ids remember which registry they came from, so
+/// one used with another registry is caught rather than drawing whatever texture happens
+/// to share its index.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct TextureId(usize);
+pub struct TextureId {
+    registry: u32,
+    index: usize,
+}
 
 #[derive(Debug)]
 pub struct TextureRegistry {
+    id: u32,
     mapping: HashMap<usize, Texture>,
     pub bind_group_layout: wgpu::BindGroupLayout,
     counter: usize,
+    white: TextureId,
 }
 
 impl TextureRegistry {
-    pub fn new(device: &wgpu::Device) -> TextureRegistry {
+    pub fn new(device: &wgpu::Device, queue: &wgpu::Queue) -> TextureRegistry {
         let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
             entries: &[
                 texture_bind_group_layout_entry(0),
@@ -80,15 +94,42 @@ impl TextureRegistry {
             label: Some("Texture Bind Group Layout"),
         });
 
-        TextureRegistry {
+        let id = NEXT_REGISTRY_ID.fetch_add(1, Ordering::Relaxed);
+        let mut registry = TextureRegistry {
+            id,
             mapping: HashMap::new(),
             bind_group_layout,
             counter: 0,
-        }
+            white: TextureId {
+                registry: id,
+                index: 0,
+            },
+        };
+        registry.white = registry.load_rgba(device, queue, &[255; 4], (1, 1), Some("White"));
+
+        registry
+    }
+
+    /// A single white pixel, loaded with the registry. Drawn with a colour and a scale it
+    /// gives a solid rectangle, e.g. for UI backgrounds and debug shapes.
+    pub fn white(&self) -> TextureId {
+        self.white
+    }
+
+    /// Whether `id` was handed out by this registry.
+    pub fn owns(&self, id: TextureId) -> bool {
+        id.registry == self.id
     }
 
+    /// The texture `id` refers to. Ids from another registry are a bug: they panic in debug
+    /// builds and find nothing otherwise.
     pub fn get(&self, id: TextureId) -> Option<&Texture> {
-        self.mapping.get(&id.0)
+        debug_assert!(self.owns(id), "{id:?} is from another texture registry");
+        if !self.owns(id) {
+            return None;
+        }
+
+        self.mapping.get(&id.index)
     }
 
     pub fn load(
@@ -100,8 +141,19 @@ impl TextureRegistry {
     ) -> Result<TextureId> {
         let image = image::load_from_memory(bytes)?;
         let rgba = image.to_rgba8();
-        let dimensions = image.dimensions();
 
+        Ok(self.load_rgba(device, queue, &rgba, image.dimensions(), label))
+    }
+
+    /// Loads already decoded RGBA pixels, e.g. a solid colour generated at runtime.
+    pub fn load_rgba(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        rgba: &[u8],
+        dimensions: (u32, u32),
+        label: Option<&str>,
+    ) -> TextureId {
         let size = wgpu::Extent3d {
             width: dimensions.0,
             height: dimensions.1,
@@ -126,7 +178,7 @@ impl TextureRegistry {
                 origin: wgpu::Origin3d::ZERO,
                 aspect: wgpu::TextureAspect::All,
             },
-            &rgba,
+            rgba,
             wgpu::TexelCopyBufferLayout {
                 offset: 0,
                 bytes_per_row: Some(4 * dimensions.0),
@@ -161,11 +213,14 @@ impl TextureRegistry {
             label,
         });
 
-        let id = TextureId(self.counter);
+        let id = TextureId {
+            registry: self.id,
+            index: self.counter,
+        };
         self.counter += 1;
 
         self.mapping.insert(
-            id.0,
+            id.index,
             Texture {
                 bind_group,
                 texture,
@@ -176,6 +231,6 @@ impl TextureRegistry {
             },
         );
 
-        Ok(id)
+        id
     }
 }
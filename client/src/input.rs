@@ -1,10 +1,19 @@
-use std::collections::HashMap;
+use std::{
+    collections::{HashMap, HashSet},
+    time::Duration,
+};
 
 #[derive(Debug, Default)]
 pub struct KeyboardState {
     pressed: HashMap<glfw::Key, glfw::Modifiers>,
     just_pressed: HashMap<glfw::Key, glfw::Modifiers>,
     just_released: HashMap<glfw::Key, glfw::Modifiers>,
+    /// Keys whose latest event this frame was a release. A key can be released and pressed
+    /// again before one update, so `just_released` alone can't say whether it is still down.
+    pending_release: HashSet<glfw::Key>,
+    /// How long each pressed key has been held, advanced by every `post_update`.
+    held: HashMap<glfw::Key, Duration>,
+    last_dt: Duration,
 }
 
 impl KeyboardState {
@@ -44,23 +53,66 @@ impl KeyboardState {
         }
     }
 
+    /// How long `key` has been held, counting the frames it was down for. `None` when it is not
+    /// held.
+    pub fn held_duration(&self, key: glfw::Key) -> Option<Duration> {
+        self.held.get(&key).copied()
+    }
+
+    /// True on the frame `key` is pressed, then once every `interval` after it has been held
+    /// for `delay`, like text input key repeat.
+    pub fn is_repeated(&self, key: glfw::Key, delay: Duration, interval: Duration) -> bool {
+        if self.is_just_pressed(key, None) {
+            return true;
+        }
+
+        let Some(held) = self.held_duration(key) else {
+            return false;
+        };
+
+        if held < delay {
+            return false;
+        }
+
+        let previous = held.saturating_sub(self.last_dt);
+        if previous < delay {
+            return true;
+        }
+
+        let interval = interval.as_secs_f64().max(f64::EPSILON);
+        let repeats = |held: Duration| ((held - delay).as_secs_f64() / interval).floor();
+        repeats(held) > repeats(previous)
+    }
+
     pub fn press(&mut self, key: glfw::Key, mods: glfw::Modifiers) {
         self.pressed.insert(key, mods);
         self.just_pressed.insert(key, mods);
+        self.pending_release.remove(&key);
+        self.held.entry(key).or_insert(Duration::ZERO);
     }
 
     pub fn release(&mut self, key: glfw::Key, mods: glfw::Modifiers) {
         self.just_released.insert(key, mods);
+        self.pending_release.insert(key);
+        self.held.remove(&key);
     }
 
-    pub fn post_update(&mut self) {
+    pub fn post_update(&mut self, dt: Duration) {
         self.just_pressed.clear();
         self.clear_released();
         self.just_released.clear();
+
+        for held in self.held.values_mut() {
+            *held += dt;
+        }
+        self.last_dt = dt;
     }
 
+    /// Keys pressed and released within one frame stay pressed for that frame, so a quick
+    /// tap is still seen by `is_pressed`.
     fn clear_released(&mut self) {
-        self.pressed
-            .retain(|k, _| !self.just_released.contains_key(k));
+        for key in self.pending_release.drain() {
+            self.pressed.remove(&key);
+        }
     }
 }
@@ -1,41 +1,68 @@
 use std::{
-    collections::{VecDeque, vec_deque},
+    collections::{HashMap, VecDeque, vec_deque},
     time::Duration,
 };
 
 use common::{
-    Entity, Result, Vec2,
+    Entity, FixedTimestep, PROTOCOL_VERSION, Result, TICK_RATE, Vec2,
+    ability::Abilities,
     instance::{Instance, LocalPlayer, Player, Position},
+    interpolation::{DelayEstimate, Interpolate, InterpolationBuffer},
     message::{
-        NetworkSpawn, OrderedInput, OwnedPlayerSync, PlayerPositionSync, ReliableMessageFromClient,
-        ReliableMessageFromServer, UnreliableMessageFromClient, UnreliableMessageFromServer,
+        NetworkSpawn, OrderedInput, OwnedPlayerSync, PlayerInit, PlayerPositionSync,
+        ReliableMessageFromClient, ReliableMessageFromServer, TickSync,
+        UnreliableMessageFromClient, UnreliableMessageFromServer,
     },
     net_obj::{LastSyncTracker, NetworkObject},
-    player::PlayerInput,
-    tick::{Tick, get_unix_millis},
+    player::{PlayerAction, PlayerInput, sanitize_direction},
+    tick::{Tick, TickClock, get_unix_millis},
+    tick_duration,
 };
 use tracing::{info, warn};
 
-use crate::{KeyboardState, backend::BackendConnection};
+use crate::{
+    KeyboardState,
+    backend::{BackendConnection, DisconnectReason},
+};
 
 pub struct InstanceData {
     instance: Instance,
     state: InstanceState,
+    disconnect_reason: Option<DisconnectReason>,
     local_player: Option<(NetworkObject, Entity)>,
-    input_buffer: InputBuffer,
-    player_history: SnapshotHistory,
+    /// Joined without a player; no input is sent and nothing is predicted.
+    spectating: bool,
+    prediction: Prediction,
+    /// Entities besides the local player the server let this client drive.
+    controlled: HashMap<NetworkObject, Controlled>,
+    player_list: Vec<(NetworkObject, String)>,
+    prediction_stats: PredictionStats,
+    entity_cap: EntityCap,
+    correction_smoothing: CorrectionSmoothing,
+    /// Local player position from the newest owned sync the server sent.
+    server_position: Option<Vec2>,
+    on_reconcile: Option<ReconcileHook>,
+    delay: DelayEstimate,
+    paused: bool,
+    clock: TickClock,
+    timestep: FixedTimestep,
+    fade_in: Duration,
+    fade_out: Duration,
+    fade_in_local_player: bool,
+    /// Position, radius and fade of despawned players still being drawn.
+    fading_out: Vec<(Vec2, f32, LifecycleAnim)>,
+    /// Action key pressed since the last input was sent, so a press in a frame without a
+    /// tick isn't lost.
+    queued_action: Option<PlayerAction>,
 }
 
-fn get_client_tick(server_tick: u64, server_unix_millis: u128) -> Tick {
-    let client_unix_millis = get_unix_millis();
-
-    let elapsed_millis = client_unix_millis.saturating_sub(server_unix_millis);
-
-    const MILLIS_PER_TICK: u128 = 1000 / 60;
+/// Takes in a tick sync and moves the instance to the tick the server is estimated to be at.
+fn sync_tick(clock: &mut TickClock, instance: &mut Instance, sync: &TickSync) {
+    clock.record(sync);
 
-    let elapsed_ticks = elapsed_millis / MILLIS_PER_TICK;
-
-    Tick::new(server_tick + elapsed_ticks as u64)
+    if let Some(tick) = clock.estimate(get_unix_millis()) {
+        instance.set_tick(tick);
+    }
 }
 
 impl InstanceData {
@@ -43,9 +70,291 @@ impl InstanceData {
         InstanceData {
             instance,
             state: InstanceState::Connecting,
+            disconnect_reason: None,
             local_player: None,
-            input_buffer: InputBuffer::default(),
-            player_history: SnapshotHistory::default(),
+            spectating: false,
+            prediction: Prediction::default(),
+            controlled: HashMap::new(),
+            player_list: Vec::new(),
+            prediction_stats: PredictionStats::default(),
+            entity_cap: EntityCap::default(),
+            correction_smoothing: CorrectionSmoothing::default(),
+            server_position: None,
+            on_reconcile: None,
+            delay: DelayEstimate::default(),
+            paused: false,
+            clock: TickClock::new(TICK_RATE),
+            timestep: FixedTimestep::new(tick_duration(TICK_RATE)),
+            fade_in: FADE_IN,
+            fade_out: FADE_OUT,
+            fade_in_local_player: false,
+            fading_out: Vec::new(),
+            queued_action: None,
+        }
+    }
+
+    /// Joins the instance without a player, to watch everyone else.
+    pub fn new_spectator(instance: Instance) -> InstanceData {
+        InstanceData {
+            spectating: true,
+            ..InstanceData::new(instance)
+        }
+    }
+
+    pub fn is_spectating(&self) -> bool {
+        self.spectating
+    }
+
+    /// Asks the instance for a player after joining as a spectator. The local player exists
+    /// once its [`PlayerInit`] arrives.
+    pub fn join_as_player(&mut self, backend: &mut BackendConnection) -> Result<()> {
+        if !self.spectating || self.state != InstanceState::Done {
+            return Ok(());
+        }
+
+        let id = self.instance.get_id();
+        backend.send_reliable_message(
+            id,
+            ReliableMessageFromClient::Connected {
+                protocol_version: PROTOCOL_VERSION,
+            },
+        )?;
+        info!("Joining instance {id} as a player");
+        self.spectating = false;
+
+        Ok(())
+    }
+
+    /// Fades the player in over `fade_in`, if given.
+    fn spawn_local_player(
+        instance: &mut Instance,
+        player_info: &PlayerInit,
+        fade_in: Option<Duration>,
+    ) -> (NetworkObject, Entity) {
+        // A spectator turning into a player may already have its player from a `Spawn`.
+        if let Some(entity) = instance.find_network_object(player_info.net_obj) {
+            instance.despawn(entity);
+        }
+
+        let entity = instance.spawn_player(
+            true,
+            player_info.position.into(),
+            player_info.name.clone(),
+            player_info.net_obj,
+            player_info.radius,
+            Some(player_info.tick),
+        );
+        if let Some(fade_in) = fade_in {
+            Self::start_fade_in(instance, entity, fade_in);
+        }
+        (player_info.net_obj, entity)
+    }
+
+    /// Takes on the player in `player_info`, either after spectating or because the server
+    /// switched this client to another character. A switch despawns the old player and drops
+    /// everything predicted for it, since the new one counts its inputs from the start.
+    fn replace_local_player(&mut self, player_info: &PlayerInit) {
+        if let Some((net_obj, entity)) = self.local_player
+            && net_obj != player_info.net_obj
+        {
+            info!(
+                "Switched from player {net_obj:?} to {:?}",
+                player_info.net_obj
+            );
+            self.leave_fading_out(entity);
+            self.forget_local_player();
+        }
+
+        let fade_in = self.fade_in_local_player.then_some(self.fade_in);
+        self.local_player = Some(Self::spawn_local_player(
+            &mut self.instance,
+            player_info,
+            fade_in,
+        ));
+    }
+
+    /// Despawns the local player and drops everything predicted for it.
+    fn forget_local_player(&mut self) {
+        if let Some((_, entity)) = self.local_player.take() {
+            self.instance.despawn(entity);
+        }
+
+        let mut prediction = Prediction::default();
+        prediction
+            .input_buffer
+            .set_min_length(self.prediction.input_buffer.get_min_length());
+        self.prediction = prediction;
+        self.server_position = None;
+        self.correction_smoothing = CorrectionSmoothing::new(self.correction_smoothing.get_time());
+    }
+
+    fn recv_pause_state(&mut self, backend: &mut BackendConnection) {
+        for msg in backend.get_reliable_messages(self.instance.get_id()) {
+            if let ReliableMessageFromServer::PauseState { paused } = msg {
+                info!("Instance {}", if *paused { "paused" } else { "resumed" });
+                self.paused = *paused;
+            }
+        }
+    }
+
+    fn recv_disconnect(&mut self, backend: &mut BackendConnection) {
+        for msg in backend.get_reliable_messages(self.instance.get_id()) {
+            match msg {
+                ReliableMessageFromServer::Kicked(reason) => {
+                    warn!("Kicked from instance {}: {reason}", self.instance.get_id());
+                    self.disconnect_reason = Some(DisconnectReason::Kicked(reason.clone()));
+                }
+                ReliableMessageFromServer::ServerShutdown => {
+                    warn!("Instance {} is shutting down", self.instance.get_id());
+                    self.disconnect_reason = Some(DisconnectReason::ServerClosed);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    pub fn get_status(&self) -> InstanceStatus {
+        match self.state {
+            InstanceState::Connecting | InstanceState::LocalLoaded => InstanceStatus::Connecting,
+            InstanceState::LoadRemote(_) => InstanceStatus::Loading,
+            InstanceState::Done => InstanceStatus::Active,
+            InstanceState::Disconnected => InstanceStatus::Disconnected,
+        }
+    }
+
+    /// Why the instance was left, once [`InstanceData::get_status`] reports
+    /// [`InstanceStatus::Disconnected`].
+    pub fn get_disconnect_reason(&self) -> Option<&DisconnectReason> {
+        self.disconnect_reason.as_ref()
+    }
+
+    pub fn get_tick(&self) -> Tick {
+        self.instance.get_tick()
+    }
+
+    /// How often and how far the local player has been corrected by the server.
+    pub fn get_prediction_stats(&self) -> &PredictionStats {
+        &self.prediction_stats
+    }
+
+    /// Where the server last said the local player is. Prediction runs ahead of it while the
+    /// player moves and matches it once the player has stood still for a round trip.
+    pub fn get_server_position(&self) -> Option<Vec2> {
+        self.server_position
+    }
+
+    /// Ignores spawns from the server once `max` networked entities exist. See
+    /// [`MAX_NETWORKED_ENTITIES`].
+    pub fn set_max_entities(&mut self, max: usize) {
+        self.entity_cap.set_max(max);
+    }
+
+    pub fn get_entity_cap(&self) -> &EntityCap {
+        &self.entity_cap
+    }
+
+    /// Keeps at least `length` inputs and position snapshots for reconciling. See
+    /// [`INPUT_BUFFER_LENGTH`].
+    pub fn set_input_buffer_length(&mut self, length: usize) {
+        self.prediction.input_buffer.set_min_length(length);
+        for controlled in self.controlled.values_mut() {
+            controlled.prediction.input_buffer.set_min_length(length);
+        }
+    }
+
+    pub fn get_input_buffer(&self) -> &InputBuffer {
+        &self.prediction.input_buffer
+    }
+
+    /// Entities besides the local player this client sends inputs for, see
+    /// [`ReliableMessageFromServer::ControlGranted`].
+    pub fn get_controlled(&self) -> Vec<NetworkObject> {
+        self.controlled.keys().copied().collect()
+    }
+
+    /// Sets the input sent for the controlled entity `net_obj` every tick from now on,
+    /// returning whether this client controls it.
+    pub fn set_controlled_input(&mut self, net_obj: NetworkObject, input: PlayerInput) -> bool {
+        let Some(controlled) = self.controlled.get_mut(&net_obj) else {
+            return false;
+        };
+
+        controlled.input = input.sanitized();
+        true
+    }
+
+    /// How long reconcile snaps take to fade out of the drawn local player, or `None` to draw
+    /// them as they happen. The simulation always snaps; only rendering and the camera are
+    /// smoothed.
+    pub fn set_correction_smoothing(&mut self, time: Option<Duration>) {
+        self.correction_smoothing.set_time(time);
+    }
+
+    pub fn get_correction_smoothing(&self) -> Option<Duration> {
+        self.correction_smoothing.get_time()
+    }
+
+    /// How long spawned entities take to fade in and despawned players take to fade out. Zero
+    /// shows or hides them at once.
+    pub fn set_fade_durations(&mut self, fade_in: Duration, fade_out: Duration) {
+        self.fade_in = fade_in;
+        self.fade_out = fade_out;
+    }
+
+    pub fn get_fade_in(&self) -> Duration {
+        self.fade_in
+    }
+
+    pub fn get_fade_out(&self) -> Duration {
+        self.fade_out
+    }
+
+    /// Whether the local player fades in like everyone else when it spawns. Off by default,
+    /// so the player is there as soon as the instance loads.
+    pub fn set_fade_in_local_player(&mut self, fade_in: bool) {
+        self.fade_in_local_player = fade_in;
+    }
+
+    /// Calls `hook` every time the local player is reconciled with the server, for smoothing
+    /// out the snap or showing it in a debug view. `None` removes it.
+    pub fn set_on_reconcile(&mut self, hook: Option<ReconcileHook>) {
+        self.on_reconcile = hook;
+    }
+
+    /// Delay and jitter of the position syncs from the server.
+    pub fn get_delay_estimate(&self) -> &DelayEstimate {
+        &self.delay
+    }
+
+    /// Everyone in the instance, including the local player, in the order they joined.
+    pub fn get_player_list(&self) -> &[(NetworkObject, String)] {
+        &self.player_list
+    }
+
+    fn recv_player_list(&mut self, backend: &mut BackendConnection) {
+        for msg in backend.get_reliable_messages(self.instance.get_id()) {
+            match msg {
+                ReliableMessageFromServer::PlayerList(list) => {
+                    self.player_list = list.clone();
+                }
+                ReliableMessageFromServer::PlayerJoined(net_obj, name) => {
+                    self.player_list.retain(|(obj, _)| obj != net_obj);
+                    self.player_list.push((*net_obj, name.clone()));
+                }
+                ReliableMessageFromServer::PlayerLeft(net_obj) => {
+                    self.player_list.retain(|(obj, _)| obj != net_obj);
+                }
+                ReliableMessageFromServer::PlayerRenamed(net_obj, name) => {
+                    for (obj, old_name) in &mut self.player_list {
+                        if obj == net_obj {
+                            *old_name = name.clone();
+                        }
+                    }
+
+                    self.instance.rename_player(*net_obj, name.clone());
+                }
+                _ => {}
+            }
         }
     }
 
@@ -53,8 +362,7 @@ impl InstanceData {
         if self.state == InstanceState::Done {
             for msg in backend.get_reliable_messages(self.instance.get_id()) {
                 if let ReliableMessageFromServer::TickSync(sync) = msg {
-                    let next_tick = get_client_tick(sync.tick, sync.unix_millis);
-                    self.instance.set_tick(next_tick);
+                    sync_tick(&mut self.clock, &mut self.instance, sync);
                 }
             }
         }
@@ -74,151 +382,446 @@ impl InstanceData {
         if kb.is_pressed(glfw::Key::A, None) {
             local_direction -= Vec2::x();
         }
-        let local_direction = if local_direction == Vec2::zeros() {
-            local_direction
-        } else {
-            local_direction.normalize()
-        };
-
+        let action = self.queued_action.take().filter(|action| {
+            self.local_player
+                .is_none_or(|(_, entity)| self.is_ready(entity, *action))
+        });
         let input = PlayerInput {
-            move_direction: local_direction.into(),
+            move_direction: sanitize_direction(local_direction).into(),
+            action,
         };
-        let order = self.input_buffer.push_input(input.clone());
+        let order = self.prediction.input_buffer.push_input(input.clone());
 
         let message = UnreliableMessageFromClient::Input(OrderedInput {
             input: input.clone(),
             order,
+            target: None,
         });
         backend.send_unreliable_message(self.instance.get_id(), message)?;
 
+        for (net_obj, controlled) in &mut self.controlled {
+            // Actions are sent once, movement until it is changed.
+            let input = controlled.input.clone();
+            controlled.input.action = None;
+            let order = controlled.prediction.input_buffer.push_input(input.clone());
+
+            let message = UnreliableMessageFromClient::Input(OrderedInput {
+                input,
+                order,
+                target: Some(*net_obj),
+            });
+            backend.send_unreliable_message(self.instance.get_id(), message)?;
+        }
+
         Ok(())
     }
 
-    fn spawn(&mut self, backend: &mut BackendConnection) -> Result<()> {
+    /// Whether `entity` may use `action` in its next input, as far as the cooldowns predicted
+    /// for it go. Actions that aren't ready are never sent, since the server would turn them
+    /// down the same way.
+    fn is_ready(&self, entity: Entity, action: PlayerAction) -> bool {
+        let Ok(abilities) = self.instance.get_world().get::<&Abilities>(entity) else {
+            return true;
+        };
+
+        let ready = abilities.is_ready_next(action.get_ability());
+        if !ready {
+            info!("{action:?} is still cooling down");
+        }
+        ready
+    }
+
+    fn recv_control(&mut self, backend: &mut BackendConnection) {
         for msg in backend.get_reliable_messages(self.instance.get_id()) {
-            let ReliableMessageFromServer::Spawn(spawn) = msg else {
-                continue;
-            };
+            match msg {
+                ReliableMessageFromServer::ControlGranted(net_obj) => {
+                    info!("Took control of {net_obj:?}");
+                    let mut prediction = Prediction::default();
+                    prediction
+                        .input_buffer
+                        .set_min_length(self.prediction.input_buffer.get_min_length());
+                    self.controlled.insert(
+                        *net_obj,
+                        Controlled {
+                            input: PlayerInput {
+                                move_direction: [0.0, 0.0],
+                                action: None,
+                            },
+                            prediction,
+                        },
+                    );
 
-            if self.local_player.map(|x| x.0) == Some(spawn.net_obj) {
-                continue;
+                    // Predicted from here on, so syncs no longer move it.
+                    if let Some(entity) = self.instance.find_network_object(*net_obj) {
+                        self.instance.set_interpolate(entity, false);
+                    }
+                }
+                ReliableMessageFromServer::ControlRevoked(net_obj) => {
+                    info!("Lost control of {net_obj:?}");
+                    self.controlled.remove(net_obj);
+                }
+                _ => {}
             }
+        }
+    }
 
-            if let NetworkSpawn::Player(position) = spawn.net_spawn {
-                self.instance
-                    .spawn_player(false, position.into(), spawn.net_obj, Some(spawn.tick));
+    fn spawn(&mut self, backend: &mut BackendConnection) -> Result<()> {
+        for msg in backend.get_reliable_messages(self.instance.get_id()) {
+            match msg {
+                ReliableMessageFromServer::Spawn(spawn) => {
+                    self.spawn_one(spawn.net_obj, &spawn.net_spawn, spawn.tick);
+                }
+                ReliableMessageFromServer::Snapshot { tick, entries } => {
+                    for entry in entries {
+                        self.spawn_one(entry.net_obj, &entry.net_spawn, *tick);
+                    }
+                }
+                ReliableMessageFromServer::Despawn(net_obj) => {
+                    if self.local_player.map(|x| x.0) == Some(*net_obj) {
+                        continue;
+                    }
+
+                    if let Some(entity) = self.instance.find_network_object(*net_obj) {
+                        self.leave_fading_out(entity);
+                        self.instance.despawn(entity);
+                    }
+                }
+                _ => {}
             }
         }
 
         Ok(())
     }
 
-    fn sync_nonlocal(&mut self, position_sync: &PlayerPositionSync) {
-        for (_, (position, net_obj, last_sync_tracker)) in self
+    /// Keeps drawing the player `entity` where it was while it fades out, since the entity
+    /// itself leaves the simulation straight away.
+    fn leave_fading_out(&mut self, entity: Entity) {
+        if self.fade_out.is_zero() {
+            return;
+        }
+
+        let Ok((position, player)) = self
             .instance
             .get_world_mut()
-            .query_mut::<(
-                &mut Position,
-                &NetworkObject,
-                &mut LastSyncTracker<Position>,
-            )>()
-            .with::<&Player>()
-            .without::<&LocalPlayer>()
+            .query_one_mut::<(&Position, &Player)>(entity)
+        else {
+            return;
+        };
+
+        let anim = LifecycleAnim::fade_out(self.fade_out);
+        self.fading_out.push((position.0, player.radius, anim));
+    }
+
+    fn start_fade_in(instance: &mut Instance, entity: Entity, duration: Duration) {
+        if !duration.is_zero() {
+            let anim = LifecycleAnim::fade_in(duration);
+            let _ = instance.get_world_mut().insert_one(entity, anim);
+        }
+    }
+
+    fn advance_fades(&mut self, dt: Duration) {
+        let mut shown = Vec::new();
+        for (entity, anim) in self
+            .instance
+            .get_world_mut()
+            .query_mut::<&mut LifecycleAnim>()
         {
-            if *net_obj != position_sync.net_obj {
-                continue;
+            anim.advance(dt);
+            if anim.is_finished() {
+                shown.push(entity);
             }
+        }
+        for entity in shown {
+            let _ = self
+                .instance
+                .get_world_mut()
+                .remove_one::<LifecycleAnim>(entity);
+        }
 
-            if !last_sync_tracker.should_update(position_sync.tick) {
-                continue;
+        for (_, _, anim) in &mut self.fading_out {
+            anim.advance(dt);
+        }
+        self.fading_out.retain(|(_, _, anim)| !anim.is_finished());
+    }
+
+    fn spawn_one(&mut self, net_obj: NetworkObject, net_spawn: &NetworkSpawn, tick: Tick) {
+        if self.local_player.map(|x| x.0) == Some(net_obj) {
+            return;
+        }
+
+        // An entity coming back into view is spawned afresh.
+        if let Some(entity) = self.instance.find_network_object(net_obj) {
+            self.instance.despawn(entity);
+        }
+
+        if !self.entity_cap.admit(self.instance.network_object_count()) {
+            return;
+        }
+
+        match net_spawn {
+            NetworkSpawn::Player {
+                position,
+                name,
+                radius,
+                interpolate,
+            } => {
+                let entity = self.instance.spawn_player(
+                    false,
+                    (*position).into(),
+                    name.clone(),
+                    net_obj,
+                    *radius,
+                    Some(tick),
+                );
+                // Controlled entities are predicted rather than eased between syncs.
+                let interpolate = *interpolate && !self.controlled.contains_key(&net_obj);
+                self.instance.set_interpolate(entity, interpolate);
+                Self::start_fade_in(&mut self.instance, entity, self.fade_in);
+            }
+            NetworkSpawn::Entity {
+                kind,
+                position,
+                collider,
+            } => {
+                let entity = self.instance.spawn_entity(
+                    *kind,
+                    (*position).into(),
+                    *collider,
+                    net_obj,
+                    false,
+                );
+                Self::start_fade_in(&mut self.instance, entity, self.fade_in);
             }
+            _ => {}
+        }
+    }
+
+    fn sync_nonlocal(&mut self, position_sync: &PlayerPositionSync) {
+        let Some(entity) = self.instance.find_network_object(position_sync.net_obj) else {
+            return;
+        };
 
-            position.0 = Vec2::new(position_sync.position[0], position_sync.position[1]);
+        if self.local_player.map(|x| x.1) == Some(entity)
+            || self.controlled.contains_key(&position_sync.net_obj)
+        {
+            return;
         }
+
+        let Ok((last_sync_tracker, _)) = self
+            .instance
+            .get_world_mut()
+            .query_one_mut::<(&mut LastSyncTracker<Position>, &Player)>(entity)
+        else {
+            return;
+        };
+
+        if !last_sync_tracker.should_update(position_sync.tick) {
+            return;
+        }
+
+        self.instance.sync_remote_position(
+            entity,
+            position_sync.tick,
+            position_sync.get_position(),
+        );
     }
 
-    fn recv_position_sync(&mut self, backend: &mut BackendConnection, dt: Duration) {
+    /// `input_pending` is set when the newest buffered input has not been predicted yet, so
+    /// replays leave it for `predict_movement`.
+    fn recv_position_sync(
+        &mut self,
+        backend: &mut BackendConnection,
+        dt: Duration,
+        input_pending: bool,
+    ) {
+        for msg in backend.get_reliable_messages(self.instance.get_id()) {
+            if let ReliableMessageFromServer::ForcedPlayerSync(forced_sync) = msg {
+                self.recv_forced_sync(forced_sync, dt, input_pending);
+            }
+        }
+
         for msg in backend.get_unreliable_messages(self.instance.get_id()) {
             match msg {
-                UnreliableMessageFromServer::PlayerPositionSync(position_sync) => {
-                    self.sync_nonlocal(position_sync);
+                UnreliableMessageFromServer::PlayerPositionSyncs(position_syncs) => {
+                    // Every sync in the batch was sent at the same time.
+                    if let Some(sync) = position_syncs.first() {
+                        self.record_delay(sync.unix_millis);
+                    }
+                    for position_sync in position_syncs {
+                        self.sync_nonlocal(position_sync);
+                    }
                 }
                 UnreliableMessageFromServer::OwnedPlayerSync(owned_player_sync) => {
-                    let Some((player, (net_obj, last_sync_tracker))) = self
-                        .instance
-                        .get_world_mut()
-                        .query_mut::<(&NetworkObject, &mut LastSyncTracker<Position>)>()
-                        .with::<&LocalPlayer>()
-                        .into_iter()
-                        .next()
-                    else {
-                        continue;
-                    };
-
-                    if *net_obj != owned_player_sync.net_obj
-                        || !last_sync_tracker.should_update(owned_player_sync.tick)
-                    {
+                    self.record_delay(owned_player_sync.unix_millis);
+                    if self.controlled.contains_key(&owned_player_sync.net_obj) {
+                        self.sync_controlled(owned_player_sync, dt, input_pending, false);
                         continue;
                     }
 
-                    let mut inputs = self
-                        .input_buffer
-                        .get_after(owned_player_sync.last_input_order);
-                    inputs.pop();
-
-                    if inputs.is_empty() {
+                    let Some(player) = self.accept_owned_sync(owned_player_sync) else {
                         continue;
-                    }
-
-                    let snapshot = self.player_history.get_nth_latest(inputs.len());
-                    let should_reconcile = match snapshot {
-                        Some(snapshot) => snapshot.is_different(owned_player_sync),
-                        None => false,
                     };
 
-                    if !should_reconcile {
-                        continue;
-                    }
-
-                    self.instance.check_and_rollback(
+                    let correction = self.prediction.reconcile(
+                        &mut self.instance,
                         player,
                         owned_player_sync,
-                        dt.as_secs_f32(),
-                        inputs,
-                        |pos| {
-                            self.player_history.push(PlayerSnapshot { position: pos });
-                            self.player_history.prune(100);
-                        },
+                        dt,
+                        input_pending,
                     );
+                    if let Some(correction) = correction {
+                        self.reconciled(Reconciliation {
+                            correction,
+                            forced: false,
+                            tick: owned_player_sync.tick,
+                        });
+                    }
                 }
                 _ => {}
             }
         }
     }
 
-    fn predict_movement(&mut self, dt: Duration) {
-        let Some((_, local_player)) = self.local_player else {
-            warn!("No local player");
+    /// Snaps to the position in `forced_sync` without replaying buffered inputs, since the
+    /// instance never got some of them.
+    fn recv_forced_sync(
+        &mut self,
+        forced_sync: &OwnedPlayerSync,
+        dt: Duration,
+        input_pending: bool,
+    ) {
+        self.record_delay(forced_sync.unix_millis);
+        if self.controlled.contains_key(&forced_sync.net_obj) {
+            self.sync_controlled(forced_sync, dt, input_pending, true);
+            return;
+        }
+
+        let Some(player) = self.accept_owned_sync(forced_sync) else {
             return;
         };
 
-        let Some(input) = self.input_buffer.get_latest() else {
-            warn!("No latest input");
+        warn!("Instance missed our inputs, snapping to its position");
+        let correction = self.instance.check_and_rollback(
+            player,
+            forced_sync,
+            dt.as_secs_f32(),
+            Vec::new(),
+            |_| {},
+        );
+        if let Some(correction) = correction {
+            self.reconciled(Reconciliation {
+                correction,
+                forced: true,
+                tick: forced_sync.tick,
+            });
+        }
+    }
+
+    /// Records a reconciliation and starts smoothing out its correction.
+    fn reconciled(&mut self, reconciliation: Reconciliation) {
+        self.prediction_stats
+            .record(reconciliation.correction.norm(), reconciliation.forced);
+        self.correction_smoothing
+            .correct(reconciliation.correction, reconciliation.forced);
+        if let Some(hook) = &mut self.on_reconcile {
+            hook(&reconciliation);
+        }
+    }
+
+    /// Measures a sync's delay and widens or narrows interpolation to match the jitter.
+    /// Syncs from servers that don't timestamp them are skipped.
+    fn record_delay(&mut self, sent_unix_millis: Option<u128>) {
+        let Some(sent_unix_millis) = sent_unix_millis else {
             return;
         };
 
-        let Some(new_position) =
-            self.instance
-                .apply_input(local_player, &input.input, dt.as_secs_f32())
+        self.delay.record(sent_unix_millis, get_unix_millis());
+        let tick_rate = self.clock.get_announced_rate();
+        self.instance
+            .set_interpolation_delay(self.delay.interpolation_delay_ticks(tick_rate));
+    }
+
+    /// The local player entity, if `sync` is meant for it and newer than the last one applied.
+    fn accept_owned_sync(&mut self, sync: &OwnedPlayerSync) -> Option<Entity> {
+        let (player, (net_obj, last_sync_tracker)) = self
+            .instance
+            .get_world_mut()
+            .query_mut::<(&NetworkObject, &mut LastSyncTracker<Position>)>()
+            .with::<&LocalPlayer>()
+            .into_iter()
+            .next()?;
+
+        if *net_obj != sync.net_obj || !last_sync_tracker.should_update(sync.tick) {
+            return None;
+        }
+
+        self.server_position = Some(sync.get_position());
+        Some(player)
+    }
+
+    /// Reconciles a controlled entity with its owned sync, or snaps it there when `forced`.
+    fn sync_controlled(
+        &mut self,
+        sync: &OwnedPlayerSync,
+        dt: Duration,
+        input_pending: bool,
+        forced: bool,
+    ) {
+        let Some(entity) = self.instance.find_network_object(sync.net_obj) else {
+            return;
+        };
+        let Some(controlled) = self.controlled.get_mut(&sync.net_obj) else {
+            return;
+        };
+
+        let Ok(last_sync_tracker) = self
+            .instance
+            .get_world_mut()
+            .query_one_mut::<&mut LastSyncTracker<Position>>(entity)
         else {
-            warn!("No valid local player");
             return;
         };
+        if !last_sync_tracker.should_update(sync.tick) {
+            return;
+        }
 
-        self.player_history.push(PlayerSnapshot {
-            position: new_position,
-        });
-        self.player_history.prune(100);
+        if forced {
+            warn!(
+                "Instance missed our inputs for {:?}, snapping it",
+                sync.net_obj
+            );
+            self.instance
+                .check_and_rollback(entity, sync, dt.as_secs_f32(), Vec::new(), |_| {});
+        } else {
+            controlled
+                .prediction
+                .reconcile(&mut self.instance, entity, sync, dt, input_pending);
+        }
+    }
+
+    fn predict_movement(&mut self, dt: Duration) {
+        let Some((_, local_player)) = self.local_player else {
+            warn!("No local player");
+            return;
+        };
+
+        if self
+            .prediction
+            .predict(&mut self.instance, local_player, dt)
+            .is_none()
+        {
+            warn!("Couldn't predict the local player");
+        }
+
+        for (net_obj, controlled) in &mut self.controlled {
+            // Not in view yet, or despawned.
+            let Some(entity) = self.instance.find_network_object(*net_obj) else {
+                continue;
+            };
+            controlled
+                .prediction
+                .predict(&mut self.instance, entity, dt);
+        }
     }
 
     pub fn update(
@@ -229,11 +832,72 @@ impl InstanceData {
     ) -> Result<()> {
         let id = self.instance.get_id();
 
-        self.instance.update_tick();
+        if kb.is_just_pressed(glfw::Key::Space, None) {
+            self.queued_action = Some(PlayerAction::Dash);
+        }
+
+        self.recv_disconnect(backend);
+
+        self.recv_pause_state(backend);
+
+        self.recv_player_list(backend);
+
+        self.recv_control(backend);
+
+        // The client renders at its own rate, so step the simulation at the rate the instance
+        // was measured to tick at. Each tick still simulates the announced tick length, as the
+        // instance's do.
+        let tick_dt = tick_duration(self.clock.get_announced_rate());
+
+        // The tick stays frozen while paused; resuming brings a fresh tick sync. The states
+        // below still run, so a kick or a lost connection during a pause isn't missed.
+        let ticks = if self.paused {
+            0
+        } else {
+            self.correction_smoothing.advance(dt);
+            self.advance_fades(dt);
+
+            self.timestep.set_dt(self.clock.get_tick_duration());
+            let ticks = self.timestep.tick(dt);
+
+            for _ in 0..ticks {
+                self.instance.update_tick();
+            }
 
-        self.recv_tick_update(backend);
+            self.recv_tick_update(backend);
+
+            ticks
+        };
+
+        let was_connected = matches!(
+            self.state,
+            InstanceState::LoadRemote(_) | InstanceState::Done
+        );
 
         let next_state = match &mut self.state {
+            InstanceState::Disconnected => None,
+            // The backend opens a dropped connection again with the same client id, and the
+            // instance hands back the held player once asked for it as on joining.
+            _ if was_connected && backend.is_instance_reconnecting(id) => {
+                info!("Reconnecting to instance {id}");
+                self.forget_local_player();
+                Some(InstanceState::LocalLoaded)
+            }
+            _ if (was_connected && !backend.is_instance_connected(id))
+                || backend.get_disconnect_reason(id).is_some()
+                || self.disconnect_reason.is_some() =>
+            {
+                // A reason the server sent before closing the connection says more than the
+                // transport's.
+                let reason = self.disconnect_reason.get_or_insert_with(|| {
+                    backend
+                        .get_disconnect_reason(id)
+                        .cloned()
+                        .unwrap_or(DisconnectReason::DisconnectedByServer)
+                });
+                warn!("Lost connection to instance {id}: {reason}");
+                Some(InstanceState::Disconnected)
+            }
             InstanceState::Connecting => {
                 // start loading
 
@@ -243,7 +907,16 @@ impl InstanceData {
             }
             InstanceState::LocalLoaded => {
                 if backend.is_instance_connected(id) {
-                    backend.send_reliable_message(id, ReliableMessageFromClient::Connected)?;
+                    let message = if self.spectating {
+                        ReliableMessageFromClient::Spectate {
+                            protocol_version: PROTOCOL_VERSION,
+                        }
+                    } else {
+                        ReliableMessageFromClient::Connected {
+                            protocol_version: PROTOCOL_VERSION,
+                        }
+                    };
+                    backend.send_reliable_message(id, message)?;
                     info!("Instance {id} Connected.");
                     Some(InstanceState::LoadRemote(LoadRemoteState::default()))
                 } else {
@@ -255,26 +928,36 @@ impl InstanceData {
                     match msg {
                         ReliableMessageFromServer::PlayerInit(player_info) => {
                             info!("Got init");
-                            let entity = self.instance.spawn_player(
-                                true,
-                                player_info.position.into(),
-                                player_info.net_obj,
-                                Some(player_info.tick),
-                            );
-                            self.local_player = Some((player_info.net_obj, entity));
+                            self.local_player = Some(Self::spawn_local_player(
+                                &mut self.instance,
+                                player_info,
+                                self.fade_in_local_player.then_some(self.fade_in),
+                            ));
                             state.set_player_obj = true;
                         }
                         ReliableMessageFromServer::TickSync(tick_sync) => {
                             info!("Got tick sync");
-                            let tick = get_client_tick(tick_sync.tick, tick_sync.unix_millis);
-                            self.instance.set_tick(tick);
+                            sync_tick(&mut self.clock, &mut self.instance, tick_sync);
                             state.tick = true;
                         }
+                        ReliableMessageFromServer::ServerFull => {
+                            warn!("Instance {id} is full");
+                            self.disconnect_reason = Some(DisconnectReason::ServerFull);
+                        }
+                        ReliableMessageFromServer::VersionMismatch { server_version } => {
+                            warn!(
+                                "Instance {id} uses protocol version {server_version}, expected {PROTOCOL_VERSION}"
+                            );
+                            self.disconnect_reason = Some(DisconnectReason::VersionMismatch {
+                                client_version: PROTOCOL_VERSION,
+                                server_version: *server_version,
+                            });
+                        }
                         _ => {}
                     }
                 }
 
-                if state.all() {
+                if state.all(self.spectating) {
                     info!("Loaded Remote");
                     backend
                         .send_reliable_message(id, ReliableMessageFromClient::ReadyForUpdates)?;
@@ -285,30 +968,124 @@ impl InstanceData {
                 }
             }
             InstanceState::Done => {
-                self.read_input(backend, kb)?;
+                for msg in backend.get_reliable_messages(id) {
+                    if let ReliableMessageFromServer::PlayerInit(player_info) = msg {
+                        info!("Got init after loading");
+                        self.replace_local_player(player_info);
+                    }
+                }
 
                 self.spawn(backend)?;
 
-                self.recv_position_sync(backend, dt);
+                if ticks == 0 || self.local_player.is_none() {
+                    self.recv_position_sync(backend, tick_dt, false);
+                } else {
+                    for i in 0..ticks {
+                        self.read_input(backend, kb)?;
+
+                        if i == 0 {
+                            self.recv_position_sync(backend, tick_dt, true);
+                        }
 
-                self.predict_movement(dt);
+                        self.predict_movement(tick_dt);
+                    }
+                }
+
+                self.instance.interpolate_remote();
 
                 None
             }
         };
 
         if let Some(next_state) = next_state {
+            let old_status = self.get_status();
             self.state = next_state;
+
+            let status = self.get_status();
+            if status != old_status {
+                info!("Instance {id} is now {status:?}");
+            }
         }
 
-        self.instance.update(dt)?;
+        if !self.paused {
+            self.instance.update(dt)?;
+        }
 
         Ok(())
     }
 
+    /// Position, collider radius and alpha of every player, including the local player, so
+    /// they can be drawn at their physical size. Players that just spawned fade in and
+    /// despawned ones are kept around while they fade out, see [`LifecycleAnim`].
+    /// The local player is drawn where [`Self::get_current_player_position`] puts it.
+    pub fn get_player_bodies(&self) -> Vec<(Vec2, f32, f32)> {
+        let local = self.local_player.map(|(_, entity)| entity);
+        let offset = self.correction_smoothing.get_offset();
+        let mut query = self
+            .instance
+            .get_world()
+            .query::<(&Position, &Player, Option<&LifecycleAnim>)>();
+
+        query
+            .iter()
+            .map(|(entity, (position, player, anim))| {
+                let alpha = anim.map_or(1.0, LifecycleAnim::get_alpha);
+                if Some(entity) == local {
+                    (position.0 + offset, player.radius, alpha)
+                } else {
+                    (position.0, player.radius, alpha)
+                }
+            })
+            .chain(
+                self.fading_out
+                    .iter()
+                    .map(|(position, radius, anim)| (*position, *radius, anim.get_alpha())),
+            )
+            .collect()
+    }
+
+    /// Where the server last put each player, with its radius: the last owned sync for the
+    /// local player and the newest sync for everyone else. Players with no sync yet are left
+    /// out.
+    pub fn get_synced_bodies(&self) -> Vec<(Vec2, f32)> {
+        let local = self.local_player.map(|(_, entity)| entity);
+        let mut query = self.instance.get_world().query::<(
+            &Position,
+            &Player,
+            Option<&Interpolate>,
+            Option<&InterpolationBuffer>,
+        )>();
+
+        query
+            .iter()
+            .filter_map(|(entity, (position, player, interpolate, buffer))| {
+                let synced = match buffer {
+                    _ if Some(entity) == local => self.server_position,
+                    Some(buffer) if interpolate.is_none_or(|interpolate| interpolate.0) => {
+                        buffer.get_latest()
+                    }
+                    _ => Some(position.0),
+                };
+                Some((synced?, player.radius))
+            })
+            .collect()
+    }
+
+    /// Where the local player is drawn and the camera follows: the predicted position with
+    /// any reconcile snap still being smoothed out.
     pub fn get_current_player_position(&mut self) -> Option<Vec2> {
+        let position = self.get_predicted_player_position()?;
+        Some(position + self.correction_smoothing.get_offset())
+    }
+
+    /// Where prediction has put the local player, snaps included.
+    pub fn get_predicted_player_position(&self) -> Option<Vec2> {
         let (_, current_player) = self.local_player?;
-        let position = self.instance.get_world_mut().query_one_mut::<&Position>(current_player).ok()?;
+        let position = self
+            .instance
+            .get_world()
+            .get::<&Position>(current_player)
+            .ok()?;
         Some(position.0)
     }
 }
@@ -320,8 +1097,9 @@ struct LoadRemoteState {
 }
 
 impl LoadRemoteState {
-    fn all(&self) -> bool {
-        self.set_player_obj && self.tick
+    /// Spectators are never sent a player, so they only wait for the tick.
+    fn all(&self, spectating: bool) -> bool {
+        (spectating || self.set_player_obj) && self.tick
     }
 }
 
@@ -331,8 +1109,23 @@ enum InstanceState {
     LocalLoaded,
     LoadRemote(LoadRemoteState),
     Done,
+    Disconnected,
+}
+
+/// Where the client is in joining an instance, for a loading screen or connection indicator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InstanceStatus {
+    /// Loading local data and waiting for the connection to the instance.
+    Connecting,
+    /// Connected and waiting for the instance to send the local player and tick.
+    Loading,
+    Active,
+    /// The connection was lost or could not be established; see
+    /// [`InstanceData::get_disconnect_reason`].
+    Disconnected,
 }
 
+#[derive(Debug)]
 struct Buffer<T> {
     inner: VecDeque<T>,
 }
@@ -373,27 +1166,97 @@ impl<T> Buffer<T> {
     }
 }
 
-#[derive(Default)]
-struct InputBuffer {
+/// Least inputs kept for replaying on reconcile. Longer round trips keep twice the inputs
+/// in flight instead, see [`InputBuffer::get_capacity`].
+pub const INPUT_BUFFER_LENGTH: usize = 100;
+
+/// Inputs sent to the server, kept until they can no longer be needed for reconciliation.
+#[derive(Debug)]
+pub struct InputBuffer {
     buffer: Buffer<OrderedInput>,
     count: u64,
+    min_length: usize,
+    /// Newest input the server has applied.
+    acked: u64,
+    /// Inputs sent but not yet applied as of the last ack: the round trip, counted in inputs.
+    in_flight: usize,
+    dropped: u64,
+}
+
+impl Default for InputBuffer {
+    fn default() -> Self {
+        InputBuffer::new(INPUT_BUFFER_LENGTH)
+    }
 }
 
 impl InputBuffer {
-    fn push_input(&mut self, input: PlayerInput) -> u64 {
+    pub fn new(min_length: usize) -> InputBuffer {
+        InputBuffer {
+            buffer: Buffer::default(),
+            count: 0,
+            min_length,
+            acked: 0,
+            in_flight: 0,
+            dropped: 0,
+        }
+    }
+
+    pub fn get_min_length(&self) -> usize {
+        self.min_length
+    }
+
+    pub fn set_min_length(&mut self, min_length: usize) {
+        self.min_length = min_length;
+    }
+
+    /// Inputs kept before the oldest are dropped: the minimum length, or twice the inputs in
+    /// flight when that is more, so jitter on a long round trip doesn't cost inputs.
+    pub fn get_capacity(&self) -> usize {
+        self.min_length.max(2 * self.in_flight)
+    }
+
+    /// Inputs dropped before the server applied them. Any of these means a reconcile may
+    /// have been skipped.
+    pub fn get_dropped_count(&self) -> u64 {
+        self.dropped
+    }
+
+    /// Adds `input`, returning its order.
+    pub fn push_input(&mut self, input: PlayerInput) -> u64 {
         self.count += 1;
         self.buffer.push(OrderedInput {
             input,
             order: self.count,
+            target: None,
         });
+
+        let mut dropped = 0;
+        while self.buffer.inner.len() > self.get_capacity() {
+            match self.buffer.inner.pop_front() {
+                Some(input) if input.order > self.acked => dropped += 1,
+                _ => {}
+            }
+        }
+        if dropped > 0 {
+            warn!("Input buffer overflowed, dropped {dropped} inputs the server hasn't applied");
+            self.dropped += dropped;
+        }
+
         self.count
     }
 
-    fn get_latest(&self) -> Option<&OrderedInput> {
+    /// Marks every input up to `order` as applied by the server.
+    pub fn ack(&mut self, order: u64) {
+        self.acked = self.acked.max(order);
+        self.in_flight = self.count.saturating_sub(self.acked) as usize;
+    }
+
+    pub fn get_latest(&self) -> Option<&OrderedInput> {
         self.buffer.get_latest()
     }
 
-    fn get_after(&self, order: u64) -> Vec<OrderedInput> {
+    /// Every input kept that is newer than `order`, oldest first.
+    pub fn get_after(&self, order: u64) -> Vec<OrderedInput> {
         self.buffer
             .iter()
             .filter(|input| input.order > order)
@@ -402,8 +1265,340 @@ impl InputBuffer {
     }
 }
 
+/// One reconciliation of the local player against the server.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Reconciliation {
+    /// How far the player was moved, from the predicted position to the corrected one.
+    pub correction: Vec2,
+    /// Whether the server made the client snap after missing its inputs.
+    pub forced: bool,
+    /// Tick of the sync that was reconciled against.
+    pub tick: Tick,
+}
+
+/// Called with every [`Reconciliation`], see [`InstanceData::set_on_reconcile`].
+pub type ReconcileHook = Box<dyn FnMut(&Reconciliation)>;
+
+/// Reconciliations of the local player against the server, for tuning prediction.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct PredictionStats {
+    reconcile_count: u64,
+    /// Reconciliations forced by the server after it missed inputs, counted in
+    /// `reconcile_count` as well.
+    forced_count: u64,
+    total_correction: f32,
+    max_correction: f32,
+}
+
+impl PredictionStats {
+    /// Counts one reconciliation that moved the player `distance` world units.
+    pub fn record(&mut self, distance: f32, forced: bool) {
+        self.reconcile_count += 1;
+        if forced {
+            self.forced_count += 1;
+        }
+        self.total_correction += distance;
+        self.max_correction = self.max_correction.max(distance);
+    }
+
+    pub fn get_reconcile_count(&self) -> u64 {
+        self.reconcile_count
+    }
+
+    pub fn get_forced_count(&self) -> u64 {
+        self.forced_count
+    }
+
+    pub fn get_mean_correction(&self) -> f32 {
+        if self.reconcile_count == 0 {
+            return 0.0;
+        }
+
+        self.total_correction / self.reconcile_count as f32
+    }
+
+    pub fn get_max_correction(&self) -> f32 {
+        self.max_correction
+    }
+}
+
+impl std::fmt::Display for PredictionStats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} ({} forced), mean {:.1}, max {:.1}",
+            self.reconcile_count,
+            self.forced_count,
+            self.get_mean_correction(),
+            self.max_correction
+        )
+    }
+}
+
+/// Default most networked entities a client keeps, far past any crowd a server should send.
+pub const MAX_NETWORKED_ENTITIES: usize = 10_000;
+
+/// Turns spawns away past a limit, so a buggy or hostile server can't grow the client's
+/// world without bound.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntityCap {
+    max: usize,
+    rejected: u64,
+    /// Spawns turned away since the last one let in, so a flood warns once rather than
+    /// once per spawn.
+    rejected_in_a_row: u64,
+}
+
+impl Default for EntityCap {
+    fn default() -> Self {
+        EntityCap::new(MAX_NETWORKED_ENTITIES)
+    }
+}
+
+impl EntityCap {
+    pub fn new(max: usize) -> EntityCap {
+        EntityCap {
+            max,
+            rejected: 0,
+            rejected_in_a_row: 0,
+        }
+    }
+
+    pub fn get_max(&self) -> usize {
+        self.max
+    }
+
+    pub fn set_max(&mut self, max: usize) {
+        self.max = max;
+    }
+
+    /// Spawns turned away so far.
+    pub fn get_rejected_count(&self) -> u64 {
+        self.rejected
+    }
+
+    /// Whether one more entity may be spawned next to `count` existing ones.
+    pub fn admit(&mut self, count: usize) -> bool {
+        if count < self.max {
+            if self.rejected_in_a_row > 0 {
+                warn!(
+                    "Ignored {} spawns past the entity cap of {}",
+                    self.rejected_in_a_row, self.max
+                );
+                self.rejected_in_a_row = 0;
+            }
+            return true;
+        }
+
+        if self.rejected_in_a_row == 0 {
+            warn!(
+                "Reached the entity cap of {}, ignoring spawns from the server",
+                self.max
+            );
+        }
+        self.rejected += 1;
+        self.rejected_in_a_row += 1;
+        false
+    }
+}
+
+/// Default time constant reconcile snaps fade out over, see [`CorrectionSmoothing`].
+pub const CORRECTION_SMOOTHING: Duration = Duration::from_millis(100);
+
+/// Offsets below this are dropped rather than decayed forever.
+const SMOOTHING_EPSILON: f32 = 0.01;
+
+/// Hides reconcile snaps from the drawn local player by keeping an offset back to where it
+/// was drawn before the snap, which decays exponentially to nothing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorrectionSmoothing {
+    time: Option<Duration>,
+    offset: Vec2,
+}
+
+impl Default for CorrectionSmoothing {
+    fn default() -> Self {
+        CorrectionSmoothing::new(Some(CORRECTION_SMOOTHING))
+    }
+}
+
+impl CorrectionSmoothing {
+    /// Smooths over `time`, or not at all with `None`.
+    pub fn new(time: Option<Duration>) -> CorrectionSmoothing {
+        CorrectionSmoothing {
+            time,
+            offset: Vec2::zeros(),
+        }
+    }
+
+    pub fn get_time(&self) -> Option<Duration> {
+        self.time
+    }
+
+    /// Turning smoothing off drops whatever offset is left.
+    pub fn set_time(&mut self, time: Option<Duration>) {
+        self.time = time;
+        if time.is_none() {
+            self.offset = Vec2::zeros();
+        }
+    }
+
+    /// Offset from the predicted position to where the player should be drawn.
+    pub fn get_offset(&self) -> Vec2 {
+        self.offset
+    }
+
+    /// Takes in a reconciliation that moved the player by `correction`. Forced ones come after
+    /// the server missed inputs, so they are shown as they happen instead of smoothed.
+    pub fn correct(&mut self, correction: Vec2, forced: bool) {
+        if forced || self.time.is_none() {
+            self.offset = Vec2::zeros();
+        } else {
+            self.offset -= correction;
+        }
+    }
+
+    pub fn advance(&mut self, dt: Duration) {
+        let Some(time) = self.time.filter(|time| !time.is_zero()) else {
+            self.offset = Vec2::zeros();
+            return;
+        };
+
+        self.offset *= (-dt.as_secs_f32() / time.as_secs_f32()).exp();
+        if self.offset.norm() < SMOOTHING_EPSILON {
+            self.offset = Vec2::zeros();
+        }
+    }
+}
+
+/// Default time spawned entities take to fade in, see [`LifecycleAnim`].
+pub const FADE_IN: Duration = Duration::from_millis(200);
+
+/// Default time despawned players take to fade out, see [`LifecycleAnim`].
+pub const FADE_OUT: Duration = Duration::from_millis(200);
+
+/// Fades an entity's alpha in as it appears or out as it disappears, for the colour it is
+/// drawn with. Spawned entities carry one as a component until they are fully shown.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LifecycleAnim {
+    fading_out: bool,
+    elapsed: Duration,
+    duration: Duration,
+}
+
+impl LifecycleAnim {
+    pub fn fade_in(duration: Duration) -> LifecycleAnim {
+        LifecycleAnim {
+            fading_out: false,
+            elapsed: Duration::ZERO,
+            duration,
+        }
+    }
+
+    pub fn fade_out(duration: Duration) -> LifecycleAnim {
+        LifecycleAnim {
+            fading_out: true,
+            ..LifecycleAnim::fade_in(duration)
+        }
+    }
+
+    pub fn is_fading_out(&self) -> bool {
+        self.fading_out
+    }
+
+    pub fn advance(&mut self, dt: Duration) {
+        self.elapsed = (self.elapsed + dt).min(self.duration);
+    }
+
+    /// Fully shown after a fade in, or gone after a fade out.
+    pub fn is_finished(&self) -> bool {
+        self.elapsed >= self.duration
+    }
+
+    pub fn get_alpha(&self) -> f32 {
+        let progress = if self.duration.is_zero() {
+            1.0
+        } else {
+            self.elapsed.as_secs_f32() / self.duration.as_secs_f32()
+        };
+
+        if self.fading_out {
+            1.0 - progress
+        } else {
+            progress
+        }
+    }
+}
+
 type SnapshotHistory = Buffer<PlayerSnapshot>;
 
+/// Inputs sent for one entity this client drives and where they were predicted to put it.
+#[derive(Debug, Default)]
+struct Prediction {
+    input_buffer: InputBuffer,
+    history: SnapshotHistory,
+}
+
+impl Prediction {
+    /// Snapshots kept for reconciling, one more than the inputs that can be replayed.
+    fn get_history_length(&self) -> usize {
+        self.input_buffer.get_capacity() + 1
+    }
+
+    /// Moves `entity` by the newest input, returning where it ends up.
+    fn predict(&mut self, instance: &mut Instance, entity: Entity, dt: Duration) -> Option<Vec2> {
+        let input = self.input_buffer.get_latest()?;
+        let position = instance.apply_input(entity, input, dt.as_secs_f32())?;
+
+        self.history.push(PlayerSnapshot { position });
+        self.history.prune(self.get_history_length());
+        Some(position)
+    }
+
+    /// Replays the inputs the server hasn't applied on top of `sync`, if `entity` wasn't
+    /// predicted where the server put it. Returns how far the entity was moved.
+    ///
+    /// `input_pending` is set when the newest buffered input has not been predicted yet.
+    fn reconcile(
+        &mut self,
+        instance: &mut Instance,
+        entity: Entity,
+        sync: &OwnedPlayerSync,
+        dt: Duration,
+        input_pending: bool,
+    ) -> Option<Vec2> {
+        self.input_buffer.ack(sync.last_input_order);
+
+        let mut inputs = self.input_buffer.get_after(sync.last_input_order);
+        if input_pending {
+            inputs.pop();
+        }
+
+        if inputs.is_empty() {
+            return None;
+        }
+
+        let snapshot = self.history.get_nth_latest(inputs.len())?;
+        if !snapshot.is_different(sync) {
+            return None;
+        }
+
+        let history_length = self.get_history_length();
+        instance.check_and_rollback(entity, sync, dt.as_secs_f32(), inputs, |position| {
+            self.history.push(PlayerSnapshot { position });
+            self.history.prune(history_length);
+        })
+    }
+}
+
+/// An entity besides the local player that the server let this client drive.
+#[derive(Debug)]
+struct Controlled {
+    /// Sent every tick, see [`InstanceData::set_controlled_input`].
+    input: PlayerInput,
+    prediction: Prediction,
+}
+
 #[derive(Debug, Clone)]
 struct PlayerSnapshot {
     position: Vec2,
@@ -411,6 +1606,6 @@ struct PlayerSnapshot {
 
 impl PlayerSnapshot {
     fn is_different(&self, owned_player_sync: &OwnedPlayerSync) -> bool {
-        Vec2::from(owned_player_sync.position).metric_distance(&self.position) > 0.1
+        owned_player_sync.get_position().metric_distance(&self.position) > 0.1
     }
 }
@@ -1,24 +1,58 @@
 use std::sync::Arc;
 
-use backend::BackendConnection;
-use common::{Result, game::character::CharacterKind};
+use backend::{BackendConnection, lag::NetworkConditions};
+use common::{Error, Result, game::character::CharacterKind};
 use game::Game;
 use input::KeyboardState;
 use tracing::{Level, info, span};
 
 pub mod backend;
+pub mod bot;
 pub mod game;
 pub mod graphics;
 pub mod input;
 pub mod instance;
+pub mod overlay;
 
-pub fn run() -> Result<()> {
+/// Window size used unless another is asked for, in screen coordinates.
+pub const DEFAULT_WINDOW_SIZE: (u32, u32) = (1920, 1080);
+
+/// Parses a window size written as `WIDTHxHEIGHT`, e.g. `1280x720`.
+pub fn parse_window_size(size: &str) -> Result<(u32, u32)> {
+    let parse = |side: &str| match side.trim().parse::<u32>() {
+        Ok(side) if side > 0 => Ok(side),
+        _ => Err(Error::InvalidWindowSize),
+    };
+
+    let (width, height) = size.split_once('x').ok_or(Error::InvalidWindowSize)?;
+
+    Ok((parse(width)?, parse(height)?))
+}
+
+/// Runs the client, logging in as `character_name`.
+///
+/// With `share` the first local client spawns the home instance and any further sharing
+/// client started while it runs joins that same instance, which is how two players are
+/// tested locally; otherwise every client gets its own. With `spectate` the client joins
+/// without a player until F4 is pressed. `window_size` is in
+/// screen coordinates and is scaled up on HiDPI monitors. `conditions` fakes a bad
+/// connection to the instance.
+pub fn run(
+    character_name: &str,
+    spectate: bool,
+    share: bool,
+    window_size: (u32, u32),
+    conditions: NetworkConditions,
+) -> Result<()> {
     let span = span!(Level::INFO, "client");
     let _enter = span.enter();
 
-    let mut backend = BackendConnection::local();
+    let mut backend = BackendConnection::local_with_conditions(conditions);
+    if share {
+        backend.share_home(backend::local::SHARED_HOME_PATH);
+    }
 
-    let character = backend.create_character("testington", CharacterKind::SoloAccount)?;
+    let character = backend.create_character(character_name, CharacterKind::SoloAccount)?;
 
     let instance_id = backend.enter_game(character.character_id)?;
 
@@ -27,14 +61,23 @@ pub fn run() -> Result<()> {
     let mut glfw = glfw::init(glfw::fail_on_errors).unwrap();
 
     glfw.window_hint(glfw::WindowHint::ClientApi(glfw::ClientApiHint::NoApi));
+    glfw.window_hint(glfw::WindowHint::ScaleToMonitor(true));
 
     let (window, events) = glfw.with_primary_monitor(|glfw, monitor| {
+        let title = format!("Dreamer's Keys - {character_name}");
         let (mut window, events) = glfw
-            .create_window(1920, 1080, "Dreamer's Keys", glfw::WindowMode::Windowed)
+            .create_window(
+                window_size.0,
+                window_size.1,
+                &title,
+                glfw::WindowMode::Windowed,
+            )
             .unwrap();
 
         window.set_key_polling(true);
         window.set_framebuffer_size_polling(true);
+        window.set_focus_polling(true);
+        window.set_content_scale_polling(true);
 
         if let Some(monitor) = monitor {
             let (mx, my, mw, mh) = monitor.get_workarea();
@@ -45,7 +88,7 @@ pub fn run() -> Result<()> {
         (Arc::new(window), events)
     });
 
-    let mut game = Game::new(backend, window.clone(), instance_id)?;
+    let mut game = Game::new(backend, window.clone(), instance_id, spectate)?;
 
     game.run(glfw, window, events)?;
 
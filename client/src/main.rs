@@ -1,14 +1,67 @@
-use tracing::info;
+use std::time::Duration;
+
+use common::ResultExt as _;
+use tracing::{info, warn};
 
 fn main() -> common::Result<()> {
     tracing_subscriber::fmt::init();
 
     let addr = format!("0.0.0.0:{}", puffin_http::DEFAULT_PORT);
-    let _server = puffin_http::Server::new(&addr).unwrap();
-    info!("Puffin profiling running.");
-    puffin::set_scopes_on(true);
+    // A second local client can't bind the same port; it just runs without profiling.
+    let _server = match puffin_http::Server::new(&addr) {
+        Ok(server) => {
+            info!("Puffin profiling running.");
+            puffin::set_scopes_on(true);
+            Some(server)
+        }
+        Err(err) => {
+            warn!("Puffin profiling disabled: {err}");
+            None
+        }
+    };
+
+    // Usage: client [character name] [--spectate] [--share] [--size WIDTHxHEIGHT]
+    //               [--bots COUNT] [--latency MILLIS] [--jitter MILLIS] [--loss PERCENT]
+    let mut spectate = false;
+    let mut share = false;
+    let mut conditions = client::backend::lag::NetworkConditions::default();
+    let mut bots = None;
+    let mut window_size = client::DEFAULT_WINDOW_SIZE;
+    let mut character_name = None;
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == "--spectate" {
+            spectate = true;
+        } else if arg == "--share" {
+            share = true;
+        } else if arg == "--bots" {
+            let count = args.next().unwrap_or_default();
+            bots = Some(count.parse().context("Invalid Bot Count")?);
+        } else if arg == "--latency" {
+            let millis = args.next().unwrap_or_default();
+            conditions.latency = Duration::from_millis(millis.parse().context("Invalid Latency")?);
+        } else if arg == "--jitter" {
+            let millis = args.next().unwrap_or_default();
+            conditions.jitter = Duration::from_millis(millis.parse().context("Invalid Jitter")?);
+        } else if arg == "--loss" {
+            let percent = args.next().unwrap_or_default();
+            let percent: u32 = percent.parse().context("Invalid Loss")?;
+            conditions.loss = percent.min(100) as f32 / 100.0;
+        } else if arg == "--size" {
+            let size = args.next().ok_or(common::Error::InvalidWindowSize)?;
+            window_size = client::parse_window_size(&size)?;
+        } else {
+            character_name.get_or_insert(arg);
+        }
+    }
+    let character_name = character_name.unwrap_or_else(|| "testington".to_string());
+
+    let result = match bots {
+        Some(count) => client::bot::run_bot(count),
+        None => client::run(&character_name, spectate, share, window_size, conditions),
+    };
 
-    if let Err(err) = client::run() {
+    if let Err(err) = result {
         tracing::error!("Crashed due to error: {err}");
         Err(err)
     } else {
@@ -0,0 +1,121 @@
+use std::{
+    collections::VecDeque,
+    time::{Duration, Instant},
+};
+
+use common::tick::Tick;
+use tracing::info;
+
+use crate::instance::PredictionStats;
+
+/// Frames the displayed frame time is averaged over.
+const FRAME_WINDOW: usize = 60;
+
+/// How often the overlay is written out while visible.
+const REPORT_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Frame timing and instance diagnostics, toggled with F3.
+///
+/// There is no text renderer yet, so while visible the overlay is written to the log instead
+/// of being drawn over the world.
+#[derive(Debug)]
+pub struct DebugOverlay {
+    visible: bool,
+    frame_times: VecDeque<Duration>,
+    last_report: Instant,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        DebugOverlay::new()
+    }
+}
+
+impl DebugOverlay {
+    pub fn new() -> DebugOverlay {
+        DebugOverlay {
+            visible: false,
+            frame_times: VecDeque::with_capacity(FRAME_WINDOW),
+            last_report: Instant::now(),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub fn record_frame(&mut self, frame_time: Duration) {
+        if self.frame_times.len() == FRAME_WINDOW {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(frame_time);
+    }
+
+    /// Average time between the last [`FRAME_WINDOW`] frames.
+    pub fn get_frame_time(&self) -> Duration {
+        if self.frame_times.is_empty() {
+            return Duration::ZERO;
+        }
+
+        self.frame_times.iter().sum::<Duration>() / self.frame_times.len() as u32
+    }
+
+    pub fn get_fps(&self) -> f32 {
+        let frame_time = self.get_frame_time().as_secs_f32();
+
+        if frame_time > 0.0 {
+            1.0 / frame_time
+        } else {
+            0.0
+        }
+    }
+
+    pub fn get_lines(
+        &self,
+        tick: Option<Tick>,
+        player_count: usize,
+        prediction: Option<&PredictionStats>,
+    ) -> Vec<String> {
+        let tick = match tick {
+            Some(tick) => tick.get().to_string(),
+            None => "-".to_string(),
+        };
+
+        let corrections = match prediction {
+            Some(prediction) => prediction.to_string(),
+            None => "-".to_string(),
+        };
+
+        vec![
+            format!("FPS: {:.0}", self.get_fps()),
+            format!(
+                "Frame time: {:.2} ms",
+                self.get_frame_time().as_secs_f64() * 1000.0
+            ),
+            format!("Tick: {tick}"),
+            format!("Players: {player_count}"),
+            format!("Corrections: {corrections}"),
+        ]
+    }
+
+    pub fn report(
+        &mut self,
+        tick: Option<Tick>,
+        player_count: usize,
+        prediction: Option<&PredictionStats>,
+    ) {
+        if !self.visible || self.last_report.elapsed() < REPORT_INTERVAL {
+            return;
+        }
+
+        self.last_report = Instant::now();
+        info!(
+            "{}",
+            self.get_lines(tick, player_count, prediction).join(" | ")
+        );
+    }
+}
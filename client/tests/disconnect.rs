@@ -0,0 +1,49 @@
+use client::backend::DisconnectReason;
+use renet_netcode::{NetcodeDisconnectReason, NetcodeError, NetcodeTransportError};
+
+#[test]
+fn netcode_timeouts_map_to_timed_out() {
+    for reason in [
+        NetcodeDisconnectReason::ConnectTokenExpired,
+        NetcodeDisconnectReason::ConnectionTimedOut,
+        NetcodeDisconnectReason::ConnectionResponseTimedOut,
+        NetcodeDisconnectReason::ConnectionRequestTimedOut,
+    ] {
+        assert_eq!(DisconnectReason::from(reason), DisconnectReason::TimedOut);
+    }
+}
+
+#[test]
+fn denied_connection_maps_to_server_full() {
+    let err = NetcodeTransportError::Netcode(NetcodeError::Disconnected(
+        NetcodeDisconnectReason::ConnectionDenied,
+    ));
+
+    assert_eq!(DisconnectReason::from(&err), DisconnectReason::ServerFull);
+}
+
+#[test]
+fn other_transport_errors_keep_their_message() {
+    let err = NetcodeTransportError::IO(std::io::Error::other("socket closed"));
+
+    assert!(matches!(
+        DisconnectReason::from(&err),
+        DisconnectReason::Transport(message) if message.contains("socket closed")
+    ));
+}
+
+#[test]
+fn only_lost_connections_are_transient() {
+    assert!(DisconnectReason::TimedOut.is_transient());
+    assert!(DisconnectReason::Transport("socket closed".to_string()).is_transient());
+
+    for reason in [
+        DisconnectReason::ServerFull,
+        DisconnectReason::Kicked("spam".to_string()),
+        DisconnectReason::DisconnectedByServer,
+        DisconnectReason::ServerClosed,
+        DisconnectReason::DisconnectedByClient,
+    ] {
+        assert!(!reason.is_transient(), "{reason:?} was retried");
+    }
+}
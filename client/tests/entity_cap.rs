@@ -0,0 +1,70 @@
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+
+use client::instance::EntityCap;
+use common::{Vec2, instance::Instance};
+use uuid::Uuid;
+
+/// Log lines written while the test's subscriber is active.
+#[derive(Clone, Default)]
+struct Log(Arc<Mutex<Vec<u8>>>);
+
+impl Write for Log {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Log {
+    fn lines(&self) -> Vec<String> {
+        String::from_utf8(self.0.lock().unwrap().clone())
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+#[test]
+fn spawn_flood_stops_at_the_cap_and_warns_once() {
+    let log = Log::default();
+    let subscriber = tracing_subscriber::fmt()
+        .with_writer({
+            let log = log.clone();
+            move || log.clone()
+        })
+        .with_ansi(false)
+        .finish();
+
+    let mut instance = Instance::new(Uuid::now_v7());
+    let mut cap = EntityCap::new(100);
+
+    tracing::subscriber::with_default(subscriber, || {
+        for i in 0..100_000 {
+            if cap.admit(instance.network_object_count()) {
+                let net_obj = instance.new_network_object();
+                instance.spawn_player(false, Vec2::zeros(), i.to_string(), net_obj, 10.0, None);
+            }
+        }
+
+        // Room again once something leaves.
+        let (net_obj, _) = instance.iter_players().next().unwrap();
+        instance.despawn(instance.find_network_object(net_obj).unwrap());
+        assert!(cap.admit(instance.network_object_count()));
+    });
+
+    assert_eq!(instance.network_object_count(), 99);
+    assert_eq!(cap.get_rejected_count(), 99_900);
+
+    let lines = log.lines();
+    assert_eq!(lines.len(), 2, "{lines:#?}");
+    assert!(lines[0].contains("Reached the entity cap of 100"));
+    assert!(lines[1].contains("Ignored 99900 spawns past the entity cap of 100"));
+}
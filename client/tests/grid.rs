@@ -0,0 +1,59 @@
+use client::graphics::grid::{Grid, MAX_GRID_LINES};
+use common::{Rect, Vec2};
+
+fn view(min: (f32, f32), max: (f32, f32)) -> Rect {
+    Rect::new(Vec2::new(min.0, min.1), Vec2::new(max.0, max.1))
+}
+
+#[test]
+fn lines_are_world_aligned_and_cover_the_view() {
+    let grid = Grid {
+        spacing: 100.0,
+        ..Grid::default()
+    };
+
+    let lines = grid.lines(view((-150.0, -50.0), (250.0, 50.0)));
+
+    let vertical: Vec<f32> = lines
+        .iter()
+        .filter(|line| line.height() > line.width())
+        .map(|line| (line.min.x + line.max.x) * 0.5)
+        .collect();
+    assert_eq!(vertical, vec![-200.0, -100.0, 0.0, 100.0, 200.0]);
+
+    let horizontal: Vec<f32> = lines
+        .iter()
+        .filter(|line| line.width() > line.height())
+        .map(|line| (line.min.y + line.max.y) * 0.5)
+        .collect();
+    assert_eq!(horizontal, vec![-100.0, 0.0]);
+}
+
+#[test]
+fn lines_scroll_with_the_camera() {
+    let grid = Grid::default();
+
+    let here = grid.lines(view((0.0, 0.0), (400.0, 300.0)));
+    let moved = grid.lines(view((30.0, 0.0), (430.0, 300.0)));
+
+    // The lines stay put in the world, so moving the view only adds or drops lines at the
+    // edges.
+    assert!(
+        moved
+            .iter()
+            .filter(|line| line.height() > line.width())
+            .all(|line| here.iter().any(|other| other.min.x == line.min.x) || line.min.x > 400.0)
+    );
+}
+
+#[test]
+fn tiny_spacing_is_coarsened() {
+    let grid = Grid {
+        spacing: 0.5,
+        ..Grid::default()
+    };
+
+    let lines = grid.lines(view((0.0, 0.0), (1920.0, 1080.0)));
+
+    assert!(lines.len() <= 2 * (MAX_GRID_LINES + 1));
+}
@@ -0,0 +1,70 @@
+use std::time::Duration;
+
+use client::{backend::BackendConnection, game::Game, instance::InstanceStatus};
+use common::{DT, Result, game::character::CharacterKind, instance::PLAYER_RADIUS};
+
+#[test]
+fn headless_game_joins_without_a_window() -> Result<()> {
+    // The backend spawns the instance binary relative to the workspace root.
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/.."))?;
+
+    let mut backend = BackendConnection::local();
+    let character = backend.create_character("headless", CharacterKind::SoloAccount)?;
+    let instance_id = backend.enter_game(character.character_id)?;
+
+    let mut game = Game::headless(backend, instance_id, false);
+
+    let mut steps = 0;
+    while game.get_current_instance_status() != Some(InstanceStatus::Active) {
+        assert!(steps < 600, "never became active");
+        game.update(DT)?;
+        std::thread::sleep(Duration::from_millis(5));
+        steps += 1;
+    }
+
+    game.into_backend().shutdown()
+}
+
+#[test]
+fn synced_positions_are_shown_on_f5() -> Result<()> {
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/.."))?;
+
+    let mut backend = BackendConnection::local();
+    let character = backend.create_character("outlined", CharacterKind::SoloAccount)?;
+    let instance_id = backend.enter_game(character.character_id)?;
+
+    let mut game = Game::headless(backend, instance_id, false);
+    assert!(!game.is_showing_synced());
+
+    game.get_keyboard_state_mut()
+        .press(glfw::Key::F5, glfw::Modifiers::empty());
+    game.update(DT)?;
+    game.get_keyboard_state_mut()
+        .release(glfw::Key::F5, glfw::Modifiers::empty());
+    assert!(game.is_showing_synced());
+
+    // The player's outline appears once the first owned sync arrives.
+    let mut steps = 0;
+    while game
+        .get_current_instance()
+        .is_none_or(|instance| instance.get_synced_bodies().is_empty())
+    {
+        assert!(steps < 600, "never synced");
+        game.update(DT)?;
+        std::thread::sleep(Duration::from_millis(5));
+        steps += 1;
+    }
+
+    let instance = game.get_current_instance().unwrap();
+    assert_eq!(
+        instance.get_synced_bodies(),
+        vec![(instance.get_server_position().unwrap(), PLAYER_RADIUS)]
+    );
+
+    game.get_keyboard_state_mut()
+        .press(glfw::Key::F5, glfw::Modifiers::empty());
+    game.update(DT)?;
+    assert!(!game.is_showing_synced());
+
+    game.into_backend().shutdown()
+}
@@ -0,0 +1,83 @@
+use client::backend::local::LocalBackend;
+use common::{Result, game::character::CharacterKind};
+
+#[test]
+fn entering_twice_reuses_the_home_instance() -> Result<()> {
+    // The backend spawns the instance binary relative to the workspace root.
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/.."))?;
+
+    let path = std::env::temp_dir().join(format!("local-home-twice-{}", std::process::id()));
+
+    // Shared, so the second entry also has the home file to find, not only the backend's own
+    // record of the home.
+    let mut backend = LocalBackend::new();
+    backend.share_home(&path);
+    let character = backend.create_character("homebody", CharacterKind::SoloAccount)?;
+
+    let first = backend.enter_game(character.character_id)?;
+    let second = backend.enter_game(character.character_id)?;
+
+    assert_eq!(first, second);
+    assert_eq!(backend.get_spawned_instance_count(), 1);
+    assert_eq!(backend.get_instance_count(), 1);
+
+    backend.shutdown()?;
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn sharing_backends_join_one_home_instance() -> Result<()> {
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/.."))?;
+    // Kept apart from other tests and clients, which would join a home shared at one path.
+    let path = std::env::temp_dir().join(format!("local-home-shared-{}", std::process::id()));
+
+    let mut host = LocalBackend::new();
+    host.share_home(&path);
+    let alice = host.create_character("alice", CharacterKind::SoloAccount)?;
+    let home = host.enter_game(alice.character_id)?;
+
+    let mut guest = LocalBackend::new();
+    guest.share_home(&path);
+    let bob = guest.create_character("bob", CharacterKind::SoloAccount)?;
+
+    assert_eq!(guest.enter_game(bob.character_id)?, home);
+    assert_eq!(host.get_spawned_instance_count(), 1);
+    assert_eq!(guest.get_spawned_instance_count(), 0);
+
+    guest.shutdown()?;
+    host.shutdown()?;
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+#[test]
+fn stale_shared_home_is_replaced() -> Result<()> {
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/.."))?;
+    let path = std::env::temp_dir().join(format!("local-home-stale-{}", std::process::id()));
+
+    // A home left behind by a client that crashed, whose instance exited.
+    let mut exited = std::process::Command::new("true").spawn()?;
+    exited.wait()?;
+    let stale = uuid::Uuid::now_v7();
+    std::fs::write(
+        &path,
+        format!(
+            "{} 127.0.0.1:1 {} {}\n",
+            stale.as_simple(),
+            "00".repeat(32),
+            exited.id()
+        ),
+    )?;
+
+    let mut backend = LocalBackend::new();
+    backend.share_home(&path);
+    let character = backend.create_character("survivor", CharacterKind::SoloAccount)?;
+
+    assert_ne!(backend.enter_game(character.character_id)?, stale);
+    assert_eq!(backend.get_spawned_instance_count(), 1);
+
+    backend.shutdown()?;
+    std::fs::remove_file(path)?;
+    Ok(())
+}
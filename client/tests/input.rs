@@ -0,0 +1,114 @@
+use std::time::Duration;
+
+use client::input::KeyboardState;
+use glfw::{Key, Modifiers};
+
+const DT: Duration = Duration::from_millis(16);
+
+#[test]
+fn held_duration_counts_updates_while_pressed() {
+    let mut keyboard = KeyboardState::default();
+    assert_eq!(keyboard.held_duration(Key::Space), None);
+
+    keyboard.press(Key::Space, Modifiers::empty());
+    assert_eq!(keyboard.held_duration(Key::Space), Some(Duration::ZERO));
+
+    for _ in 0..3 {
+        keyboard.post_update(DT);
+    }
+    assert_eq!(keyboard.held_duration(Key::Space), Some(DT * 3));
+
+    keyboard.release(Key::Space, Modifiers::empty());
+    assert_eq!(keyboard.held_duration(Key::Space), None);
+
+    keyboard.post_update(DT);
+    assert_eq!(keyboard.held_duration(Key::Space), None);
+}
+
+#[test]
+fn repeat_fires_on_press_then_after_delay_at_interval() {
+    let mut keyboard = KeyboardState::default();
+    let delay = DT * 5;
+    let interval = DT * 2;
+
+    keyboard.press(Key::Down, Modifiers::empty());
+
+    let mut fired = Vec::new();
+    for frame in 0..10 {
+        if keyboard.is_repeated(Key::Down, delay, interval) {
+            fired.push(frame);
+        }
+        keyboard.post_update(DT);
+    }
+
+    assert_eq!(fired, vec![0, 5, 7, 9]);
+}
+
+#[test]
+fn press_then_release_in_one_frame_is_a_tap() {
+    let mut keyboard = KeyboardState::default();
+
+    keyboard.press(Key::E, Modifiers::empty());
+    keyboard.release(Key::E, Modifiers::empty());
+
+    assert!(keyboard.is_pressed(Key::E, None));
+    assert!(keyboard.is_just_pressed(Key::E, None));
+    assert!(keyboard.is_just_released(Key::E, None));
+
+    keyboard.post_update(DT);
+
+    assert!(!keyboard.is_pressed(Key::E, None));
+    assert!(!keyboard.is_just_pressed(Key::E, None));
+    assert!(!keyboard.is_just_released(Key::E, None));
+}
+
+#[test]
+fn release_then_press_in_one_frame_stays_pressed() {
+    let mut keyboard = KeyboardState::default();
+
+    keyboard.press(Key::W, Modifiers::empty());
+    keyboard.post_update(DT);
+
+    keyboard.release(Key::W, Modifiers::empty());
+    keyboard.press(Key::W, Modifiers::empty());
+
+    assert!(keyboard.is_pressed(Key::W, None));
+    assert!(keyboard.is_just_pressed(Key::W, None));
+    assert!(keyboard.is_just_released(Key::W, None));
+
+    keyboard.post_update(DT);
+
+    assert!(keyboard.is_pressed(Key::W, None));
+    assert!(!keyboard.is_just_pressed(Key::W, None));
+    assert!(!keyboard.is_just_released(Key::W, None));
+    assert_eq!(keyboard.held_duration(Key::W), Some(DT));
+}
+
+#[test]
+fn press_release_press_in_one_frame_stays_pressed() {
+    let mut keyboard = KeyboardState::default();
+
+    keyboard.press(Key::A, Modifiers::empty());
+    keyboard.release(Key::A, Modifiers::empty());
+    keyboard.press(Key::A, Modifiers::empty());
+    keyboard.post_update(DT);
+
+    assert!(keyboard.is_pressed(Key::A, None));
+}
+
+#[test]
+fn release_across_frames_is_seen_once() {
+    let mut keyboard = KeyboardState::default();
+
+    keyboard.press(Key::D, Modifiers::empty());
+    keyboard.post_update(DT);
+    keyboard.post_update(DT);
+
+    keyboard.release(Key::D, Modifiers::empty());
+    assert!(keyboard.is_just_released(Key::D, None));
+    assert!(keyboard.is_pressed(Key::D, None));
+
+    keyboard.post_update(DT);
+    assert!(!keyboard.is_just_released(Key::D, None));
+    assert!(!keyboard.is_pressed(Key::D, None));
+}
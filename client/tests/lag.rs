@@ -0,0 +1,135 @@
+use std::time::Duration;
+
+use client::{
+    backend::{
+        BackendConnection,
+        lag::{LagSimulator, NetworkConditions},
+    },
+    game::Game,
+    instance::InstanceStatus,
+};
+use common::{
+    DT, Result,
+    game::character::CharacterKind,
+    message::{ReliableMessageFromServer, UnreliableMessageFromServer},
+};
+
+fn kicked(reason: &ReliableMessageFromServer) -> &str {
+    match reason {
+        ReliableMessageFromServer::Kicked(reason) => reason,
+        other => panic!("unexpected {other:?}"),
+    }
+}
+
+#[test]
+fn reliable_messages_are_delayed_in_order() {
+    let mut lag = LagSimulator::with_seed(
+        NetworkConditions {
+            latency: Duration::from_millis(100),
+            jitter: Duration::from_millis(50),
+            loss: 1.0,
+        },
+        1,
+    );
+
+    for i in 0..10 {
+        lag.push_reliable(ReliableMessageFromServer::Kicked(i.to_string()));
+        lag.advance(Duration::from_millis(1));
+    }
+    assert!(lag.take_reliable().is_empty());
+
+    lag.advance(Duration::from_millis(200));
+    let delivered = lag.take_reliable();
+    let reasons: Vec<_> = delivered.iter().map(kicked).collect();
+    assert_eq!(reasons, ["0", "1", "2", "3", "4", "5", "6", "7", "8", "9"]);
+    assert!(lag.is_empty());
+    assert_eq!(lag.get_dropped_count(), 0);
+}
+
+#[test]
+fn unreliable_messages_are_dropped_at_the_loss_rate() {
+    let mut lag = LagSimulator::with_seed(
+        NetworkConditions {
+            latency: Duration::from_millis(50),
+            jitter: Duration::ZERO,
+            loss: 0.1,
+        },
+        7,
+    );
+
+    for _ in 0..1000 {
+        lag.push_unreliable(UnreliableMessageFromServer::PlayerPositionSyncs(Vec::new()));
+    }
+
+    let dropped = lag.get_dropped_count();
+    assert!((50..150).contains(&dropped), "dropped {dropped} of 1000");
+
+    lag.advance(Duration::from_millis(49));
+    assert!(lag.take_unreliable().is_empty());
+    lag.advance(Duration::from_millis(1));
+    assert_eq!(lag.take_unreliable().len() as u64, 1000 - dropped);
+}
+
+#[test]
+fn prediction_recovers_under_packet_loss() -> Result<()> {
+    // The backend spawns the instance binary relative to the workspace root.
+    std::env::set_current_dir(concat!(env!("CARGO_MANIFEST_DIR"), "/.."))?;
+
+    let mut backend = BackendConnection::local_with_conditions(NetworkConditions {
+        latency: Duration::from_millis(50),
+        jitter: Duration::from_millis(20),
+        loss: 0.1,
+    });
+    let character = backend.create_character("lossy", CharacterKind::SoloAccount)?;
+    let instance_id = backend.enter_game(character.character_id)?;
+
+    let mut game = Game::headless(backend, instance_id, false);
+
+    let step = |game: &mut Game| -> Result<()> {
+        game.update(DT)?;
+        std::thread::sleep(Duration::from_millis(5));
+        Ok(())
+    };
+
+    let mut steps = 0;
+    while game.get_current_instance_status() != Some(InstanceStatus::Active) {
+        assert!(steps < 600, "never became active");
+        step(&mut game)?;
+        steps += 1;
+    }
+
+    let start = game.get_current_player_position().unwrap();
+
+    game.get_keyboard_state_mut()
+        .press(glfw::Key::D, glfw::Modifiers::empty());
+    for _ in 0..60 {
+        step(&mut game)?;
+    }
+    game.get_keyboard_state_mut()
+        .release(glfw::Key::D, glfw::Modifiers::empty());
+
+    // Stand still until the server's view has caught up with what was predicted.
+    let mut settled = false;
+    for _ in 0..600 {
+        step(&mut game)?;
+
+        let instance = game.get_current_instance().unwrap();
+        let predicted = instance.get_predicted_player_position().unwrap();
+        let server = instance.get_server_position();
+        if server.is_some_and(|server| server.metric_distance(&predicted) < 0.01) {
+            settled = true;
+            break;
+        }
+    }
+
+    let end = game.get_current_player_position().unwrap();
+    let mut backend = game.into_backend();
+    let dropped = backend.get_simulated_drops(instance_id);
+    backend.shutdown()?;
+
+    assert!(settled, "prediction never matched the server again");
+    assert!(end.x > start.x, "player never moved");
+    assert!(dropped > 0, "no messages were dropped");
+
+    Ok(())
+}
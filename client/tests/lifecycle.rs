@@ -0,0 +1,40 @@
+use std::time::Duration;
+
+use client::instance::LifecycleAnim;
+
+const FADE: Duration = Duration::from_millis(200);
+
+#[test]
+fn fading_in_goes_from_invisible_to_shown() {
+    let mut anim = LifecycleAnim::fade_in(FADE);
+    assert_eq!(anim.get_alpha(), 0.0);
+    assert!(!anim.is_finished());
+
+    anim.advance(FADE / 2);
+    assert!((anim.get_alpha() - 0.5).abs() < 1e-6);
+
+    anim.advance(FADE);
+    assert_eq!(anim.get_alpha(), 1.0);
+    assert!(anim.is_finished());
+}
+
+#[test]
+fn fading_out_goes_from_shown_to_invisible() {
+    let mut anim = LifecycleAnim::fade_out(FADE);
+    assert!(anim.is_fading_out());
+    assert_eq!(anim.get_alpha(), 1.0);
+
+    anim.advance(FADE / 4);
+    assert!((anim.get_alpha() - 0.75).abs() < 1e-6);
+
+    anim.advance(FADE);
+    assert_eq!(anim.get_alpha(), 0.0);
+    assert!(anim.is_finished());
+}
+
+#[test]
+fn zero_length_fades_finish_at_once() {
+    assert_eq!(LifecycleAnim::fade_in(Duration::ZERO).get_alpha(), 1.0);
+    assert!(LifecycleAnim::fade_in(Duration::ZERO).is_finished());
+    assert_eq!(LifecycleAnim::fade_out(Duration::ZERO).get_alpha(), 0.0);
+}
@@ -0,0 +1,76 @@
+use client::graphics::minimap::Minimap;
+use common::{Rect, Vec2};
+
+const SCREEN: Vec2 = Vec2::new(1280.0, 720.0);
+
+fn minimap() -> Minimap {
+    Minimap {
+        range: 1000.0,
+        size: 110.0,
+        margin: 10.0,
+        dot_size: 10.0,
+        ..Minimap::default()
+    }
+}
+
+#[test]
+fn minimap_sits_in_the_top_right_corner() {
+    assert_eq!(
+        minimap().bounds(SCREEN),
+        Rect::new(Vec2::new(1160.0, 600.0), Vec2::new(1270.0, 710.0))
+    );
+}
+
+#[test]
+fn dots_are_relative_to_the_centre_and_scaled_by_range() {
+    let centre = Vec2::new(5000.0, -200.0);
+    let dots = minimap().dots(
+        centre,
+        &[
+            centre,
+            centre + Vec2::new(500.0, 0.0),
+            centre + Vec2::new(-1000.0, 1000.0),
+        ],
+        SCREEN,
+    );
+
+    // The middle is at (1215, 655), and range maps to 50 screen coordinates.
+    assert_eq!(
+        dots,
+        vec![
+            Vec2::new(1215.0, 655.0),
+            Vec2::new(1240.0, 655.0),
+            Vec2::new(1165.0, 705.0),
+        ]
+    );
+}
+
+#[test]
+fn off_range_players_are_pinned_to_the_edge() {
+    let dots = minimap().dots(
+        Vec2::zeros(),
+        &[Vec2::new(4000.0, 0.0), Vec2::new(-8000.0, -2000.0)],
+        SCREEN,
+    );
+
+    assert_eq!(dots[0], Vec2::new(1265.0, 655.0));
+    // Still pointing the same way, just pulled in.
+    assert_eq!(dots[1], Vec2::new(1165.0, 642.5));
+
+    let bounds = minimap().bounds(SCREEN);
+    assert!(dots.iter().all(|dot| bounds.contains(*dot)));
+}
+
+#[test]
+fn no_range_draws_no_dots() {
+    let minimap = Minimap {
+        range: 0.0,
+        ..minimap()
+    };
+
+    assert!(
+        minimap
+            .dots(Vec2::zeros(), &[Vec2::zeros()], SCREEN)
+            .is_empty()
+    );
+}
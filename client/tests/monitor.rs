@@ -0,0 +1,59 @@
+use client::backend::monitor::{HUNG_AFTER_POLLS, TickMonitor};
+use common::ready::InstanceReport;
+
+fn report(tick: u64, paused: bool) -> Option<InstanceReport> {
+    Some(InstanceReport {
+        tick,
+        players: 1,
+        paused,
+    })
+}
+
+#[test]
+fn advancing_instances_are_never_hung() {
+    let mut monitor = TickMonitor::default();
+
+    for tick in 0..20 {
+        monitor.record(report(tick * 60, false));
+        assert!(!monitor.is_hung());
+    }
+    assert_eq!(monitor.get_last_report(), report(19 * 60, false));
+}
+
+#[test]
+fn a_tick_unchanged_for_enough_polls_is_hung_until_it_moves() {
+    let mut monitor = TickMonitor::default();
+
+    monitor.record(report(100, false));
+    for _ in 1..HUNG_AFTER_POLLS {
+        monitor.record(report(100, false));
+        assert!(!monitor.is_hung());
+    }
+    monitor.record(report(100, false));
+    assert!(monitor.is_hung());
+    assert_eq!(monitor.get_stalled_polls(), HUNG_AFTER_POLLS);
+
+    monitor.record(report(101, false));
+    assert!(!monitor.is_hung());
+}
+
+#[test]
+fn missing_reports_count_as_stalled() {
+    let mut monitor = TickMonitor::new(2);
+
+    monitor.record(report(100, false));
+    monitor.record(None);
+    assert!(!monitor.is_hung());
+    monitor.record(None);
+    assert!(monitor.is_hung());
+}
+
+#[test]
+fn paused_instances_are_not_hung() {
+    let mut monitor = TickMonitor::new(2);
+
+    for _ in 0..10 {
+        monitor.record(report(100, true));
+    }
+    assert!(!monitor.is_hung());
+}
@@ -0,0 +1,91 @@
+use std::time::Duration;
+
+use client::instance::{CorrectionSmoothing, InputBuffer, PredictionStats};
+use common::{Vec2, player::PlayerInput};
+
+const STILL: PlayerInput = PlayerInput {
+    move_direction: [0.0, 0.0],
+    action: None,
+};
+
+#[test]
+fn prediction_stats_track_count_mean_and_max() {
+    let mut stats = PredictionStats::default();
+    assert_eq!(stats.get_reconcile_count(), 0);
+    assert_eq!(stats.get_mean_correction(), 0.0);
+
+    stats.record(2.0, false);
+    stats.record(10.0, true);
+    stats.record(3.0, false);
+
+    assert_eq!(stats.get_reconcile_count(), 3);
+    assert_eq!(stats.get_forced_count(), 1);
+    assert_eq!(stats.get_mean_correction(), 5.0);
+    assert_eq!(stats.get_max_correction(), 10.0);
+    assert_eq!(stats.to_string(), "3 (1 forced), mean 5.0, max 10.0");
+}
+
+#[test]
+fn correction_smoothing_eases_into_a_snap() {
+    let mut smoothing = CorrectionSmoothing::new(Some(Duration::from_millis(100)));
+    smoothing.correct(Vec2::new(10.0, 0.0), false);
+
+    // Still drawn where it was before the snap.
+    assert_eq!(smoothing.get_offset(), Vec2::new(-10.0, 0.0));
+
+    smoothing.advance(Duration::from_millis(100));
+    let offset = smoothing.get_offset();
+    assert!(offset.x > -4.0 && offset.x < -3.0, "{offset}");
+
+    smoothing.advance(Duration::from_secs(2));
+    assert_eq!(smoothing.get_offset(), Vec2::zeros());
+}
+
+#[test]
+fn forced_or_unsmoothed_corrections_snap() {
+    let mut smoothing = CorrectionSmoothing::default();
+    smoothing.correct(Vec2::new(10.0, 0.0), false);
+    smoothing.correct(Vec2::new(5.0, 5.0), true);
+    assert_eq!(smoothing.get_offset(), Vec2::zeros());
+
+    smoothing.correct(Vec2::new(10.0, 0.0), false);
+    smoothing.set_time(None);
+    assert_eq!(smoothing.get_offset(), Vec2::zeros());
+    smoothing.correct(Vec2::new(10.0, 0.0), false);
+    assert_eq!(smoothing.get_offset(), Vec2::zeros());
+}
+
+#[test]
+fn input_buffer_keeps_a_long_round_trip_of_inputs() {
+    // Five seconds of round trip at 60 ticks a second.
+    let round_trip = 300;
+    let mut buffer = InputBuffer::default();
+    assert!(buffer.get_capacity() < round_trip);
+
+    for order in 1..=1000 {
+        assert_eq!(buffer.push_input(STILL.clone()), order);
+        buffer.ack(order.saturating_sub(round_trip as u64));
+    }
+
+    let unapplied = buffer.get_after(1000 - round_trip as u64);
+    assert_eq!(unapplied.len(), round_trip);
+    assert!(unapplied.windows(2).all(|w| w[1].order == w[0].order + 1));
+    assert_eq!(buffer.get_dropped_count(), 0);
+}
+
+#[test]
+fn input_buffer_counts_unapplied_inputs_it_drops() {
+    let mut buffer = InputBuffer::new(10);
+    for _ in 0..15 {
+        buffer.push_input(STILL.clone());
+    }
+    assert_eq!(buffer.get_dropped_count(), 5);
+    assert_eq!(buffer.get_after(0).len(), 10);
+
+    // Applied inputs can go without counting.
+    buffer.ack(15);
+    for _ in 0..10 {
+        buffer.push_input(STILL.clone());
+    }
+    assert_eq!(buffer.get_dropped_count(), 5);
+}
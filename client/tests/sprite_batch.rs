@@ -0,0 +1,266 @@
+use std::panic::AssertUnwindSafe;
+
+use client::graphics::{
+    camera::{Camera2D, CameraUniform},
+    create_camera_bind_group_layout, create_render_pipeline,
+    sprite_batch::{QUAD_INDICES, SpriteBatch},
+    texture::TextureRegistry,
+};
+use common::{Vec2, Vec4};
+use wgpu::util::DeviceExt;
+
+const SIZE: u32 = 16;
+const FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+/// Rows copied out of a texture must be padded to this many bytes.
+const ROW_BYTES: u32 = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+
+const RED: [u8; 4] = [255, 0, 0, 255];
+const BLACK: [u8; 4] = [0, 0, 0, 255];
+
+#[test]
+fn quad_triangles_are_counter_clockwise() {
+    // Top left, top right, bottom left, bottom right, with y up.
+    let corners = [
+        Vec2::new(0.0, 1.0),
+        Vec2::new(1.0, 1.0),
+        Vec2::new(0.0, 0.0),
+        Vec2::new(1.0, 0.0),
+    ];
+
+    for triangle in QUAD_INDICES.chunks(3) {
+        let [a, b, c] = [0, 1, 2].map(|i| corners[triangle[i] as usize]);
+        let ab = b - a;
+        let ac = c - a;
+        assert!(ab.x * ac.y - ab.y * ac.x > 0.0, "{triangle:?} is clockwise");
+    }
+}
+
+struct Target {
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    pipeline: wgpu::RenderPipeline,
+    camera_bind_group: wgpu::BindGroup,
+    textures: TextureRegistry,
+    texture: wgpu::Texture,
+}
+
+impl Target {
+    /// An offscreen target showing world units 0 to [`SIZE`] on both axes, or `None` when
+    /// this machine has no adapter to render with.
+    fn new() -> Option<Target> {
+        let instance = wgpu::Instance::new(&wgpu::InstanceDescriptor::default());
+        let adapter = [false, true]
+            .into_iter()
+            .find_map(|force_fallback_adapter| {
+                pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+                    force_fallback_adapter,
+                    ..Default::default()
+                }))
+                .ok()
+            })?;
+        let (device, queue) = pollster::block_on(adapter.request_device(&wgpu::DeviceDescriptor {
+            required_limits: wgpu::Limits::downlevel_defaults(),
+            ..Default::default()
+        }))
+        .ok()?;
+
+        let textures = TextureRegistry::new(&device, &queue);
+        let camera_layout = create_camera_bind_group_layout(&device);
+        let pipeline =
+            create_render_pipeline(&device, FORMAT, &textures.bind_group_layout, &camera_layout);
+
+        let half = SIZE as f32 / 2.0;
+        let mut camera_uniform = CameraUniform::new();
+        camera_uniform.update_view_proj(&Camera2D::new(
+            Vec2::new(half, half),
+            Vec2::new(SIZE as f32, SIZE as f32),
+        ));
+        let camera_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: None,
+            contents: bytemuck::cast_slice(&[camera_uniform]),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let camera_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: None,
+            layout: &camera_layout,
+            entries: &[wgpu::BindGroupEntry {
+                binding: 0,
+                resource: camera_buffer.as_entire_binding(),
+            }],
+        });
+
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: None,
+            size: wgpu::Extent3d {
+                width: SIZE,
+                height: SIZE,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: FORMAT,
+            usage: wgpu::TextureUsages::RENDER_ATTACHMENT | wgpu::TextureUsages::COPY_SRC,
+            view_formats: &[],
+        });
+
+        Some(Target {
+            device,
+            queue,
+            pipeline,
+            camera_bind_group,
+            textures,
+            texture,
+        })
+    }
+
+    /// Draws a red square of `scale` at `position` on black, returning the pixels top row
+    /// first.
+    fn render(&mut self, position: Vec2, scale: Vec2) -> Vec<[u8; 4]> {
+        let white = self.textures.white();
+        let mut sprite_batch = SpriteBatch::new(&self.device);
+        let view = self
+            .texture
+            .create_view(&wgpu::TextureViewDescriptor::default());
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor::default());
+
+        {
+            let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: None,
+                color_attachments: &[Some(wgpu::RenderPassColorAttachment {
+                    view: &view,
+                    resolve_target: None,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::BLACK),
+                        store: wgpu::StoreOp::Store,
+                    },
+                })],
+                depth_stencil_attachment: None,
+                timestamp_writes: None,
+                occlusion_query_set: None,
+            });
+            render_pass.set_pipeline(&self.pipeline);
+            render_pass.set_bind_group(1, &self.camera_bind_group, &[]);
+
+            sprite_batch
+                .draw(white, position)
+                .scale(scale)
+                .colour(Vec4::new(1.0, 0.0, 0.0, 1.0))
+                .draw(&mut sprite_batch, &self.textures);
+            sprite_batch.end(&self.device, &self.queue, &self.textures, &mut render_pass);
+        }
+
+        let buffer = self.device.create_buffer(&wgpu::BufferDescriptor {
+            label: None,
+            size: (ROW_BYTES * SIZE) as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
+        });
+        encoder.copy_texture_to_buffer(
+            self.texture.as_image_copy(),
+            wgpu::TexelCopyBufferInfo {
+                buffer: &buffer,
+                layout: wgpu::TexelCopyBufferLayout {
+                    offset: 0,
+                    bytes_per_row: Some(ROW_BYTES),
+                    rows_per_image: None,
+                },
+            },
+            self.texture.size(),
+        );
+        self.queue.submit([encoder.finish()]);
+
+        buffer.slice(..).map_async(wgpu::MapMode::Read, |result| {
+            result.unwrap();
+        });
+        self.device.poll(wgpu::PollType::Wait).unwrap();
+
+        let bytes = buffer.slice(..).get_mapped_range();
+        bytes
+            .chunks(ROW_BYTES as usize)
+            .flat_map(|row| row[..(SIZE * 4) as usize].chunks(4))
+            .map(|pixel| pixel.try_into().unwrap())
+            .collect()
+    }
+}
+
+/// Colour at world point (`x`, `y`), given pixels top row first.
+fn at(pixels: &[[u8; 4]], x: u32, y: u32) -> [u8; 4] {
+    pixels[((SIZE - 1 - y) * SIZE + x) as usize]
+}
+
+#[test]
+fn flipped_and_unflipped_sprites_are_drawn() {
+    let Some(mut target) = Target::new() else {
+        eprintln!("No graphics adapter, skipping");
+        return;
+    };
+
+    // Covering the left half.
+    let pixels = target.render(Vec2::new(0.0, 0.0), Vec2::new(8.0, 16.0));
+    assert_eq!(at(&pixels, 4, 8), RED);
+    assert_eq!(at(&pixels, 12, 8), BLACK);
+
+    // Flipped on x, so it reaches back from 16 over the right half.
+    let pixels = target.render(Vec2::new(16.0, 0.0), Vec2::new(-8.0, 16.0));
+    assert_eq!(at(&pixels, 4, 8), BLACK);
+    assert_eq!(at(&pixels, 12, 8), RED);
+
+    // Flipped on y, reaching down from 16 over the top half.
+    let pixels = target.render(Vec2::new(0.0, 16.0), Vec2::new(16.0, -8.0));
+    assert_eq!(at(&pixels, 8, 4), BLACK);
+    assert_eq!(at(&pixels, 8, 12), RED);
+}
+
+#[test]
+fn texture_ids_only_work_with_their_own_registry() {
+    let Some(mut target) = Target::new() else {
+        eprintln!("No graphics adapter, skipping");
+        return;
+    };
+
+    let mut other = TextureRegistry::new(&target.device, &target.queue);
+    let ours = target
+        .textures
+        .load_rgba(&target.device, &target.queue, &[255; 4], (1, 1), None);
+    let theirs = other.load_rgba(&target.device, &target.queue, &[255; 4], (1, 1), None);
+
+    // Both are the first texture loaded into their registry, so only the registry tells them
+    // apart.
+    assert_ne!(ours, theirs);
+    assert!(target.textures.owns(ours));
+    assert!(!target.textures.owns(theirs));
+    assert!(target.textures.get(ours).is_some());
+
+    // Caught by a debug assertion where there is one, and never drawn either way.
+    let found =
+        std::panic::catch_unwind(AssertUnwindSafe(|| target.textures.get(theirs).is_some()));
+    assert_eq!(found.is_err(), cfg!(debug_assertions));
+    assert!(!found.unwrap_or(false));
+}
+
+#[test]
+fn registry_comes_with_one_white_pixel() {
+    let Some(mut target) = Target::new() else {
+        eprintln!("No graphics adapter, skipping");
+        return;
+    };
+
+    let white = target.textures.white();
+    let texture = target.textures.get(white).unwrap();
+    assert_eq!((texture.get_width(), texture.get_height()), (1, 1));
+
+    // Loading more doesn't replace it.
+    let loaded = target
+        .textures
+        .load_rgba(&target.device, &target.queue, &[0; 4], (1, 1), None);
+    assert_ne!(loaded, white);
+    assert_eq!(target.textures.white(), white);
+
+    // Tinted, it draws a solid rectangle of the tint.
+    let pixels = target.render(Vec2::new(4.0, 4.0), Vec2::new(8.0, 8.0));
+    assert_eq!(at(&pixels, 8, 8), RED);
+    assert_eq!(at(&pixels, 2, 8), BLACK);
+}
@@ -0,0 +1,51 @@
+use client::{
+    graphics::{outline, sprite_scale, view_size},
+    parse_window_size,
+};
+use common::{Rect, Vec2};
+
+#[test]
+fn window_size_parses_width_by_height() {
+    assert_eq!(parse_window_size("1280x720").unwrap(), (1280, 720));
+    assert!(parse_window_size("1280").is_err());
+    assert!(parse_window_size("0x720").is_err());
+    assert!(parse_window_size("widexhigh").is_err());
+}
+
+#[test]
+fn hidpi_framebuffer_shows_the_same_part_of_the_world() {
+    assert_eq!(view_size((1920, 1080), 1.0), Vec2::new(1920.0, 1080.0));
+    assert_eq!(view_size((3840, 2160), 2.0), Vec2::new(1920.0, 1080.0));
+}
+
+#[test]
+fn sprite_scale_matches_collider_diameter() {
+    assert_eq!(
+        sprite_scale(Vec2::new(256.0, 256.0), 50.0),
+        Vec2::new(100.0 / 256.0, 100.0 / 256.0)
+    );
+    assert_eq!(
+        sprite_scale(Vec2::new(100.0, 50.0), 25.0),
+        Vec2::new(0.5, 1.0)
+    );
+}
+
+#[test]
+fn outline_lines_the_inside_without_overlap() {
+    let bounds = Rect::new(Vec2::new(-50.0, -50.0), Vec2::new(50.0, 50.0));
+    let edges = outline(bounds, 3.0);
+
+    let area: f32 = edges.iter().map(|edge| edge.width() * edge.height()).sum();
+    assert_eq!(area, 100.0 * 100.0 - 94.0 * 94.0);
+    for edge in edges {
+        assert!(bounds.contains(edge.min) && bounds.contains(edge.max));
+    }
+
+    // Too thick for the box just fills it.
+    let tiny = Rect::new(Vec2::zeros(), Vec2::new(4.0, 4.0));
+    let area: f32 = outline(tiny, 10.0)
+        .iter()
+        .map(|edge| edge.width() * edge.height())
+        .sum();
+    assert_eq!(area, 16.0);
+}
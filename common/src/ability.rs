@@ -0,0 +1,105 @@
+use std::collections::HashMap;
+
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+
+use crate::tick::Tick;
+
+/// Inputs a player has to send after dashing before it may dash again.
+pub const DASH_COOLDOWN_INPUTS: u64 = 60;
+
+/// An ability with a cooldown, used through a [`crate::player::PlayerAction`].
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, Encode, Decode,
+)]
+#[non_exhaustive]
+pub enum AbilityId {
+    Dash,
+}
+
+impl AbilityId {
+    /// Inputs from using the ability until it can be used again.
+    pub fn get_cooldown(&self) -> u64 {
+        match self {
+            AbilityId::Dash => DASH_COOLDOWN_INPUTS,
+        }
+    }
+}
+
+/// When each of a player's abilities can next be used.
+///
+/// Cooldowns end at an input tick, a count of the inputs applied to the player, rather than a
+/// server tick or anything the client sends. The client predicts an input well before the
+/// server applies it, but both count it the same way, so they agree on whether the ability
+/// was ready. The owner is sent the count and the running cooldowns with every
+/// [`crate::message::OwnedPlayerSync`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Abilities {
+    pub cooldowns: HashMap<AbilityId, Tick>,
+    /// Inputs applied to the player so far, the clock the cooldowns run on.
+    pub inputs_applied: u64,
+}
+
+impl Abilities {
+    pub fn new() -> Abilities {
+        Abilities::default()
+    }
+
+    /// Abilities with the given cooldown end ticks after `inputs_applied` inputs, as sent by
+    /// the server.
+    pub fn from_cooldowns(
+        inputs_applied: u64,
+        cooldowns: impl IntoIterator<Item = (AbilityId, Tick)>,
+    ) -> Abilities {
+        Abilities {
+            cooldowns: cooldowns.into_iter().collect(),
+            inputs_applied,
+        }
+    }
+
+    /// The input tick of the last input applied.
+    pub fn get_tick(&self) -> Tick {
+        Tick::new(self.inputs_applied)
+    }
+
+    /// Counts one more applied input and returns its input tick.
+    pub fn next_tick(&mut self) -> Tick {
+        self.inputs_applied += 1;
+        self.get_tick()
+    }
+
+    /// Whether the next input applied may use `ability`.
+    pub fn is_ready_next(&self, ability: AbilityId) -> bool {
+        self.is_ready(ability, Tick::new(self.inputs_applied + 1))
+    }
+
+    /// Whether the input at `tick` may use `ability`.
+    pub fn is_ready(&self, ability: AbilityId, tick: Tick) -> bool {
+        self.cooldowns.get(&ability).is_none_or(|end| tick >= *end)
+    }
+
+    /// Uses `ability` for the input at `tick` and starts its cooldown, unless it is still
+    /// cooling down. Returns whether it was used.
+    pub fn try_use(&mut self, ability: AbilityId, tick: Tick) -> bool {
+        if !self.is_ready(ability, tick) {
+            return false;
+        }
+
+        let end = Tick::new(tick.get() + ability.get_cooldown());
+        self.cooldowns.insert(ability, end);
+        true
+    }
+
+    /// Cooldowns still running at `tick`, ordered by ability so the same state always encodes
+    /// the same way.
+    pub fn get_running(&self, tick: Tick) -> Vec<(AbilityId, Tick)> {
+        let mut running: Vec<_> = self
+            .cooldowns
+            .iter()
+            .filter(|(_, end)| **end > tick)
+            .map(|(ability, end)| (*ability, *end))
+            .collect();
+        running.sort();
+        running
+    }
+}
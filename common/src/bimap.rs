@@ -0,0 +1,78 @@
+use std::{collections::HashMap, hash::Hash};
+
+/// A one-to-one map that can be looked up from either side.
+///
+/// Every left value maps to exactly one right value and back. Inserting a pair replaces any
+/// pair either value was already part of, and removing from one side removes from both, so
+/// the two directions can't drift apart.
+#[derive(Debug, Clone)]
+pub struct BiMap<L, R> {
+    left_to_right: HashMap<L, R>,
+    right_to_left: HashMap<R, L>,
+}
+
+impl<L, R> Default for BiMap<L, R> {
+    fn default() -> Self {
+        BiMap {
+            left_to_right: HashMap::new(),
+            right_to_left: HashMap::new(),
+        }
+    }
+}
+
+impl<L: Copy + Eq + Hash, R: Copy + Eq + Hash> BiMap<L, R> {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Pairs `left` with `right`, unpairing whatever either was paired with before.
+    pub fn insert(&mut self, left: L, right: R) {
+        self.remove_by_left(&left);
+        self.remove_by_right(&right);
+
+        self.left_to_right.insert(left, right);
+        self.right_to_left.insert(right, left);
+    }
+
+    pub fn get_by_left(&self, left: &L) -> Option<&R> {
+        self.left_to_right.get(left)
+    }
+
+    pub fn get_by_right(&self, right: &R) -> Option<&L> {
+        self.right_to_left.get(right)
+    }
+
+    pub fn contains_left(&self, left: &L) -> bool {
+        self.left_to_right.contains_key(left)
+    }
+
+    pub fn contains_right(&self, right: &R) -> bool {
+        self.right_to_left.contains_key(right)
+    }
+
+    /// Removes the pair `left` is part of and returns its right value.
+    pub fn remove_by_left(&mut self, left: &L) -> Option<R> {
+        let right = self.left_to_right.remove(left)?;
+        self.right_to_left.remove(&right);
+        Some(right)
+    }
+
+    /// Removes the pair `right` is part of and returns its left value.
+    pub fn remove_by_right(&mut self, right: &R) -> Option<L> {
+        let left = self.right_to_left.remove(right)?;
+        self.left_to_right.remove(&left);
+        Some(left)
+    }
+
+    pub fn len(&self) -> usize {
+        self.left_to_right.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.left_to_right.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&L, &R)> {
+        self.left_to_right.iter()
+    }
+}
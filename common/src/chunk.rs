@@ -0,0 +1,102 @@
+//! Sends payloads too big for a single network message as a run of chunks over an ordered
+//! reliable channel. Chunks of one payload are always sent back to back, so the receiver only
+//! has to append them until the announced length is reached. Messages are packed with
+//! [`crate::compress`] first, so large ones travel zstd compressed.
+
+use bincode::{
+    Decode, Encode,
+    config::{Configuration, Limit, LittleEndian, Varint},
+};
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Result, compress, message::encode};
+
+/// Payload bytes carried by one chunk. Well below [`crate::message::MAX_MESSAGE_SIZE`], so
+/// every chunk decodes as an ordinary message.
+pub const CHUNK_SIZE: usize = 16 * 1024;
+
+/// Largest payload a peer may make us reassemble.
+pub const MAX_PAYLOAD_SIZE: usize = 1024 * 1024;
+
+/// The bincode configuration reassembled payloads are decoded with.
+pub const PAYLOAD_CONFIG: Configuration<LittleEndian, Varint, Limit<MAX_PAYLOAD_SIZE>> =
+    bincode::config::standard().with_limit::<MAX_PAYLOAD_SIZE>();
+
+#[derive(Debug, Serialize, Deserialize, Encode, Decode)]
+pub struct PayloadChunk {
+    /// Length of the whole payload this chunk belongs to.
+    pub total_len: u32,
+    pub data: Vec<u8>,
+}
+
+/// Encodes and packs `message`, then splits it into encoded [`PayloadChunk`]s, ready to
+/// send in order.
+pub fn split_message<T: Encode>(message: &T) -> Result<Vec<Vec<u8>>> {
+    let payload = bincode::encode_to_vec(message, PAYLOAD_CONFIG)?;
+    split_payload(&compress::pack(&payload)?)
+}
+
+pub fn split_payload(payload: &[u8]) -> Result<Vec<Vec<u8>>> {
+    if payload.is_empty() || payload.len() > MAX_PAYLOAD_SIZE {
+        return Err(Error::InvalidPayload);
+    }
+
+    payload
+        .chunks(CHUNK_SIZE)
+        .map(|data| {
+            encode(&PayloadChunk {
+                total_len: payload.len() as u32,
+                data: data.to_vec(),
+            })
+        })
+        .collect()
+}
+
+/// Reassembles payloads from [`PayloadChunk`]s received in order.
+#[derive(Debug, Default)]
+pub struct PayloadAssembler {
+    buffer: Vec<u8>,
+    total_len: usize,
+}
+
+impl PayloadAssembler {
+    pub fn new() -> PayloadAssembler {
+        PayloadAssembler {
+            buffer: Vec::new(),
+            total_len: 0,
+        }
+    }
+
+    /// Adds one chunk, returning the payload once its last chunk has arrived. A chunk that
+    /// doesn't fit the payload in progress discards it.
+    pub fn push(&mut self, chunk: PayloadChunk) -> Result<Option<Vec<u8>>> {
+        let total_len = chunk.total_len as usize;
+
+        if self.buffer.is_empty() {
+            if total_len == 0 || total_len > MAX_PAYLOAD_SIZE {
+                return Err(Error::InvalidPayload);
+            }
+            self.total_len = total_len;
+        }
+
+        if total_len != self.total_len || self.buffer.len() + chunk.data.len() > total_len {
+            self.buffer.clear();
+            return Err(Error::InvalidPayload);
+        }
+
+        self.buffer.extend_from_slice(&chunk.data);
+
+        if self.buffer.len() == self.total_len {
+            Ok(Some(std::mem::take(&mut self.buffer)))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Unpacks and decodes a message reassembled by a [`PayloadAssembler`].
+pub fn decode_payload<T: Decode<()>>(payload: &[u8]) -> Result<T> {
+    let payload = compress::unpack(payload)?;
+    let (message, _) = bincode::decode_from_slice(&payload, PAYLOAD_CONFIG)?;
+    Ok(message)
+}
@@ -0,0 +1,44 @@
+//! zstd compression for payloads sent through [`crate::chunk`]. Every packed payload starts
+//! with a one-byte header saying whether the rest is compressed.
+
+use crate::{Error, Result, chunk::MAX_PAYLOAD_SIZE};
+
+/// Payloads up to this many bytes are sent as they are; compressing them saves too little
+/// to be worth the time. A 30 player snapshot is around 850 bytes and packs to about 580.
+pub const COMPRESSION_THRESHOLD: usize = 256;
+
+const COMPRESSION_LEVEL: i32 = 3;
+
+const HEADER_RAW: u8 = 0;
+const HEADER_ZSTD: u8 = 1;
+
+/// Prefixes `payload` with its header, compressing it when it is above
+/// [`COMPRESSION_THRESHOLD`] and compression actually makes it smaller.
+pub fn pack(payload: &[u8]) -> Result<Vec<u8>> {
+    if payload.len() > COMPRESSION_THRESHOLD {
+        let compressed = zstd::bulk::compress(payload, COMPRESSION_LEVEL)?;
+        if compressed.len() < payload.len() {
+            let mut packed = Vec::with_capacity(compressed.len() + 1);
+            packed.push(HEADER_ZSTD);
+            packed.extend_from_slice(&compressed);
+            return Ok(packed);
+        }
+    }
+
+    let mut packed = Vec::with_capacity(payload.len() + 1);
+    packed.push(HEADER_RAW);
+    packed.extend_from_slice(payload);
+    Ok(packed)
+}
+
+/// Reverses [`pack`]. A compressed payload may not expand past [`MAX_PAYLOAD_SIZE`], so a
+/// peer can't make us allocate more than it could have sent uncompressed.
+pub fn unpack(packed: &[u8]) -> Result<Vec<u8>> {
+    match packed.split_first() {
+        Some((&HEADER_RAW, payload)) => Ok(payload.to_vec()),
+        Some((&HEADER_ZSTD, compressed)) => {
+            zstd::bulk::decompress(compressed, MAX_PAYLOAD_SIZE).map_err(|_| Error::InvalidPayload)
+        }
+        _ => Err(Error::InvalidPayload),
+    }
+}
@@ -1,3 +1,8 @@
+use renet_netcode::NETCODE_USER_DATA_BYTES;
+
+/// Longest display name, in characters, that is shown to other players.
+pub const MAX_NAME_CHARS: usize = 24;
+
 #[derive(Debug, Clone)]
 pub struct Character {
     pub account_id: u64,
@@ -12,3 +17,25 @@ pub enum CharacterKind {
     SoloAccount,
     SoloCharacter,
 }
+
+/// Shortens `name` to at most [`MAX_NAME_CHARS`] characters without splitting one.
+pub fn cap_name(name: &str) -> String {
+    name.chars().take(MAX_NAME_CHARS).collect()
+}
+
+/// Packs a character name into connect token user data. The token is signed, so the
+/// instance can trust the name it reads back with [`name_from_user_data`].
+pub fn name_to_user_data(name: &str) -> [u8; NETCODE_USER_DATA_BYTES] {
+    let name = cap_name(name);
+    let bytes = name.as_bytes();
+
+    let mut data = [0; NETCODE_USER_DATA_BYTES];
+    data[0] = bytes.len() as u8;
+    data[1..=bytes.len()].copy_from_slice(bytes);
+    data
+}
+
+pub fn name_from_user_data(data: &[u8; NETCODE_USER_DATA_BYTES]) -> String {
+    let len = data[0] as usize;
+    cap_name(&String::from_utf8_lossy(&data[1..=len]))
+}
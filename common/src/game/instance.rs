@@ -1,9 +1,167 @@
+use std::path::Path;
+
 use rapier2d::na::Vector2;
+use serde::{Deserialize, Serialize};
+
+use crate::{Error, Rect, Result, ResultExt};
 
 type Vec2 = Vector2<f32>;
 
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum CollisionShape {
     Rectangle { min: Vec2, max: Vec2 },
     Wall { min: Vec2, max: Vec2 },
     Circle { center: Vec2, radius: f32 },
 }
+
+impl CollisionShape {
+    fn validate(&self) -> Result<()> {
+        match self {
+            CollisionShape::Rectangle { min, max } | CollisionShape::Wall { min, max } => {
+                if !(min.iter().chain(max.iter()).all(|v| v.is_finite())) {
+                    return Err(Error::InvalidLevel(format!("{self:?} is not finite")));
+                }
+
+                if min.x >= max.x || min.y >= max.y {
+                    return Err(Error::InvalidLevel(format!(
+                        "{self:?} has min not below max"
+                    )));
+                }
+            }
+            CollisionShape::Circle { center, radius } => {
+                if !(center.iter().all(|v| v.is_finite()) && radius.is_finite()) {
+                    return Err(Error::InvalidLevel(format!("{self:?} is not finite")));
+                }
+
+                if *radius <= 0.0 {
+                    return Err(Error::InvalidLevel(format!(
+                        "{self:?} has a non-positive radius"
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Half the width and height of the world in levels that don't set their own bounds. Large
+/// enough to never be in the way, small enough that `f32` positions stay precise to well
+/// under a hundredth of a unit.
+pub const DEFAULT_WORLD_HALF_SIZE: f32 = 100_000.0;
+
+fn default_bounds() -> Rect {
+    Rect::new(
+        Vec2::repeat(-DEFAULT_WORLD_HALF_SIZE),
+        Vec2::repeat(DEFAULT_WORLD_HALF_SIZE),
+    )
+}
+
+/// The static layout of an instance, loaded from a JSON level file:
+///
+/// ```json
+/// {
+///     "colliders": [
+///         { "Rectangle": { "min": [256.0, 256.0], "max": [768.0, 512.0] } },
+///         { "Circle": { "center": [-300.0, 0.0], "radius": 40.0 } }
+///     ],
+///     "spawn_points": [[0.0, 0.0], [0.0, -200.0]],
+///     "bounds": { "min": [-2000.0, -2000.0], "max": [2000.0, 2000.0] }
+/// }
+/// ```
+///
+/// Players can't move outside `bounds`, which default to [`DEFAULT_WORLD_HALF_SIZE`] around
+/// the origin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LevelDef {
+    pub colliders: Vec<CollisionShape>,
+    #[serde(default)]
+    pub spawn_points: Vec<Vec2>,
+    #[serde(default = "default_bounds")]
+    pub bounds: Rect,
+}
+
+impl Default for LevelDef {
+    /// The single obstacle every instance had before levels could be loaded.
+    fn default() -> Self {
+        LevelDef {
+            colliders: vec![CollisionShape::Rectangle {
+                min: Vec2::new(256.0, 256.0),
+                max: Vec2::new(768.0, 512.0),
+            }],
+            spawn_points: Vec::new(),
+            bounds: default_bounds(),
+        }
+    }
+}
+
+impl LevelDef {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<LevelDef> {
+        let path = path.as_ref();
+        let context = format!("Failed to load level {}", path.display());
+
+        let content = std::fs::read_to_string(path).context(context.clone())?;
+
+        LevelDef::parse(&content).context(context)
+    }
+
+    pub fn parse(content: &str) -> Result<LevelDef> {
+        let level: LevelDef = serde_json::from_str(content)?;
+        level.validate()?;
+        Ok(level)
+    }
+
+    /// Writes the level in the format [`LevelDef::load`] reads.
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let context = format!("Failed to save level {}", path.display());
+
+        let content = self.to_json().context(context.clone())?;
+
+        std::fs::write(path, content).context(context)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    pub fn validate(&self) -> Result<()> {
+        for collider in &self.colliders {
+            collider.validate()?;
+        }
+
+        let bounds = &self.bounds;
+        if !(bounds
+            .min
+            .iter()
+            .chain(bounds.max.iter())
+            .all(|v| v.is_finite()))
+        {
+            return Err(Error::InvalidLevel(format!(
+                "bounds {bounds:?} are not finite"
+            )));
+        }
+
+        if bounds.min.x >= bounds.max.x || bounds.min.y >= bounds.max.y {
+            return Err(Error::InvalidLevel(format!(
+                "bounds {bounds:?} have min not below max"
+            )));
+        }
+
+        for spawn_point in &self.spawn_points {
+            if !spawn_point.iter().all(|v| v.is_finite()) {
+                return Err(Error::InvalidLevel(format!(
+                    "spawn point {spawn_point:?} is not finite"
+                )));
+            }
+
+            if !bounds.contains(*spawn_point) {
+                return Err(Error::InvalidLevel(format!(
+                    "spawn point {spawn_point:?} is outside the bounds"
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
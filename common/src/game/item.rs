@@ -1,12 +1,22 @@
+use bincode::{
+    Decode, Encode,
+    de::Decoder,
+    enc::Encoder,
+    error::{DecodeError, EncodeError},
+};
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
 pub enum ItemExemplar {
     Individual { item: Item },
     Stackable { stackable_id: String, amount: usize },
 }
 
-#[derive(Debug, Clone)]
+/// Encoded by hand so the id goes over the network as its 16 raw bytes, the same way
+/// [`ReliableMessageFromServer::InstanceId`](crate::message::ReliableMessageFromServer::InstanceId)
+/// is sent.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Item {
     pub id: Uuid,
     pub name: String,
@@ -18,13 +28,43 @@ pub struct Item {
     pub condition: u16,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+impl Encode for Item {
+    fn encode<E: Encoder>(&self, encoder: &mut E) -> Result<(), EncodeError> {
+        self.id.into_bytes().encode(encoder)?;
+        self.name.encode(encoder)?;
+        self.base_id.encode(encoder)?;
+        self.category.encode(encoder)?;
+        self.rarity.encode(encoder)?;
+        self.implicits.encode(encoder)?;
+        self.explicits.encode(encoder)?;
+        self.condition.encode(encoder)
+    }
+}
+
+impl<Context> Decode<Context> for Item {
+    fn decode<D: Decoder<Context = Context>>(decoder: &mut D) -> Result<Self, DecodeError> {
+        Ok(Item {
+            id: Uuid::from_bytes(Decode::decode(decoder)?),
+            name: Decode::decode(decoder)?,
+            base_id: Decode::decode(decoder)?,
+            category: Decode::decode(decoder)?,
+            rarity: Decode::decode(decoder)?,
+            implicits: Decode::decode(decoder)?,
+            explicits: Decode::decode(decoder)?,
+            condition: Decode::decode(decoder)?,
+        })
+    }
+}
+
+bincode::impl_borrow_decode!(Item);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
 pub enum ItemCategory {
     Sword,
     Key,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
 pub enum Rarity {
     /// Gray (0 mods)
     Insignificant,
@@ -51,7 +91,7 @@ impl Rarity {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
 pub struct Modifier {
     pub modifier_id: String,
     pub rolls: Vec<i32>,
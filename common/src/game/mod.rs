@@ -1,4 +1,121 @@
+use std::collections::HashMap;
+
+use hecs::{Entity, World};
+
+use crate::{Error, Result, instance::DisplayName};
+
 pub mod instance;
 pub mod inventory;
 pub mod item;
-pub mod character;
\ No newline at end of file
+pub mod character;
+
+#[derive(Debug, Clone)]
+pub struct Account {
+    pub account_id: u64,
+    pub name: String,
+}
+
+/// Component linking a player entity to the account logged in as it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountId(pub u64);
+
+/// The accounts known to a server and the entities of the ones currently online.
+pub struct Game {
+    accounts: HashMap<u64, Account>,
+    online_accounts: HashMap<u64, Entity>,
+    world: World,
+    /// Only registered accounts may log in, and only logged in accounts may act. Without
+    /// it, as in local play, any account id is accepted and logged in on first use.
+    authorization: bool,
+}
+
+impl std::fmt::Debug for Game {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Game")
+            .field("authorization", &self.authorization)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Game {
+    pub fn new(authorization: bool) -> Game {
+        Game {
+            accounts: HashMap::new(),
+            online_accounts: HashMap::new(),
+            world: World::new(),
+            authorization,
+        }
+    }
+
+    pub fn add_account(&mut self, account: Account) {
+        self.accounts.insert(account.account_id, account);
+    }
+
+    pub fn get_account(&self, account_id: u64) -> Option<&Account> {
+        self.accounts.get(&account_id)
+    }
+
+    pub fn get_world(&self) -> &World {
+        &self.world
+    }
+
+    pub fn is_online(&self, account_id: u64) -> bool {
+        self.online_accounts.contains_key(&account_id)
+    }
+
+    pub fn online_count(&self) -> usize {
+        self.online_accounts.len()
+    }
+
+    /// The player entity of a logged in account.
+    pub fn get_account_entity(&self, account_id: u64) -> Option<Entity> {
+        self.online_accounts.get(&account_id).copied()
+    }
+
+    /// Spawns the account's player entity and marks it online.
+    pub fn login(&mut self, account_id: u64) -> Result<Entity> {
+        if self.is_online(account_id) {
+            return Err(Error::AlreadyLoggedIn);
+        }
+
+        let name = match self.accounts.get(&account_id) {
+            Some(account) => account.name.clone(),
+            None if self.authorization => return Err(Error::UnknownAccount),
+            None => {
+                let name = format!("Account {account_id}");
+                self.add_account(Account {
+                    account_id,
+                    name: name.clone(),
+                });
+                name
+            }
+        };
+
+        let entity = self.world.spawn((AccountId(account_id), DisplayName(name)));
+        self.online_accounts.insert(account_id, entity);
+
+        Ok(entity)
+    }
+
+    /// Despawns the account's player entity. The account itself stays known.
+    pub fn logout(&mut self, account_id: u64) -> Result<()> {
+        let entity = self
+            .online_accounts
+            .remove(&account_id)
+            .ok_or(Error::NotLoggedIn)?;
+        // Nothing else despawns account entities, so it is still there.
+        let _ = self.world.despawn(entity);
+
+        Ok(())
+    }
+
+    /// The entity an account acts through, checked before carrying out anything it asks
+    /// for. With authorization off, an account that isn't online yet is logged in.
+    pub fn authorize(&mut self, account_id: u64) -> Result<Entity> {
+        match self.get_account_entity(account_id) {
+            Some(entity) => Ok(entity),
+            None if self.authorization => Err(Error::NotLoggedIn),
+            None => self.login(account_id),
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use bincode::{Decode, Encode};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::{
+    Result,
+    ability::{Abilities, AbilityId},
+    message::{decode, encode},
+    tick::Tick,
+};
+
+/// Prefix of the line a source instance writes in answer to the `handoff` pipe command,
+/// followed by the hex encoded [`PlayerHandoff`].
+pub const HANDOFF_TAG: &str = "HANDOFF";
+
+/// A player moving from one instance to another, as the source instance hands it to its
+/// manager and the manager passes it on to the destination.
+///
+/// The protocol, all over the instances' pipes:
+/// 1. The manager sends the source `handoff <client_id> <character_id>`.
+/// 2. The source answers with a [`HANDOFF_TAG`] line and keeps the player until told how the
+///    handoff went.
+/// 3. The manager rewrites `client_id` to its connection to the destination and sends the
+///    destination `accept-handoff <payload>`, which spawns the player there.
+/// 4. The manager sends the source `handoff-done <client_id>`, which removes the player and
+///    leaves its client watching, or `handoff-failed <client_id>` if the destination couldn't
+///    take it, which leaves the player where it was.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub struct PlayerHandoff {
+    /// The connection the player belongs to in whichever instance the payload is sent to.
+    pub client_id: u64,
+    pub character_id: u32,
+    pub name: String,
+    pub radius: f32,
+    /// Where the player stood in the source instance.
+    pub position: [f32; 2],
+    /// Inputs left on each running cooldown. Input ticks start over in every instance, so
+    /// the end ticks themselves mean nothing at the destination.
+    pub cooldowns: Vec<(AbilityId, u64)>,
+}
+
+impl PlayerHandoff {
+    /// The cooldowns of `abilities` still running after the last input applied.
+    pub fn remaining_cooldowns(abilities: &Abilities) -> Vec<(AbilityId, u64)> {
+        let tick = abilities.get_tick();
+        abilities
+            .get_running(tick)
+            .into_iter()
+            .map(|(ability, end)| (ability, end.get() - tick.get()))
+            .collect()
+    }
+
+    /// The handed over cooldowns for a player that hasn't sent an input yet.
+    pub fn get_abilities(&self) -> Abilities {
+        Abilities::from_cooldowns(
+            0,
+            self.cooldowns
+                .iter()
+                .map(|(ability, remaining)| (*ability, Tick::new(*remaining))),
+        )
+    }
+
+    /// The payload as a single word, to send as the argument of a pipe command.
+    pub fn to_hex(&self) -> Result<String> {
+        Ok(hex::encode(encode(self)?))
+    }
+
+    pub fn from_hex(payload: &str) -> Result<PlayerHandoff> {
+        decode(&hex::decode(payload.trim())?)
+    }
+
+    /// The line carrying the handoff, newline included.
+    pub fn to_line(&self) -> Result<String> {
+        Ok(format!("{HANDOFF_TAG} {}\n", self.to_hex()?))
+    }
+
+    /// Reads a line written by [`PlayerHandoff::to_line`]. Any other line gives `None`.
+    pub fn parse(line: &str) -> Option<PlayerHandoff> {
+        let payload = line.trim().strip_prefix(HANDOFF_TAG)?;
+        if !payload.starts_with(' ') {
+            return None;
+        }
+
+        PlayerHandoff::from_hex(payload)
+            .inspect_err(|err| warn!("Malformed handoff payload: {err}"))
+            .ok()
+    }
+}
@@ -1,29 +1,112 @@
+use bincode::{Decode, Encode};
 use hecs::{Entity, EntityBuilder, World};
-use rapier2d::prelude::{ColliderBuilder, ColliderHandle, RigidBodyBuilder, RigidBodyHandle};
+use rand::{SeedableRng, rngs::StdRng};
+use rapier2d::prelude::{Ball, ColliderBuilder, ColliderHandle, RigidBodyBuilder, RigidBodyHandle};
+use serde::{Deserialize, Serialize};
 use std::{collections::HashMap, fmt::Debug, time::Duration};
-use tracing::{info, instrument};
+use tracing::{info, instrument, warn};
 use uuid::Uuid;
 
 use crate::{
-    message::{OrderedInput, OwnedPlayerSync}, net_obj::{LastSyncTracker, NetworkObject}, physics::Physics, player::{apply_input, PlayerInput}, tick::Tick, Result, Vec2
+    Rect, Result, Vec2, Vec3,
+    ability::Abilities,
+    game::instance::{CollisionShape, LevelDef},
+    interpolation::{INTERPOLATION_DELAY_TICKS, Interpolate, InterpolationBuffer},
+    message::{OrderedInput, OwnedPlayerSync},
+    navigation::{NAV_CELL_SIZE, NavGrid},
+    net_obj::{LastSyncTracker, NetworkObject},
+    physics::Physics,
+    player::{DASH_DISTANCE, PLAYER_SPEED, PlayerAction, apply_input},
+    tick::Tick,
 };
 
+/// Collider radius of a player whose spawn doesn't ask for another size.
+pub const PLAYER_RADIUS: f32 = 50.0;
+
+/// Candidate spawn points around the origin for levels that don't define their own.
+const SPAWN_RING_RADIUS: f32 = 150.0;
+const SPAWN_RING_POINTS: usize = 8;
+
 pub struct Instance {
     id: Uuid,
     physics: Physics,
     world: World,
     tick: Tick,
+    rng: StdRng,
+    spawn_points: Vec<Vec2>,
+    /// Players are kept inside these, see [`LevelDef::bounds`].
+    bounds: Rect,
+    /// Fastest a player may move per second on the server, not counting dashes.
+    speed_cap: f32,
+    /// Ticks remote entities are drawn behind, see [`Instance::interpolate_remote`].
+    interpolation_delay: u64,
+    /// Where players fit, baked from the static colliders when the level loads.
+    navigation: NavGrid,
+    /// Entities spawned through [`Instance::spawn_player`], kept in step with
+    /// [`Instance::despawn`].
+    network_objects: HashMap<NetworkObject, Entity>,
+    /// Entities spawned with `broadcast` that clients haven't been told about yet, see
+    /// [`Instance::take_broadcast_spawns`].
+    pending_broadcasts: Vec<NetworkObject>,
 }
 
 #[derive(Debug)]
 pub struct LocalPlayer;
 
+/// How an entity spawned with [`Instance::spawn_entity`] moves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+pub enum EntityKind {
+    /// Never moves, like level geometry.
+    Static,
+    /// Moved by setting its [`Position`], like players.
+    Kinematic,
+}
+
+/// Collider of an entity spawned with [`Instance::spawn_entity`], centred on its position.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Encode, Decode)]
+pub enum EntityCollider {
+    Ball { radius: f32 },
+    Cuboid { half_extents: [f32; 2] },
+}
+
+impl EntityCollider {
+    fn builder(&self) -> ColliderBuilder {
+        match *self {
+            EntityCollider::Ball { radius } => ColliderBuilder::ball(radius),
+            EntityCollider::Cuboid {
+                half_extents: [x, y],
+            } => ColliderBuilder::cuboid(x, y),
+        }
+    }
+}
+
+/// Marks an entity whose spawn is sent to every client, see [`Instance::spawn_entity`].
 #[derive(Debug)]
-pub struct Player {}
+pub struct Broadcast;
+
+#[derive(Debug)]
+pub struct Player {
+    /// Radius of the player's collider.
+    pub radius: f32,
+}
 
 #[derive(Debug)]
 pub struct Position(pub Vec2);
 
+/// Lifts a world position onto the z = 0 plane, for anything that places objects in 3D.
+pub fn position_to_translation(position: Vec2) -> Vec3 {
+    Vec3::new(position.x, position.y, 0.0)
+}
+
+/// Drops the z of a 3D translation, the inverse of [`position_to_translation`].
+pub fn translation_to_position(translation: Vec3) -> Vec2 {
+    translation.xy()
+}
+
+/// Character name shown in a player's name tag.
+#[derive(Debug, Clone)]
+pub struct DisplayName(pub String);
+
 #[derive(Debug, Default)]
 pub struct LastInputTracker {
     pub order: u64,
@@ -45,18 +128,159 @@ impl Debug for Instance {
 
 impl Instance {
     pub fn new(id: Uuid) -> Instance {
+        Instance::with_level(id, &LevelDef::default())
+    }
+
+    pub fn with_level(id: Uuid, level: &LevelDef) -> Instance {
         let mut i = Instance {
             id,
             physics: Physics::new(),
             world: World::new(),
             tick: Tick::new(0),
+            rng: Self::seeded_rng(id),
+            spawn_points: level.spawn_points.clone(),
+            bounds: level.bounds,
+            speed_cap: PLAYER_SPEED,
+            interpolation_delay: INTERPOLATION_DELAY_TICKS,
+            navigation: NavGrid::open(level.bounds, NAV_CELL_SIZE),
+            network_objects: HashMap::new(),
+            pending_broadcasts: Vec::new(),
         };
 
-        i.spawn_obstacle();
+        for shape in &level.colliders {
+            i.spawn_static_collider(shape.clone());
+        }
+        i.bake_navigation(NAV_CELL_SIZE);
 
         i
     }
 
+    /// Seeds the instance RNG from its id, so an instance replays the same ids and content
+    /// every time it is created with that id.
+    fn seeded_rng(id: Uuid) -> StdRng {
+        let mut seed = <StdRng as SeedableRng>::Seed::default();
+        seed[..16].copy_from_slice(id.as_bytes());
+        StdRng::from_seed(seed)
+    }
+
+    pub fn rng_mut(&mut self) -> &mut StdRng {
+        &mut self.rng
+    }
+
+    pub fn new_network_object(&mut self) -> NetworkObject {
+        NetworkObject::new_from_rng(&mut self.rng)
+    }
+
+    pub fn get_spawn_points(&self) -> &[Vec2] {
+        &self.spawn_points
+    }
+
+    pub fn get_bounds(&self) -> Rect {
+        self.bounds
+    }
+
+    pub fn get_speed_cap(&self) -> f32 {
+        self.speed_cap
+    }
+
+    /// Caps how far a player may move per second, on top of the cap on its dashes. Defaults
+    /// to [`PLAYER_SPEED`], the speed inputs move players at.
+    pub fn set_speed_cap(&mut self, speed_cap: f32) {
+        self.speed_cap = speed_cap;
+    }
+
+    /// The level as it stands now, ready to be saved and loaded again with
+    /// [`Instance::with_level`]. Only static colliders are exported; players and anything else
+    /// that moves are left out.
+    pub fn export_level(&self) -> LevelDef {
+        let mut query = self.world.query::<&CollisionShape>().without::<&Player>();
+
+        LevelDef {
+            colliders: query.iter().map(|(_, shape)| shape.clone()).collect(),
+            spawn_points: self.spawn_points.clone(),
+            bounds: self.bounds,
+        }
+    }
+
+    /// Picks the first spawn point where a player of `radius` would not overlap anything.
+    /// `reserved` holds the positions and radii handed out for players that have not been
+    /// spawned yet. Falls back to the origin when every point is taken.
+    pub fn find_spawn_point(&self, radius: f32, reserved: &[(Vec2, f32)]) -> Vec2 {
+        let ring = (0..SPAWN_RING_POINTS).map(|i| {
+            let angle = i as f32 / SPAWN_RING_POINTS as f32 * std::f32::consts::TAU;
+            Vec2::new(angle.cos(), angle.sin()) * SPAWN_RING_RADIUS
+        });
+
+        let candidates: Vec<Vec2> = if self.spawn_points.is_empty() {
+            std::iter::once(Vec2::zeros()).chain(ring).collect()
+        } else {
+            self.spawn_points.clone()
+        };
+
+        let shape = Ball::new(radius);
+
+        candidates
+            .into_iter()
+            .find(|candidate| {
+                reserved
+                    .iter()
+                    .all(|(pos, other)| pos.metric_distance(candidate) >= radius + other)
+                    && self.physics.is_free(*candidate, &shape)
+            })
+            .unwrap_or_else(Vec2::zeros)
+    }
+
+    /// Whether a player of `radius` standing at `point` would be inside the world bounds and
+    /// clear of the level's static colliders. Other players don't count. Sees the colliders
+    /// as of the last [`Instance::update`].
+    pub fn is_walkable(&self, point: Vec2, radius: f32) -> bool {
+        self.bounds.contains(point) && self.physics.is_free_of_fixed(point, &Ball::new(radius))
+    }
+
+    /// Rebakes the navigation grid from the static colliders with cells of `cell_size`. Levels
+    /// are baked at [`NAV_CELL_SIZE`] when they load; colliders spawned afterwards are only
+    /// seen by pathing once this is called again.
+    pub fn bake_navigation(&mut self, cell_size: f32) {
+        self.physics.update(&mut self.world);
+
+        let mut query = self.world.query::<&CollisionShape>();
+        let extent = query
+            .iter()
+            .filter_map(|(entity, _)| self.entity_aabb(entity))
+            .reduce(|a, b| {
+                Rect::new(
+                    Vec2::new(a.min.x.min(b.min.x), a.min.y.min(b.min.y)),
+                    Vec2::new(a.max.x.max(b.max.x), a.max.y.max(b.max.y)),
+                )
+            });
+        drop(query);
+
+        // Past a player's radius and a cell from every collider, everything is open anyway.
+        self.navigation = match extent {
+            Some(extent) => {
+                let margin = Vec2::repeat(PLAYER_RADIUS + cell_size);
+                let extent = Rect::new(
+                    self.bounds.clamp(extent.min - margin),
+                    self.bounds.clamp(extent.max + margin),
+                );
+                NavGrid::bake(extent, self.bounds, cell_size, |center| {
+                    self.is_walkable(center, PLAYER_RADIUS)
+                })
+            }
+            None => NavGrid::open(self.bounds, cell_size),
+        };
+    }
+
+    pub fn get_navigation(&self) -> &NavGrid {
+        &self.navigation
+    }
+
+    /// Waypoints for a player-sized walker from `from` to `to`, around the level's static
+    /// colliders. See [`NavGrid::find_path`].
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        self.navigation.find_path(from, to)
+    }
+
     pub fn get_world(&self) -> &World {
         &self.world
     }
@@ -69,6 +293,10 @@ impl Instance {
         &self.physics
     }
 
+    pub fn get_physics_mut(&mut self) -> &mut Physics {
+        &mut self.physics
+    }
+
     pub fn get_id(&self) -> Uuid {
         self.id
     }
@@ -85,59 +313,181 @@ impl Instance {
         self.tick = tick;
     }
 
-    pub fn find_network_object(&self, needle: NetworkObject) -> Option<Entity> {
-        for (entity, net_obj) in &mut self.world.query::<&NetworkObject>() {
-            if needle == *net_obj {
-                return Some(entity);
+    /// Changes the display name of the player owning `net_obj`, returning whether one exists.
+    pub fn rename_player(&mut self, net_obj: NetworkObject, name: String) -> bool {
+        let Some(entity) = self.find_network_object(net_obj) else {
+            return false;
+        };
+
+        match self.world.get::<&mut DisplayName>(entity) {
+            Ok(mut display_name) => {
+                display_name.0 = name;
+                true
             }
+            Err(_) => false,
         }
+    }
 
-        None
+    pub fn player_count(&self) -> usize {
+        self.world.query::<()>().with::<&Player>().iter().count()
     }
 
-    pub fn spawn_obstacle(
-        &mut self,
-    ) -> Entity {
-        let pos = Vec2::new(512.0, 384.0);
+    /// Every player's network object and position, in no particular order.
+    pub fn iter_players(&self) -> impl Iterator<Item = (NetworkObject, Vec2)> {
+        // The query borrows the world, so the pairs are collected before handing them out.
+        self.world
+            .query::<(&NetworkObject, &Position)>()
+            .with::<&Player>()
+            .iter()
+            .map(|(_, (net_obj, position))| (*net_obj, position.0))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    /// Entities with a network object, spawned through [`Instance::spawn_entity`] or
+    /// [`Instance::spawn_player`].
+    pub fn network_object_count(&self) -> usize {
+        self.network_objects.len()
+    }
+
+    /// Looks `needle` up in the index of networked entities.
+    pub fn find_network_object(&self, needle: NetworkObject) -> Option<Entity> {
+        let found = self.network_objects.get(&needle).copied().filter(|entity| {
+            self.world
+                .get::<&NetworkObject>(*entity)
+                .is_ok_and(|net_obj| *net_obj == needle)
+        });
+
+        debug_assert!(
+            found.is_some()
+                || !self
+                    .world
+                    .query::<&NetworkObject>()
+                    .iter()
+                    .any(|(_, net_obj)| *net_obj == needle),
+            "{needle:?} is in the world but missing from the index"
+        );
+
+        found
+    }
+
+    /// Bounding box of the entity's collider, placed at its synced [`Position`] rather than
+    /// wherever the physics body was last moved to.
+    pub fn entity_aabb(&self, entity: Entity) -> Option<Rect> {
+        let mut query = self
+            .world
+            .query_one::<(&Position, &ColliderHandle)>(entity)
+            .ok()?;
+        let (position, collider) = query.get()?;
+
+        self.physics.collider_aabb(*collider, position.0)
+    }
+
+    pub fn spawn_static_collider(&mut self, shape: CollisionShape) -> Entity {
+        let (pos, collider) = match &shape {
+            CollisionShape::Rectangle { min, max } | CollisionShape::Wall { min, max } => {
+                let half_extents = (max - min) / 2.0;
+                (
+                    min + half_extents,
+                    ColliderBuilder::cuboid(half_extents.x, half_extents.y),
+                )
+            }
+            CollisionShape::Circle { center, radius } => (*center, ColliderBuilder::ball(*radius)),
+        };
 
         let mut e = EntityBuilder::new();
-        e.add(Position(pos));
+        e.add(Position(pos)).add(shape);
 
         let rb = self
             .physics
             .insert_rigid_body(RigidBodyBuilder::fixed().position(pos.into()));
 
-        let coll = self
-            .physics
-            .insert_collider_with_parent(ColliderBuilder::cuboid(256.0, 128.0), rb);
+        let coll = self.physics.insert_collider_with_parent(collider, rb);
 
         e.add(rb).add(coll);
 
         self.world.spawn(e.build())
     }
 
-    pub fn spawn_player(
+    /// Spawns a networked entity with a collider at `position`. With `broadcast`, every
+    /// client is sent its spawn once the server picks it up from
+    /// [`Instance::take_broadcast_spawns`], and late joiners find it in their snapshot.
+    pub fn spawn_entity(
         &mut self,
-        local_player: bool,
+        kind: EntityKind,
         position: Vec2,
+        collider: EntityCollider,
         net_obj: NetworkObject,
-        tick: Option<Tick>,
+        broadcast: bool,
     ) -> Entity {
-        let mut e = EntityBuilder::new();
-        e.add(Player {})
-            .add(Position(position))
-            .add(net_obj)
-            .add(LastInputTracker::default());
-
+        let body = match kind {
+            EntityKind::Static => RigidBodyBuilder::fixed(),
+            EntityKind::Kinematic => RigidBodyBuilder::kinematic_position_based(),
+        };
         let rb = self
             .physics
-            .insert_rigid_body(RigidBodyBuilder::kinematic_position_based());
+            .insert_rigid_body(body.position(position.into()));
 
         let coll = self
             .physics
-            .insert_collider_with_parent(ColliderBuilder::ball(50.0), rb);
+            .insert_collider_with_parent(collider.builder(), rb);
 
-        e.add(rb).add(coll);
+        let mut e = EntityBuilder::new();
+        e.add(Position(position))
+            .add(kind)
+            .add(collider)
+            .add(net_obj)
+            .add(rb)
+            .add(coll);
+
+        if broadcast {
+            e.add(Broadcast);
+            self.pending_broadcasts.push(net_obj);
+        }
+
+        let entity = self.world.spawn(e.build());
+        self.network_objects.insert(net_obj, entity);
+        entity
+    }
+
+    /// Network objects spawned with `broadcast` since the last call.
+    pub fn take_broadcast_spawns(&mut self) -> Vec<NetworkObject> {
+        std::mem::take(&mut self.pending_broadcasts)
+    }
+
+    /// Every entity spawned with `broadcast` that is still around.
+    pub fn iter_broadcast(&self) -> impl Iterator<Item = NetworkObject> {
+        self.world
+            .query::<&NetworkObject>()
+            .with::<&Broadcast>()
+            .iter()
+            .map(|(_, net_obj)| *net_obj)
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+
+    pub fn spawn_player(
+        &mut self,
+        local_player: bool,
+        position: Vec2,
+        name: String,
+        net_obj: NetworkObject,
+        radius: f32,
+        tick: Option<Tick>,
+    ) -> Entity {
+        let entity = self.spawn_entity(
+            EntityKind::Kinematic,
+            position,
+            EntityCollider::Ball { radius },
+            net_obj,
+            false,
+        );
+
+        let mut e = EntityBuilder::new();
+        e.add(Player { radius })
+            .add(DisplayName(name))
+            .add(LastInputTracker::default())
+            .add(Abilities::default());
 
         if local_player {
             e.add(LocalPlayer);
@@ -147,7 +497,8 @@ impl Instance {
             e.add(LastSyncTracker::<Position>::new(tick));
         }
 
-        self.world.spawn(e.build())
+        self.world.insert(entity, e.build()).unwrap();
+        entity
     }
 
     pub fn despawn(&mut self, entity: Entity) {
@@ -159,9 +510,72 @@ impl Instance {
             Err(hecs::QueryOneError::NoSuchEntity) => return,
         }
 
+        if let Ok(net_obj) = self.world.get::<&NetworkObject>(entity) {
+            self.network_objects.remove(&*net_obj);
+        }
+
         self.world.despawn(entity).unwrap();
     }
 
+    /// Marks a remote entity as eased between syncs or snapped to them. See [`Interpolate`].
+    pub fn set_interpolate(&mut self, entity: Entity, interpolate: bool) {
+        let _ = self.world.insert(
+            entity,
+            (Interpolate(interpolate), InterpolationBuffer::default()),
+        );
+    }
+
+    /// Applies a position sync from the server to a remote entity. Interpolated entities
+    /// queue it for [`Instance::interpolate_remote`]; the rest snap to it straight away.
+    pub fn sync_remote_position(&mut self, entity: Entity, tick: Tick, position: Vec2) {
+        let Ok((current, interpolate, buffer)) = self.world.query_one_mut::<(
+            &mut Position,
+            Option<&Interpolate>,
+            Option<&mut InterpolationBuffer>,
+        )>(entity) else {
+            return;
+        };
+
+        match buffer {
+            Some(buffer) if interpolate.is_none_or(|interpolate| interpolate.0) => {
+                buffer.push(tick, position);
+            }
+            _ => current.0 = position,
+        }
+    }
+
+    pub fn get_interpolation_delay(&self) -> u64 {
+        self.interpolation_delay
+    }
+
+    /// Sets how many ticks behind remote entities are drawn. Starts at
+    /// [`INTERPOLATION_DELAY_TICKS`]; a client on a jittery connection raises it so a newer
+    /// sync has usually arrived by the time it is needed.
+    pub fn set_interpolation_delay(&mut self, ticks: u64) {
+        self.interpolation_delay = ticks;
+    }
+
+    /// Moves interpolated remote entities to where they were
+    /// [`Instance::get_interpolation_delay`] ticks ago.
+    pub fn interpolate_remote(&mut self) {
+        let render_tick = Tick::new(self.tick.get().saturating_sub(self.interpolation_delay));
+
+        for (_, (position, interpolate, buffer)) in self
+            .world
+            .query_mut::<(&mut Position, &Interpolate, &mut InterpolationBuffer)>()
+            .without::<&LocalPlayer>()
+        {
+            if !interpolate.0 {
+                continue;
+            }
+
+            buffer.discard_before(render_tick);
+            if let Some(sampled) = buffer.sample(render_tick) {
+                position.0 = sampled;
+            }
+        }
+    }
+
     pub fn update_tick(&mut self) {
         self.tick.increment();
     }
@@ -174,31 +588,54 @@ impl Instance {
     }
 
     pub fn apply_inputs(&mut self, dt: f32, net_obj_inputs: &HashMap<NetworkObject, OrderedInput>) {
-        for (_, (position, net_obj, last_input, collider, rigid_body, _)) in
+        for (_, (position, net_obj, last_input, abilities, collider, rigid_body, _)) in
             self.world.query_mut::<(
                 &mut Position,
                 &NetworkObject,
                 &mut LastInputTracker,
+                &mut Abilities,
                 &ColliderHandle,
                 &RigidBodyHandle,
                 &mut Player,
             )>()
         {
             if let Some(input) = net_obj_inputs.get(net_obj) {
-                apply_input(
+                let start = position.0;
+
+                let performed = apply_input(
                     &self.physics,
                     position,
-                    &input.input,
+                    input,
+                    abilities,
                     *collider,
                     *rigid_body,
                     dt,
                 );
 
+                // The server is authoritative over how far a player gets each tick, whatever
+                // the client sent.
+                let mut max_distance = self.speed_cap * dt;
+                if performed == Some(PlayerAction::Dash) {
+                    max_distance += DASH_DISTANCE;
+                }
+                let moved = position.0 - start;
+                if moved.norm() > max_distance * 1.001 {
+                    warn!(
+                        "{net_obj:?} moved {} in one tick, clamping to {max_distance}",
+                        moved.norm()
+                    );
+                    position.0 = start + moved.normalize() * max_distance;
+                }
+
+                position.0 = self.bounds.clamp(position.0);
+
                 last_input.order = input.order;
             }
         }
     }
 
+    /// Resets the player to the synced position and replays `inputs` on top of it. Returns
+    /// the correction, from where the player was predicted to be to where it ended up.
     pub fn check_and_rollback<F>(
         &mut self,
         player: Entity,
@@ -206,37 +643,49 @@ impl Instance {
         dt: f32,
         inputs: Vec<OrderedInput>,
         mut save_snapshot: F,
-    ) where
+    ) -> Option<Vec2>
+    where
         F: FnMut(Vec2),
     {
-        let Ok((position, collider, rigid_body)) =
-            self.world
-                .query_one_mut::<(&mut Position, &ColliderHandle, &RigidBodyHandle)>(player)
-        else {
-            return;
+        let Ok((position, abilities, collider, rigid_body)) = self.world.query_one_mut::<(
+            &mut Position,
+            &mut Abilities,
+            &ColliderHandle,
+            &RigidBodyHandle,
+        )>(player) else {
+            return None;
         };
 
-        position.0 = Vec2::new(owned_player_sync.position[0], owned_player_sync.position[1]);
+        let predicted = position.0;
+        position.0 = owned_player_sync.get_position();
+        *abilities = owned_player_sync.get_abilities();
 
         for input in inputs {
             apply_input(
                 &self.physics,
                 position,
-                &input.input,
+                &input,
+                abilities,
                 *collider,
                 *rigid_body,
                 dt,
             );
+            // Clamped like on the server, so the replay ends where the server put the player.
+            position.0 = self.bounds.clamp(position.0);
 
             save_snapshot(position.0);
         }
+
+        Some(position.0 - predicted)
     }
 
-    pub fn apply_input(&mut self, player: Entity, input: &PlayerInput, dt: f32) -> Option<Vec2> {
-        let Ok((position, collider, rigid_body)) =
-            self.world
-                .query_one_mut::<(&mut Position, &ColliderHandle, &RigidBodyHandle)>(player)
-        else {
+    pub fn apply_input(&mut self, player: Entity, input: &OrderedInput, dt: f32) -> Option<Vec2> {
+        let Ok((position, abilities, collider, rigid_body)) = self.world.query_one_mut::<(
+            &mut Position,
+            &mut Abilities,
+            &ColliderHandle,
+            &RigidBodyHandle,
+        )>(player) else {
             return None;
         };
 
@@ -244,17 +693,19 @@ impl Instance {
             &self.physics,
             position,
             input,
+            abilities,
             *collider,
             *rigid_body,
             dt,
         );
+        position.0 = self.bounds.clamp(position.0);
 
         Some(position.0)
     }
 
-    pub fn print_player_positions(&mut self) {
-        for (_, position) in self.world.query_mut::<&Position>().with::<&Player>() {
-            info!("{position:?}");
+    pub fn print_player_positions(&self) {
+        for (net_obj, position) in self.iter_players() {
+            info!("{net_obj:?}: {position:?}");
         }
     }
 }
@@ -0,0 +1,131 @@
+use std::collections::VecDeque;
+
+use crate::{Vec2, tick::Tick};
+
+/// Ticks remote entities are drawn behind the client's tick, so there is usually a newer
+/// sync to ease towards.
+pub const INTERPOLATION_DELAY_TICKS: u64 = 3;
+
+/// Most ticks [`DelayEstimate::interpolation_delay_ticks`] asks for, however bad the jitter.
+pub const MAX_INTERPOLATION_DELAY_TICKS: u64 = 12;
+
+/// Share of each new measurement mixed into [`DelayEstimate`], the gain RTP uses for jitter.
+const DELAY_SMOOTHING: f32 = 1.0 / 16.0;
+
+/// Syncs kept per entity. Older ones are dropped even if nothing has been drawn past them.
+pub const MAX_INTERPOLATION_SAMPLES: usize = 16;
+
+/// Whether a remote entity eases between position syncs. Entities that move discontinuously
+/// on purpose, like projectiles or teleporting bosses, set this to `false` and snap to every
+/// sync instead. The instance sends entities without it as interpolated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Interpolate(pub bool);
+
+/// Received positions of a remote entity, oldest first.
+#[derive(Debug, Default)]
+pub struct InterpolationBuffer {
+    samples: VecDeque<(Tick, Vec2)>,
+}
+
+impl InterpolationBuffer {
+    /// Adds a sync. Syncs at or before the newest one are ignored.
+    pub fn push(&mut self, tick: Tick, position: Vec2) {
+        if self.samples.back().is_some_and(|(last, _)| *last >= tick) {
+            return;
+        }
+
+        self.samples.push_back((tick, position));
+        while self.samples.len() > MAX_INTERPOLATION_SAMPLES {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// The newest sync, which is where the server last put the entity.
+    pub fn get_latest(&self) -> Option<Vec2> {
+        self.samples.back().map(|(_, position)| *position)
+    }
+
+    /// The position at `tick`, eased between the syncs around it. Before the first sync it
+    /// is the first position and after the last it holds the last one.
+    pub fn sample(&self, tick: Tick) -> Option<Vec2> {
+        let next = self.samples.iter().position(|(t, _)| *t >= tick);
+
+        match next {
+            None => self.samples.back().map(|(_, position)| *position),
+            Some(0) => self.samples.front().map(|(_, position)| *position),
+            Some(i) => {
+                let (from_tick, from) = self.samples[i - 1];
+                let (to_tick, to) = self.samples[i];
+                let t = (tick.get() - from_tick.get()) as f32
+                    / (to_tick.get() - from_tick.get()) as f32;
+                Some(from.lerp(&to, t))
+            }
+        }
+    }
+
+    /// Drops syncs that can no longer be drawn, keeping the one just before `tick`.
+    pub fn discard_before(&mut self, tick: Tick) {
+        while self.samples.get(1).is_some_and(|(t, _)| *t <= tick) {
+            self.samples.pop_front();
+        }
+    }
+}
+
+/// Smoothed one-way delay and jitter of the syncs a client receives, measured from the
+/// server's send time in each sync.
+///
+/// The delay is relative to the server's clock, so it is off by however far the two clocks
+/// disagree and can even be negative. The jitter only compares syncs with each other, so a
+/// steady skew cancels out of it.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct DelayEstimate {
+    delay_millis: Option<f32>,
+    jitter_millis: f32,
+    /// Unsmoothed delay of the previous sync, which jitter is measured against.
+    last_delay_millis: Option<f32>,
+}
+
+impl DelayEstimate {
+    /// Adds a sync the server sent at `sent_unix_millis`, received at `received_unix_millis`
+    /// by the client's clock.
+    pub fn record(&mut self, sent_unix_millis: u128, received_unix_millis: u128) {
+        let delay = received_unix_millis as f64 - sent_unix_millis as f64;
+        let delay = delay as f32;
+
+        if let Some(last) = self.last_delay_millis {
+            let deviation = (delay - last).abs();
+            self.jitter_millis += (deviation - self.jitter_millis) * DELAY_SMOOTHING;
+        }
+        self.last_delay_millis = Some(delay);
+
+        self.delay_millis = Some(match self.delay_millis {
+            Some(smoothed) => smoothed + (delay - smoothed) * DELAY_SMOOTHING,
+            None => delay,
+        });
+    }
+
+    /// Smoothed one-way delay, or `None` before the first timestamped sync.
+    pub fn get_delay_millis(&self) -> Option<f32> {
+        self.delay_millis
+    }
+
+    pub fn get_jitter_millis(&self) -> f32 {
+        self.jitter_millis
+    }
+
+    /// Interpolation delay that rides out the current jitter: [`INTERPOLATION_DELAY_TICKS`]
+    /// plus twice the jitter, capped at [`MAX_INTERPOLATION_DELAY_TICKS`].
+    pub fn interpolation_delay_ticks(&self, tick_rate: u32) -> u64 {
+        let jitter_ticks = (2.0 * self.jitter_millis * tick_rate as f32 / 1000.0).ceil() as u64;
+
+        (INTERPOLATION_DELAY_TICKS + jitter_ticks).min(MAX_INTERPOLATION_DELAY_TICKS)
+    }
+}
@@ -1,26 +1,58 @@
+pub mod ability;
+pub mod bimap;
+pub mod chunk;
+pub mod compress;
 pub mod game;
+pub mod handoff;
 pub mod instance;
+pub mod interpolation;
 pub mod message;
+pub mod navigation;
 pub mod net_obj;
 pub mod physics;
 pub mod player;
+pub mod ready;
 pub mod result;
 pub mod tick;
+pub mod timestep;
 
 use std::time::Duration;
 
 use rapier2d::na::{Vector2, Vector3, Vector4};
 pub use result::{Error, Result, ResultExt};
+pub use timestep::FixedTimestep;
+
+/// Bumped whenever the layout or meaning of a network message changes, so a stale
+/// client is turned away instead of silently misreading messages.
+pub const PROTOCOL_VERSION: u32 = 26;
+
+/// Default simulation rate in ticks per second. Instances may run at a different rate, which
+/// they announce in every [`message::TickSync`].
+pub const TICK_RATE: u32 = 60;
 
 /// 60 FPS
 pub const DT: Duration = Duration::from_nanos(16666666);
 
+/// Length of one tick at `tick_rate` ticks per second.
+pub fn tick_duration(tick_rate: u32) -> Duration {
+    Duration::from_secs(1) / tick_rate
+}
+
 pub type Vec2 = Vector2<f32>;
 pub type Vec3 = Vector3<f32>;
 pub type Vec4 = Vector4<f32>;
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[derive(
+    Debug,
+    Clone,
+    Copy,
+    PartialEq,
+    bytemuck::Pod,
+    bytemuck::Zeroable,
+    serde::Serialize,
+    serde::Deserialize,
+)]
 pub struct Rect {
     pub min: Vec2,
     pub max: Vec2,
@@ -38,6 +70,18 @@ impl Rect {
     pub fn height(&self) -> f32 {
         self.max.y - self.min.y
     }
+
+    pub fn contains(&self, point: Vec2) -> bool {
+        (self.min.x..=self.max.x).contains(&point.x) && (self.min.y..=self.max.y).contains(&point.y)
+    }
+
+    /// The point inside the rectangle closest to `point`.
+    pub fn clamp(&self, point: Vec2) -> Vec2 {
+        Vec2::new(
+            point.x.clamp(self.min.x, self.max.x),
+            point.y.clamp(self.min.y, self.max.y),
+        )
+    }
 }
 
 pub use hecs::Entity;
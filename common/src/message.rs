@@ -1,18 +1,90 @@
-use bincode::{Decode, Encode};
+use bincode::{
+    Decode, Encode,
+    config::{Configuration, LittleEndian, Limit, Varint},
+};
+use renet_netcode::ConnectToken;
 use serde::{Deserialize, Serialize};
 
-use crate::{net_obj::NetworkObject, player::PlayerInput, tick::Tick};
+use crate::{
+    Error, Result, Vec2,
+    ability::{Abilities, AbilityId},
+    instance::{EntityCollider, EntityKind},
+    net_obj::NetworkObject,
+    player::PlayerInput,
+    tick::Tick,
+};
+
+/// Largest message, in bytes, a peer may make us decode. Anything claiming more is
+/// rejected before its contents are allocated.
+pub const MAX_MESSAGE_SIZE: usize = 64 * 1024;
+
+/// The bincode configuration every network message is encoded and decoded with.
+pub const MESSAGE_CONFIG: Configuration<LittleEndian, Varint, Limit<MAX_MESSAGE_SIZE>> =
+    bincode::config::standard().with_limit::<MAX_MESSAGE_SIZE>();
+
+/// Encodes a network message with [`MESSAGE_CONFIG`]. bincode measures the message before
+/// writing it, so this makes a single exact-size allocation, which renet then takes
+/// ownership of.
+pub fn encode<T: Encode>(message: &T) -> Result<Vec<u8>> {
+    let bytes = bincode::encode_to_vec(message, MESSAGE_CONFIG)?;
+    Ok(bytes)
+}
+
+/// Encodes an [`UnreliableMessageFromServer::PlayerPositionSyncs`] from syncs already encoded
+/// with [`encode`], so a sync sent to many clients is only encoded once.
+pub fn encode_position_syncs(syncs: &[&[u8]]) -> Result<Vec<u8>> {
+    let mut bytes = encode(&UnreliableMessageFromServer::PlayerPositionSyncs(Vec::new()))?;
+    // An empty list ends in its length, a single zero byte.
+    bytes.pop();
+    bytes.extend(encode(&(syncs.len() as u64))?);
+    for sync in syncs {
+        bytes.extend_from_slice(sync);
+    }
+    Ok(bytes)
+}
+
+/// Decodes a network message with [`MESSAGE_CONFIG`].
+pub fn decode<T: Decode<()>>(bytes: &[u8]) -> Result<T> {
+    let (message, _) = bincode::decode_from_slice(bytes, MESSAGE_CONFIG)?;
+    Ok(message)
+}
+
+/// Reads a [`ConnectToken`] received over the network. Truncated or garbage bytes
+/// become [`Error::InvalidToken`] so callers can refuse to connect instead of crashing.
+pub fn read_connect_token(bytes: &[u8]) -> Result<ConnectToken> {
+    ConnectToken::read(&mut &bytes[..]).map_err(|_| Error::InvalidToken)
+}
 
 #[derive(Debug, Serialize, Deserialize, Encode, Decode)]
 pub struct TickSync {
     pub tick: u64,
+    /// The instance's wall-clock time at `tick`.
     pub unix_millis: u128,
+    /// Ticks per second the instance simulates at.
+    pub tick_rate: u32,
+    /// Bumped whenever the tick stops following the wall-clock at a steady rate, e.g. on a
+    /// pause or a tick rate change. Only syncs from the same timeline measure the tick rate,
+    /// see [`crate::tick::TickClock`].
+    pub timeline: u32,
 }
 
 #[derive(Debug, Serialize, Deserialize, Encode, Decode)]
 #[non_exhaustive]
 pub enum NetworkSpawn {
-    Player([f32; 2]),
+    Player {
+        position: [f32; 2],
+        name: String,
+        radius: f32,
+        /// Whether the client eases between position syncs rather than snapping. See
+        /// [`crate::interpolation::Interpolate`].
+        interpolate: bool,
+    },
+    /// Anything else spawned with [`crate::instance::Instance::spawn_entity`].
+    Entity {
+        kind: EntityKind,
+        position: [f32; 2],
+        collider: EntityCollider,
+    },
 }
 
 #[derive(Debug, Serialize, Deserialize, Encode, Decode)]
@@ -22,10 +94,19 @@ pub struct Spawn {
     pub tick: Tick,
 }
 
+/// One entity in a [`ReliableMessageFromServer::Snapshot`].
+#[derive(Debug, Serialize, Deserialize, Encode, Decode)]
+pub struct SpawnEntry {
+    pub net_obj: NetworkObject,
+    pub net_spawn: NetworkSpawn,
+}
+
 #[derive(Debug, Serialize, Deserialize, Encode, Decode)]
 pub struct PlayerInit {
     pub net_obj: NetworkObject,
     pub position: [f32; 2],
+    pub name: String,
+    pub radius: f32,
     pub tick: Tick,
 }
 
@@ -34,8 +115,42 @@ pub enum ReliableMessageFromServer {
     InstanceId([u8; 16]),
     TickSync(TickSync),
     Spawn(Spawn),
+    /// Every entity in the instance, sent once to a client that just became ready for
+    /// updates. Entities spawned afterwards arrive as individual [`Spawn`]s.
+    Snapshot {
+        tick: Tick,
+        entries: Vec<SpawnEntry>,
+    },
     PlayerInit(PlayerInit),
     Despawn(NetworkObject),
+    VersionMismatch { server_version: u32 },
+    /// Everyone currently in the instance, sent once after connecting.
+    PlayerList(Vec<(NetworkObject, String)>),
+    PlayerJoined(NetworkObject, String),
+    PlayerLeft(NetworkObject),
+    PlayerRenamed(NetworkObject, String),
+    PauseState { paused: bool },
+    /// Sent right before the instance disconnects the client, with the reason to show.
+    Kicked(String),
+    /// The instance is closing and will disconnect everyone shortly.
+    ServerShutdown,
+    /// The instance has no room for another player or spectator and is about to disconnect
+    /// the client.
+    ServerFull,
+    /// The client may now send inputs for this entity besides its own player, see
+    /// [`OrderedInput::target`].
+    ControlGranted(NetworkObject),
+    /// The client no longer controls this entity.
+    ControlRevoked(NetworkObject),
+    /// The outcome of an [`AdminCommand`] the client sent.
+    AdminReply(String),
+    /// The client sent an [`AdminCommand`] but isn't the instance's owner.
+    AdminRejected,
+    /// Sent instead of [`UnreliableMessageFromServer::OwnedPlayerSync`] after the server
+    /// missed a long run of the client's inputs. The client snaps to the position without
+    /// replaying its buffered inputs. Reliable, since until it arrives the client keeps
+    /// replaying inputs the server never got.
+    ForcedPlayerSync(OwnedPlayerSync),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
@@ -43,6 +158,16 @@ pub struct PlayerPositionSync {
     pub net_obj: NetworkObject,
     pub position: [f32; 2],
     pub tick: Tick,
+    /// Server wall clock, in milliseconds since the Unix epoch, when the sync was sent. Lets
+    /// the client estimate delay and jitter from the syncs it already receives, but only
+    /// relative to the server's clock, so the delay is off by however far the clocks disagree.
+    pub unix_millis: Option<u128>,
+}
+
+impl PlayerPositionSync {
+    pub fn get_position(&self) -> Vec2 {
+        self.position.into()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
@@ -51,26 +176,76 @@ pub struct OwnedPlayerSync {
     pub position: [f32; 2],
     pub tick: Tick,
     pub last_input_order: u64,
+    /// Inputs the server had applied to the player by then, the input tick its
+    /// [`Abilities`] cooldowns run on.
+    pub inputs_applied: u64,
+    /// End ticks of the player's [`Abilities`] cooldowns still running after that input,
+    /// restored before the client replays the inputs after it. Only the owner is sent these.
+    pub cooldowns: Vec<(AbilityId, Tick)>,
+    /// Server wall clock, in milliseconds since the Unix epoch, when the sync was sent. Lets
+    /// the client estimate delay and jitter from the syncs it already receives, but only
+    /// relative to the server's clock, so the delay is off by however far the clocks disagree.
+    pub unix_millis: Option<u128>,
+}
+
+impl OwnedPlayerSync {
+    pub fn get_position(&self) -> Vec2 {
+        self.position.into()
+    }
+
+    pub fn get_abilities(&self) -> Abilities {
+        Abilities::from_cooldowns(self.inputs_applied, self.cooldowns.iter().copied())
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Encode, Decode)]
 #[non_exhaustive]
 pub enum UnreliableMessageFromServer {
-    PlayerPositionSync(PlayerPositionSync),
+    /// Every player's position for one tick, sent to all clients at once so the server
+    /// encodes it once instead of once per player.
+    PlayerPositionSyncs(Vec<PlayerPositionSync>),
     OwnedPlayerSync(OwnedPlayerSync),
 }
 
 #[derive(Debug, Serialize, Deserialize, Encode, Decode)]
 #[non_exhaustive]
 pub enum ReliableMessageFromClient {
-    Connected,
+    Connected { protocol_version: u32 },
     ReadyForUpdates,
+    /// Only honoured while the sender is the sole player in the instance.
+    Pause,
+    Resume,
+    /// Joins without a player. The client is sent the tick, the player list and every
+    /// entity, but owns nothing until it sends [`ReliableMessageFromClient::Connected`].
+    Spectate { protocol_version: u32 },
+    /// Only honoured from the instance's owner. Answered with
+    /// [`ReliableMessageFromServer::AdminReply`] or [`ReliableMessageFromServer::AdminRejected`].
+    Admin(AdminCommand),
+    /// Sent before the client closes the connection for good. Its player is removed straight
+    /// away instead of being held for a reconnect, which a dropped connection would get.
+    Leave,
+}
+
+/// Instance management a client may ask for over the network, mirroring the backend's pipe
+/// commands.
+#[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
+#[non_exhaustive]
+pub enum AdminCommand {
+    /// Reply with the tick, player count and pause state.
+    Status,
+    /// Remove the player called `name`, showing them `reason`.
+    Kick { name: String, reason: String },
+    SetTickRate(u32),
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct OrderedInput {
     pub input: PlayerInput,
     pub order: u64,
+    /// Entity the input is for, when not the sender's own player. Only entities the client
+    /// was sent [`ReliableMessageFromServer::ControlGranted`] for are accepted. Orders count
+    /// up separately for each target.
+    pub target: Option<NetworkObject>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Encode, Decode)]
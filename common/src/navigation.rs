@@ -0,0 +1,243 @@
+use std::{
+    cmp::Ordering,
+    collections::{BinaryHeap, HashMap},
+};
+
+use crate::{Rect, Vec2};
+
+/// Default side of one navigation cell in world units, half a default player's width.
+pub const NAV_CELL_SIZE: f32 = 50.0;
+
+/// Cells [`NavGrid::find_path`] expands before giving up, so an unreachable goal in a huge
+/// open world fails quickly instead of searching all of it.
+pub const MAX_PATH_SEARCH: usize = 20_000;
+
+type Cell = (i32, i32);
+
+/// Coarse grid of which places a player fits, baked from the level's static colliders.
+///
+/// Only the area around the colliders is baked. Cells outside it are open as long as their
+/// centre is inside the world bounds.
+#[derive(Debug, Clone)]
+pub struct NavGrid {
+    cell_size: f32,
+    bounds: Rect,
+    /// First baked cell.
+    min_cell: Cell,
+    columns: i32,
+    rows: i32,
+    blocked: Vec<bool>,
+}
+
+impl NavGrid {
+    /// Bakes every cell of `cell_size` overlapping `extent`, asking `is_walkable` about its
+    /// centre.
+    pub fn bake(
+        extent: Rect,
+        bounds: Rect,
+        cell_size: f32,
+        is_walkable: impl Fn(Vec2) -> bool,
+    ) -> NavGrid {
+        let cell_size = cell_size.max(1.0);
+        let min_cell = cell_of(extent.min, cell_size);
+        let max_cell = cell_of(extent.max, cell_size);
+        let columns = (max_cell.0 - min_cell.0 + 1).max(0);
+        let rows = (max_cell.1 - min_cell.1 + 1).max(0);
+
+        let mut blocked = Vec::with_capacity((columns * rows) as usize);
+        for y in 0..rows {
+            for x in 0..columns {
+                let center = center_of((min_cell.0 + x, min_cell.1 + y), cell_size);
+                blocked.push(!is_walkable(center));
+            }
+        }
+
+        NavGrid {
+            cell_size,
+            bounds,
+            min_cell,
+            columns,
+            rows,
+            blocked,
+        }
+    }
+
+    /// A grid with nothing baked, where everything inside `bounds` is open.
+    pub fn open(bounds: Rect, cell_size: f32) -> NavGrid {
+        NavGrid {
+            cell_size: cell_size.max(1.0),
+            bounds,
+            min_cell: (0, 0),
+            columns: 0,
+            rows: 0,
+            blocked: Vec::new(),
+        }
+    }
+
+    pub fn get_cell_size(&self) -> f32 {
+        self.cell_size
+    }
+
+    /// Whether the cell containing `point` is open.
+    pub fn is_open(&self, point: Vec2) -> bool {
+        self.is_open_cell(cell_of(point, self.cell_size))
+    }
+
+    fn is_open_cell(&self, cell: Cell) -> bool {
+        let x = cell.0 - self.min_cell.0;
+        let y = cell.1 - self.min_cell.1;
+
+        if (0..self.columns).contains(&x) && (0..self.rows).contains(&y) {
+            !self.blocked[(y * self.columns + x) as usize]
+        } else {
+            self.bounds.contains(center_of(cell, self.cell_size))
+        }
+    }
+
+    /// Waypoints from `from` to `to` with A*, ending exactly at `to` and leaving out `from`.
+    /// Waypoints are cell centres, only kept where the path turns. `None` when `to` is
+    /// blocked or can't be reached within [`MAX_PATH_SEARCH`] cells.
+    pub fn find_path(&self, from: Vec2, to: Vec2) -> Option<Vec<Vec2>> {
+        let start = cell_of(from, self.cell_size);
+        let goal = cell_of(to, self.cell_size);
+
+        if !self.is_open_cell(goal) {
+            return None;
+        }
+
+        let mut open = BinaryHeap::new();
+        let mut came_from: HashMap<Cell, Cell> = HashMap::new();
+        let mut cost: HashMap<Cell, f32> = HashMap::new();
+
+        cost.insert(start, 0.0);
+        open.push(Candidate {
+            cell: start,
+            estimate: octile(start, goal),
+        });
+
+        let mut expanded = 0;
+        while let Some(Candidate { cell, estimate }) = open.pop() {
+            if cell == goal {
+                return Some(self.waypoints(&came_from, goal, to));
+            }
+
+            let cell_cost = cost[&cell];
+            // A cheaper route to this cell was found after this entry was queued.
+            if estimate > cell_cost + octile(cell, goal) {
+                continue;
+            }
+
+            expanded += 1;
+            if expanded > MAX_PATH_SEARCH {
+                return None;
+            }
+
+            for (dx, dy) in NEIGHBOURS {
+                let next = (cell.0 + dx, cell.1 + dy);
+                if !self.is_open_cell(next) {
+                    continue;
+                }
+
+                // Diagonal steps can't squeeze between two blocked corners.
+                if dx != 0
+                    && dy != 0
+                    && !(self.is_open_cell((cell.0 + dx, cell.1))
+                        && self.is_open_cell((cell.0, cell.1 + dy)))
+                {
+                    continue;
+                }
+
+                let step = if dx != 0 && dy != 0 {
+                    std::f32::consts::SQRT_2
+                } else {
+                    1.0
+                };
+                let next_cost = cell_cost + step;
+                if cost.get(&next).is_some_and(|known| *known <= next_cost) {
+                    continue;
+                }
+
+                cost.insert(next, next_cost);
+                came_from.insert(next, cell);
+                open.push(Candidate {
+                    cell: next,
+                    estimate: next_cost + octile(next, goal),
+                });
+            }
+        }
+
+        None
+    }
+
+    fn waypoints(&self, came_from: &HashMap<Cell, Cell>, goal: Cell, to: Vec2) -> Vec<Vec2> {
+        let mut cells = vec![goal];
+        while let Some(previous) = came_from.get(cells.last().unwrap()) {
+            cells.push(*previous);
+        }
+        cells.reverse();
+
+        // Keep the cells where the direction changes, skipping the start and the goal.
+        let mut waypoints: Vec<Vec2> = cells
+            .windows(3)
+            .filter(|w| (w[1].0 - w[0].0, w[1].1 - w[0].1) != (w[2].0 - w[1].0, w[2].1 - w[1].1))
+            .map(|w| center_of(w[1], self.cell_size))
+            .collect();
+        waypoints.push(to);
+
+        waypoints
+    }
+}
+
+const NEIGHBOURS: [(i32, i32); 8] = [
+    (1, 0),
+    (-1, 0),
+    (0, 1),
+    (0, -1),
+    (1, 1),
+    (1, -1),
+    (-1, 1),
+    (-1, -1),
+];
+
+fn cell_of(point: Vec2, cell_size: f32) -> Cell {
+    (
+        (point.x / cell_size).floor() as i32,
+        (point.y / cell_size).floor() as i32,
+    )
+}
+
+fn center_of(cell: Cell, cell_size: f32) -> Vec2 {
+    Vec2::new(
+        (cell.0 as f32 + 0.5) * cell_size,
+        (cell.1 as f32 + 0.5) * cell_size,
+    )
+}
+
+/// Cheapest cost between two cells on an open grid, with diagonals costing √2.
+fn octile(a: Cell, b: Cell) -> f32 {
+    let dx = (a.0 - b.0).abs() as f32;
+    let dy = (a.1 - b.1).abs() as f32;
+
+    dx.max(dy) + (std::f32::consts::SQRT_2 - 1.0) * dx.min(dy)
+}
+
+/// A queued cell, ordered so the lowest estimate pops first from a [`BinaryHeap`].
+#[derive(Debug, PartialEq)]
+struct Candidate {
+    cell: Cell,
+    estimate: f32,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.estimate.total_cmp(&self.estimate)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
@@ -1,6 +1,7 @@
 use std::marker::PhantomData;
 
 use bincode::{Decode, Encode};
+use rand::Rng;
 use serde::{Deserialize, Serialize};
 
 use crate::tick::Tick;
@@ -18,6 +19,10 @@ impl NetworkObject {
         Self::Dynamic(rand::random())
     }
 
+    pub fn new_from_rng(rng: &mut impl Rng) -> Self {
+        Self::Dynamic(rng.random())
+    }
+
     pub fn new_static(id: u64) -> Self {
         Self::Static(id)
     }
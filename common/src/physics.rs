@@ -1,25 +1,53 @@
-use std::fmt::Debug;
+use std::{fmt::Debug, num::NonZeroUsize};
 
 use hecs::World;
 use rapier2d::{
     parry::query::{ShapeCastHit, ShapeCastOptions},
     prelude::*,
 };
+use tracing::warn;
 
-use crate::{Vec2, instance::Position};
+use crate::{DT, Rect, Vec2, instance::Position};
+
+/// Velocity solver iterations per step. More make stacked and pushing bodies stiffer at the
+/// cost of time per step.
+pub const SOLVER_ITERATIONS: usize = 4;
+
+/// Most substeps continuous collision detection takes per step, so fast projectiles don't
+/// tunnel through thin colliders.
+pub const MAX_CCD_SUBSTEPS: usize = 1;
+
+/// The integration parameters every [`Physics`] starts with, on the server and the client
+/// alike. They are pinned here rather than left to rapier's defaults, so upgrading rapier
+/// can't change how bodies move on one side only.
+///
+/// For the client's prediction to agree with the server, these must be the same on both:
+/// `num_solver_iterations`, `num_internal_pgs_iterations`,
+/// `num_additional_friction_iterations`, `max_ccd_substeps`, `length_unit` and the
+/// `normalized_*` error and prediction distances. Even then results only match bit for bit
+/// on the same platform, since rapier's `enhanced-determinism` feature isn't enabled.
+///
+/// `dt` only starts out as [`DT`]. An instance can run at another tick rate, so a step has
+/// to be given the duration of the tick it simulates rather than rely on it.
+pub fn integration_parameters() -> IntegrationParameters {
+    IntegrationParameters {
+        dt: DT.as_secs_f32(),
+        num_solver_iterations: NonZeroUsize::new(SOLVER_ITERATIONS).unwrap(),
+        max_ccd_substeps: MAX_CCD_SUBSTEPS,
+        ..IntegrationParameters::default()
+    }
+}
 
 pub struct Physics {
     rigid_body_set: RigidBodySet,
     collider_set: ColliderSet,
-    physics_pipeline: PhysicsPipeline,
     island_manager: IslandManager,
-    broad_phase: DefaultBroadPhase,
-    narrow_phase: NarrowPhase,
     impulse_joint_set: ImpulseJointSet,
     multibody_joint_set: MultibodyJointSet,
-    ccd_solver: CCDSolver,
     query_pipeline: QueryPipeline,
     integration_parameters: IntegrationParameters,
+    /// Smallest half extent of any fixed collider as of the last [`Physics::update`].
+    min_fixed_half_extent: Option<f32>,
 }
 
 impl Debug for Physics {
@@ -36,40 +64,81 @@ impl Default for Physics {
 
 impl Physics {
     pub fn new() -> Physics {
+        Physics::with_parameters(integration_parameters())
+    }
+
+    /// Keeps `integration_parameters` instead of [`integration_parameters`]. Nothing steps the
+    /// simulation yet, so they are only stored for when dynamic bodies need one.
+    pub fn with_parameters(integration_parameters: IntegrationParameters) -> Physics {
         let rigid_body_set = RigidBodySet::new();
         let collider_set = ColliderSet::new();
 
-        let integration_parameters = IntegrationParameters::default();
-        let physics_pipeline = PhysicsPipeline::new();
         let island_manager = IslandManager::new();
-        let broad_phase = DefaultBroadPhase::new();
-        let narrow_phase = NarrowPhase::new();
         let impulse_joint_set = ImpulseJointSet::new();
         let multibody_joint_set = MultibodyJointSet::new();
-        let ccd_solver = CCDSolver::new();
         let query_pipeline = QueryPipeline::new();
 
         Physics {
             rigid_body_set,
             collider_set,
-            physics_pipeline,
             island_manager,
-            broad_phase,
-            narrow_phase,
             impulse_joint_set,
             multibody_joint_set,
-            ccd_solver,
             query_pipeline,
             integration_parameters,
+            min_fixed_half_extent: None,
         }
     }
 
+    pub fn get_integration_parameters(&self) -> &IntegrationParameters {
+        &self.integration_parameters
+    }
+
+    /// See [`integration_parameters`] for which fields the server and client must agree on.
+    pub fn set_integration_parameters(&mut self, integration_parameters: IntegrationParameters) {
+        self.integration_parameters = integration_parameters;
+    }
+
+    pub fn get_solver_iterations(&self) -> NonZeroUsize {
+        self.integration_parameters.num_solver_iterations
+    }
+
+    pub fn set_solver_iterations(&mut self, iterations: NonZeroUsize) {
+        self.integration_parameters.num_solver_iterations = iterations;
+    }
+
+    pub fn get_max_ccd_substeps(&self) -> usize {
+        self.integration_parameters.max_ccd_substeps
+    }
+
+    pub fn set_max_ccd_substeps(&mut self, substeps: usize) {
+        self.integration_parameters.max_ccd_substeps = substeps;
+    }
+
     pub fn update(&mut self, world: &mut World) {
-        for (_, (pos, rb)) in world.query_mut::<(&Position, &RigidBodyHandle)>() {
-            self.rigid_body_set[*rb].set_position(pos.0.into(), false);
+        for (entity, (pos, rb)) in world.query_mut::<(&Position, &RigidBodyHandle)>() {
+            // A body removed without despawning its entity shouldn't take the whole world
+            // down with it.
+            let Some(rigid_body) = self.rigid_body_set.get_mut(*rb) else {
+                warn!("Entity {entity:?} has a rigid body that was already removed");
+                continue;
+            };
+            rigid_body.set_position(pos.0.into(), false);
         }
 
         self.query_pipeline.update(&self.collider_set);
+
+        self.min_fixed_half_extent = self
+            .collider_set
+            .iter()
+            .filter(|(_, collider)| {
+                collider
+                    .parent()
+                    .and_then(|parent| self.rigid_body_set.get(parent))
+                    .is_none_or(|rigid_body| rigid_body.is_fixed())
+            })
+            .map(|(_, collider)| collider.shape().compute_local_aabb().half_extents().min())
+            .min_by(f32::total_cmp);
     }
 
     pub fn insert_rigid_body(&mut self, rigid_body: impl Into<RigidBody>) -> RigidBodyHandle {
@@ -100,6 +169,62 @@ impl Physics {
             .insert_with_parent(collider, rigid_body, &mut self.rigid_body_set)
     }
 
+    /// Smallest half extent of the collider's local bounding box, or `None` if it was removed.
+    pub fn min_half_extent(&self, collider: ColliderHandle) -> Option<f32> {
+        let collider = self.collider_set.get(collider)?;
+
+        Some(collider.shape().compute_local_aabb().half_extents().min())
+    }
+
+    /// Smallest half extent of the thinnest fixed collider as of the last [`Physics::update`],
+    /// or `None` without any.
+    pub fn min_fixed_half_extent(&self) -> Option<f32> {
+        self.min_fixed_half_extent
+    }
+
+    /// World-space bounding box of the collider when its body sits at `position`, or `None`
+    /// if it was removed.
+    pub fn collider_aabb(&self, collider: ColliderHandle, position: Vec2) -> Option<Rect> {
+        let collider = self.collider_set.get(collider)?;
+
+        let body_position: Isometry<Real> = position.into();
+        let position = match collider.position_wrt_parent() {
+            Some(relative) => body_position * relative,
+            None => body_position,
+        };
+
+        let aabb = collider.shape().compute_aabb(&position);
+
+        Some(Rect::new(aabb.mins.coords, aabb.maxs.coords))
+    }
+
+    /// Whether `shape` placed at `position` would overlap any collider.
+    pub fn is_free(&self, position: Vec2, shape: &dyn Shape) -> bool {
+        self.query_pipeline
+            .intersection_with_shape(
+                &self.rigid_body_set,
+                &self.collider_set,
+                &position.into(),
+                shape,
+                QueryFilter::default(),
+            )
+            .is_none()
+    }
+
+    /// Whether `shape` placed at `position` would overlap any fixed collider, ignoring
+    /// players and anything else that moves.
+    pub fn is_free_of_fixed(&self, position: Vec2, shape: &dyn Shape) -> bool {
+        self.query_pipeline
+            .intersection_with_shape(
+                &self.rigid_body_set,
+                &self.collider_set,
+                &position.into(),
+                shape,
+                QueryFilter::only_fixed(),
+            )
+            .is_none()
+    }
+
     pub fn cast_shape(
         &self,
         shape_position: Vec2,
@@ -5,38 +5,139 @@ use rapier2d::{
 };
 use serde::{Deserialize, Serialize};
 
-use crate::{Vec2, instance::Position, physics::Physics};
+use crate::{
+    Vec2,
+    ability::{Abilities, AbilityId},
+    instance::Position,
+    message::OrderedInput,
+    physics::Physics,
+};
+
+/// Distance a player moves per second.
+pub const PLAYER_SPEED: f32 = 500.0;
+
+/// Move directions with no component longer than this count as standing still, since
+/// normalizing them would mostly amplify rounding error.
+pub const MIN_MOVE_LENGTH: f32 = 1.0e-6;
+
+/// Extra distance a dash covers, on top of the tick's normal movement.
+pub const DASH_DISTANCE: f32 = 200.0;
+
+/// Shortest step a movement is split into, however thin the shapes involved. Every step is
+/// a shape cast, so this keeps thin walls from making movement arbitrarily expensive.
+const MIN_STEP: f32 = 0.5;
+
+/// A one-off action sent along with an input, predicted by the client like movement.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Encode, Decode)]
+#[non_exhaustive]
+pub enum PlayerAction {
+    /// Jumps [`DASH_DISTANCE`] further along the move direction. Does nothing while standing
+    /// still or while [`AbilityId::Dash`] is cooling down.
+    Dash,
+}
+
+impl PlayerAction {
+    /// The ability whose cooldown the action waits for.
+    pub fn get_ability(&self) -> AbilityId {
+        match self {
+            PlayerAction::Dash => AbilityId::Dash,
+        }
+    }
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize, Encode, Decode)]
 pub struct PlayerInput {
     pub move_direction: [f32; 2],
+    pub action: Option<PlayerAction>,
+}
+
+impl PlayerInput {
+    /// Unit vector to move along, or zero to stand still. See [`sanitize_direction`].
+    pub fn direction(&self) -> Vec2 {
+        sanitize_direction(self.move_direction.into())
+    }
+
+    /// The same input with its direction replaced by [`PlayerInput::direction`].
+    pub fn sanitized(&self) -> PlayerInput {
+        PlayerInput {
+            move_direction: self.direction().into(),
+            action: self.action,
+        }
+    }
+}
+
+/// Normalizes `direction`, or gives zero when it is non-finite or within
+/// [`MIN_MOVE_LENGTH`] of zero. Inputs come straight from clients, so a NaN or infinite direction
+/// must never reach a position.
+pub fn sanitize_direction(direction: Vec2) -> Vec2 {
+    if !direction.iter().all(|v| v.is_finite()) {
+        return Vec2::zeros();
+    }
+
+    // Dividing by the largest component first keeps huge but finite directions from
+    // overflowing the length.
+    let scale = direction.amax();
+    if scale < MIN_MOVE_LENGTH {
+        return Vec2::zeros();
+    }
+
+    let direction = direction / scale;
+    direction / direction.norm()
 }
 
+/// Moves the player by `input` and performs its action if `abilities` allow. Every input
+/// applied advances the cooldowns by one input tick. Returns the action performed, if any.
 #[profiling::function]
 pub fn apply_input(
     physics: &Physics,
     position: &mut Position,
-    input: &PlayerInput,
+    input: &OrderedInput,
+    abilities: &mut Abilities,
     shape: ColliderHandle,
     curr_player: RigidBodyHandle,
     dt: f32,
-) {
-    let speed = 500.0;
-    let movement = if input.move_direction == [0.0, 0.0] {
-        Vec2::zeros()
-    } else {
-        Vec2::from(input.move_direction).normalize() * speed * dt
+) -> Option<PlayerAction> {
+    let direction = input.input.direction();
+    let mut movement = direction * PLAYER_SPEED * dt;
+
+    let tick = abilities.next_tick();
+    let performed = match input.input.action {
+        Some(PlayerAction::Dash)
+            if direction != Vec2::zeros() && abilities.try_use(AbilityId::Dash, tick) =>
+        {
+            movement += direction * DASH_DISTANCE;
+            Some(PlayerAction::Dash)
+        }
+        _ => None,
     };
 
-    let out = move_character(
-        physics,
-        movement,
-        shape,
-        position.0,
-        QueryFilter::default().exclude_rigid_body(curr_player),
-    );
+    // A shape that starts within `offset` of a collider can be cast straight through it, so
+    // a large movement (high speed or a long frame) is split into steps. None is longer than
+    // the half extent of the shape or of the thinnest fixed collider, so a step never
+    // carries the shape's center past the middle of what it runs into, and the next cast
+    // still pushes it back out the side it came from.
+    let thinnest = physics.min_fixed_half_extent().unwrap_or(f32::INFINITY);
+    let max_step = physics
+        .min_half_extent(shape)
+        .unwrap_or(MIN_STEP)
+        .min(thinnest)
+        .max(MIN_STEP);
+    let steps = (movement.norm() / max_step).ceil().max(1.0);
+    let step = movement / steps;
+
+    for _ in 0..steps as usize {
+        let out = move_character(
+            physics,
+            step,
+            shape,
+            position.0,
+            QueryFilter::default().exclude_rigid_body(curr_player),
+        );
+
+        position.0 += out;
+    }
 
-    position.0 += out;
+    performed
 }
 
 #[profiling::function]
@@ -45,7 +146,7 @@ fn move_character(
     movement: Vec2,
     shape: ColliderHandle,
     shape_translation: Vec2,
-    mut filter: QueryFilter,
+    filter: QueryFilter,
 ) -> Vec2 {
     let mut translation_remaining = movement;
 
@@ -54,8 +155,13 @@ fn move_character(
     let offset = 2.0;
     let mut iters_remaining = 5;
 
+    let mut last_normal: Option<Vec2> = None;
+
+    // The collider we just hit stays part of `filter` on purpose: sliding along it can
+    // still run into it again (e.g. a concave corner formed by one shape), and excluding
+    // it would let the next cast pass straight through.
     while translation_remaining.norm_squared() > 1.0e-6 && iters_remaining > 0 {
-        if let Some((hit_entity, hit)) = physics.cast_shape(
+        if let Some((_, hit)) = physics.cast_shape(
             shape_translation + effective_translation,
             translation_remaining,
             shape,
@@ -68,20 +174,33 @@ fn move_character(
             filter,
         ) {
             // We hit something, compute and apply the allowed interference-free translation.
-            let allowed_dist = hit.time_of_impact;
-            let allowed_translation = movement * allowed_dist;
+            // The time of impact is relative to the cast velocity, which is the remaining
+            // translation rather than the full movement after the first slide.
+            let allowed_translation = translation_remaining * hit.time_of_impact;
             effective_translation += allowed_translation;
             translation_remaining -= allowed_translation;
 
             // Slide along hit normal plane projection
-            let projection = project_on_plane(translation_remaining, &hit.normal1);
+            let mut projection = project_on_plane(translation_remaining, &hit.normal1);
+
+            // If sliding along this surface heads back into the previous one, or back against
+            // the movement asked for, we are wedged in an inside corner. In 2D the crease
+            // between two planes is a single point, so stop instead of bouncing between both
+            // walls. The second check catches corners sharper than a right angle, where the
+            // slide off the first wall is turned away from the corner by the second.
+            if last_normal.is_some_and(|normal| projection.dot(&normal) < 0.0)
+                || projection.dot(&movement) < 0.0
+            {
+                projection = Vec2::zeros();
+            }
+
             if projection.norm_squared() > 1.0e-6 {
                 translation_remaining = projection.normalize() * translation_remaining.norm();
             } else {
                 translation_remaining = Vec2::zeros();
             }
 
-            // filter = filter.exclude_collider(hit_entity);
+            last_normal = Some(*hit.normal1);
         } else {
             // No interference along the path.
             effective_translation += translation_remaining;
@@ -0,0 +1,74 @@
+use std::{io::BufRead, net::SocketAddr, str::FromStr as _};
+
+use tracing::warn;
+
+use crate::{Error, Result};
+
+/// Prefix of the line an instance writes once it is listening, followed by its address.
+/// Anything else the reader sees before it, such as stray log output, is skipped.
+pub const READY_TAG: &str = "READY";
+
+/// The line announcing that an instance listens on `addr`, newline included.
+pub fn ready_line(addr: SocketAddr) -> String {
+    format!("{READY_TAG} {addr}\n")
+}
+
+/// Reads lines until the ready line and returns the address in it.
+pub fn read_ready(reader: &mut impl BufRead) -> Result<SocketAddr> {
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(Error::InstanceNotReady);
+        }
+
+        match line.trim().strip_prefix(READY_TAG) {
+            Some(addr) if addr.starts_with(' ') => return Ok(SocketAddr::from_str(addr.trim())?),
+            _ => warn!(
+                "Skipping line from instance before it was ready: {:?}",
+                line.trim()
+            ),
+        }
+    }
+}
+
+/// Prefix of the line an instance writes in answer to the `report` pipe command.
+pub const REPORT_TAG: &str = "REPORT";
+
+/// An instance's simulation health, as its manager polls it over the pipe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InstanceReport {
+    pub tick: u64,
+    pub players: usize,
+    /// The tick doesn't advance while paused, so a paused instance isn't hung.
+    pub paused: bool,
+}
+
+impl InstanceReport {
+    /// The line carrying the report, newline included.
+    pub fn to_line(&self) -> String {
+        format!(
+            "{REPORT_TAG} {} {} {}\n",
+            self.tick, self.players, self.paused
+        )
+    }
+
+    /// Reads a line written by [`InstanceReport::to_line`]. Any other line gives `None`.
+    pub fn parse(line: &str) -> Option<InstanceReport> {
+        let fields = line.trim().strip_prefix(REPORT_TAG)?;
+        if !fields.starts_with(' ') {
+            return None;
+        }
+
+        let mut parts = fields.split_whitespace();
+
+        let report = InstanceReport {
+            tick: parts.next()?.parse().ok()?,
+            players: parts.next()?.parse().ok()?,
+            paused: parts.next()?.parse().ok()?,
+        };
+
+        parts.next().is_none().then_some(report)
+    }
+}
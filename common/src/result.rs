@@ -32,12 +32,41 @@ pub enum Error {
     Surface(#[from] wgpu::SurfaceError),
     #[error(transparent)]
     Image(#[from] image::ImageError),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error("Invalid Level: {0}")]
+    InvalidLevel(String),
+    #[error("Invalid Tick Rate")]
+    InvalidTickRate,
+    #[error("Invalid Window Size")]
+    InvalidWindowSize,
+    #[error("Instance exited before it was ready")]
+    InstanceNotReady,
+    #[error("Instance {0} is unavailable")]
+    InstanceUnavailable(uuid::Uuid),
+    #[error("Expected tx;rx pipe handles, got {0:?}")]
+    InvalidPipeHandles(String),
     #[error("Invalid Key Length")]
     InvalidKeyLength,
+    #[error("Invalid Token")]
+    InvalidToken,
+    #[error("Invalid Payload")]
+    InvalidPayload,
+    #[error("Protocol version mismatch: client {client_version}, server {server_version}")]
+    VersionMismatch {
+        client_version: u32,
+        server_version: u32,
+    },
     #[error("Invalid Character Id")]
     InvalidCharacterId,
     #[error("Invalid Character Kind")]
     InvalidCharacterKind,
+    #[error("Unknown Account")]
+    UnknownAccount,
+    #[error("Account already logged in")]
+    AlreadyLoggedIn,
+    #[error("Account not logged in")]
+    NotLoggedIn,
     #[error("{0}, Inner: {1}")]
     Context(String, Box<Error>),
 }
@@ -1,7 +1,13 @@
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::{
+    collections::VecDeque,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
 
 use bincode::{Decode, Encode};
 use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::message::TickSync;
 
 #[derive(
     Serialize,
@@ -39,3 +45,131 @@ pub fn get_unix_millis() -> u128 {
         .expect("System time is before Unix epoch")
         .as_millis()
 }
+
+/// Most tick syncs a [`TickClock`] measures the tick rate over. At the default broadcast
+/// interval of 10 s that is the last minute.
+pub const TICK_RATE_WINDOW: usize = 7;
+
+/// Shortest stretch of instance time the tick rate is measured over. Syncs are stamped in
+/// whole milliseconds and taken partway into a tick, so shorter spans are mostly rounding.
+pub const MIN_TICK_RATE_SPAN: Duration = Duration::from_secs(5);
+
+/// Furthest, as a fraction of the announced rate, a measured tick rate may stray before it is
+/// put down to a hitch on the instance rather than drift, and measuring starts over.
+pub const MAX_TICK_RATE_DEVIATION: f64 = 0.05;
+
+/// Estimates the instance's tick from its [`TickSync`]s, correcting for the instance ticking
+/// slightly faster or slower than the rate it announces.
+///
+/// Every sync pairs a tick with the instance's wall-clock time at that tick. Within one
+/// [`TickSync::timeline`], the ticks elapsed between the oldest and newest of the last
+/// [`TICK_RATE_WINDOW`] syncs, over the instance time between them, is the rate the instance
+/// actually ticks at. Both ends come from the instance, so the client's own clock doesn't skew
+/// the measurement, and the error of a single stamp shrinks as the window grows rather than
+/// adding up. Until the window spans [`MIN_TICK_RATE_SPAN`] the announced rate is used.
+///
+/// The current tick is extrapolated from the newest sync at the measured rate, and the client
+/// steps its simulation at that rate too, so it stays in step between syncs instead of
+/// drifting further from the instance until the next one.
+#[derive(Debug, Clone)]
+pub struct TickClock {
+    syncs: VecDeque<(u64, u128)>,
+    timeline: u32,
+    announced_rate: u32,
+    tick_rate: f64,
+}
+
+impl TickClock {
+    pub fn new(tick_rate: u32) -> TickClock {
+        TickClock {
+            syncs: VecDeque::with_capacity(TICK_RATE_WINDOW),
+            timeline: 0,
+            announced_rate: tick_rate,
+            tick_rate: tick_rate as f64,
+        }
+    }
+
+    /// Takes in a sync, starting the measurement over if it is from a new timeline or
+    /// announces a new tick rate.
+    pub fn record(&mut self, sync: &TickSync) {
+        let announced_rate = sync.tick_rate.max(1);
+        let continues = self.syncs.back().is_some_and(|(tick, unix_millis)| {
+            sync.timeline == self.timeline
+                && announced_rate == self.announced_rate
+                && sync.tick >= *tick
+                && sync.unix_millis >= *unix_millis
+        });
+
+        if !continues {
+            self.restart(sync);
+            return;
+        }
+
+        if self.syncs.len() == TICK_RATE_WINDOW {
+            self.syncs.pop_front();
+        }
+        self.syncs.push_back((sync.tick, sync.unix_millis));
+
+        let Some(measured) = self.measure() else {
+            return;
+        };
+
+        let deviation = measured / self.announced_rate as f64 - 1.0;
+        if deviation.abs() > MAX_TICK_RATE_DEVIATION {
+            warn!(
+                "Instance ticked at {measured:.2}/s against the {} it announced, measuring again",
+                self.announced_rate
+            );
+            self.restart(sync);
+            return;
+        }
+
+        self.tick_rate = measured;
+    }
+
+    fn restart(&mut self, sync: &TickSync) {
+        self.syncs.clear();
+        self.syncs.push_back((sync.tick, sync.unix_millis));
+        self.timeline = sync.timeline;
+        self.announced_rate = sync.tick_rate.max(1);
+        self.tick_rate = self.announced_rate as f64;
+    }
+
+    /// Ticks per second between the oldest and newest sync, once they are far enough apart.
+    fn measure(&self) -> Option<f64> {
+        let (first_tick, first_millis) = self.syncs.front()?;
+        let (last_tick, last_millis) = self.syncs.back()?;
+
+        let span_millis = last_millis - first_millis;
+        if span_millis < MIN_TICK_RATE_SPAN.as_millis() {
+            return None;
+        }
+
+        Some((last_tick - first_tick) as f64 * 1000.0 / span_millis as f64)
+    }
+
+    /// The instance's tick at `unix_millis`, extrapolated from the newest sync.
+    pub fn estimate(&self, unix_millis: u128) -> Option<Tick> {
+        let (tick, synced_at) = self.syncs.back()?;
+        let elapsed_millis = unix_millis.saturating_sub(*synced_at);
+        let elapsed_ticks = (elapsed_millis as f64 * self.tick_rate / 1000.0).floor();
+
+        Some(Tick::new(tick + elapsed_ticks as u64))
+    }
+
+    /// Ticks per second the instance has been measured to run at, or its announced rate
+    /// until there is enough to measure.
+    pub fn get_tick_rate(&self) -> f64 {
+        self.tick_rate
+    }
+
+    /// The rate the instance announced in its newest sync.
+    pub fn get_announced_rate(&self) -> u32 {
+        self.announced_rate
+    }
+
+    /// Length of one tick at the measured rate.
+    pub fn get_tick_duration(&self) -> Duration {
+        Duration::from_secs_f64(1.0 / self.tick_rate)
+    }
+}
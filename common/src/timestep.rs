@@ -0,0 +1,80 @@
+use std::time::Duration;
+
+use tracing::warn;
+
+/// Default most fixed steps a single [`FixedTimestep::tick`] asks for. Any time past that is
+/// dropped, so a slow step can't leave ever more steps to catch up on.
+pub const MAX_CATCH_UP_STEPS: u32 = 10;
+
+/// Turns variable frame times into a whole number of fixed steps, carrying the leftover time
+/// into the next frame.
+#[derive(Debug, Clone)]
+pub struct FixedTimestep {
+    accumulator: Duration,
+    dt: Duration,
+    max_steps: u32,
+    /// Steps dropped by clamping since the last warning, to warn once per burst.
+    dropped: u64,
+}
+
+impl FixedTimestep {
+    pub fn new(dt: Duration) -> FixedTimestep {
+        FixedTimestep {
+            accumulator: Duration::ZERO,
+            dt,
+            max_steps: MAX_CATCH_UP_STEPS,
+            dropped: 0,
+        }
+    }
+
+    pub fn get_dt(&self) -> Duration {
+        self.dt
+    }
+
+    /// Changes the step length. Time already accumulated is kept and counted in the new steps.
+    pub fn set_dt(&mut self, dt: Duration) {
+        self.dt = dt;
+    }
+
+    pub fn get_max_steps(&self) -> u32 {
+        self.max_steps
+    }
+
+    pub fn set_max_steps(&mut self, max_steps: u32) {
+        self.max_steps = max_steps;
+    }
+
+    /// Time accumulated towards the next step.
+    pub fn get_accumulator(&self) -> Duration {
+        self.accumulator
+    }
+
+    /// Adds `elapsed` and returns how many fixed steps to run for it, at most the max steps.
+    pub fn tick(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+
+        if self.dt.is_zero() {
+            return 0;
+        }
+
+        let (accumulated, dt) = (self.accumulator.as_nanos(), self.dt.as_nanos());
+        let steps = accumulated / dt;
+        self.accumulator = Duration::from_nanos((accumulated % dt) as u64);
+
+        if steps > self.max_steps as u128 {
+            let dropped = steps - self.max_steps as u128;
+            if self.dropped == 0 {
+                warn!("Fell {dropped} steps behind, skipping them");
+            }
+            self.dropped += dropped as u64;
+            return self.max_steps;
+        }
+
+        if self.dropped > 0 {
+            warn!("Caught up after skipping {} steps", self.dropped);
+            self.dropped = 0;
+        }
+
+        steps as u32
+    }
+}
@@ -0,0 +1,48 @@
+use common::{
+    ability::{Abilities, AbilityId, DASH_COOLDOWN_INPUTS},
+    tick::Tick,
+};
+
+#[test]
+fn used_ability_waits_out_its_cooldown() {
+    let mut abilities = Abilities::new();
+    assert!(abilities.is_ready(AbilityId::Dash, Tick::new(1)));
+
+    assert!(abilities.try_use(AbilityId::Dash, Tick::new(1)));
+    let ready_at = 1 + DASH_COOLDOWN_INPUTS;
+    assert!(!abilities.is_ready(AbilityId::Dash, Tick::new(ready_at - 1)));
+    assert!(!abilities.try_use(AbilityId::Dash, Tick::new(ready_at - 1)));
+
+    // Turning it down doesn't restart the cooldown.
+    assert!(abilities.try_use(AbilityId::Dash, Tick::new(ready_at)));
+}
+
+#[test]
+fn only_running_cooldowns_are_synced() {
+    let mut abilities = Abilities::new();
+    abilities.try_use(AbilityId::Dash, Tick::new(10));
+    let end = Tick::new(10 + DASH_COOLDOWN_INPUTS);
+
+    assert_eq!(
+        abilities.get_running(Tick::new(10)),
+        [(AbilityId::Dash, end)]
+    );
+    assert!(abilities.get_running(end).is_empty());
+    assert_eq!(
+        Abilities::from_cooldowns(0, abilities.get_running(Tick::new(10))),
+        abilities
+    );
+}
+
+#[test]
+fn cooldowns_run_on_applied_inputs() {
+    let mut abilities = Abilities::new();
+    assert_eq!(abilities.next_tick(), Tick::new(1));
+    assert!(abilities.try_use(AbilityId::Dash, abilities.get_tick()));
+
+    for _ in 1..DASH_COOLDOWN_INPUTS {
+        assert!(!abilities.is_ready_next(AbilityId::Dash));
+        abilities.next_tick();
+    }
+    assert!(abilities.is_ready_next(AbilityId::Dash));
+}
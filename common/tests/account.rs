@@ -0,0 +1,67 @@
+use common::{
+    Error,
+    game::{Account, AccountId, Game},
+    instance::DisplayName,
+};
+
+fn alice() -> Account {
+    Account {
+        account_id: 1,
+        name: "alice".to_string(),
+    }
+}
+
+#[test]
+fn login_spawns_and_logout_despawns_the_account_entity() {
+    let mut game = Game::new(true);
+    game.add_account(alice());
+
+    let entity = game.login(1).unwrap();
+    assert!(game.is_online(1));
+    assert_eq!(game.online_count(), 1);
+    assert_eq!(game.get_account_entity(1), Some(entity));
+    assert_eq!(
+        *game.get_world().get::<&AccountId>(entity).unwrap(),
+        AccountId(1)
+    );
+    assert_eq!(
+        game.get_world().get::<&DisplayName>(entity).unwrap().0,
+        "alice"
+    );
+
+    assert!(matches!(game.login(1), Err(Error::AlreadyLoggedIn)));
+
+    game.logout(1).unwrap();
+    assert!(!game.is_online(1));
+    assert_eq!(game.get_account_entity(1), None);
+    assert!(!game.get_world().contains(entity));
+    assert!(game.get_account(1).is_some());
+
+    assert!(matches!(game.logout(1), Err(Error::NotLoggedIn)));
+
+    // Logging back in gives a fresh entity.
+    let again = game.login(1).unwrap();
+    assert_eq!(game.get_account_entity(1), Some(again));
+}
+
+#[test]
+fn authorization_gates_unknown_and_offline_accounts() {
+    let mut game = Game::new(true);
+    game.add_account(alice());
+
+    assert!(matches!(game.login(2), Err(Error::UnknownAccount)));
+    assert!(matches!(game.authorize(1), Err(Error::NotLoggedIn)));
+
+    let entity = game.login(1).unwrap();
+    assert_eq!(game.authorize(1).unwrap(), entity);
+}
+
+#[test]
+fn without_authorization_any_account_logs_in_on_first_use() {
+    let mut game = Game::new(false);
+
+    let entity = game.authorize(7).unwrap();
+    assert!(game.is_online(7));
+    assert_eq!(game.authorize(7).unwrap(), entity);
+    assert!(game.get_account(7).is_some());
+}
@@ -0,0 +1,58 @@
+use common::{bimap::BiMap, net_obj::NetworkObject};
+
+#[test]
+fn insert_is_visible_from_both_sides() {
+    let mut map = BiMap::new();
+    let obj = NetworkObject::Dynamic(7);
+
+    map.insert(1u64, obj);
+
+    assert_eq!(map.get_by_left(&1), Some(&obj));
+    assert_eq!(map.get_by_right(&obj), Some(&1));
+    assert_eq!(map.len(), 1);
+}
+
+#[test]
+fn removing_by_left_removes_the_right_side() {
+    let mut map = BiMap::new();
+    let obj = NetworkObject::Dynamic(7);
+    map.insert(1u64, obj);
+
+    assert_eq!(map.remove_by_left(&1), Some(obj));
+
+    assert!(!map.contains_right(&obj));
+    assert!(map.is_empty());
+    assert_eq!(map.remove_by_right(&obj), None);
+}
+
+#[test]
+fn removing_by_right_removes_the_left_side() {
+    let mut map = BiMap::new();
+    let obj = NetworkObject::Dynamic(7);
+    map.insert(1u64, obj);
+
+    assert_eq!(map.remove_by_right(&obj), Some(1));
+
+    assert!(!map.contains_left(&1));
+    assert!(map.is_empty());
+    assert_eq!(map.remove_by_left(&1), None);
+}
+
+#[test]
+fn reinserting_either_side_replaces_the_old_pair() {
+    let mut map = BiMap::new();
+    let first = NetworkObject::Dynamic(1);
+    let second = NetworkObject::Dynamic(2);
+
+    map.insert(1u64, first);
+    map.insert(1, second);
+
+    assert_eq!(map.get_by_left(&1), Some(&second));
+    assert!(!map.contains_right(&first));
+
+    map.insert(2, second);
+
+    assert_eq!(map.get_by_right(&second), Some(&2));
+    assert!(!map.contains_left(&1));
+    assert_eq!(map.len(), 1);
+}
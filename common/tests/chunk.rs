@@ -0,0 +1,55 @@
+use common::{
+    Error,
+    chunk::{CHUNK_SIZE, MAX_PAYLOAD_SIZE, PayloadAssembler, PayloadChunk, split_payload},
+    message::decode,
+};
+
+#[test]
+fn split_payload_reassembles() {
+    let payload: Vec<u8> = (0..CHUNK_SIZE * 3 + 17).map(|i| i as u8).collect();
+
+    let chunks = split_payload(&payload).unwrap();
+    assert_eq!(chunks.len(), 4);
+
+    let mut assembler = PayloadAssembler::new();
+    let (last, rest) = chunks.split_last().unwrap();
+    for chunk in rest {
+        assert_eq!(assembler.push(decode(chunk).unwrap()).unwrap(), None);
+    }
+
+    assert_eq!(
+        assembler.push(decode(last).unwrap()).unwrap(),
+        Some(payload)
+    );
+}
+
+#[test]
+fn oversized_payload_is_rejected() {
+    let mut assembler = PayloadAssembler::new();
+
+    let result = assembler.push(PayloadChunk {
+        total_len: MAX_PAYLOAD_SIZE as u32 + 1,
+        data: vec![0; 16],
+    });
+
+    assert!(matches!(result, Err(Error::InvalidPayload)));
+}
+
+#[test]
+fn chunk_overrunning_its_payload_is_rejected() {
+    let mut assembler = PayloadAssembler::new();
+
+    let result = assembler.push(PayloadChunk {
+        total_len: 4,
+        data: vec![0; 8],
+    });
+
+    assert!(matches!(result, Err(Error::InvalidPayload)));
+
+    // The assembler starts over with the next payload.
+    let result = assembler.push(PayloadChunk {
+        total_len: 2,
+        data: vec![1, 2],
+    });
+    assert_eq!(result.unwrap(), Some(vec![1, 2]));
+}
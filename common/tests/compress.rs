@@ -0,0 +1,80 @@
+use common::{
+    Error,
+    chunk::{PAYLOAD_CONFIG, PayloadAssembler, decode_payload, split_message},
+    compress::{COMPRESSION_THRESHOLD, pack, unpack},
+    instance::PLAYER_RADIUS,
+    message::{NetworkSpawn, ReliableMessageFromServer, SpawnEntry, decode},
+    net_obj::NetworkObject,
+    tick::Tick,
+};
+
+fn snapshot(players: usize) -> ReliableMessageFromServer {
+    ReliableMessageFromServer::Snapshot {
+        tick: Tick::new(1234),
+        entries: (0..players)
+            .map(|i| SpawnEntry {
+                net_obj: NetworkObject::new_rand(),
+                net_spawn: NetworkSpawn::Player {
+                    position: [i as f32 * 37.5 - 400.0, (i % 7) as f32 * 120.0],
+                    name: format!("Player {i}"),
+                    radius: PLAYER_RADIUS,
+                    interpolate: true,
+                },
+            })
+            .collect(),
+    }
+}
+
+#[test]
+fn small_payload_is_sent_raw() {
+    let payload = vec![7u8; COMPRESSION_THRESHOLD];
+
+    let packed = pack(&payload).unwrap();
+
+    assert_eq!(packed.len(), payload.len() + 1);
+    assert_eq!(unpack(&packed).unwrap(), payload);
+}
+
+#[test]
+fn large_payload_is_compressed() {
+    let payload = vec![7u8; COMPRESSION_THRESHOLD * 8];
+
+    let packed = pack(&payload).unwrap();
+
+    assert!(packed.len() < payload.len());
+    assert_eq!(unpack(&packed).unwrap(), payload);
+}
+
+#[test]
+fn unknown_header_is_rejected() {
+    assert!(matches!(unpack(&[9, 1, 2, 3]), Err(Error::InvalidPayload)));
+    assert!(matches!(unpack(&[]), Err(Error::InvalidPayload)));
+}
+
+#[test]
+fn thirty_player_snapshot_compresses() {
+    let message = snapshot(30);
+    let encoded = bincode::encode_to_vec(&message, PAYLOAD_CONFIG).unwrap();
+
+    // Random network object ids and float positions leave little to squeeze out, but the
+    // names and enum tags still shrink it by about a third.
+    let packed = pack(&encoded).unwrap();
+    assert!(
+        packed.len() * 10 < encoded.len() * 8,
+        "30 player snapshot only went from {} to {} bytes",
+        encoded.len(),
+        packed.len()
+    );
+
+    let chunks = split_message(&message).unwrap();
+    let mut assembler = PayloadAssembler::new();
+    let mut payload = None;
+    for chunk in chunks {
+        payload = assembler.push(decode(&chunk).unwrap()).unwrap();
+    }
+    let decoded: ReliableMessageFromServer = decode_payload(&payload.unwrap()).unwrap();
+    assert_eq!(
+        bincode::encode_to_vec(&decoded, PAYLOAD_CONFIG).unwrap(),
+        encoded
+    );
+}
@@ -0,0 +1,67 @@
+//! Counts heap allocations made while encoding. Kept in its own test binary so no other
+//! test runs concurrently and disturbs the global counter.
+
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use common::{
+    message::{PlayerPositionSync, UnreliableMessageFromServer, encode},
+    net_obj::NetworkObject,
+    tick::Tick,
+};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.alloc(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { System.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        unsafe { System.realloc(ptr, layout, new_size) }
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAllocator = CountingAllocator;
+
+fn count_allocations(f: impl FnOnce()) -> usize {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    f();
+    ALLOCATIONS.load(Ordering::Relaxed) - before
+}
+
+#[test]
+fn encode_allocates_once_per_message() {
+    let message = UnreliableMessageFromServer::PlayerPositionSyncs(
+        (0..64)
+            .map(|i| PlayerPositionSync {
+                net_obj: NetworkObject::new_rand(),
+                position: [i as f32, -(i as f32)],
+                tick: Tick::new(i),
+                unix_millis: Some(1_700_000_000_000),
+            })
+            .collect(),
+    );
+
+    let allocations = count_allocations(|| {
+        for _ in 0..100 {
+            drop(encode(&message).unwrap());
+        }
+    });
+
+    assert_eq!(
+        allocations, 100,
+        "expected one allocation per encoded message"
+    );
+}
@@ -0,0 +1,68 @@
+use common::{
+    Result,
+    ability::{Abilities, AbilityId, DASH_COOLDOWN_INPUTS},
+    handoff::PlayerHandoff,
+    tick::Tick,
+};
+
+fn handoff() -> PlayerHandoff {
+    PlayerHandoff {
+        client_id: u64::MAX,
+        character_id: 3,
+        name: "Bob the Brave".to_string(),
+        radius: 16.0,
+        position: [120.5, -40.0],
+        cooldowns: vec![(AbilityId::Dash, 12)],
+    }
+}
+
+#[test]
+fn handoffs_round_trip_through_their_line() -> Result<()> {
+    let handoff = handoff();
+
+    let line = handoff.to_line()?;
+    assert!(line.ends_with('\n'));
+    assert_eq!(line.lines().count(), 1);
+    assert_eq!(PlayerHandoff::parse(&line), Some(handoff));
+
+    Ok(())
+}
+
+#[test]
+fn other_lines_are_not_handoffs() -> Result<()> {
+    let payload = handoff().to_hex()?;
+
+    assert_eq!(PlayerHandoff::parse("REPORT 1 2 false\n"), None);
+    assert_eq!(PlayerHandoff::parse(&format!("HANDOFF{payload}\n")), None);
+    assert_eq!(PlayerHandoff::parse("HANDOFF not-hex\n"), None);
+    assert_eq!(
+        PlayerHandoff::parse(&format!("HANDOFF {}\n", &payload[..8])),
+        None
+    );
+
+    Ok(())
+}
+
+#[test]
+fn cooldowns_carry_over_as_inputs_left() {
+    let mut abilities = Abilities::new();
+    assert!(abilities.try_use(AbilityId::Dash, Tick::new(100)));
+    abilities.inputs_applied = 110;
+
+    let cooldowns = PlayerHandoff::remaining_cooldowns(&abilities);
+    assert_eq!(cooldowns, [(AbilityId::Dash, DASH_COOLDOWN_INPUTS - 10)]);
+
+    // Input ticks start over at the destination, so the cooldown ends that many inputs in.
+    let arrived = PlayerHandoff {
+        cooldowns,
+        ..handoff()
+    }
+    .get_abilities();
+    let remaining = DASH_COOLDOWN_INPUTS - 10;
+    assert!(!arrived.is_ready(AbilityId::Dash, Tick::new(remaining - 1)));
+    assert!(arrived.is_ready(AbilityId::Dash, Tick::new(remaining)));
+
+    // Finished cooldowns are left behind.
+    abilities.inputs_applied = 100 + DASH_COOLDOWN_INPUTS;
+    assert!(PlayerHandoff::remaining_cooldowns(&abilities).is_empty());
+}
@@ -0,0 +1,274 @@
+use std::{collections::HashMap, time::Duration};
+
+use common::{
+    Rect, Vec2, Vec3,
+    game::instance::{CollisionShape, LevelDef},
+    instance::{
+        Instance, PLAYER_RADIUS, Position, position_to_translation, translation_to_position,
+    },
+    message::OrderedInput,
+    player::PlayerInput,
+};
+use rand::Rng;
+use uuid::Uuid;
+
+#[test]
+fn same_id_gives_same_sequence() {
+    let id = Uuid::now_v7();
+    let mut a = Instance::new(id);
+    let mut b = Instance::new(id);
+
+    for _ in 0..8 {
+        assert_eq!(a.new_network_object(), b.new_network_object());
+        assert_eq!(a.rng_mut().random::<u64>(), b.rng_mut().random::<u64>());
+    }
+
+    let mut other = Instance::new(Uuid::now_v7());
+    assert_ne!(a.new_network_object(), other.new_network_object());
+}
+
+#[test]
+fn despawned_network_object_is_not_found() {
+    let mut instance = Instance::new(Uuid::now_v7());
+
+    let alice = instance.new_network_object();
+    let bob = instance.new_network_object();
+    let alice_entity = instance.spawn_player(
+        false,
+        Vec2::zeros(),
+        "alice".to_string(),
+        alice,
+        PLAYER_RADIUS,
+        None,
+    );
+    let bob_entity = instance.spawn_player(
+        false,
+        Vec2::zeros(),
+        "bob".to_string(),
+        bob,
+        PLAYER_RADIUS,
+        None,
+    );
+
+    assert_eq!(instance.find_network_object(alice), Some(alice_entity));
+
+    instance.despawn(alice_entity);
+
+    assert_eq!(instance.find_network_object(alice), None);
+    assert_eq!(instance.find_network_object(bob), Some(bob_entity));
+}
+
+#[test]
+fn player_queries_skip_static_colliders() {
+    let mut instance = Instance::new(Uuid::now_v7());
+    assert_eq!(instance.player_count(), 0);
+
+    let alice = instance.new_network_object();
+    let bob = instance.new_network_object();
+    instance.spawn_player(
+        false,
+        Vec2::new(1.0, 2.0),
+        "alice".to_string(),
+        alice,
+        PLAYER_RADIUS,
+        None,
+    );
+    let bob_entity = instance.spawn_player(
+        false,
+        Vec2::new(-3.0, 4.0),
+        "bob".to_string(),
+        bob,
+        PLAYER_RADIUS,
+        None,
+    );
+
+    assert_eq!(instance.player_count(), 2);
+
+    let mut players: Vec<_> = instance.iter_players().collect();
+    players.sort_by(|a, b| a.1.x.total_cmp(&b.1.x));
+    assert_eq!(
+        players,
+        vec![(bob, Vec2::new(-3.0, 4.0)), (alice, Vec2::new(1.0, 2.0))]
+    );
+
+    instance.despawn(bob_entity);
+    assert_eq!(instance.player_count(), 1);
+}
+
+#[test]
+fn player_collider_uses_spawn_radius() {
+    let mut instance = Instance::new(Uuid::now_v7());
+
+    let small = instance.new_network_object();
+    let big = instance.new_network_object();
+    // Both start under the default level's obstacle, whose bottom edge is at y = 256.
+    let small = instance.spawn_player(
+        false,
+        Vec2::new(400.0, 0.0),
+        "small".to_string(),
+        small,
+        PLAYER_RADIUS,
+        None,
+    );
+    let big = instance.spawn_player(
+        false,
+        Vec2::new(600.0, 0.0),
+        "big".to_string(),
+        big,
+        PLAYER_RADIUS * 2.0,
+        None,
+    );
+    instance.update(Duration::ZERO).unwrap();
+
+    let big_aabb = instance.entity_aabb(big).unwrap();
+    assert_eq!(big_aabb.max.x - big_aabb.min.x, PLAYER_RADIUS * 4.0);
+
+    let up = OrderedInput {
+        input: PlayerInput {
+            move_direction: [0.0, 1.0],
+            action: None,
+        },
+        order: 1,
+        target: None,
+    };
+    for _ in 0..120 {
+        instance.apply_input(small, &up, 1.0 / 60.0);
+        instance.apply_input(big, &up, 1.0 / 60.0);
+    }
+
+    let small_top = instance.entity_aabb(small).unwrap().max.y;
+    let big_top = instance.entity_aabb(big).unwrap().max.y;
+    assert!(
+        (small_top - 256.0).abs() < 5.0,
+        "small player stopped at {small_top}"
+    );
+    assert!(
+        (big_top - 256.0).abs() < 5.0,
+        "big player stopped at {big_top}"
+    );
+}
+
+#[test]
+fn spawn_points_are_spaced_by_radius() {
+    let instance = Instance::new(Uuid::now_v7());
+
+    let first = instance.find_spawn_point(PLAYER_RADIUS * 2.0, &[]);
+    let second = instance.find_spawn_point(PLAYER_RADIUS, &[(first, PLAYER_RADIUS * 2.0)]);
+
+    assert!(first.metric_distance(&second) >= PLAYER_RADIUS * 3.0);
+}
+
+#[test]
+fn movement_stops_at_the_world_bounds() {
+    let level = LevelDef {
+        colliders: Vec::new(),
+        spawn_points: Vec::new(),
+        bounds: Rect::new(Vec2::new(-500.0, -500.0), Vec2::new(500.0, 500.0)),
+    };
+    let mut instance = Instance::with_level(Uuid::now_v7(), &level);
+
+    let predicted = instance.new_network_object();
+    let predicted = instance.spawn_player(
+        true,
+        Vec2::zeros(),
+        "predicted".to_string(),
+        predicted,
+        PLAYER_RADIUS,
+        None,
+    );
+    let authoritative = instance.new_network_object();
+    instance.spawn_player(
+        false,
+        Vec2::zeros(),
+        "authoritative".to_string(),
+        authoritative,
+        PLAYER_RADIUS,
+        None,
+    );
+    instance.update(Duration::ZERO).unwrap();
+
+    let right = PlayerInput {
+        move_direction: [1.0, 0.0],
+        action: None,
+    };
+    for order in 1..=240 {
+        let input = OrderedInput {
+            input: right.clone(),
+            order,
+            target: None,
+        };
+        instance.apply_input(predicted, &input, 1.0 / 60.0);
+
+        let inputs = HashMap::from([(authoritative, input)]);
+        instance.apply_inputs(1.0 / 60.0, &inputs);
+    }
+
+    let positions: HashMap<_, _> = instance.iter_players().collect();
+    assert_eq!(positions[&authoritative], Vec2::new(500.0, 0.0));
+    assert_eq!(
+        instance.get_world().get::<&Position>(predicted).unwrap().0,
+        Vec2::new(500.0, 0.0)
+    );
+}
+
+#[test]
+fn walkable_points_avoid_walls_but_not_players() {
+    let level = LevelDef {
+        colliders: vec![
+            CollisionShape::Wall {
+                min: Vec2::new(-10.0, -500.0),
+                max: Vec2::new(10.0, 500.0),
+            },
+            CollisionShape::Circle {
+                center: Vec2::new(300.0, 0.0),
+                radius: 40.0,
+            },
+        ],
+        spawn_points: Vec::new(),
+        bounds: Rect::new(Vec2::new(-1000.0, -1000.0), Vec2::new(1000.0, 1000.0)),
+    };
+    let mut instance = Instance::with_level(Uuid::now_v7(), &level);
+
+    let net_obj = instance.new_network_object();
+    instance.spawn_player(
+        false,
+        Vec2::new(-200.0, 0.0),
+        "standing".to_string(),
+        net_obj,
+        PLAYER_RADIUS,
+        None,
+    );
+    instance.update(Duration::ZERO).unwrap();
+
+    // Inside the wall, or close enough for the player's edge to clip it.
+    assert!(!instance.is_walkable(Vec2::zeros(), PLAYER_RADIUS));
+    assert!(!instance.is_walkable(Vec2::new(55.0, 0.0), PLAYER_RADIUS));
+    assert!(instance.is_walkable(Vec2::new(65.0, 0.0), PLAYER_RADIUS));
+
+    // A smaller player fits where a bigger one would touch the pillar.
+    assert!(!instance.is_walkable(Vec2::new(200.0, 0.0), PLAYER_RADIUS * 2.0));
+    assert!(instance.is_walkable(Vec2::new(200.0, 0.0), PLAYER_RADIUS));
+
+    // Past the end of the wall, on top of another player, and outside the world.
+    assert!(instance.is_walkable(Vec2::new(0.0, 600.0), PLAYER_RADIUS));
+    assert!(instance.is_walkable(Vec2::new(-200.0, 0.0), PLAYER_RADIUS));
+    assert!(!instance.is_walkable(Vec2::new(1200.0, 0.0), PLAYER_RADIUS));
+}
+
+#[test]
+fn positions_sit_on_the_ground_plane() {
+    let position = Vec2::new(-120.5, 300.0);
+
+    assert_eq!(
+        position_to_translation(position),
+        Vec3::new(-120.5, 300.0, 0.0)
+    );
+    assert_eq!(
+        translation_to_position(position_to_translation(position)),
+        position
+    );
+    assert_eq!(
+        translation_to_position(Vec3::new(1.0, 2.0, 7.0)),
+        Vec2::new(1.0, 2.0)
+    );
+}
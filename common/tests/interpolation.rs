@@ -0,0 +1,159 @@
+use common::{
+    Entity, Vec2,
+    instance::{Instance, PLAYER_RADIUS, Position},
+    interpolation::{
+        DelayEstimate, INTERPOLATION_DELAY_TICKS, InterpolationBuffer,
+        MAX_INTERPOLATION_DELAY_TICKS, MAX_INTERPOLATION_SAMPLES,
+    },
+    tick::Tick,
+};
+use uuid::Uuid;
+
+fn spawn_remote(instance: &mut Instance, interpolate: bool) -> Entity {
+    let net_obj = instance.new_network_object();
+    let entity = instance.spawn_player(
+        false,
+        Vec2::zeros(),
+        "remote".to_string(),
+        net_obj,
+        PLAYER_RADIUS,
+        Some(Tick::new(0)),
+    );
+    instance.set_interpolate(entity, interpolate);
+    entity
+}
+
+fn position(instance: &Instance, entity: Entity) -> Vec2 {
+    instance.get_world().get::<&Position>(entity).unwrap().0
+}
+
+#[test]
+fn buffer_eases_between_syncs() {
+    let mut buffer = InterpolationBuffer::default();
+    assert_eq!(buffer.sample(Tick::new(5)), None);
+
+    buffer.push(Tick::new(10), Vec2::new(0.0, 0.0));
+    buffer.push(Tick::new(14), Vec2::new(100.0, -40.0));
+
+    assert_eq!(buffer.sample(Tick::new(8)), Some(Vec2::new(0.0, 0.0)));
+    assert_eq!(buffer.sample(Tick::new(11)), Some(Vec2::new(25.0, -10.0)));
+    assert_eq!(buffer.sample(Tick::new(20)), Some(Vec2::new(100.0, -40.0)));
+}
+
+#[test]
+fn buffer_ignores_stale_syncs_and_stays_bounded() {
+    let mut buffer = InterpolationBuffer::default();
+
+    buffer.push(Tick::new(10), Vec2::zeros());
+    buffer.push(Tick::new(10), Vec2::new(5.0, 5.0));
+    buffer.push(Tick::new(9), Vec2::new(5.0, 5.0));
+    assert_eq!(buffer.len(), 1);
+    assert_eq!(buffer.get_latest(), Some(Vec2::zeros()));
+
+    for tick in 11..100 {
+        buffer.push(Tick::new(tick), Vec2::zeros());
+    }
+    assert_eq!(buffer.len(), MAX_INTERPOLATION_SAMPLES);
+
+    buffer.discard_before(Tick::new(95));
+    assert_eq!(buffer.len(), 5);
+}
+
+#[test]
+fn interpolated_entity_eases_behind_the_tick() {
+    let mut instance = Instance::new(Uuid::now_v7());
+    let entity = spawn_remote(&mut instance, true);
+
+    instance.sync_remote_position(entity, Tick::new(10), Vec2::zeros());
+    instance.sync_remote_position(entity, Tick::new(12), Vec2::new(200.0, 0.0));
+
+    // Nothing moves until the entity is interpolated.
+    assert_eq!(position(&instance, entity), Vec2::zeros());
+
+    instance.set_tick(Tick::new(11 + INTERPOLATION_DELAY_TICKS));
+    instance.interpolate_remote();
+    assert_eq!(position(&instance, entity), Vec2::new(100.0, 0.0));
+
+    instance.set_tick(Tick::new(12 + INTERPOLATION_DELAY_TICKS));
+    instance.interpolate_remote();
+    assert_eq!(position(&instance, entity), Vec2::new(200.0, 0.0));
+}
+
+#[test]
+fn flagged_entity_snaps_to_every_sync() {
+    let mut instance = Instance::new(Uuid::now_v7());
+    let entity = spawn_remote(&mut instance, false);
+
+    instance.sync_remote_position(entity, Tick::new(10), Vec2::zeros());
+    instance.set_tick(Tick::new(10));
+    instance.interpolate_remote();
+
+    // A teleport lands in one step, without passing through the space in between.
+    instance.sync_remote_position(entity, Tick::new(11), Vec2::new(5000.0, 0.0));
+    assert_eq!(position(&instance, entity), Vec2::new(5000.0, 0.0));
+
+    instance.set_tick(Tick::new(11));
+    instance.interpolate_remote();
+    assert_eq!(position(&instance, entity), Vec2::new(5000.0, 0.0));
+}
+
+#[test]
+fn steady_syncs_keep_the_default_delay() {
+    let mut estimate = DelayEstimate::default();
+    assert_eq!(estimate.get_delay_millis(), None);
+
+    // A client clock 500ms behind the server's gives a negative delay, but no jitter.
+    for sent in (0..100u128).map(|i| 1_700_000_000_000 + i * 16) {
+        estimate.record(sent, sent - 500);
+    }
+
+    assert_eq!(estimate.get_delay_millis(), Some(-500.0));
+    assert_eq!(estimate.get_jitter_millis(), 0.0);
+    assert_eq!(
+        estimate.interpolation_delay_ticks(60),
+        INTERPOLATION_DELAY_TICKS
+    );
+}
+
+#[test]
+fn jitter_widens_the_delay_up_to_the_cap() {
+    let mut estimate = DelayEstimate::default();
+    for i in 0..200u128 {
+        let sent = 1_700_000_000_000 + i * 16;
+        let delay = if i % 2 == 0 { 20 } else { 60 };
+        estimate.record(sent, sent + delay);
+    }
+
+    assert!((estimate.get_jitter_millis() - 40.0).abs() < 1.0);
+    let ticks = estimate.interpolation_delay_ticks(60);
+    assert!(ticks > INTERPOLATION_DELAY_TICKS);
+    assert!(ticks < MAX_INTERPOLATION_DELAY_TICKS);
+
+    for i in 200..400u128 {
+        let sent = 1_700_000_000_000 + i * 16;
+        let delay = if i % 2 == 0 { 0 } else { 1000 };
+        estimate.record(sent, sent + delay);
+    }
+    assert_eq!(
+        estimate.interpolation_delay_ticks(60),
+        MAX_INTERPOLATION_DELAY_TICKS
+    );
+}
+
+#[test]
+fn raised_interpolation_delay_draws_further_behind() {
+    let mut instance = Instance::new(Uuid::now_v7());
+    let entity = spawn_remote(&mut instance, true);
+    assert_eq!(
+        instance.get_interpolation_delay(),
+        INTERPOLATION_DELAY_TICKS
+    );
+
+    instance.sync_remote_position(entity, Tick::new(10), Vec2::zeros());
+    instance.sync_remote_position(entity, Tick::new(20), Vec2::new(100.0, 0.0));
+
+    instance.set_interpolation_delay(5);
+    instance.set_tick(Tick::new(20));
+    instance.interpolate_remote();
+    assert_eq!(position(&instance, entity), Vec2::new(50.0, 0.0));
+}
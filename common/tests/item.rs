@@ -0,0 +1,65 @@
+use common::{
+    game::item::{Item, ItemCategory, ItemExemplar, Modifier, Rarity},
+    message::{decode, encode},
+};
+use uuid::Uuid;
+
+fn sword() -> Item {
+    Item {
+        id: Uuid::now_v7(),
+        name: "Dreamer's Edge".to_string(),
+        base_id: "sword_iron".to_string(),
+        category: ItemCategory::Sword,
+        rarity: Rarity::Legendary,
+        implicits: vec![Modifier {
+            modifier_id: "attack_speed".to_string(),
+            rolls: vec![12],
+        }],
+        explicits: vec![
+            Modifier {
+                modifier_id: "fire_damage".to_string(),
+                rolls: vec![4, 9],
+            },
+            Modifier {
+                modifier_id: "life_leech".to_string(),
+                rolls: vec![-3],
+            },
+        ],
+        condition: 870,
+    }
+}
+
+#[test]
+fn item_with_modifiers_round_trips() {
+    let item = sword();
+
+    let bytes = encode(&item).unwrap();
+    let decoded: Item = decode(&bytes).unwrap();
+
+    assert_eq!(decoded, item);
+}
+
+#[test]
+fn item_id_is_sent_as_raw_bytes() {
+    let item = sword();
+
+    let bytes = encode(&item).unwrap();
+
+    assert_eq!(bytes[..16], item.id.into_bytes());
+}
+
+#[test]
+fn exemplars_round_trip() {
+    for exemplar in [
+        ItemExemplar::Individual { item: sword() },
+        ItemExemplar::Stackable {
+            stackable_id: "key_copper".to_string(),
+            amount: 40,
+        },
+    ] {
+        let bytes = encode(&exemplar).unwrap();
+        let decoded: ItemExemplar = decode(&bytes).unwrap();
+
+        assert_eq!(decoded, exemplar);
+    }
+}
@@ -0,0 +1,118 @@
+use std::time::Duration;
+
+use common::{
+    Error, Vec2,
+    game::instance::{CollisionShape, DEFAULT_WORLD_HALF_SIZE, LevelDef},
+    instance::Instance,
+};
+use uuid::Uuid;
+
+#[test]
+fn level_file_populates_instance() {
+    let level = LevelDef::parse(
+        r#"{
+            "colliders": [
+                { "Wall": { "min": [-10.0, -100.0], "max": [10.0, 100.0] } },
+                { "Circle": { "center": [300.0, 0.0], "radius": 40.0 } }
+            ],
+            "spawn_points": [[0.0, 200.0]]
+        }"#,
+    )
+    .unwrap();
+
+    let instance = Instance::with_level(Uuid::now_v7(), &level);
+
+    assert_eq!(instance.get_spawn_points(), &[Vec2::new(0.0, 200.0)]);
+
+    let mut shapes = instance
+        .get_world()
+        .query::<&CollisionShape>()
+        .iter()
+        .map(|(entity, _)| instance.entity_aabb(entity).unwrap())
+        .collect::<Vec<_>>();
+    shapes.sort_by(|a, b| a.min.x.total_cmp(&b.min.x));
+
+    assert_eq!(shapes.len(), 2);
+    assert!((shapes[0].min - Vec2::new(-10.0, -100.0)).norm() < 1.0e-3);
+    assert!((shapes[1].max - Vec2::new(340.0, 40.0)).norm() < 1.0e-3);
+}
+
+#[test]
+fn malformed_geometry_is_rejected() {
+    for content in [
+        r#"{ "colliders": [{ "Rectangle": { "min": [10.0, 0.0], "max": [0.0, 10.0] } }] }"#,
+        r#"{ "colliders": [{ "Circle": { "center": [0.0, 0.0], "radius": -1.0 } }] }"#,
+        r#"{ "colliders": [{ "Triangle": {} }] }"#,
+        r#"{ "colliders": [{ "Wall": { "min": [0.0], "max": [1.0, 1.0] } }] }"#,
+    ] {
+        assert!(matches!(
+            LevelDef::parse(content),
+            Err(Error::InvalidLevel(_) | Error::Json(_))
+        ));
+    }
+}
+
+#[test]
+fn bounds_default_to_a_large_world_and_are_validated() {
+    let level = LevelDef::parse(r#"{ "colliders": [] }"#).unwrap();
+    assert_eq!(
+        level.bounds.max,
+        Vec2::repeat(DEFAULT_WORLD_HALF_SIZE),
+        "levels without bounds get the default"
+    );
+
+    let level = LevelDef::parse(
+        r#"{ "colliders": [], "bounds": { "min": [-10.0, -20.0], "max": [30.0, 40.0] } }"#,
+    )
+    .unwrap();
+    assert_eq!(level.bounds.min, Vec2::new(-10.0, -20.0));
+    assert_eq!(level.bounds.max, Vec2::new(30.0, 40.0));
+
+    for content in [
+        r#"{ "colliders": [], "bounds": { "min": [10.0, 0.0], "max": [0.0, 10.0] } }"#,
+        r#"{ "colliders": [], "spawn_points": [[50.0, 0.0]], "bounds": { "min": [0.0, 0.0], "max": [10.0, 10.0] } }"#,
+    ] {
+        assert!(matches!(
+            LevelDef::parse(content),
+            Err(Error::InvalidLevel(_))
+        ));
+    }
+}
+
+#[test]
+fn exported_level_loads_back_the_same() {
+    let level = LevelDef::parse(
+        r#"{
+            "colliders": [
+                { "Wall": { "min": [-10.0, -100.0], "max": [10.0, 100.0] } },
+                { "Rectangle": { "min": [256.0, 256.0], "max": [768.0, 512.0] } },
+                { "Circle": { "center": [300.0, 0.0], "radius": 40.0 } }
+            ],
+            "spawn_points": [[0.0, 200.0], [0.0, -200.0]],
+            "bounds": { "min": [-2000.0, -2000.0], "max": [2000.0, 2000.0] }
+        }"#,
+    )
+    .unwrap();
+
+    let mut instance = Instance::with_level(Uuid::now_v7(), &level);
+    let net_obj = instance.new_network_object();
+    instance.spawn_player(
+        false,
+        Vec2::zeros(),
+        "Player".to_string(),
+        net_obj,
+        20.0,
+        None,
+    );
+    instance.update(Duration::from_millis(16)).unwrap();
+
+    let exported = instance.export_level();
+    assert_eq!(exported, level, "players are not part of the level");
+
+    let reloaded = LevelDef::parse(&exported.to_json().unwrap()).unwrap();
+    assert_eq!(reloaded, level);
+    assert_eq!(
+        Instance::with_level(Uuid::now_v7(), &reloaded).export_level(),
+        level
+    );
+}
@@ -0,0 +1,188 @@
+use bincode::{Decode, Encode};
+use common::{
+    PROTOCOL_VERSION,
+    ability::AbilityId,
+    message::{
+        MAX_MESSAGE_SIZE, MESSAGE_CONFIG, NetworkSpawn, OrderedInput, OwnedPlayerSync, PlayerInit,
+        PlayerPositionSync, ReliableMessageFromClient, ReliableMessageFromServer, Spawn,
+        SpawnEntry, TickSync, UnreliableMessageFromClient, UnreliableMessageFromServer, decode,
+        encode, encode_position_syncs,
+    },
+    net_obj::NetworkObject,
+    player::PlayerInput,
+    tick::Tick,
+};
+
+#[test]
+fn oversized_length_prefix_is_rejected() {
+    // A varint length prefix claiming a terabyte-long byte vector, with no payload behind it.
+    let mut bytes = Vec::new();
+    bincode::encode_into_std_write(1u64 << 40, &mut bytes, MESSAGE_CONFIG).unwrap();
+
+    let result = bincode::decode_from_slice::<Vec<u8>, _>(&bytes, MESSAGE_CONFIG);
+
+    assert!(matches!(
+        result,
+        Err(bincode::error::DecodeError::LimitExceeded)
+    ));
+}
+
+#[test]
+fn message_at_limit_is_accepted() {
+    let payload = vec![1u8; MAX_MESSAGE_SIZE / 2];
+    let bytes = bincode::encode_to_vec(&payload, MESSAGE_CONFIG).unwrap();
+
+    let (decoded, _) = bincode::decode_from_slice::<Vec<u8>, _>(&bytes, MESSAGE_CONFIG).unwrap();
+
+    assert_eq!(decoded, payload);
+}
+
+fn round_trip<T: Encode + Decode<()>>(message: T) {
+    let bytes = encode(&message).unwrap();
+    let decoded: T = decode(&bytes).unwrap();
+
+    assert_eq!(encode(&decoded).unwrap(), bytes);
+}
+
+#[test]
+fn every_message_round_trips() {
+    let net_obj = NetworkObject::new_rand();
+    let tick = Tick::new(42);
+
+    round_trip(ReliableMessageFromServer::InstanceId([3; 16]));
+    round_trip(ReliableMessageFromServer::TickSync(TickSync {
+        tick: 42,
+        unix_millis: 1_700_000_000_000,
+        tick_rate: 30,
+        timeline: 2,
+    }));
+    round_trip(ReliableMessageFromServer::Spawn(Spawn {
+        net_obj,
+        net_spawn: NetworkSpawn::Player {
+            position: [1.0, -2.0],
+            name: "Bøb".to_string(),
+            radius: 50.0,
+            interpolate: true,
+        },
+        tick,
+    }));
+    round_trip(ReliableMessageFromServer::Snapshot {
+        tick,
+        entries: vec![SpawnEntry {
+            net_obj,
+            net_spawn: NetworkSpawn::Player {
+                position: [1.0, -2.0],
+                name: "Bøb".to_string(),
+                radius: 50.0,
+                interpolate: false,
+            },
+        }],
+    });
+    round_trip(ReliableMessageFromServer::PlayerInit(PlayerInit {
+        net_obj,
+        position: [1.0, -2.0],
+        name: "Bøb".to_string(),
+        radius: 50.0,
+        tick,
+    }));
+    round_trip(ReliableMessageFromServer::Despawn(net_obj));
+    round_trip(ReliableMessageFromServer::PlayerList(vec![(
+        net_obj,
+        "Bøb".to_string(),
+    )]));
+    round_trip(ReliableMessageFromServer::PlayerJoined(
+        net_obj,
+        "Bøb".to_string(),
+    ));
+    round_trip(ReliableMessageFromServer::PlayerLeft(net_obj));
+    round_trip(ReliableMessageFromServer::PlayerRenamed(
+        net_obj,
+        "名前".to_string(),
+    ));
+    round_trip(ReliableMessageFromServer::PauseState { paused: true });
+    round_trip(ReliableMessageFromServer::VersionMismatch {
+        server_version: PROTOCOL_VERSION,
+    });
+    round_trip(ReliableMessageFromServer::Kicked("Spamming".to_string()));
+    round_trip(ReliableMessageFromServer::ServerShutdown);
+    round_trip(ReliableMessageFromServer::ServerFull);
+    round_trip(ReliableMessageFromServer::ForcedPlayerSync(
+        OwnedPlayerSync {
+            net_obj,
+            position: [1.0, -2.0],
+            tick,
+            last_input_order: 7,
+            inputs_applied: 7,
+            cooldowns: Vec::new(),
+            unix_millis: None,
+        },
+    ));
+
+    round_trip(UnreliableMessageFromServer::PlayerPositionSyncs(vec![
+        PlayerPositionSync {
+            net_obj,
+            position: [1.0, -2.0],
+            tick,
+            unix_millis: Some(1_700_000_000_000),
+        },
+    ]));
+    round_trip(UnreliableMessageFromServer::OwnedPlayerSync(
+        OwnedPlayerSync {
+            net_obj,
+            position: [1.0, -2.0],
+            tick,
+            last_input_order: 7,
+            inputs_applied: 7,
+            cooldowns: vec![(AbilityId::Dash, Tick::new(67))],
+            unix_millis: None,
+        },
+    ));
+
+    round_trip(ReliableMessageFromClient::Connected {
+        protocol_version: PROTOCOL_VERSION,
+    });
+    round_trip(ReliableMessageFromClient::ReadyForUpdates);
+    round_trip(ReliableMessageFromClient::Pause);
+    round_trip(ReliableMessageFromClient::Resume);
+    round_trip(ReliableMessageFromClient::Leave);
+    round_trip(ReliableMessageFromClient::Spectate {
+        protocol_version: PROTOCOL_VERSION,
+    });
+
+    round_trip(UnreliableMessageFromClient::Input(OrderedInput {
+        input: PlayerInput {
+            move_direction: [0.0, 1.0],
+            action: None,
+        },
+        order: 7,
+        target: None,
+    }));
+}
+
+#[test]
+fn pre_encoded_position_syncs_match_the_message() {
+    // Enough syncs that the list's length takes more than one byte.
+    let syncs: Vec<_> = (0..200)
+        .map(|i| PlayerPositionSync {
+            net_obj: NetworkObject::new_static(i),
+            position: [i as f32, -1.0],
+            tick: Tick::new(i),
+            unix_millis: None,
+        })
+        .collect();
+    let encoded = syncs
+        .iter()
+        .map(encode)
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    let slices: Vec<_> = encoded.iter().map(Vec::as_slice).collect();
+
+    let bytes = encode_position_syncs(&slices).unwrap();
+
+    let message = UnreliableMessageFromServer::PlayerPositionSyncs(syncs);
+    assert_eq!(bytes, encode(&message).unwrap());
+    assert!(matches!(
+        decode(&bytes).unwrap(),
+        UnreliableMessageFromServer::PlayerPositionSyncs(decoded) if decoded.len() == 200
+    ));
+}
@@ -0,0 +1,390 @@
+use common::{
+    Entity, Vec2,
+    ability::{Abilities, AbilityId, DASH_COOLDOWN_INPUTS},
+    instance::Position,
+    message::OrderedInput,
+    physics::Physics,
+    player::{
+        DASH_DISTANCE, PLAYER_SPEED, PlayerAction, PlayerInput, apply_input, sanitize_direction,
+    },
+    tick::Tick,
+};
+use hecs::World;
+use rapier2d::prelude::{
+    Ball, ColliderBuilder, ColliderHandle, Isometry, Point, RigidBodyBuilder, RigidBodyHandle,
+};
+
+const DT: f32 = 1.0 / 60.0;
+
+struct Scene {
+    physics: Physics,
+    world: World,
+    /// Order of the last input applied.
+    order: u64,
+}
+
+impl Scene {
+    fn new() -> Scene {
+        Scene {
+            physics: Physics::new(),
+            world: World::new(),
+            order: 0,
+        }
+    }
+
+    fn wall(&mut self, center: Vec2, half_extents: Vec2) {
+        self.rotated_wall(center, 0.0, half_extents);
+    }
+
+    /// A wall turned by `angle` radians around its center.
+    fn rotated_wall(&mut self, center: Vec2, angle: f32, half_extents: Vec2) {
+        self.fixed(
+            center,
+            angle,
+            ColliderBuilder::cuboid(half_extents.x, half_extents.y),
+        );
+    }
+
+    /// A single collider running through `points`.
+    fn polyline(&mut self, points: &[Vec2]) {
+        let points = points.iter().map(|point| Point::from(*point)).collect();
+        self.fixed(Vec2::zeros(), 0.0, ColliderBuilder::polyline(points, None));
+    }
+
+    fn fixed(&mut self, center: Vec2, angle: f32, collider: ColliderBuilder) {
+        let rb = self
+            .physics
+            .insert_rigid_body(RigidBodyBuilder::fixed().position(Isometry::new(center, angle)));
+        let coll = self.physics.insert_collider_with_parent(collider, rb);
+        self.world.spawn((Position(center), rb, coll));
+    }
+
+    fn player(&mut self, position: Vec2) -> Entity {
+        self.player_with_radius(position, 50.0)
+    }
+
+    fn player_with_radius(&mut self, position: Vec2, radius: f32) -> Entity {
+        let rb = self
+            .physics
+            .insert_rigid_body(RigidBodyBuilder::kinematic_position_based());
+        let coll = self
+            .physics
+            .insert_collider_with_parent(ColliderBuilder::ball(radius), rb);
+        self.world
+            .spawn((Position(position), Abilities::default(), rb, coll))
+    }
+
+    fn step(&mut self, player: Entity, move_direction: [f32; 2]) -> Vec2 {
+        self.step_input(
+            player,
+            &PlayerInput {
+                move_direction,
+                action: None,
+            },
+        )
+        .0
+    }
+
+    fn step_input(&mut self, player: Entity, input: &PlayerInput) -> (Vec2, Option<PlayerAction>) {
+        self.physics.update(&mut self.world);
+
+        let (position, abilities, collider, rigid_body) = self
+            .world
+            .query_one_mut::<(
+                &mut Position,
+                &mut Abilities,
+                &ColliderHandle,
+                &RigidBodyHandle,
+            )>(player)
+            .unwrap();
+
+        self.order += 1;
+        let performed = apply_input(
+            &self.physics,
+            position,
+            &ordered(input.clone(), self.order),
+            abilities,
+            *collider,
+            *rigid_body,
+            DT,
+        );
+
+        (position.0, performed)
+    }
+}
+
+fn ordered(input: PlayerInput, order: u64) -> OrderedInput {
+    OrderedInput {
+        input,
+        order,
+        target: None,
+    }
+}
+
+fn dash(move_direction: [f32; 2]) -> PlayerInput {
+    PlayerInput {
+        move_direction,
+        action: Some(PlayerAction::Dash),
+    }
+}
+
+/// Pushes the player along `move_direction` until it should be wedged in a corner, then
+/// checks it stays put outside the walls. Returns where it settled.
+fn assert_settles_in_corner(scene: &mut Scene, player: Entity, move_direction: [f32; 2]) -> Vec2 {
+    for _ in 0..60 {
+        scene.step(player, move_direction);
+    }
+
+    let settled = scene.step(player, move_direction);
+    // A little smaller than the player, which stops `offset` short of the walls.
+    assert!(
+        scene.physics.is_free_of_fixed(settled, &Ball::new(49.0)),
+        "{settled:?} is inside a wall"
+    );
+
+    for _ in 0..30 {
+        let position = scene.step(player, move_direction);
+        assert!(
+            position.metric_distance(&settled) < 1.0e-3,
+            "player jittered from {settled:?} to {position:?}"
+        );
+    }
+
+    settled
+}
+
+#[test]
+fn inside_corner_stops_without_jitter() {
+    // Pushing straight into the corner, and more along one wall than the other.
+    for move_direction in [[1.0, 1.0], [1.0, 0.3], [0.3, 1.0]] {
+        let mut scene = Scene::new();
+        // Walls whose inner faces meet at (100, 100).
+        scene.wall(Vec2::new(110.0, 0.0), Vec2::new(10.0, 500.0));
+        scene.wall(Vec2::new(0.0, 110.0), Vec2::new(500.0, 10.0));
+        let player = scene.player(Vec2::new(-100.0, -100.0));
+
+        let settled = assert_settles_in_corner(&mut scene, player, move_direction);
+        assert!(
+            settled.x > 45.0 && settled.y > 45.0,
+            "{settled:?} stopped short of the corner pushing {move_direction:?}"
+        );
+    }
+}
+
+#[test]
+fn acute_corner_stops_without_jitter() {
+    for move_direction in [[1.0, 1.0], [1.0, 0.3]] {
+        let mut scene = Scene::new();
+        scene.wall(Vec2::new(110.0, 0.0), Vec2::new(10.0, 500.0));
+        // Sloping down towards the first wall, so the two meet at about 73 degrees.
+        scene.rotated_wall(Vec2::new(0.0, 110.0), -0.3, Vec2::new(500.0, 10.0));
+        let player = scene.player(Vec2::new(-100.0, -100.0));
+
+        assert_settles_in_corner(&mut scene, player, move_direction);
+    }
+}
+
+#[test]
+fn corner_of_a_single_collider_stops_without_jitter() {
+    // Sliding along the collider runs into it again, which is why the collider hit stays in
+    // the query filter: excluded, the player would walk straight through the corner.
+    for move_direction in [[1.0, 1.0], [1.0, 0.3]] {
+        let mut scene = Scene::new();
+        scene.polyline(&[
+            Vec2::new(100.0, -500.0),
+            Vec2::new(100.0, 100.0),
+            Vec2::new(-500.0, 100.0),
+        ]);
+        let player = scene.player(Vec2::new(-100.0, -100.0));
+
+        let settled = assert_settles_in_corner(&mut scene, player, move_direction);
+        assert!(
+            settled.x <= 50.0 && settled.y <= 50.0,
+            "{settled:?} went through the corner pushing {move_direction:?}"
+        );
+    }
+}
+
+#[test]
+fn single_wall_slides() {
+    let mut scene = Scene::new();
+    scene.wall(Vec2::new(110.0, 0.0), Vec2::new(10.0, 500.0));
+    let player = scene.player(Vec2::new(0.0, 0.0));
+
+    let mut position = Vec2::zeros();
+    for _ in 0..30 {
+        position = scene.step(player, [1.0, 1.0]);
+    }
+
+    assert!(position.x <= 50.0, "{position:?} is inside the wall");
+    assert!(
+        position.y > 100.0,
+        "{position:?} did not slide along the wall"
+    );
+}
+
+#[test]
+fn long_steps_do_not_tunnel_through_walls() {
+    let mut scene = Scene::new();
+    scene.wall(Vec2::new(300.0, 0.0), Vec2::new(2.0, 500.0));
+    let player = scene.player(Vec2::new(0.0, 0.0));
+
+    // Inputs covering far more than the player's own size each; after the first the player
+    // starts out against the wall.
+    for order in 1..=5 {
+        scene.physics.update(&mut scene.world);
+        let (position, collider, rigid_body) = scene
+            .world
+            .query_one_mut::<(&mut Position, &ColliderHandle, &RigidBodyHandle)>(player)
+            .unwrap();
+        apply_input(
+            &scene.physics,
+            position,
+            &ordered(
+                PlayerInput {
+                    move_direction: [1.0, 0.0],
+                    action: None,
+                },
+                order,
+            ),
+            &mut Abilities::default(),
+            *collider,
+            *rigid_body,
+            2.0,
+        );
+
+        assert!(
+            position.0.x > 240.0 && position.0.x <= 250.0,
+            "{:?} was not stopped at the wall",
+            position.0
+        );
+    }
+}
+
+#[test]
+fn small_player_against_a_thin_wall_does_not_tunnel() {
+    let mut scene = Scene::new();
+    scene.wall(Vec2::new(300.0, 0.0), Vec2::new(0.05, 500.0));
+    // Already touching the wall, well within the offset kept from it.
+    let player = scene.player_with_radius(Vec2::new(294.95, 0.0), 5.0);
+
+    for _ in 0..30 {
+        let position = scene.step(player, [1.0, 0.0]);
+        assert!(position.x < 300.0, "{position:?} went through the wall");
+    }
+}
+
+#[test]
+fn non_finite_input_does_not_move_player() {
+    let mut scene = Scene::new();
+    let player = scene.player(Vec2::new(10.0, 20.0));
+
+    for move_direction in [
+        [f32::NAN, 0.0],
+        [f32::INFINITY, 1.0],
+        [0.0, f32::NEG_INFINITY],
+    ] {
+        let position = scene.step(player, move_direction);
+        assert_eq!(position, Vec2::new(10.0, 20.0));
+    }
+}
+
+#[test]
+fn bad_directions_are_sanitized_to_no_movement() {
+    for direction in [
+        Vec2::new(f32::NAN, 0.0),
+        Vec2::new(1.0e-20, 0.0),
+        Vec2::new(-1.0e-20, 1.0e-20),
+        Vec2::zeros(),
+    ] {
+        assert_eq!(
+            sanitize_direction(direction),
+            Vec2::zeros(),
+            "{direction:?}"
+        );
+    }
+}
+
+#[test]
+fn good_directions_are_normalized() {
+    let diagonal = sanitize_direction(Vec2::new(1.0, 1.0));
+    assert!((diagonal.norm() - 1.0).abs() < 1e-6);
+    assert!((diagonal.x - diagonal.y).abs() < 1e-6);
+
+    // Large enough that squaring the components would overflow.
+    let huge = sanitize_direction(Vec2::new(f32::MAX, 0.0));
+    assert_eq!(huge, Vec2::new(1.0, 0.0));
+
+    let sanitized = PlayerInput {
+        move_direction: [3.0, 4.0],
+        action: None,
+    }
+    .sanitized();
+    assert_eq!(sanitized.move_direction, [0.6, 0.8]);
+}
+
+#[test]
+fn tiny_and_diagonal_inputs_move_as_expected() {
+    let mut scene = Scene::new();
+    let player = scene.player(Vec2::new(10.0, 20.0));
+
+    let position = scene.step(player, [1.0e-20, 0.0]);
+    assert_eq!(position, Vec2::new(10.0, 20.0));
+
+    let position = scene.step(player, [1.0, 1.0]);
+    let moved = position - Vec2::new(10.0, 20.0);
+    assert!((moved.norm() - PLAYER_SPEED * DT).abs() < 1e-3);
+    assert!((moved.x - moved.y).abs() < 1e-3);
+}
+
+#[test]
+fn dash_adds_its_distance_then_cools_down() {
+    let mut scene = Scene::new();
+    let player = scene.player(Vec2::zeros());
+
+    let (position, performed) = scene.step_input(player, &dash([1.0, 0.0]));
+    assert_eq!(performed, Some(PlayerAction::Dash));
+    assert!((position.x - (PLAYER_SPEED * DT + DASH_DISTANCE)).abs() < 1.0e-3);
+
+    // Every input until the cooldown is over moves normally.
+    for _ in 1..DASH_COOLDOWN_INPUTS {
+        let start = scene.world.get::<&Position>(player).unwrap().0;
+        let (position, performed) = scene.step_input(player, &dash([1.0, 0.0]));
+        assert_eq!(performed, None);
+        assert!((position.x - start.x - PLAYER_SPEED * DT).abs() < 1.0e-3);
+    }
+
+    let (_, performed) = scene.step_input(player, &dash([1.0, 0.0]));
+    assert_eq!(performed, Some(PlayerAction::Dash));
+}
+
+#[test]
+fn dash_needs_a_direction() {
+    let mut scene = Scene::new();
+    let player = scene.player(Vec2::zeros());
+
+    let (position, performed) = scene.step_input(player, &dash([0.0, 0.0]));
+    assert_eq!(performed, None);
+    assert_eq!(position, Vec2::zeros());
+    assert!(
+        scene
+            .world
+            .get::<&Abilities>(player)
+            .unwrap()
+            .is_ready(AbilityId::Dash, Tick::new(2))
+    );
+}
+
+#[test]
+fn dash_stops_at_walls() {
+    let mut scene = Scene::new();
+    scene.wall(Vec2::new(150.0, 0.0), Vec2::new(0.5, 500.0));
+    let player = scene.player(Vec2::zeros());
+
+    let (position, performed) = scene.step_input(player, &dash([1.0, 0.0]));
+    assert_eq!(performed, Some(PlayerAction::Dash));
+    assert!(
+        position.x > 90.0 && position.x <= 100.0,
+        "{position:?} was not stopped at the wall"
+    );
+}
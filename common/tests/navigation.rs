@@ -0,0 +1,92 @@
+use common::{
+    Rect, Vec2,
+    game::instance::{CollisionShape, LevelDef},
+    instance::{Instance, PLAYER_RADIUS},
+    navigation::NAV_CELL_SIZE,
+};
+use uuid::Uuid;
+
+fn instance_with(colliders: Vec<CollisionShape>) -> Instance {
+    let level = LevelDef {
+        colliders,
+        spawn_points: Vec::new(),
+        bounds: Rect::new(Vec2::new(-2000.0, -2000.0), Vec2::new(2000.0, 2000.0)),
+    };
+    Instance::with_level(Uuid::now_v7(), &level)
+}
+
+fn wall() -> CollisionShape {
+    CollisionShape::Wall {
+        min: Vec2::new(-10.0, -500.0),
+        max: Vec2::new(10.0, 500.0),
+    }
+}
+
+/// Walks the path in small steps, checking a player never overlaps the level. The start is
+/// off its cell's centre, so the check leaves the player half a cell of slack.
+fn assert_walkable(instance: &Instance, from: Vec2, path: &[Vec2]) {
+    let radius = PLAYER_RADIUS - NAV_CELL_SIZE / 2.0;
+    let mut previous = from;
+    for &waypoint in path {
+        let steps = (previous.metric_distance(&waypoint) / 5.0).ceil().max(1.0) as usize;
+        for i in 0..=steps {
+            let point = previous.lerp(&waypoint, i as f32 / steps as f32);
+            assert!(instance.is_walkable(point, radius), "path clips {point:?}");
+        }
+        previous = waypoint;
+    }
+}
+
+#[test]
+fn path_goes_around_a_wall() {
+    let instance = instance_with(vec![wall()]);
+    let from = Vec2::new(-300.0, 0.0);
+    let to = Vec2::new(300.0, 0.0);
+
+    let path = instance.find_path(from, to).unwrap();
+
+    assert_eq!(path.last(), Some(&to));
+    assert!(path.iter().any(|waypoint| waypoint.y.abs() > 500.0));
+    assert_walkable(&instance, from, &path);
+}
+
+#[test]
+fn open_ground_is_a_straight_line() {
+    let instance = instance_with(vec![wall()]);
+    let to = Vec2::new(-1000.0, 800.0);
+
+    assert_eq!(
+        instance.find_path(Vec2::new(-1000.0, -800.0), to),
+        Some(vec![to])
+    );
+}
+
+#[test]
+fn blocked_or_enclosed_goals_have_no_path() {
+    let box_wall = |min: Vec2, max: Vec2| CollisionShape::Wall { min, max };
+    let instance = instance_with(vec![
+        box_wall(Vec2::new(500.0, 500.0), Vec2::new(900.0, 520.0)),
+        box_wall(Vec2::new(500.0, 880.0), Vec2::new(900.0, 900.0)),
+        box_wall(Vec2::new(500.0, 500.0), Vec2::new(520.0, 900.0)),
+        box_wall(Vec2::new(880.0, 500.0), Vec2::new(900.0, 900.0)),
+        wall(),
+    ]);
+    let from = Vec2::new(-300.0, 0.0);
+
+    assert_eq!(instance.find_path(from, Vec2::zeros()), None);
+    assert_eq!(instance.find_path(from, Vec2::new(700.0, 700.0)), None);
+    assert_eq!(instance.find_path(from, Vec2::new(3000.0, 0.0)), None);
+}
+
+#[test]
+fn grid_resolution_can_be_changed() {
+    let mut instance = instance_with(vec![wall()]);
+    assert_eq!(instance.get_navigation().get_cell_size(), NAV_CELL_SIZE);
+
+    instance.bake_navigation(20.0);
+    assert_eq!(instance.get_navigation().get_cell_size(), 20.0);
+
+    let from = Vec2::new(-300.0, 0.0);
+    let path = instance.find_path(from, Vec2::new(300.0, 0.0)).unwrap();
+    assert_walkable(&instance, from, &path);
+}
@@ -0,0 +1,71 @@
+use std::num::NonZeroUsize;
+
+use common::{
+    DT, Vec2,
+    instance::{Instance, Position},
+    physics::{MAX_CCD_SUBSTEPS, Physics, SOLVER_ITERATIONS, integration_parameters},
+};
+use hecs::World;
+use rapier2d::prelude::{ColliderBuilder, IntegrationParameters, RigidBodyBuilder};
+use uuid::Uuid;
+
+#[test]
+fn update_skips_bodies_that_were_already_removed() {
+    let mut physics = Physics::new();
+    let mut world = World::new();
+
+    let removed = physics.insert_rigid_body(RigidBodyBuilder::kinematic_position_based());
+    let kept = physics.insert_rigid_body(RigidBodyBuilder::kinematic_position_based());
+    world.spawn((Position(Vec2::new(-500.0, 0.0)), removed));
+    world.spawn((Position(Vec2::new(500.0, 0.0)), kept));
+
+    physics.remove_rigid_body(removed);
+
+    // Used to panic indexing the missing body.
+    physics.update(&mut world);
+    physics.update(&mut world);
+}
+
+#[test]
+fn removed_colliders_have_no_bounds() {
+    let mut physics = Physics::new();
+
+    let body = physics.insert_rigid_body(RigidBodyBuilder::fixed());
+    let collider = physics.insert_collider_with_parent(ColliderBuilder::ball(10.0), body);
+    assert_eq!(physics.min_half_extent(collider), Some(10.0));
+    assert!(physics.collider_aabb(collider, Vec2::zeros()).is_some());
+
+    // Takes the body's colliders with it.
+    physics.remove_rigid_body(body);
+
+    assert_eq!(physics.min_half_extent(collider), None);
+    assert_eq!(physics.collider_aabb(collider, Vec2::zeros()), None);
+}
+
+#[test]
+fn server_and_client_start_from_the_pinned_parameters() {
+    let instance = Instance::new(Uuid::now_v7());
+    let parameters = instance.get_physics().get_integration_parameters();
+
+    assert_eq!(parameters.dt, DT.as_secs_f32());
+    assert_eq!(parameters.num_solver_iterations.get(), SOLVER_ITERATIONS);
+    assert_eq!(parameters.max_ccd_substeps, MAX_CCD_SUBSTEPS);
+}
+
+#[test]
+fn integration_parameters_can_be_tuned() {
+    let mut physics = Physics::with_parameters(IntegrationParameters {
+        max_ccd_substeps: 4,
+        ..integration_parameters()
+    });
+    assert_eq!(physics.get_max_ccd_substeps(), 4);
+
+    let iterations = NonZeroUsize::new(8).unwrap();
+    physics.set_solver_iterations(iterations);
+    physics.set_max_ccd_substeps(2);
+    assert_eq!(physics.get_solver_iterations(), iterations);
+    assert_eq!(physics.get_max_ccd_substeps(), 2);
+
+    physics.set_integration_parameters(integration_parameters());
+    assert_eq!(physics.get_solver_iterations().get(), SOLVER_ITERATIONS);
+}
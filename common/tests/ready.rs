@@ -0,0 +1,53 @@
+use std::{io::Cursor, net::SocketAddr};
+
+use common::{
+    Error,
+    ready::{InstanceReport, read_ready, ready_line},
+};
+
+#[test]
+fn ready_line_is_found_among_log_output() {
+    let addr: SocketAddr = "127.0.0.1:40123".parse().unwrap();
+    let output = format!(
+        "2026-10-15T12:00:00Z INFO instance: Started server on 127.0.0.1:40123\n\
+         10.0.0.1:9999\n\
+         READYish 10.0.0.2:1\n\
+         {}\
+         READY 10.0.0.3:2\n",
+        ready_line(addr)
+    );
+
+    let mut reader = Cursor::new(output);
+
+    assert_eq!(read_ready(&mut reader).unwrap(), addr);
+}
+
+#[test]
+fn instance_exiting_before_ready_is_an_error() {
+    let mut reader = Cursor::new("some log line\n");
+
+    assert!(matches!(
+        read_ready(&mut reader),
+        Err(Error::InstanceNotReady)
+    ));
+}
+
+#[test]
+fn reports_round_trip_through_their_line() {
+    let report = InstanceReport {
+        tick: 1234,
+        players: 3,
+        paused: true,
+    };
+
+    assert_eq!(InstanceReport::parse(&report.to_line()), Some(report));
+}
+
+#[test]
+fn other_lines_are_not_reports() {
+    assert_eq!(InstanceReport::parse("READY 127.0.0.1:1\n"), None);
+    assert_eq!(InstanceReport::parse("REPORT 1 2\n"), None);
+    assert_eq!(InstanceReport::parse("REPORT 1 2 false extra\n"), None);
+    assert_eq!(InstanceReport::parse("REPORTED 1 2 false\n"), None);
+    assert_eq!(InstanceReport::parse("REPORT1 2 false\n"), None);
+}
@@ -0,0 +1,227 @@
+use std::{collections::HashMap, time::Duration};
+
+use common::{
+    Entity, Vec2,
+    ability::{Abilities, AbilityId, DASH_COOLDOWN_INPUTS},
+    instance::{Instance, PLAYER_RADIUS, Position},
+    message::{OrderedInput, OwnedPlayerSync},
+    net_obj::NetworkObject,
+    player::{DASH_DISTANCE, PlayerAction, PlayerInput},
+    tick::Tick,
+};
+use uuid::Uuid;
+
+const DT: f32 = 1.0 / 60.0;
+
+/// Index of the last input the server acknowledges.
+const ACKED: usize = 20;
+
+/// Runs diagonally into the default level's obstacle and then slides along it, so the
+/// replay has to go through collision resolution rather than just adding up directions.
+fn inputs() -> Vec<OrderedInput> {
+    (1..=60)
+        .map(|order| OrderedInput {
+            input: PlayerInput {
+                move_direction: if order <= 45 { [1.0, 1.0] } else { [1.0, 0.0] },
+                action: None,
+            },
+            order,
+            target: None,
+        })
+        .collect()
+}
+
+fn setup(position: Vec2) -> (Instance, Entity, NetworkObject) {
+    let mut instance = Instance::new(Uuid::from_u128(7));
+    let net_obj = instance.new_network_object();
+    let player = instance.spawn_player(
+        true,
+        position,
+        "player".to_string(),
+        net_obj,
+        PLAYER_RADIUS,
+        None,
+    );
+    instance.update(Duration::ZERO).unwrap();
+    (instance, player, net_obj)
+}
+
+fn simulate(instance: &mut Instance, player: Entity, inputs: &[OrderedInput]) -> Vec<Vec2> {
+    inputs
+        .iter()
+        .map(|input| instance.apply_input(player, input, DT).unwrap())
+        .collect()
+}
+
+fn position(instance: &Instance, player: Entity) -> Vec2 {
+    instance.get_world().get::<&Position>(player).unwrap().0
+}
+
+/// Predicts every input, then rolls back to `server_position` as of input [`ACKED`] and
+/// replays the rest. Returns the predicted positions, the replayed ones and where the player
+/// ended up.
+fn predict_and_rollback(server_position: impl Fn(Vec2) -> Vec2) -> (Vec<Vec2>, Vec<Vec2>, Vec2) {
+    let inputs = inputs();
+    let (mut client, player, net_obj) = setup(Vec2::zeros());
+
+    let predicted = simulate(&mut client, player, &inputs);
+
+    let sync = OwnedPlayerSync {
+        net_obj,
+        position: server_position(predicted[ACKED - 1]).into(),
+        tick: Tick::new(ACKED as u64),
+        last_input_order: inputs[ACKED - 1].order,
+        inputs_applied: ACKED as u64,
+        cooldowns: Vec::new(),
+        unix_millis: None,
+    };
+
+    let mut replayed = Vec::new();
+    client.check_and_rollback(player, &sync, DT, inputs[ACKED..].to_vec(), |position| {
+        replayed.push(position)
+    });
+
+    (predicted, replayed, position(&client, player))
+}
+
+#[test]
+fn rollback_matches_resimulation_from_sync() {
+    let offset = Vec2::new(0.0, -30.0);
+    let (predicted, replayed, final_position) = predict_and_rollback(|position| position + offset);
+
+    let (mut fresh, fresh_player, _) = setup(predicted[ACKED - 1] + offset);
+    let expected = simulate(&mut fresh, fresh_player, &inputs()[ACKED..]);
+
+    assert_eq!(replayed, expected);
+    assert_eq!(final_position, *expected.last().unwrap());
+    assert_ne!(
+        final_position,
+        *predicted.last().unwrap(),
+        "the correction should change where the player ends up"
+    );
+}
+
+#[test]
+fn rollback_to_predicted_position_keeps_prediction() {
+    let (predicted, replayed, final_position) = predict_and_rollback(|position| position);
+
+    assert_eq!(replayed, predicted[ACKED..]);
+    assert_eq!(final_position, *predicted.last().unwrap());
+}
+
+#[test]
+fn rollback_reports_how_far_the_player_was_corrected() {
+    let inputs = inputs();
+    let (mut client, player, net_obj) = setup(Vec2::zeros());
+    let predicted = simulate(&mut client, player, &inputs);
+
+    let sync = OwnedPlayerSync {
+        net_obj,
+        position: (predicted[ACKED - 1] + Vec2::new(0.0, -30.0)).into(),
+        tick: Tick::new(ACKED as u64),
+        last_input_order: inputs[ACKED - 1].order,
+        inputs_applied: ACKED as u64,
+        cooldowns: Vec::new(),
+        unix_millis: None,
+    };
+    let correction = client
+        .check_and_rollback(player, &sync, DT, inputs[ACKED..].to_vec(), |_| {})
+        .unwrap();
+
+    let expected = position(&client, player) - predicted.last().unwrap();
+    assert!(correction.norm() > 0.0);
+    assert_eq!(correction, expected);
+}
+
+/// Heads away from the default level's obstacle, dashing at input 5.
+fn dash_inputs() -> Vec<OrderedInput> {
+    (1..=10)
+        .map(|order| OrderedInput {
+            input: PlayerInput {
+                move_direction: [-1.0, 0.0],
+                action: (order == 5).then_some(PlayerAction::Dash),
+            },
+            order,
+            target: None,
+        })
+        .collect()
+}
+
+/// What the server makes of `inputs` for a player whose dash only comes off cooldown at
+/// input `ready_at`, applying them the way an instance does. Returns the player's position
+/// after each input and the sync it sends after input `acked`.
+fn serve(inputs: &[OrderedInput], ready_at: u64, acked: usize) -> (Vec<Vec2>, OwnedPlayerSync) {
+    let (mut server, player, net_obj) = setup(Vec2::zeros());
+    *server
+        .get_world_mut()
+        .get::<&mut Abilities>(player)
+        .unwrap() = Abilities::from_cooldowns(0, [(AbilityId::Dash, Tick::new(ready_at))]);
+
+    let mut positions = Vec::new();
+    let mut sync = None;
+    for input in inputs {
+        server.apply_inputs(DT, &HashMap::from([(net_obj, input.clone())]));
+        positions.push(position(&server, player));
+
+        if input.order == acked as u64 {
+            let abilities = server.get_world().get::<&Abilities>(player).unwrap();
+            sync = Some(OwnedPlayerSync {
+                net_obj,
+                position: position(&server, player).into(),
+                tick: Tick::new(input.order),
+                last_input_order: input.order,
+                inputs_applied: abilities.inputs_applied,
+                cooldowns: abilities.get_running(abilities.get_tick()),
+                unix_millis: None,
+            });
+        }
+    }
+
+    (positions, sync.unwrap())
+}
+
+#[test]
+fn dash_on_cooldown_is_turned_down_on_both_sides() {
+    let inputs = dash_inputs();
+    let (mut client, player, _) = setup(Vec2::zeros());
+    let predicted = simulate(&mut client, player, &inputs);
+
+    // An earlier dash the client never predicted keeps the server's cooling down past input 5.
+    let (authoritative, sync) = serve(&inputs, 8, 3);
+    assert!(
+        (authoritative[4].x - predicted[4].x - DASH_DISTANCE).abs() < 1.0e-3,
+        "only the client dashed"
+    );
+
+    let correction = client
+        .check_and_rollback(player, &sync, DT, inputs[3..].to_vec(), |_| {})
+        .unwrap();
+    assert_eq!(position(&client, player), *authoritative.last().unwrap());
+    assert!((correction.norm() - DASH_DISTANCE).abs() < 1.0e-3);
+
+    // With the server's cooldowns the client would not have sent the dash at all.
+    let abilities = client.get_world().get::<&Abilities>(player).unwrap();
+    assert!(!abilities.is_ready(AbilityId::Dash, Tick::new(5)));
+    assert!(abilities.is_ready(AbilityId::Dash, Tick::new(8)));
+}
+
+#[test]
+fn dash_the_server_applied_is_not_replayed_again() {
+    let inputs = dash_inputs();
+    let (mut client, player, _) = setup(Vec2::zeros());
+    let predicted = simulate(&mut client, player, &inputs);
+
+    let (authoritative, sync) = serve(&inputs, 0, 6);
+    assert_eq!(authoritative, predicted);
+    assert_eq!(
+        sync.cooldowns,
+        [(AbilityId::Dash, Tick::new(5 + DASH_COOLDOWN_INPUTS))]
+    );
+
+    let correction = client
+        .check_and_rollback(player, &sync, DT, inputs[6..].to_vec(), |_| {})
+        .unwrap();
+
+    assert_eq!(correction, Vec2::zeros());
+    assert_eq!(position(&client, player), *predicted.last().unwrap());
+}
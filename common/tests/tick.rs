@@ -0,0 +1,128 @@
+use common::{
+    message::TickSync,
+    tick::{MIN_TICK_RATE_SPAN, TICK_RATE_WINDOW, Tick, TickClock},
+};
+
+const START_MILLIS: u128 = 1_700_000_000_000;
+
+/// A sync from an instance that has ticked at `rate` per second for `seconds`.
+fn sync_after(seconds: u64, rate: f64, timeline: u32) -> TickSync {
+    TickSync {
+        tick: 1000 + (seconds as f64 * rate) as u64,
+        unix_millis: START_MILLIS + seconds as u128 * 1000,
+        tick_rate: 60,
+        timeline,
+    }
+}
+
+#[test]
+fn announced_rate_is_used_until_there_is_enough_to_measure() {
+    let mut clock = TickClock::new(60);
+    assert_eq!(clock.estimate(START_MILLIS), None);
+
+    clock.record(&sync_after(0, 61.0, 0));
+    assert_eq!(clock.get_tick_rate(), 60.0);
+    assert_eq!(clock.estimate(START_MILLIS + 1000), Some(Tick::new(1060)));
+
+    let too_soon = MIN_TICK_RATE_SPAN.as_secs() - 1;
+    clock.record(&sync_after(too_soon, 61.0, 0));
+    assert_eq!(clock.get_tick_rate(), 60.0);
+}
+
+#[test]
+fn drift_is_measured_and_corrected_for() {
+    let mut clock = TickClock::new(60);
+
+    // An instance a little fast falls further ahead of the announced rate every sync.
+    for seconds in (0..=60).step_by(10) {
+        clock.record(&sync_after(seconds, 60.6, 0));
+    }
+
+    assert!((clock.get_tick_rate() - 60.6).abs() < 0.01);
+    assert!(clock.get_tick_duration() < common::tick_duration(60));
+
+    // Ten seconds on, the estimate still matches the instance rather than lagging six ticks.
+    let expected = sync_after(70, 60.6, 0).tick;
+    let estimate = clock.estimate(START_MILLIS + 70_000).unwrap().get();
+    assert!(estimate.abs_diff(expected) <= 1, "{estimate} vs {expected}");
+}
+
+#[test]
+fn only_the_latest_syncs_are_measured() {
+    let mut clock = TickClock::new(60);
+
+    // The instance slows down partway through; the old rate drops out of the window.
+    let mut tick = 1000;
+    let mut millis = START_MILLIS;
+    for i in 0..TICK_RATE_WINDOW * 2 {
+        let rate = if i < TICK_RATE_WINDOW { 61 } else { 59 };
+        clock.record(&TickSync {
+            tick,
+            unix_millis: millis,
+            tick_rate: 60,
+            timeline: 0,
+        });
+        tick += rate * 10;
+        millis += 10_000;
+    }
+
+    assert!((clock.get_tick_rate() - 59.0).abs() < 1.0e-6);
+}
+
+#[test]
+fn a_new_timeline_starts_over() {
+    let mut clock = TickClock::new(60);
+    for seconds in (0..=30).step_by(10) {
+        clock.record(&sync_after(seconds, 60.6, 0));
+    }
+    assert!(clock.get_tick_rate() > 60.0);
+
+    // A pause leaves the tick behind the wall-clock, which mustn't read as a slow instance.
+    clock.record(&TickSync {
+        tick: sync_after(30, 60.6, 0).tick,
+        unix_millis: START_MILLIS + 90_000,
+        tick_rate: 60,
+        timeline: 1,
+    });
+
+    assert_eq!(clock.get_tick_rate(), 60.0);
+    assert_eq!(
+        clock.estimate(START_MILLIS + 90_000),
+        Some(Tick::new(sync_after(30, 60.6, 0).tick))
+    );
+}
+
+#[test]
+fn a_new_tick_rate_starts_over() {
+    let mut clock = TickClock::new(60);
+    for seconds in (0..=30).step_by(10) {
+        clock.record(&sync_after(seconds, 60.6, 0));
+    }
+
+    clock.record(&TickSync {
+        tick_rate: 30,
+        ..sync_after(40, 60.6, 0)
+    });
+
+    assert_eq!(clock.get_announced_rate(), 30);
+    assert_eq!(clock.get_tick_rate(), 30.0);
+}
+
+#[test]
+fn implausible_rates_are_put_down_to_a_hitch() {
+    let mut clock = TickClock::new(60);
+    clock.record(&sync_after(0, 60.0, 0));
+
+    // The instance stalled for a few seconds without announcing anything.
+    clock.record(&sync_after(10, 40.0, 0));
+    assert_eq!(clock.get_tick_rate(), 60.0);
+
+    // Measuring starts again from the sync after the hitch.
+    clock.record(&TickSync {
+        tick: sync_after(10, 40.0, 0).tick + 606,
+        unix_millis: START_MILLIS + 20_000,
+        tick_rate: 60,
+        timeline: 0,
+    });
+    assert!((clock.get_tick_rate() - 60.6).abs() < 0.01);
+}
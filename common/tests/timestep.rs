@@ -0,0 +1,50 @@
+use std::time::Duration;
+
+use common::{FixedTimestep, timestep::MAX_CATCH_UP_STEPS};
+
+const DT: Duration = Duration::from_millis(10);
+
+#[test]
+fn leftover_time_carries_into_the_next_tick() {
+    let mut timestep = FixedTimestep::new(DT);
+
+    assert_eq!(timestep.tick(Duration::from_millis(4)), 0);
+    assert_eq!(timestep.tick(Duration::from_millis(4)), 0);
+    // 12ms in, so one step and 2ms carried.
+    assert_eq!(timestep.tick(Duration::from_millis(4)), 1);
+    assert_eq!(timestep.get_accumulator(), Duration::from_millis(2));
+    assert_eq!(timestep.tick(Duration::from_millis(8)), 1);
+    assert_eq!(timestep.get_accumulator(), Duration::ZERO);
+}
+
+#[test]
+fn slow_frames_catch_up_with_several_steps() {
+    let mut timestep = FixedTimestep::new(DT);
+
+    assert_eq!(timestep.tick(Duration::from_millis(35)), 3);
+    assert_eq!(timestep.get_accumulator(), Duration::from_millis(5));
+    assert_eq!(timestep.tick(Duration::from_millis(5)), 1);
+}
+
+#[test]
+fn long_stalls_are_clamped_and_the_rest_dropped() {
+    let mut timestep = FixedTimestep::new(DT);
+
+    assert_eq!(timestep.tick(Duration::from_secs(10)), MAX_CATCH_UP_STEPS);
+    // The stall doesn't keep the next frames catching up.
+    assert_eq!(timestep.get_accumulator(), Duration::ZERO);
+    assert_eq!(timestep.tick(DT), 1);
+
+    timestep.set_max_steps(2);
+    assert_eq!(timestep.tick(Duration::from_millis(55)), 2);
+    assert_eq!(timestep.get_accumulator(), Duration::from_millis(5));
+}
+
+#[test]
+fn changing_dt_keeps_accumulated_time() {
+    let mut timestep = FixedTimestep::new(DT);
+
+    assert_eq!(timestep.tick(Duration::from_millis(8)), 0);
+    timestep.set_dt(Duration::from_millis(4));
+    assert_eq!(timestep.tick(Duration::ZERO), 2);
+}
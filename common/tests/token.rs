@@ -0,0 +1,44 @@
+use std::{
+    net::SocketAddr,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use common::{Error, message::read_connect_token};
+use renet_netcode::ConnectToken;
+
+#[test]
+fn garbage_token_is_rejected() {
+    for bytes in [&[][..], &[0xAB; 7][..], &[0x5A; 4096][..]] {
+        assert!(matches!(
+            read_connect_token(bytes),
+            Err(Error::InvalidToken)
+        ));
+    }
+}
+
+#[test]
+fn valid_token_round_trips() {
+    let server_addr: SocketAddr = "127.0.0.1:6969".parse().unwrap();
+    let token = ConnectToken::generate(
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap(),
+        0,
+        30 * 60,
+        3,
+        30 * 60,
+        vec![server_addr],
+        None,
+        &[1; 32],
+    )
+    .unwrap();
+
+    let mut bytes = Vec::new();
+    token.write(&mut bytes).unwrap();
+
+    let read = read_connect_token(&bytes).unwrap();
+    assert_eq!(read.client_id, 3);
+
+    assert!(matches!(
+        read_connect_token(&bytes[..bytes.len() / 2]),
+        Err(Error::InvalidToken)
+    ));
+}
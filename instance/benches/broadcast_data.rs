@@ -0,0 +1,65 @@
+#[path = "../tests/support/mod.rs"]
+mod support;
+
+use std::time::{Duration, Instant};
+
+use common::{
+    DT,
+    message::{OrderedInput, UnreliableMessageFromClient},
+    player::PlayerInput,
+};
+use criterion::{Criterion, criterion_group, criterion_main};
+use support::Harness;
+
+const PLAYERS: usize = 64;
+
+fn broadcast_data(c: &mut Criterion) {
+    let mut harness = Harness::new().unwrap();
+
+    for i in 0..PLAYERS {
+        let index = harness.add_client(&format!("player{i}")).unwrap();
+        harness.join(index).unwrap();
+    }
+
+    let mut order = 0;
+
+    c.bench_function(&format!("instance step with {PLAYERS} players"), |b| {
+        b.iter_custom(|iters| {
+            let mut total = Duration::ZERO;
+
+            for _ in 0..iters {
+                // Keep every client sending input and acking packets so none of them time
+                // out, but only time the server's own step.
+                order += 1;
+                for client in &mut harness.clients {
+                    client
+                        .send_unreliable(UnreliableMessageFromClient::Input(OrderedInput {
+                            input: PlayerInput {
+                                move_direction: [0.0, 0.0],
+                                action: None,
+                            },
+                            order,
+                            target: None,
+                        }))
+                        .unwrap();
+                    client.send_packets().unwrap();
+                }
+
+                let start = Instant::now();
+                harness.game.step(DT).unwrap();
+                total += start.elapsed();
+
+                for client in &mut harness.clients {
+                    client.update(DT).unwrap();
+                    client.reliable.clear();
+                    client.unreliable.clear();
+                }
+            }
+
+            total
+        })
+    });
+}
+
+criterion_group!(benches, broadcast_data);
+criterion_main!(benches);
@@ -0,0 +1,172 @@
+use std::collections::{HashMap, HashSet};
+
+use common::{Rect, Vec2, net_obj::NetworkObject};
+
+/// Side of one area in world units. A client is sent the players in its own area and the
+/// eight around it, which covers more than a full screen in every direction.
+pub const AREA_SIZE: f32 = 2000.0;
+
+/// How far past the areas around a client's player another player it was sent may go before
+/// it is taken away again. Without it, a player pacing along an area edge would be
+/// despawned and respawned over and over.
+pub const AREA_HYSTERESIS: f32 = 200.0;
+
+/// One cell of the coarse grid the world is split into for broadcasting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Area {
+    pub x: i32,
+    pub y: i32,
+}
+
+impl Area {
+    pub fn of(position: Vec2) -> Area {
+        Area {
+            x: (position.x / AREA_SIZE).floor() as i32,
+            y: (position.y / AREA_SIZE).floor() as i32,
+        }
+    }
+
+    /// Whether `other` is this area or one of the eight around it.
+    pub fn is_adjacent(&self, other: Area) -> bool {
+        (self.x - other.x).abs() <= 1 && (self.y - other.y).abs() <= 1
+    }
+
+    /// The world covered by this area and the eight around it.
+    pub fn neighbourhood(&self) -> Rect {
+        Rect::new(
+            Vec2::new((self.x - 1) as f32, (self.y - 1) as f32) * AREA_SIZE,
+            Vec2::new((self.x + 2) as f32, (self.y + 2) as f32) * AREA_SIZE,
+        )
+    }
+}
+
+/// Which area every player is in, and which players each client has been sent.
+///
+/// Clients that aren't watching, such as spectators or players still loading, are sent
+/// everything.
+#[derive(Debug)]
+pub struct AreaTracker {
+    areas: HashMap<NetworkObject, Area>,
+    positions: HashMap<NetworkObject, Vec2>,
+    /// Players each watching client has been sent, keyed by client id.
+    known: HashMap<u64, HashSet<NetworkObject>>,
+    hysteresis: f32,
+}
+
+impl Default for AreaTracker {
+    fn default() -> Self {
+        AreaTracker {
+            areas: HashMap::new(),
+            positions: HashMap::new(),
+            known: HashMap::new(),
+            hysteresis: AREA_HYSTERESIS,
+        }
+    }
+}
+
+/// Players that came into or went out of a client's view since the last update.
+#[derive(Debug, Default)]
+pub struct AreaChange {
+    pub entered: Vec<NetworkObject>,
+    pub left: Vec<NetworkObject>,
+}
+
+impl AreaTracker {
+    pub fn get_hysteresis(&self) -> f32 {
+        self.hysteresis
+    }
+
+    /// Sets how far past a client's view a player it was sent may go before it is taken
+    /// away. See [`AREA_HYSTERESIS`].
+    pub fn set_hysteresis(&mut self, hysteresis: f32) {
+        self.hysteresis = hysteresis.max(0.0);
+    }
+
+    /// Recomputes every player's area from its position.
+    pub fn set_positions(&mut self, players: impl Iterator<Item = (NetworkObject, Vec2)>) {
+        self.positions.clear();
+        self.positions.extend(players);
+        self.areas.clear();
+        self.areas.extend(
+            self.positions
+                .iter()
+                .map(|(net_obj, position)| (*net_obj, Area::of(*position))),
+        );
+    }
+
+    pub fn get_area(&self, net_obj: NetworkObject) -> Option<Area> {
+        self.areas.get(&net_obj).copied()
+    }
+
+    /// Players in the areas around `viewer`, including `viewer` itself.
+    pub fn visible_from(&self, viewer: NetworkObject) -> HashSet<NetworkObject> {
+        let Some(viewer_area) = self.get_area(viewer) else {
+            return HashSet::new();
+        };
+
+        self.areas
+            .iter()
+            .filter(|(_, area)| viewer_area.is_adjacent(**area))
+            .map(|(net_obj, _)| *net_obj)
+            .collect()
+    }
+
+    /// Starts limiting what `client_id` is sent, given the players it already has.
+    pub fn watch(&mut self, client_id: u64, known: HashSet<NetworkObject>) {
+        self.known.insert(client_id, known);
+    }
+
+    pub fn is_watching(&self, client_id: u64) -> bool {
+        self.known.contains_key(&client_id)
+    }
+
+    /// Whether `client_id` should be sent updates about `net_obj`.
+    pub fn sees(&self, client_id: u64, net_obj: NetworkObject) -> bool {
+        self.known
+            .get(&client_id)
+            .is_none_or(|known| known.contains(&net_obj))
+    }
+
+    /// Brings a watching client's known players in line with what `viewer` can see now.
+    /// Players it already has are kept until they are [`AreaTracker::get_hysteresis`] past
+    /// the areas around `viewer`. Returns `None` for clients that aren't watching.
+    pub fn update_client(&mut self, client_id: u64, viewer: NetworkObject) -> Option<AreaChange> {
+        let mut visible = self.visible_from(viewer);
+        let known = self.known.get_mut(&client_id)?;
+
+        // Keep the old view while the viewer itself is between being despawned and removed.
+        let Some(viewer_area) = self.areas.get(&viewer) else {
+            return Some(AreaChange::default());
+        };
+
+        let view = viewer_area.neighbourhood();
+        let margin = Vec2::new(self.hysteresis, self.hysteresis);
+        let kept = Rect::new(view.min - margin, view.max + margin);
+        visible.extend(known.iter().filter(|net_obj| {
+            self.positions
+                .get(net_obj)
+                .is_some_and(|position| kept.contains(*position))
+        }));
+
+        let change = AreaChange {
+            entered: visible.difference(known).copied().collect(),
+            left: known.difference(&visible).copied().collect(),
+        };
+        *known = visible;
+
+        Some(change)
+    }
+
+    pub fn remove_client(&mut self, client_id: u64) {
+        self.known.remove(&client_id);
+    }
+
+    /// Forgets a despawned player, so it is sent as new if the object ever comes back.
+    pub fn remove_player(&mut self, net_obj: NetworkObject) {
+        self.areas.remove(&net_obj);
+        self.positions.remove(&net_obj);
+        for known in self.known.values_mut() {
+            known.remove(&net_obj);
+        }
+    }
+}
@@ -1,14 +1,156 @@
-use common::Result;
+use common::{
+    Error, Result, ResultExt,
+    handoff::PlayerHandoff,
+    ready::{InstanceReport, ready_line},
+};
 use std::{
     io::{BufRead as _, BufReader, Write as _},
     net::SocketAddr,
+    os::fd::RawFd,
+    path::PathBuf,
+    sync::mpsc::Sender,
+    time::Duration,
 };
+use tracing::{info, warn};
 
 #[derive(Debug)]
 #[non_exhaustive]
 pub enum Message {
     Shutdown,
     Teapot,
+    /// Remove the player called `name`, showing them `reason`.
+    Kick {
+        name: String,
+        reason: String,
+    },
+    /// Write the input log to this file.
+    DumpInputLog(PathBuf),
+    /// Log the tick, player count and pause state.
+    Status,
+    /// Answer on the pipe with an [`InstanceReport`], for the manager's monitoring.
+    Report,
+    Pause,
+    Resume,
+    /// Log every player's position.
+    DumpPositions,
+    SetTickRate(u32),
+    /// Broadcast a tick sync this often, or never for zero.
+    SetTickBroadcastInterval(Duration),
+    /// Give the client `client_id` a new character called `name` in place of its player.
+    SwitchCharacter {
+        client_id: u64,
+        name: String,
+    },
+    /// Answer on the pipe with a [`PlayerHandoff`] of the player of `client_id`, playing
+    /// `character_id`, for the manager to pass on to another instance.
+    BeginHandoff {
+        client_id: u64,
+        character_id: u32,
+    },
+    /// The destination took the player handed off for `client_id`, so remove it here.
+    FinishHandoff(u64),
+    /// The destination couldn't take the player handed off for `client_id`, so keep it.
+    CancelHandoff(u64),
+    /// Take a player handed off by another instance.
+    AcceptHandoff(PlayerHandoff),
+}
+
+/// Parses one line of the pipe protocol. Unknown or malformed lines give `None`.
+pub fn parse_message(line: &str) -> Option<Message> {
+    let line = line.trim();
+    let (command, argument) = line.split_once(' ').unwrap_or((line, ""));
+
+    match command {
+        "shutdown" => Some(Message::Shutdown),
+        "teapot" => Some(Message::Teapot),
+        "status" => Some(Message::Status),
+        "report" => Some(Message::Report),
+        "pause" => Some(Message::Pause),
+        "resume" => Some(Message::Resume),
+        "dump-positions" => Some(Message::DumpPositions),
+        "set-tickrate" => match argument.trim().parse() {
+            Ok(tick_rate) if tick_rate > 0 => Some(Message::SetTickRate(tick_rate)),
+            _ => None,
+        },
+        "set-tick-broadcast" => {
+            let interval = Duration::from_millis(argument.trim().parse().ok()?);
+            Some(Message::SetTickBroadcastInterval(interval))
+        }
+        "kick" => {
+            // Names may contain spaces, so a tab separates the name from the reason.
+            let (name, reason) = argument.split_once('\t').unwrap_or((argument, ""));
+            Some(Message::Kick {
+                name: name.to_string(),
+                reason: reason.to_string(),
+            })
+        }
+        "switch-character" => {
+            let (client_id, name) = argument.split_once(' ')?;
+            Some(Message::SwitchCharacter {
+                client_id: client_id.parse().ok()?,
+                name: name.to_string(),
+            })
+        }
+        "handoff" => {
+            let (client_id, character_id) = argument.split_once(' ')?;
+            Some(Message::BeginHandoff {
+                client_id: client_id.parse().ok()?,
+                character_id: character_id.parse().ok()?,
+            })
+        }
+        "handoff-done" => Some(Message::FinishHandoff(argument.parse().ok()?)),
+        "handoff-failed" => Some(Message::CancelHandoff(argument.parse().ok()?)),
+        "accept-handoff" => match PlayerHandoff::from_hex(argument) {
+            Ok(handoff) => Some(Message::AcceptHandoff(handoff)),
+            Err(err) => {
+                warn!("Malformed handoff payload: {err}");
+                None
+            }
+        },
+        "dump-inputs" if !argument.is_empty() => Some(Message::DumpInputLog(argument.into())),
+        _ => None,
+    }
+}
+
+/// Reads the `tx;rx` argument a piped instance is started with into the file descriptors
+/// it writes to and reads from.
+pub fn parse_pipe_handles(arg: &str) -> Result<(RawFd, RawFd)> {
+    let Some((tx, rx)) = arg.split_once(';') else {
+        return Err(Error::InvalidPipeHandles(arg.to_string()));
+    };
+
+    let tx = tx
+        .parse()
+        .context(format!("Invalid tx pipe handle in {arg:?}"))?;
+    let rx = rx
+        .parse()
+        .context(format!("Invalid rx pipe handle in {arg:?}"))?;
+
+    Ok((tx, rx))
+}
+
+/// How often a piped instance checks that the process that started it is still running.
+pub const PARENT_CHECK_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Sends [`Message::Shutdown`] once the parent process exits, even if it was killed.
+///
+/// The pipe reaching EOF usually says as much, but other instances spawned by the same
+/// client can inherit its end of the pipe and keep it open.
+fn watch_parent(msg_tx: Sender<Message>) {
+    let parent = std::os::unix::process::parent_id();
+
+    std::thread::spawn(move || {
+        loop {
+            std::thread::sleep(PARENT_CHECK_INTERVAL);
+
+            // An orphan is adopted by another process, which changes its parent id.
+            if std::os::unix::process::parent_id() != parent {
+                warn!("Parent process {parent} exited, shutting down");
+                let _ = msg_tx.send(Message::Shutdown);
+                return;
+            }
+        }
+    });
 }
 
 #[derive(Debug)]
@@ -30,6 +172,8 @@ impl BackendCommunication {
     ) -> BackendCommunication {
         let (msg_tx, msg_rx) = std::sync::mpsc::channel();
 
+        watch_parent(msg_tx.clone());
+
         std::thread::spawn(move || {
             let mut rx = BufReader::new(rx);
 
@@ -37,25 +181,72 @@ impl BackendCommunication {
             loop {
                 msg.clear();
 
-                rx.read_line(&mut msg).unwrap();
+                match rx.read_line(&mut msg) {
+                    Ok(0) => {
+                        info!("Backend closed the pipe, shutting down");
+                        break;
+                    }
+                    Ok(_) => {}
+                    Err(err) => {
+                        warn!("Failed to read from the backend pipe, shutting down: {err}");
+                        break;
+                    }
+                }
 
-                let msg = msg.trim();
+                if msg.trim().is_empty() {
+                    continue;
+                }
 
-                if msg == "shutdown" {
-                    msg_tx.send(Message::Shutdown).unwrap();
-                } else if msg == "teapot" {
-                    msg_tx.send(Message::Teapot).unwrap();
+                match parse_message(&msg) {
+                    Some(message) => {
+                        // The instance stopped listening, so nobody is left to tell.
+                        if msg_tx.send(message).is_err() {
+                            return;
+                        }
+                    }
+                    None => warn!("Ignoring unknown command {:?}", msg.trim()),
                 }
             }
+
+            // Without its parent the instance would never be told to stop.
+            let _ = msg_tx.send(Message::Shutdown);
         });
 
         BackendCommunication::Pipe(PipeComm { tx, rx: msg_rx })
     }
 
+    /// Announces the address the instance listens on, which is an ephemeral port. Without a
+    /// pipe the ready line goes to stdout, so whatever started the instance can still find it.
     pub fn notify_ready(&mut self, server_addr: SocketAddr) -> Result<()> {
         match self {
             BackendCommunication::Pipe(PipeComm { tx, .. }) => {
-                tx.write_all(format!("{server_addr}\n").as_bytes())?;
+                tx.write_all(ready_line(server_addr).as_bytes())?;
+            }
+            BackendCommunication::None => {
+                let mut stdout = std::io::stdout().lock();
+                stdout.write_all(ready_line(server_addr).as_bytes())?;
+                stdout.flush()?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn report(&mut self, report: &InstanceReport) -> Result<()> {
+        match self {
+            BackendCommunication::Pipe(PipeComm { tx, .. }) => {
+                tx.write_all(report.to_line().as_bytes())?;
+            }
+            BackendCommunication::None => {}
+        }
+
+        Ok(())
+    }
+
+    pub fn handoff(&mut self, handoff: &PlayerHandoff) -> Result<()> {
+        match self {
+            BackendCommunication::Pipe(PipeComm { tx, .. }) => {
+                tx.write_all(handoff.to_line()?.as_bytes())?;
             }
             BackendCommunication::None => {}
         }
@@ -0,0 +1,90 @@
+use std::{cmp::Reverse, collections::HashMap};
+
+use common::{Vec2, net_obj::NetworkObject};
+
+/// Default bytes of position syncs each client is sent per tick, on top of its own player's
+/// sync. Fits about a hundred players, so it only matters in crowds.
+pub const SYNC_BUDGET_BYTES: usize = 4096;
+
+/// Which position syncs each client gets when they don't all fit in its budget.
+///
+/// Nearer players go first, but players skipped for more ticks in a row go before those, so
+/// a crowd is sent in turns instead of the far side never updating.
+#[derive(Debug)]
+pub struct SyncBudget {
+    bytes_per_tick: usize,
+    /// Ticks in a row each player was skipped, keyed by client id. Only clients over budget
+    /// have an entry.
+    skipped: HashMap<u64, HashMap<NetworkObject, u32>>,
+}
+
+impl Default for SyncBudget {
+    fn default() -> Self {
+        SyncBudget::new(SYNC_BUDGET_BYTES)
+    }
+}
+
+impl SyncBudget {
+    pub fn new(bytes_per_tick: usize) -> SyncBudget {
+        SyncBudget {
+            bytes_per_tick,
+            skipped: HashMap::new(),
+        }
+    }
+
+    pub fn get_bytes_per_tick(&self) -> usize {
+        self.bytes_per_tick
+    }
+
+    pub fn set_bytes_per_tick(&mut self, bytes_per_tick: usize) {
+        self.bytes_per_tick = bytes_per_tick;
+    }
+
+    /// Whether `client_id` had syncs skipped on its last selection.
+    pub fn is_over(&self, client_id: u64) -> bool {
+        self.skipped.contains_key(&client_id)
+    }
+
+    /// Picks the syncs that fit in `client_id`'s budget once `used` bytes have already been
+    /// sent this tick. Each candidate is a player with its position and its encoded sync.
+    /// With no `viewer` position, such as for spectators, only skipped players go first.
+    pub fn select<'a>(
+        &mut self,
+        client_id: u64,
+        viewer: Option<Vec2>,
+        used: usize,
+        mut candidates: Vec<(NetworkObject, Vec2, &'a [u8])>,
+    ) -> Vec<&'a [u8]> {
+        let skipped = self.skipped.remove(&client_id).unwrap_or_default();
+
+        let skipped_for = |net_obj: &NetworkObject| skipped.get(net_obj).copied().unwrap_or(0);
+        let distance = |position: &Vec2| viewer.map_or(0.0, |viewer| (position - viewer).norm());
+        candidates.sort_by(|(a, a_pos, _), (b, b_pos, _)| {
+            Reverse(skipped_for(a))
+                .cmp(&Reverse(skipped_for(b)))
+                .then(distance(a_pos).total_cmp(&distance(b_pos)))
+        });
+
+        let mut left = self.bytes_per_tick.saturating_sub(used);
+        let mut selected = Vec::new();
+        let mut now_skipped = HashMap::new();
+        for (net_obj, _, sync) in candidates {
+            if sync.len() <= left {
+                left -= sync.len();
+                selected.push(sync);
+            } else {
+                now_skipped.insert(net_obj, skipped_for(&net_obj) + 1);
+            }
+        }
+
+        if !now_skipped.is_empty() {
+            self.skipped.insert(client_id, now_skipped);
+        }
+
+        selected
+    }
+
+    pub fn remove_client(&mut self, client_id: u64) {
+        self.skipped.remove(&client_id);
+    }
+}
@@ -0,0 +1,14 @@
+use common::net_obj::NetworkObject;
+
+/// Something that happened in the game for other systems to react to, without the code that
+/// caused it calling them directly. Sent with [`crate::Game::send_event`] and handled once per
+/// tick, in the order they were sent.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum GameEvent {
+    /// A player entity was added to the world.
+    PlayerSpawned(NetworkObject),
+    /// A player entity was taken out of the world, because its client left, was kicked or
+    /// switched character.
+    PlayerRemoved(NetworkObject),
+}
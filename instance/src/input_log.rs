@@ -0,0 +1,110 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    fs::File,
+    io::{BufWriter, Write},
+    path::Path,
+};
+
+use common::{Result, Vec2, message::OrderedInput, net_obj::NetworkObject};
+
+/// What happened to one player during a recorded tick.
+#[derive(Debug, Clone)]
+pub struct PlayerRecord {
+    pub net_obj: NetworkObject,
+    /// The input applied this tick, if one arrived in time.
+    pub input: Option<OrderedInput>,
+    /// Where the player ended up after the input.
+    pub position: Vec2,
+}
+
+#[derive(Debug, Clone)]
+pub struct TickRecord {
+    pub tick: u64,
+    pub players: Vec<PlayerRecord>,
+}
+
+/// The inputs the server applied and where they left each player, for the last `capacity`
+/// ticks. The server-side counterpart of the client's snapshot history, for comparing with
+/// client logs when hunting desyncs.
+#[derive(Debug)]
+pub struct InputLog {
+    capacity: usize,
+    ticks: VecDeque<TickRecord>,
+}
+
+impl InputLog {
+    pub fn new(capacity: usize) -> InputLog {
+        InputLog {
+            capacity,
+            ticks: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    /// Records a tick, forgetting the oldest one once the log is full.
+    pub fn record(
+        &mut self,
+        tick: u64,
+        inputs: &HashMap<NetworkObject, OrderedInput>,
+        players: impl Iterator<Item = (NetworkObject, Vec2)>,
+    ) {
+        if self.capacity == 0 {
+            return;
+        }
+
+        let players = players
+            .map(|(net_obj, position)| PlayerRecord {
+                net_obj,
+                input: inputs.get(&net_obj).cloned(),
+                position,
+            })
+            .collect();
+
+        if self.ticks.len() >= self.capacity {
+            self.ticks.pop_front();
+        }
+        self.ticks.push_back(TickRecord { tick, players });
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &TickRecord> {
+        self.ticks.iter()
+    }
+
+    pub fn len(&self) -> usize {
+        self.ticks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ticks.is_empty()
+    }
+
+    /// Writes one tab separated line per player per tick:
+    /// `tick net_obj order move_x,move_y x,y`, with `-` where no input was applied.
+    pub fn write(&self, writer: &mut impl Write) -> Result<()> {
+        for record in &self.ticks {
+            for player in &record.players {
+                let input = match &player.input {
+                    Some(input) => format!(
+                        "{}\t{},{}",
+                        input.order, input.input.move_direction[0], input.input.move_direction[1]
+                    ),
+                    None => "-\t-".to_string(),
+                };
+                writeln!(
+                    writer,
+                    "{}\t{:?}\t{input}\t{},{}",
+                    record.tick, player.net_obj, player.position.x, player.position.y
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn dump(&self, path: impl AsRef<Path>) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write(&mut writer)?;
+        writer.flush()?;
+
+        Ok(())
+    }
+}
@@ -1,32 +1,61 @@
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet, VecDeque},
     fmt::Debug,
+    net::SocketAddr,
     time::{Duration, Instant},
 };
 
+use area::{Area, AreaTracker};
 use backend::{BackendCommunication, Message};
+use budget::SyncBudget;
 use common::{
-    DT, Entity, Result, Vec2,
-    instance::{Instance, LastInputTracker, Player, Position},
+    Entity, FixedTimestep, PROTOCOL_VERSION, Result, TICK_RATE, Vec2,
+    ability::Abilities,
+    bimap::BiMap,
+    game::{character::cap_name, instance::LevelDef},
+    handoff::PlayerHandoff,
+    instance::{
+        DisplayName, EntityCollider, EntityKind, Instance, LastInputTracker, PLAYER_RADIUS, Player,
+        Position,
+    },
+    interpolation::Interpolate,
     message::{
-        NetworkSpawn, OrderedInput, OwnedPlayerSync, PlayerInit, PlayerPositionSync,
-        ReliableMessageFromClient, ReliableMessageFromServer, Spawn, TickSync,
-        UnreliableMessageFromClient, UnreliableMessageFromServer,
+        AdminCommand, NetworkSpawn, OrderedInput, OwnedPlayerSync, PlayerInit, PlayerPositionSync,
+        ReliableMessageFromClient, ReliableMessageFromServer, Spawn, SpawnEntry, TickSync,
+        UnreliableMessageFromClient, UnreliableMessageFromServer, encode, encode_position_syncs,
     },
     net_obj::NetworkObject,
+    ready::InstanceReport,
     tick::get_unix_millis,
+    tick_duration,
 };
-use server::Server;
+use event::GameEvent;
+use indexmap::IndexMap;
+use input_log::InputLog;
+use renet::ClientId;
+use server::{MAX_CLIENTS, Server};
 use tick::{TickData, tick};
-use tracing::{Level, error, info, instrument, span, warn};
+use tracing::{Level, debug, error, info, instrument, span, warn};
 use uuid::Uuid;
 
 // pub mod player;
+pub mod area;
 pub mod backend;
+pub mod budget;
+pub mod event;
+pub mod input_log;
 pub mod server;
 pub mod tick;
 
-pub fn run(id: Uuid, key: [u8; 32], mut comm: BackendCommunication) -> Result<()> {
+pub fn run(
+    id: Uuid,
+    key: [u8; 32],
+    owner: Option<u64>,
+    level: LevelDef,
+    tick_rate: u32,
+    input_log_ticks: Option<usize>,
+    mut comm: BackendCommunication,
+) -> Result<()> {
     let span = span!(Level::INFO, "instance", %id);
     let _enter = span.enter();
 
@@ -35,59 +64,100 @@ pub fn run(id: Uuid, key: [u8; 32], mut comm: BackendCommunication) -> Result<()
     info!("Started server on {}", server.local_address());
     comm.notify_ready(server.local_address())?;
 
-    let mut game = Game::new(id, server);
+    let mut game = Game::with_level(id, &level, server);
+    game.set_tick_rate(tick_rate);
+    game.set_owner(owner);
+    if let Some(ticks) = input_log_ticks {
+        game.enable_input_log(ticks);
+    }
 
     let mut start_time = Instant::now();
-    let mut accumulator = Duration::ZERO;
     let result: Result<()> = 'main: loop {
         let elapsed = start_time.elapsed();
-        accumulator += elapsed;
         start_time = Instant::now();
 
-        if let Err(e) = game.server.update(elapsed) {
+        if let Err(e) = game.step(elapsed) {
             break 'main Err(e);
         }
 
-        while let Some(event) = game.server.get_event() {
-            match event {
-                renet::ServerEvent::ClientConnected { client_id } => {
-                    info!("Client connected: {client_id}");
-                    game.message_queues
-                        .insert(client_id, MessageQueue::default());
-                }
-                renet::ServerEvent::ClientDisconnected { client_id, reason } => {
-                    info!("Client disconnected: {client_id}, reason: {reason:?}");
-                    if let Some(net) = game.client_map.client_to_net_obj.remove(&client_id) {
-                        game.client_map.net_obj_to_client.remove(&net);
-                        let entity = game.instance.find_network_object(net).unwrap();
-                        game.despawn_and_broadcast(entity, net)?;
-                    }
-                    game.message_queues.remove(&client_id);
-                }
-            }
-        }
-
-        while accumulator >= DT {
-            accumulator -= DT;
-
-            if let Err(err) = game.update(DT) {
-                break 'main Err(err);
-            }
-        }
-
-        game.server.send_packets();
+        profiling::finish_frame!();
 
         while let Some(msg) = comm.message() {
             match msg {
                 Message::Shutdown => {
                     info!("Got shutdown message. Exiting...");
-                    break 'main Ok(());
+                    break 'main game.shutdown(SHUTDOWN_DRAIN);
+                }
+                Message::Kick { name, reason } => match game.find_player_by_name(&name) {
+                    Some(net_obj) => {
+                        if let Err(e) = game.kick_player(net_obj, &reason) {
+                            break 'main Err(e);
+                        }
+                    }
+                    None => warn!("No player named {name} to kick"),
+                },
+                Message::DumpInputLog(path) => match game.get_input_log() {
+                    Some(log) => match log.dump(&path) {
+                        Ok(()) => info!("Dumped input log to {}", path.display()),
+                        Err(e) => warn!("Failed to dump input log to {}: {e}", path.display()),
+                    },
+                    None => warn!("Input log is not enabled"),
+                },
+                Message::Status => info!("{}", game.get_status()),
+                Message::Report => {
+                    if let Err(e) = comm.report(&game.get_report()) {
+                        warn!("Failed to send a report to the backend: {e}");
+                    }
+                }
+                Message::Pause | Message::Resume => {
+                    let paused = matches!(msg, Message::Pause);
+                    if let Err(e) = game.set_paused(paused) {
+                        break 'main Err(e);
+                    }
+                }
+                Message::DumpPositions => game.get_instance().print_player_positions(),
+                Message::SetTickRate(tick_rate) => {
+                    if let Err(e) = game.change_tick_rate(tick_rate) {
+                        break 'main Err(e);
+                    }
+                }
+                Message::SetTickBroadcastInterval(interval) => {
+                    game.set_tick_broadcast_interval(interval)
+                }
+                Message::SwitchCharacter { client_id, name } => {
+                    if let Err(e) = game.switch_character(client_id, &name, PLAYER_RADIUS) {
+                        break 'main Err(e);
+                    }
+                }
+                Message::BeginHandoff {
+                    client_id,
+                    character_id,
+                } => {
+                    // Without an answer the manager gives up on the handoff by itself.
+                    let Some(handoff) = game.begin_handoff(client_id, character_id) else {
+                        continue;
+                    };
+                    if let Err(e) = comm.handoff(&handoff) {
+                        warn!("Failed to send a handoff to the backend: {e}");
+                        game.cancel_handoff(client_id);
+                    }
+                }
+                Message::FinishHandoff(client_id) => {
+                    if let Err(e) = game.finish_handoff(client_id) {
+                        break 'main Err(e);
+                    }
+                }
+                Message::CancelHandoff(client_id) => game.cancel_handoff(client_id),
+                Message::AcceptHandoff(handoff) => {
+                    if let Err(e) = game.accept_handoff(handoff) {
+                        break 'main Err(e);
+                    }
                 }
                 _ => {}
             }
         }
 
-        std::thread::sleep(DT.saturating_sub(start_time.elapsed()));
+        std::thread::sleep(game.get_tick_duration().saturating_sub(start_time.elapsed()));
     };
 
     if let Err(err) = result {
@@ -99,28 +169,154 @@ pub fn run(id: Uuid, key: [u8; 32], mut comm: BackendCommunication) -> Result<()
     Ok(())
 }
 
+/// Longest the instance keeps resending [`ReliableMessageFromServer::ServerShutdown`] to
+/// clients that haven't acknowledged it before disconnecting them anyway.
+pub const SHUTDOWN_DRAIN: Duration = Duration::from_millis(500);
+
+/// Longest a client being disconnected with a reason, such as
+/// [`ReliableMessageFromServer::Kicked`], stays connected waiting for it to acknowledge that
+/// reason.
+pub const DISCONNECT_DRAIN: Duration = Duration::from_millis(500);
+
+/// Reliable messages kept per client per tick. Further ones are dropped, since the ones
+/// already queued may depend on each other.
+pub const MAX_QUEUED_RELIABLE_MESSAGES: usize = 64;
+
+/// Unreliable messages kept per client per tick. The oldest are dropped first, as newer
+/// inputs supersede them.
+pub const MAX_QUEUED_UNRELIABLE_MESSAGES: usize = 128;
+
+/// Messages from a client that were dropped because its queue was full.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct DroppedMessages {
+    pub reliable: u64,
+    pub unreliable: u64,
+}
+
 #[derive(Default)]
 struct MessageQueue {
     reliable: Vec<ReliableMessageFromClient>,
-    unreliable: Vec<UnreliableMessageFromClient>,
+    unreliable: VecDeque<UnreliableMessageFromClient>,
+    dropped: DroppedMessages,
+}
+
+impl MessageQueue {
+    /// Queues `msg` unless the reliable queue is full. Returns whether it was kept.
+    fn push_reliable(&mut self, msg: ReliableMessageFromClient) -> bool {
+        if self.reliable.len() >= MAX_QUEUED_RELIABLE_MESSAGES {
+            self.dropped.reliable += 1;
+            return false;
+        }
+
+        self.reliable.push(msg);
+        true
+    }
+
+    /// Queues `msg`, dropping the oldest unreliable message if the queue is full.
+    fn push_unreliable(&mut self, msg: UnreliableMessageFromClient) {
+        if self.unreliable.len() >= MAX_QUEUED_UNRELIABLE_MESSAGES {
+            self.unreliable.pop_front();
+            self.dropped.unreliable += 1;
+        }
+
+        self.unreliable.push_back(msg);
+    }
+}
+
+type ClientNetworkObjectMap = BiMap<ClientId, NetworkObject>;
+
+/// Missing inputs in a row after which the owning client is told to snap to the server's
+/// position instead of replaying everything since its last acknowledged input.
+const INPUT_GAP_RESYNC_THRESHOLD: u64 = 10;
+
+/// Least inputs queued per player before the oldest are dropped. Players with a longer round
+/// trip get a round trip's worth of ticks instead, so a burst held up by the network isn't
+/// cut short.
+pub const INPUT_QUEUE_LENGTH: usize = 10;
+
+/// Inputs a client sent that never reached the server, or were dropped before being applied.
+#[derive(Debug, Default)]
+struct InputGapStats {
+    gaps: u64,
+    missed: u64,
+    largest: u64,
+    dropped: u64,
 }
 
-#[derive(Default, Debug)]
-struct ClientNetworkObjectMap {
-    client_to_net_obj: HashMap<u64, NetworkObject>,
-    net_obj_to_client: HashMap<NetworkObject, u64>,
+/// A player that will be spawned at the start of the next update.
+#[derive(Debug)]
+struct PlayerSpawnRequest {
+    position: Vec2,
+    name: String,
+    net_obj: NetworkObject,
+    radius: f32,
+    abilities: Abilities,
 }
 
-#[derive(Default)]
 struct ClientInputs {
     inputs: HashMap<NetworkObject, Vec<OrderedInput>>,
+    last_orders: HashMap<NetworkObject, u64>,
+    gap_stats: HashMap<NetworkObject, InputGapStats>,
+    resyncs: HashSet<NetworkObject>,
+    /// See [`INPUT_QUEUE_LENGTH`].
+    min_length: usize,
+}
+
+impl Default for ClientInputs {
+    fn default() -> Self {
+        ClientInputs {
+            inputs: HashMap::new(),
+            last_orders: HashMap::new(),
+            gap_stats: HashMap::new(),
+            resyncs: HashSet::new(),
+            min_length: INPUT_QUEUE_LENGTH,
+        }
+    }
 }
 
 impl ClientInputs {
     fn push_input(&mut self, net_obj: NetworkObject, input: OrderedInput) {
+        let last_order = self.last_orders.entry(net_obj).or_insert(input.order);
+        let gap = input.order.saturating_sub(*last_order + 1);
+        *last_order = (*last_order).max(input.order);
+
+        if gap > 0 {
+            let stats = self.gap_stats.entry(net_obj).or_default();
+            stats.gaps += 1;
+            stats.missed += gap;
+            stats.largest = stats.largest.max(gap);
+
+            if gap > INPUT_GAP_RESYNC_THRESHOLD {
+                warn!("Missed {gap} inputs from {net_obj:?}, forcing a resync");
+                self.resyncs.insert(net_obj);
+            }
+        }
+
         self.inputs.entry(net_obj).or_default().push(input);
     }
 
+    /// Whether the player fell far enough behind to need a [`ForcedPlayerSync`], clearing the
+    /// request. Only take it when the sync is sent, which forcing
+    /// [`OwnedSyncTracker::should_send`] guarantees.
+    ///
+    /// [`ForcedPlayerSync`]: ReliableMessageFromServer::ForcedPlayerSync
+    fn take_resync(&mut self, net_obj: NetworkObject) -> bool {
+        self.resyncs.remove(&net_obj)
+    }
+
+    fn remove(&mut self, net_obj: NetworkObject) {
+        self.inputs.remove(&net_obj);
+        self.last_orders.remove(&net_obj);
+        self.resyncs.remove(&net_obj);
+
+        if let Some(stats) = self.gap_stats.remove(&net_obj) {
+            info!(
+                "Inputs from {net_obj:?}: {} missed in {} gaps, largest {}, {} dropped",
+                stats.missed, stats.gaps, stats.largest, stats.dropped
+            );
+        }
+    }
+
     fn pop_inputs(&mut self) -> HashMap<NetworkObject, OrderedInput> {
         let mut inputs = HashMap::new();
 
@@ -138,29 +334,180 @@ impl ClientInputs {
         inputs
     }
 
-    fn prune(&mut self, max_length: usize) {
-        for (_, ord_inputs) in self.inputs.iter_mut() {
-            while ord_inputs.len() > max_length {
-                if let Some((min_index, _)) = ord_inputs
-                    .iter()
-                    .enumerate()
-                    .min_by_key(|(_, input)| input.order)
-                {
-                    ord_inputs.remove(min_index);
-                }
+    /// Drops the oldest inputs of players with more queued than the longer of the minimum
+    /// length and `round_trip_ticks` for that player.
+    fn prune(&mut self, round_trip_ticks: impl Fn(NetworkObject) -> usize) {
+        for (net_obj, ord_inputs) in self.inputs.iter_mut() {
+            let max_length = self.min_length.max(round_trip_ticks(*net_obj));
+            let overflow = ord_inputs.len().saturating_sub(max_length);
+            if overflow == 0 {
+                continue;
             }
+
+            ord_inputs.sort_by_key(|input| input.order);
+            ord_inputs.drain(..overflow);
+
+            warn!("Input queue of {net_obj:?} overflowed, dropped {overflow} unapplied inputs");
+            self.gap_stats.entry(*net_obj).or_default().dropped += overflow as u64;
+        }
+    }
+}
+
+/// Ticks a player has to stay in place before other clients only get its position every
+/// [`IDLE_KEEPALIVE_TICKS`] ticks.
+pub const IDLE_AFTER_TICKS: u32 = 60;
+
+/// Ticks between position syncs of an idle player, so a lost sync is still corrected.
+pub const IDLE_KEEPALIVE_TICKS: u32 = 30;
+
+/// Where a player was last tick and for how many ticks it has stayed there.
+#[derive(Debug)]
+struct Stillness {
+    position: Vec2,
+    ticks: u32,
+}
+
+#[derive(Default)]
+struct IdleTracker {
+    players: HashMap<NetworkObject, Stillness>,
+}
+
+impl IdleTracker {
+    /// Records where the player is this tick and returns whether other clients should be
+    /// sent its position.
+    fn should_broadcast(&mut self, net_obj: NetworkObject, position: Vec2) -> bool {
+        let still = self
+            .players
+            .entry(net_obj)
+            .or_insert(Stillness { position, ticks: 0 });
+
+        if still.position == position {
+            still.ticks = still.ticks.saturating_add(1);
+        } else {
+            still.position = position;
+            still.ticks = 0;
         }
+
+        still.ticks < IDLE_AFTER_TICKS
+            || (still.ticks - IDLE_AFTER_TICKS).is_multiple_of(IDLE_KEEPALIVE_TICKS)
+    }
+
+    fn remove(&mut self, net_obj: NetworkObject) {
+        self.players.remove(&net_obj);
     }
 }
 
+/// Ticks between owned syncs while new inputs keep being applied. The client replays every
+/// input after the acknowledged one, so it doesn't need a sync each tick.
+pub const OWNED_SYNC_INTERVAL: u32 = 4;
+
+/// Ticks between owned syncs while no new input is applied, so a lost sync is still
+/// corrected.
+pub const OWNED_SYNC_KEEPALIVE_TICKS: u32 = 30;
+
+#[derive(Debug)]
+struct OwnedSyncState {
+    last_order: u64,
+    ticks_since: u32,
+}
+
+#[derive(Default)]
+struct OwnedSyncTracker {
+    players: HashMap<NetworkObject, OwnedSyncState>,
+}
+
+impl OwnedSyncTracker {
+    /// Counts a tick for the player and returns whether its owner should be sent a sync
+    /// acknowledging `order`. `force` sends one regardless.
+    fn should_send(&mut self, net_obj: NetworkObject, order: u64, force: bool) -> bool {
+        // A new player gets its first sync straight away.
+        let state = self.players.entry(net_obj).or_insert(OwnedSyncState {
+            last_order: order,
+            ticks_since: OWNED_SYNC_KEEPALIVE_TICKS,
+        });
+        state.ticks_since = state.ticks_since.saturating_add(1);
+
+        let interval = if order > state.last_order {
+            OWNED_SYNC_INTERVAL
+        } else {
+            OWNED_SYNC_KEEPALIVE_TICKS
+        };
+
+        if !force && state.ticks_since < interval {
+            return false;
+        }
+
+        state.last_order = order;
+        state.ticks_since = 0;
+        true
+    }
+
+    fn remove(&mut self, net_obj: NetworkObject) {
+        self.players.remove(&net_obj);
+    }
+}
+
+/// Ticks a disconnected client's player is kept frozen in place, in case the client comes
+/// back after a brief network blip.
+pub const RECONNECT_GRACE_TICKS: u32 = 5 * TICK_RATE;
+
+/// The player of a client that dropped, waiting for it to reconnect.
+///
+/// A reconnecting client is matched by the `client_id` in its connect token. The token is
+/// signed with the instance's key, so only whoever was handed a token for that id can
+/// reclaim the player.
+#[derive(Debug)]
+struct HeldPlayer {
+    net_obj: NetworkObject,
+    ticks_left: u32,
+}
+
 pub struct Game {
     instance: Instance,
     server: Server,
     tick: TickData,
     message_queues: HashMap<u64, MessageQueue>,
     client_map: ClientNetworkObjectMap,
-    player_spawn_requests: Vec<(Vec2, NetworkObject)>,
+    player_spawn_requests: Vec<PlayerSpawnRequest>,
     inputs: ClientInputs,
+    idle: IdleTracker,
+    owned_syncs: OwnedSyncTracker,
+    budget: SyncBudget,
+    timestep: FixedTimestep,
+    /// Clients told why they are being disconnected, with how much longer they are given to
+    /// acknowledge it. See [`DISCONNECT_DRAIN`].
+    pending_disconnects: HashMap<u64, Duration>,
+    /// Players of disconnected clients, keyed by client id, until they reconnect or
+    /// [`RECONNECT_GRACE_TICKS`] pass.
+    held_players: HashMap<u64, HeldPlayer>,
+    /// The players in `held_players`, so checking whether a player is held doesn't scan them.
+    held_objects: HashSet<NetworkObject>,
+    /// Clients connected through [`ReliableMessageFromClient::Spectate`] that have no player.
+    spectators: HashSet<u64>,
+    /// In the order the players joined, which [`ReliableMessageFromServer::PlayerList`] keeps.
+    player_names: IndexMap<NetworkObject, String>,
+    /// Entities driven by a player besides its own, mapped to that player. See
+    /// [`Game::grant_control`].
+    controllers: HashMap<NetworkObject, NetworkObject>,
+    areas: AreaTracker,
+    paused: bool,
+    tick_rate: u32,
+    /// Players, held players and spectators admitted before others are turned away.
+    max_clients: usize,
+    input_log: Option<InputLog>,
+    /// Client id the manager started the instance for. Only its connection may send
+    /// [`AdminCommand`]s.
+    owner: Option<u64>,
+    /// Sent since the last tick, handled at the end of the next one.
+    events: Vec<GameEvent>,
+    /// Handled in the last tick, oldest first.
+    handled_events: Vec<GameEvent>,
+    /// Players handed to the manager for another instance, by client id, kept until the
+    /// manager says whether the destination took them.
+    outgoing_handoffs: HashMap<u64, NetworkObject>,
+    /// Players handed over from another instance, by client id, waiting for their client to
+    /// connect.
+    incoming_handoffs: HashMap<u64, PlayerHandoff>,
 }
 
 impl Debug for Game {
@@ -170,20 +517,682 @@ impl Debug for Game {
 }
 
 impl Game {
-    fn new(instance_id: Uuid, server: Server) -> Game {
+    pub fn new(instance_id: Uuid, server: Server) -> Game {
+        Game::with_level(instance_id, &LevelDef::default(), server)
+    }
+
+    pub fn with_level(instance_id: Uuid, level: &LevelDef, server: Server) -> Game {
         Game {
-            instance: Instance::new(instance_id),
+            instance: Instance::with_level(instance_id, level),
             server,
             tick: TickData::new(),
             message_queues: HashMap::new(),
             client_map: ClientNetworkObjectMap::default(),
             player_spawn_requests: Vec::new(),
             inputs: ClientInputs::default(),
+            idle: IdleTracker::default(),
+            owned_syncs: OwnedSyncTracker::default(),
+            budget: SyncBudget::default(),
+            timestep: FixedTimestep::new(tick_duration(TICK_RATE)),
+            pending_disconnects: HashMap::new(),
+            held_players: HashMap::new(),
+            held_objects: HashSet::new(),
+            spectators: HashSet::new(),
+            player_names: IndexMap::new(),
+            controllers: HashMap::new(),
+            areas: AreaTracker::default(),
+            paused: false,
+            tick_rate: TICK_RATE,
+            max_clients: MAX_CLIENTS,
+            input_log: None,
+            owner: None,
+            events: Vec::new(),
+            handled_events: Vec::new(),
+            outgoing_handoffs: HashMap::new(),
+            incoming_handoffs: HashMap::new(),
+        }
+    }
+
+    /// Runs the simulation at `tick_rate` ticks per second instead of [`TICK_RATE`]. A rate of
+    /// 0 has no tick duration and is ignored.
+    pub fn set_tick_rate(&mut self, tick_rate: u32) {
+        if tick_rate == 0 {
+            warn!("Ignoring a tick rate of 0");
+            return;
         }
+
+        self.tick_rate = tick_rate;
+    }
+
+    /// Switches to `tick_rate` while clients are connected, telling them straight away so
+    /// their tick estimate follows.
+    pub fn change_tick_rate(&mut self, tick_rate: u32) -> Result<()> {
+        if tick_rate == 0 {
+            warn!("Ignoring a tick rate of 0");
+            return Ok(());
+        }
+
+        info!("Tick rate changed from {} to {tick_rate}", self.tick_rate);
+        self.set_tick_rate(tick_rate);
+        self.tick.break_timeline();
+
+        let message = self.tick_sync();
+        self.server.broadcast_reliable_message(message)
+    }
+
+    /// Admits at most `max_clients` players and spectators, held players included. Clients
+    /// past that are sent [`ReliableMessageFromServer::ServerFull`] and disconnected. Only
+    /// up to [`server::FULL_HEADROOM`] more than [`MAX_CLIENTS`] can be told.
+    pub fn set_max_clients(&mut self, max_clients: usize) {
+        self.max_clients = max_clients;
+    }
+
+    pub fn get_max_clients(&self) -> usize {
+        self.max_clients
+    }
+
+    /// Broadcasts a tick sync every `interval`, or never for a zero interval. See
+    /// [`tick::TICK_BROADCAST_INTERVAL`].
+    pub fn set_tick_broadcast_interval(&mut self, interval: Duration) {
+        self.tick.set_broadcast_interval(interval);
+    }
+
+    pub fn get_tick_broadcast_interval(&self) -> Duration {
+        self.tick.get_broadcast_interval()
+    }
+
+    /// Caps the position syncs each client is sent per tick at `bytes`, besides its own
+    /// player's sync. See [`budget::SYNC_BUDGET_BYTES`].
+    pub fn set_sync_budget(&mut self, bytes: usize) {
+        self.budget.set_bytes_per_tick(bytes);
+    }
+
+    pub fn get_sync_budget(&self) -> usize {
+        self.budget.get_bytes_per_tick()
+    }
+
+    /// Keeps at least `length` inputs queued per player before dropping the oldest. See
+    /// [`INPUT_QUEUE_LENGTH`].
+    pub fn set_input_queue_length(&mut self, length: usize) {
+        self.inputs.min_length = length;
+    }
+
+    pub fn get_input_queue_length(&self) -> usize {
+        self.inputs.min_length
+    }
+
+    pub fn get_tick_rate(&self) -> u32 {
+        self.tick_rate
+    }
+
+    /// Lets the client `owner` send [`AdminCommand`]s. Without an owner they are all rejected.
+    pub fn set_owner(&mut self, owner: Option<u64>) {
+        self.owner = owner;
+    }
+
+    pub fn get_owner(&self) -> Option<u64> {
+        self.owner
+    }
+
+    /// Queues `event` to be handled at the end of the next tick, after those already queued.
+    pub fn send_event(&mut self, event: GameEvent) {
+        self.events.push(event);
+    }
+
+    /// Events handled in the last tick, in the order they were sent.
+    pub fn get_handled_events(&self) -> &[GameEvent] {
+        &self.handled_events
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// A one-line summary of the instance, for the backend's `status` command.
+    pub fn get_status(&self) -> String {
+        format!(
+            "tick {} | {} players | {} spectators | {} held | {} ticks/s{}",
+            self.instance.get_tick().get(),
+            self.instance.player_count(),
+            self.spectators.len(),
+            self.held_players.len(),
+            self.tick_rate,
+            if self.paused { " | paused" } else { "" }
+        )
+    }
+
+    /// The tick, player count and pause state, for the backend's `report` command.
+    pub fn get_report(&self) -> InstanceReport {
+        InstanceReport {
+            tick: self.instance.get_tick().get(),
+            players: self.instance.player_count(),
+            paused: self.paused,
+        }
+    }
+
+    /// Starts recording the applied inputs and resulting positions of the last `ticks` ticks.
+    pub fn enable_input_log(&mut self, ticks: usize) {
+        self.input_log = Some(InputLog::new(ticks));
+    }
+
+    pub fn get_input_log(&self) -> Option<&InputLog> {
+        self.input_log.as_ref()
+    }
+
+    pub fn get_tick_duration(&self) -> Duration {
+        tick_duration(self.tick_rate)
+    }
+
+    pub fn get_instance(&self) -> &Instance {
+        &self.instance
+    }
+
+    pub fn get_instance_mut(&mut self) -> &mut Instance {
+        &mut self.instance
+    }
+
+    pub fn get_server_mut(&mut self) -> &mut Server {
+        &mut self.server
+    }
+
+    /// How many messages from `client_id` were dropped because its queue was full.
+    pub fn get_dropped_messages(&self, client_id: u64) -> Option<DroppedMessages> {
+        Some(self.message_queues.get(&client_id)?.dropped)
+    }
+
+    /// Whether `net_obj`'s client dropped and the player is waiting for it to reconnect.
+    pub fn is_player_held(&self, net_obj: NetworkObject) -> bool {
+        self.held_objects.contains(&net_obj)
+    }
+
+    /// Sets how far past a client's surrounding areas another player may go before the
+    /// client is sent its despawn. See [`area::AREA_HYSTERESIS`].
+    pub fn set_area_hysteresis(&mut self, hysteresis: f32) {
+        self.areas.set_hysteresis(hysteresis);
+    }
+
+    pub fn get_area_hysteresis(&self) -> f32 {
+        self.areas.get_hysteresis()
+    }
+
+    /// The broadcast area `net_obj` was in at the end of the last tick.
+    pub fn get_area(&self, net_obj: NetworkObject) -> Option<Area> {
+        self.areas.get_area(net_obj)
+    }
+
+    pub fn local_address(&self) -> SocketAddr {
+        self.server.local_address()
+    }
+
+    /// Advances the server by `elapsed` wall-clock time, running as many fixed
+    /// updates as fit into the accumulated time and flushing packets afterwards.
+    pub fn step(&mut self, elapsed: Duration) -> Result<()> {
+        self.server.update(elapsed)?;
+
+        self.handle_server_events()?;
+
+        let dt = self.get_tick_duration();
+        self.timestep.set_dt(dt);
+
+        for _ in 0..self.timestep.tick(elapsed) {
+            self.update(dt)?;
+        }
+
+        self.server.send_packets();
+
+        self.flush_pending_disconnects(elapsed);
+
+        Ok(())
+    }
+
+    /// Disconnects clients once they acknowledged the message saying why, or once they had
+    /// [`DISCONNECT_DRAIN`] to do so.
+    fn flush_pending_disconnects(&mut self, elapsed: Duration) {
+        self.pending_disconnects.retain(|client_id, left| {
+            *left = left.saturating_sub(elapsed);
+
+            let flushed = self.server.is_client_flushed(*client_id);
+            if !flushed && !left.is_zero() {
+                return true;
+            }
+
+            if !flushed {
+                warn!("Client {client_id} didn't acknowledge why it is disconnected in time");
+            }
+            self.server.disconnect(*client_id);
+            false
+        });
+    }
+
+    /// Tells every client the instance is closing, then keeps the connection pumped for at
+    /// most `drain` so the message arrives, and finally disconnects everyone. Clients can
+    /// then show that the server closed instead of waiting to time out.
+    pub fn shutdown(&mut self, drain: Duration) -> Result<()> {
+        let clients = self.server.client_ids().len();
+        info!("Shutting down, telling {clients} clients");
+
+        self.server
+            .broadcast_reliable_message(ReliableMessageFromServer::ServerShutdown)?;
+        self.server.send_packets();
+
+        let deadline = Instant::now() + drain;
+        let mut last_update = Instant::now();
+        while !self.server.is_reliable_flushed() && Instant::now() < deadline {
+            std::thread::sleep(self.get_tick_duration().min(deadline - Instant::now()));
+
+            self.server.update(last_update.elapsed())?;
+            last_update = Instant::now();
+            self.server.send_packets();
+        }
+
+        if !self.server.is_reliable_flushed() {
+            warn!("Not every client acknowledged the shutdown in time");
+        }
+
+        self.server.disconnect_all();
+
+        Ok(())
+    }
+
+    fn handle_server_events(&mut self) -> Result<()> {
+        while let Some(event) = self.server.get_event() {
+            match event {
+                renet::ServerEvent::ClientConnected { client_id } => {
+                    info!("Client connected: {client_id}");
+                    self.message_queues
+                        .insert(client_id, MessageQueue::default());
+                }
+                renet::ServerEvent::ClientDisconnected { client_id, reason } => {
+                    info!("Client disconnected: {client_id}, reason: {reason:?}");
+                    // Only a lost connection may come back; anything else ended it on purpose.
+                    match reason {
+                        renet::DisconnectReason::Transport => self.hold_client(client_id),
+                        _ => self.remove_client(client_id)?,
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Despawns the client's player, if it has one, and tells everyone it left.
+    fn remove_client(&mut self, client_id: u64) -> Result<()> {
+        if let Some(net_obj) = self.detach_player(client_id) {
+            self.remove_player(net_obj)?;
+        }
+        self.spectators.remove(&client_id);
+        self.outgoing_handoffs.remove(&client_id);
+        self.areas.remove_client(client_id);
+        self.budget.remove_client(client_id);
+        self.message_queues.remove(&client_id);
+
+        Ok(())
+    }
+
+    /// Forgets a dropped client but keeps its player frozen for [`RECONNECT_GRACE_TICKS`].
+    fn hold_client(&mut self, client_id: u64) {
+        if let Some(net_obj) = self.detach_player(client_id) {
+            info!("Holding player {net_obj:?} of client {client_id} for a reconnect");
+            self.held_players.insert(
+                client_id,
+                HeldPlayer {
+                    net_obj,
+                    ticks_left: RECONNECT_GRACE_TICKS,
+                },
+            );
+            self.held_objects.insert(net_obj);
+        }
+        // The manager can't finish a handoff for a client that isn't there to switch over.
+        if let Some(net_obj) = self.outgoing_handoffs.remove(&client_id) {
+            info!("Calling off the handoff of player {net_obj:?} of client {client_id}");
+        }
+        self.spectators.remove(&client_id);
+        self.areas.remove_client(client_id);
+        self.budget.remove_client(client_id);
+        self.message_queues.remove(&client_id);
+    }
+
+    /// Unlinks the client from its player, leaving the entity in the world.
+    fn detach_player(&mut self, client_id: u64) -> Option<NetworkObject> {
+        let net_obj = self.client_map.remove_by_left(&client_id)?;
+        self.inputs.remove(net_obj);
+        self.idle.remove(net_obj);
+        self.owned_syncs.remove(net_obj);
+
+        Some(net_obj)
+    }
+
+    /// Despawns a player that no client owns any more and tells everyone it left.
+    fn remove_player(&mut self, net_obj: NetworkObject) -> Result<()> {
+        self.controllers
+            .retain(|controlled, owner| *owner != net_obj && *controlled != net_obj);
+
+        match self.instance.find_network_object(net_obj) {
+            Some(entity) => self.despawn_and_broadcast(entity, net_obj)?,
+            None => warn!("Player {net_obj:?} already despawned"),
+        }
+
+        if self.player_names.shift_remove(&net_obj).is_some() {
+            let message = ReliableMessageFromServer::PlayerLeft(net_obj);
+            self.server.broadcast_reliable_message(message)?;
+        }
+
+        Ok(())
+    }
+
+    /// Lets the client owning the player `owner` send inputs for `net_obj`, an entity with a
+    /// player's components. Syncs for `net_obj` then go to that client as owned syncs.
+    pub fn grant_control(&mut self, owner: NetworkObject, net_obj: NetworkObject) -> Result<()> {
+        if let Some(previous) = self.controllers.insert(net_obj, owner)
+            && previous != owner
+        {
+            self.send_to_player(previous, ReliableMessageFromServer::ControlRevoked(net_obj))?;
+        }
+
+        // A fresh controller counts orders from the start.
+        self.inputs.remove(net_obj);
+        self.owned_syncs.remove(net_obj);
+        if let Some(entity) = self.instance.find_network_object(net_obj)
+            && let Ok(mut last_input) = self
+                .instance
+                .get_world()
+                .get::<&mut LastInputTracker>(entity)
+        {
+            last_input.order = 0;
+        }
+
+        self.send_to_player(owner, ReliableMessageFromServer::ControlGranted(net_obj))
+    }
+
+    /// Hands `net_obj` back to the server, if a player controls it.
+    pub fn revoke_control(&mut self, net_obj: NetworkObject) -> Result<()> {
+        let Some(owner) = self.controllers.remove(&net_obj) else {
+            return Ok(());
+        };
+        self.inputs.remove(net_obj);
+        self.owned_syncs.remove(net_obj);
+
+        self.send_to_player(owner, ReliableMessageFromServer::ControlRevoked(net_obj))
+    }
+
+    /// The player controlling `net_obj`, either its own or through [`Game::grant_control`].
+    fn controlling_player(&self, net_obj: NetworkObject) -> NetworkObject {
+        self.controllers.get(&net_obj).copied().unwrap_or(net_obj)
+    }
+
+    /// Sends `message` to the client owning the player `net_obj`, if it is connected.
+    fn send_to_player(
+        &mut self,
+        net_obj: NetworkObject,
+        message: ReliableMessageFromServer,
+    ) -> Result<()> {
+        if let Some(&client_id) = self.client_map.get_by_right(&net_obj) {
+            self.server.send_reliable_message(client_id, message)?;
+        }
+
+        Ok(())
+    }
+
+    /// Removes held players whose client didn't come back in time.
+    fn expire_held_players(&mut self) -> Result<()> {
+        let mut expired = Vec::new();
+        self.held_players.retain(|client_id, held| {
+            held.ticks_left = held.ticks_left.saturating_sub(1);
+            if held.ticks_left == 0 {
+                info!("Client {client_id} did not reconnect in time");
+                expired.push(held.net_obj);
+            }
+            held.ticks_left > 0
+        });
+
+        for net_obj in expired {
+            self.held_objects.remove(&net_obj);
+            self.remove_player(net_obj)?;
+        }
+
+        Ok(())
+    }
+
+    /// The first player in the instance going by `name`.
+    pub fn find_player_by_name(&self, name: &str) -> Option<NetworkObject> {
+        self.player_names
+            .iter()
+            .find(|(_, player_name)| *player_name == name)
+            .map(|(net_obj, _)| *net_obj)
+    }
+
+    /// Tells the client owning `net_obj` why it is being removed, despawns its player and
+    /// disconnects it once it acknowledged that message, or after [`DISCONNECT_DRAIN`].
+    pub fn kick_player(&mut self, net_obj: NetworkObject, reason: &str) -> Result<()> {
+        let Some(&client_id) = self.client_map.get_by_right(&net_obj) else {
+            warn!("Tried to kick unknown player {net_obj:?}");
+            return Ok(());
+        };
+
+        info!("Kicking client {client_id}: {reason}");
+
+        let message = ReliableMessageFromServer::Kicked(reason.to_string());
+        self.server.send_reliable_message(client_id, message)?;
+
+        self.remove_client(client_id)?;
+        self.pending_disconnects.insert(client_id, DISCONNECT_DRAIN);
+
+        Ok(())
+    }
+
+    /// Renames the player owning `net_obj` and tells every client about it.
+    pub fn rename_player(&mut self, net_obj: NetworkObject, name: &str) -> Result<()> {
+        let name = cap_name(name);
+
+        if !self.instance.rename_player(net_obj, name.clone()) {
+            warn!("Tried to rename unknown player {net_obj:?}");
+            return Ok(());
+        }
+
+        self.player_names.insert(net_obj, name.clone());
+
+        let message = ReliableMessageFromServer::PlayerRenamed(net_obj, name);
+        self.server.broadcast_reliable_message(message)?;
+
+        Ok(())
+    }
+
+    /// Puts the client `client_id` in control of a new character called `name` with a
+    /// collider of `radius`, without dropping its connection. The old player leaves as if its
+    /// client had, and the new one spawns where it stood under a fresh [`NetworkObject`].
+    /// Returns `None` for clients without a player.
+    pub fn switch_character(
+        &mut self,
+        client_id: u64,
+        name: &str,
+        radius: f32,
+    ) -> Result<Option<NetworkObject>> {
+        let Some(&old) = self.client_map.get_by_left(&client_id) else {
+            warn!("Client {client_id} has no player to switch from");
+            return Ok(None);
+        };
+
+        self.replace_player(client_id, old, name, radius, None, Abilities::default())
+            .map(Some)
+    }
+
+    /// Despawns `old`, the player of `client_id`, and spawns one called `name` in its place,
+    /// at `position` if given and otherwise where `old` stood. A spawn point stands in if
+    /// that isn't walkable.
+    fn replace_player(
+        &mut self,
+        client_id: u64,
+        old: NetworkObject,
+        name: &str,
+        radius: f32,
+        position: Option<Vec2>,
+        abilities: Abilities,
+    ) -> Result<NetworkObject> {
+        let controlled: Vec<_> = self
+            .controllers
+            .iter()
+            .filter(|(_, owner)| **owner == old)
+            .map(|(net_obj, _)| *net_obj)
+            .collect();
+        for net_obj in controlled {
+            self.revoke_control(net_obj)?;
+        }
+
+        let position = position.or_else(|| {
+            let entity = self.instance.find_network_object(old)?;
+            let position = self.instance.get_world().get::<&Position>(entity).ok()?.0;
+            Some(position)
+        });
+
+        self.detach_player(client_id);
+        self.remove_player(old)?;
+
+        let reserved: Vec<(Vec2, f32)> = self
+            .player_spawn_requests
+            .iter()
+            .map(|request| (request.position, request.radius))
+            .collect();
+        let position = position
+            .filter(|position| self.instance.is_walkable(*position, radius))
+            .unwrap_or_else(|| self.instance.find_spawn_point(radius, &reserved));
+
+        let name = cap_name(name);
+        let net_obj = self.instance.new_network_object();
+        self.client_map.insert(client_id, net_obj);
+
+        info!("Client {client_id} switched from player {old:?} to {net_obj:?}");
+
+        self.player_spawn_requests.push(PlayerSpawnRequest {
+            position,
+            name: name.clone(),
+            net_obj,
+            radius,
+            abilities,
+        });
+
+        let message = ReliableMessageFromServer::PlayerInit(PlayerInit {
+            net_obj,
+            position: position.into(),
+            name: name.clone(),
+            radius,
+            tick: self.instance.get_tick(),
+        });
+        self.server.send_reliable_message(client_id, message)?;
+
+        self.player_names.insert(net_obj, name.clone());
+
+        let message = ReliableMessageFromServer::PlayerJoined(net_obj, name);
+        self.server.broadcast_reliable_message(message)?;
+
+        Ok(net_obj)
+    }
+
+    /// Snapshots the player of `client_id`, playing `character_id`, for the manager to hand
+    /// to another instance. The player stays until [`Game::finish_handoff`] or
+    /// [`Game::cancel_handoff`]. Returns `None` for clients without a player.
+    pub fn begin_handoff(&mut self, client_id: u64, character_id: u32) -> Option<PlayerHandoff> {
+        let Some(&net_obj) = self.client_map.get_by_left(&client_id) else {
+            warn!("Client {client_id} has no player to hand off");
+            return None;
+        };
+
+        let entity = self.instance.find_network_object(net_obj)?;
+        let world = self.instance.get_world();
+        let position = world.get::<&Position>(entity).ok()?.0;
+        let radius = world.get::<&Player>(entity).ok()?.radius;
+        let cooldowns = PlayerHandoff::remaining_cooldowns(&*world.get::<&Abilities>(entity).ok()?);
+        let name = self.player_names.get(&net_obj)?.clone();
+
+        info!("Handing off player {net_obj:?} of client {client_id}");
+        self.outgoing_handoffs.insert(client_id, net_obj);
+
+        Some(PlayerHandoff {
+            client_id,
+            character_id,
+            name,
+            radius,
+            position: position.into(),
+            cooldowns,
+        })
+    }
+
+    /// Removes a player the destination instance took, leaving its client watching. Does
+    /// nothing if the client has switched to another player since the handoff began.
+    pub fn finish_handoff(&mut self, client_id: u64) -> Result<()> {
+        let Some(net_obj) = self.outgoing_handoffs.remove(&client_id) else {
+            warn!("Client {client_id} has no handoff to finish");
+            return Ok(());
+        };
+
+        if self.client_map.get_by_left(&client_id) != Some(&net_obj) {
+            warn!("Client {client_id} no longer plays {net_obj:?}, keeping its player");
+            return Ok(());
+        }
+
+        info!("Player {net_obj:?} of client {client_id} left for another instance");
+
+        let controlled: Vec<_> = self
+            .controllers
+            .iter()
+            .filter(|(_, owner)| **owner == net_obj)
+            .map(|(net_obj, _)| *net_obj)
+            .collect();
+        for controlled in controlled {
+            self.revoke_control(controlled)?;
+        }
+
+        self.detach_player(client_id);
+        self.remove_player(net_obj)?;
+        if self.message_queues.contains_key(&client_id) {
+            self.spectators.insert(client_id);
+        }
+
+        Ok(())
+    }
+
+    /// Keeps a player whose destination instance couldn't take it.
+    pub fn cancel_handoff(&mut self, client_id: u64) {
+        if let Some(net_obj) = self.outgoing_handoffs.remove(&client_id) {
+            info!("Handoff of player {net_obj:?} of client {client_id} failed, keeping it");
+        }
+    }
+
+    /// Whether the player of `client_id` was handed to the manager and is waiting on the
+    /// destination.
+    pub fn is_handing_off(&self, client_id: u64) -> bool {
+        self.outgoing_handoffs.contains_key(&client_id)
+    }
+
+    /// Takes a player handed over from another instance. A client that already has a player
+    /// here gets the handed over one in its place, anyone else gets it once it connects.
+    pub fn accept_handoff(&mut self, handoff: PlayerHandoff) -> Result<()> {
+        info!(
+            "Accepting character {} as client {}",
+            handoff.character_id, handoff.client_id
+        );
+
+        let Some(&old) = self.client_map.get_by_left(&handoff.client_id) else {
+            self.incoming_handoffs.insert(handoff.client_id, handoff);
+            return Ok(());
+        };
+
+        self.replace_player(
+            handoff.client_id,
+            old,
+            &handoff.name,
+            handoff.radius,
+            Some(Vec2::from(handoff.position)),
+            handoff.get_abilities(),
+        )?;
+
+        Ok(())
     }
 
     fn despawn_and_broadcast(&mut self, entity: Entity, net_obj: NetworkObject) -> Result<()> {
         self.instance.despawn(entity);
+        self.areas.remove_player(net_obj);
+        self.send_event(GameEvent::PlayerRemoved(net_obj));
 
         let message = ReliableMessageFromServer::Despawn(net_obj);
 
@@ -192,108 +1201,362 @@ impl Game {
         Ok(())
     }
 
+    #[profiling::function]
     fn receive_messages(&mut self) -> Result<()> {
         for client_id in self.server.client_ids() {
+            // Clients on their way out are only waited on to read why.
+            if self.pending_disconnects.contains_key(&client_id) {
+                continue;
+            }
+
             let Some(message_queue) = self.message_queues.get_mut(&client_id) else {
                 continue;
             };
 
-            while let Some(msg) = self
-                .server
-                .receive_reliable_message(client_id)
-                .transpose()?
-            {
-                message_queue.reliable.push(msg);
+            let mut overflowed = 0;
+
+            while let Some(msg) = self.server.receive_reliable_message(client_id) {
+                match msg {
+                    Ok(msg) => overflowed += usize::from(!message_queue.push_reliable(msg)),
+                    Err(err) => warn!("Dropping reliable message from {client_id}: {err}"),
+                }
             }
 
-            while let Some(msg) = self
-                .server
-                .receive_unreliable_message(client_id)
-                .transpose()?
-            {
-                message_queue.unreliable.push(msg);
+            while let Some(msg) = self.server.poll_large(client_id) {
+                match msg {
+                    Ok(msg) => overflowed += usize::from(!message_queue.push_reliable(msg)),
+                    Err(err) => warn!("Dropping large message from {client_id}: {err}"),
+                }
+            }
+
+            if overflowed > 0 {
+                warn!("Dropped {overflowed} reliable messages from {client_id}: queue is full");
+            }
+
+            while let Some(msg) = self.server.receive_unreliable_message(client_id) {
+                match msg {
+                    Ok(msg) => message_queue.push_unreliable(msg),
+                    Err(err) => warn!("Dropping unreliable message from {client_id}: {err}"),
+                }
             }
         }
 
         Ok(())
     }
 
+    #[profiling::function]
     fn read_inputs(&mut self) -> Result<()> {
         for client_id in self.server.client_ids() {
             if let Some(message_queue) = self.message_queues.get(&client_id) {
                 for msg in &message_queue.unreliable {
                     if let UnreliableMessageFromClient::Input(ordered_input) = msg {
-                        if let Some(net_obj) = self.client_map.client_to_net_obj.get(&client_id) {
-                            self.inputs.push_input(*net_obj, ordered_input.clone());
-                        } else {
+                        let Some(&net_obj) = self.client_map.get_by_left(&client_id) else {
                             warn!("Unknown client_id: {client_id}");
+                            continue;
+                        };
+
+                        let target = ordered_input.target.unwrap_or(net_obj);
+                        if self.controlling_player(target) != net_obj {
+                            warn!(
+                                "Client {client_id} sent input for {target:?} it doesn't control"
+                            );
+                            continue;
                         }
+
+                        // Cleaned up once here, so the input log and reconciliation see
+                        // exactly what was applied.
+                        let mut ordered_input = ordered_input.clone();
+                        ordered_input.input = ordered_input.input.sanitized();
+                        self.inputs.push_input(target, ordered_input);
                     }
                 }
             }
         }
 
-        self.inputs.prune(10);
+        let tick_rate = self.tick_rate;
+        let (server, client_map) = (&self.server, &self.client_map);
+        self.inputs.prune(|net_obj| {
+            client_map.get_by_right(&net_obj).map_or(0, |client_id| {
+                (server.rtt(*client_id).as_secs_f64() * tick_rate as f64).ceil() as usize
+            })
+        });
+
+        Ok(())
+    }
+
+    /// The instance's current tick, stamped with the current time.
+    fn tick_sync(&self) -> ReliableMessageFromServer {
+        ReliableMessageFromServer::TickSync(TickSync {
+            tick: self.instance.get_tick().get(),
+            unix_millis: get_unix_millis(),
+            tick_rate: self.tick_rate,
+            timeline: self.tick.get_timeline(),
+        })
+    }
+
+    /// Everyone in the instance, as sent in [`ReliableMessageFromServer::PlayerList`].
+    fn player_list(&self) -> Vec<(NetworkObject, String)> {
+        self.player_names
+            .iter()
+            .map(|(net_obj, name)| (*net_obj, name.clone()))
+            .collect()
+    }
+
+    /// Position, radius and name of the held player `net_obj`, if it is still whole enough for
+    /// its client to take back.
+    fn reclaimable(&self, net_obj: NetworkObject) -> Option<(Vec2, f32, String)> {
+        let entity = self.instance.find_network_object(net_obj)?;
+        let world = self.instance.get_world();
+        let position = world.get::<&Position>(entity).ok()?.0;
+        let radius = world.get::<&Player>(entity).ok()?.radius;
+        let name = self.player_names.get(&net_obj)?.clone();
+        Some((position, radius, name))
+    }
+
+    /// Removes what is left of held players that are gone by the time their client comes
+    /// back, before it is spawned a new one.
+    fn remove_unreclaimable_players(&mut self) -> Result<()> {
+        let gone: Vec<_> = self
+            .held_players
+            .iter()
+            .filter(|(client_id, held)| {
+                self.message_queues.get(client_id).is_some_and(|queue| {
+                    queue.reliable.iter().any(|msg| {
+                        matches!(
+                            msg,
+                            ReliableMessageFromClient::Connected { protocol_version }
+                                if *protocol_version == PROTOCOL_VERSION
+                        )
+                    })
+                }) && self.reclaimable(held.net_obj).is_none()
+            })
+            .map(|(client_id, held)| (*client_id, held.net_obj))
+            .collect();
+
+        for (client_id, net_obj) in gone {
+            warn!("Held player {net_obj:?} is gone, spawning a new one");
+            self.held_players.remove(&client_id);
+            self.held_objects.remove(&net_obj);
+            self.remove_player(net_obj)?;
+        }
 
         Ok(())
     }
 
     fn handle_connections(&mut self) -> Result<()> {
+        self.remove_unreclaimable_players()?;
+
         for (client_id, message_queue) in &self.message_queues {
             for msg in &message_queue.reliable {
                 match msg {
-                    ReliableMessageFromClient::Connected => {
+                    ReliableMessageFromClient::Connected { protocol_version }
+                    | ReliableMessageFromClient::Spectate { protocol_version }
+                        if *protocol_version != PROTOCOL_VERSION =>
+                    {
+                        warn!(
+                            "Client {client_id} uses protocol version {protocol_version}, expected {PROTOCOL_VERSION}"
+                        );
+                        let message = ReliableMessageFromServer::VersionMismatch {
+                            server_version: PROTOCOL_VERSION,
+                        };
+                        self.server.send_reliable_message(*client_id, message)?;
+                        self.pending_disconnects.insert(*client_id, DISCONNECT_DRAIN);
+                    }
+                    ReliableMessageFromClient::Connected { .. }
+                    | ReliableMessageFromClient::Spectate { .. }
+                        if !self.client_map.contains_left(client_id)
+                            && !self.spectators.contains(client_id)
+                            && !self.held_players.contains_key(client_id)
+                            && self.client_map.len()
+                                + self.spectators.len()
+                                + self.held_players.len()
+                                >= self.max_clients =>
+                    {
+                        warn!("Client {client_id} tried to join, but the instance is full");
+                        let message = ReliableMessageFromServer::ServerFull;
+                        self.server.send_reliable_message(*client_id, message)?;
+                        self.pending_disconnects.insert(*client_id, DISCONNECT_DRAIN);
+                    }
+                    ReliableMessageFromClient::Spectate { .. } => {
+                        info!("Received spectate from {client_id}");
+
+                        if self.client_map.contains_left(client_id) {
+                            warn!("Client {client_id} already has a player, ignoring spectate");
+                            continue;
+                        }
+
+                        if !self.spectators.insert(*client_id) {
+                            warn!("spectate called more than once");
+                            continue;
+                        }
+
+                        let message = self.tick_sync();
+                        self.server.send_reliable_message(*client_id, message)?;
+
+                        let message = ReliableMessageFromServer::PlayerList(self.player_list());
+                        self.server.send_reliable_message(*client_id, message)?;
+                    }
+                    ReliableMessageFromClient::Connected { .. } => {
                         info!("Received connected from {client_id}");
-                        if self.client_map.client_to_net_obj.contains_key(client_id) {
+
+                        if self.client_map.contains_left(client_id) {
                             warn!("connected called more than once");
                             continue;
                         }
 
-                        let net_obj = NetworkObject::new_rand();
-                        self.client_map
-                            .client_to_net_obj
-                            .insert(*client_id, net_obj);
-                        self.client_map
-                            .net_obj_to_client
-                            .insert(net_obj, *client_id);
+                        if self.spectators.remove(client_id) {
+                            info!("Spectator {client_id} is joining as a player");
+
+                            // It was sent every player while spectating; the next area update
+                            // takes away the ones out of view.
+                            let known = self
+                                .instance
+                                .iter_players()
+                                .map(|(net_obj, _)| net_obj)
+                                .collect();
+                            self.areas.watch(*client_id, known);
+                        }
 
-                        let position = Vec2::zeros();
+                        if let Some(held) = self.held_players.remove(client_id) {
+                            self.held_objects.remove(&held.net_obj);
+                            // Held players that are gone were removed before handling messages.
+                            if let Some((position, radius, name)) = self.reclaimable(held.net_obj) {
+                                info!("Client {client_id} reclaimed player {:?}", held.net_obj);
+
+                                self.client_map.insert(*client_id, held.net_obj);
+
+                                let message = ReliableMessageFromServer::PlayerInit(PlayerInit {
+                                    net_obj: held.net_obj,
+                                    position: position.into(),
+                                    name,
+                                    radius,
+                                    tick: self.instance.get_tick(),
+                                });
+                                self.server.send_reliable_message(*client_id, message)?;
+
+                                let message = self.tick_sync();
+                                self.server.send_reliable_message(*client_id, message)?;
+
+                                let message =
+                                    ReliableMessageFromServer::PlayerList(self.player_list());
+                                self.server.send_reliable_message(*client_id, message)?;
+
+                                let controlled: Vec<_> = self
+                                    .controllers
+                                    .iter()
+                                    .filter(|(_, owner)| **owner == held.net_obj)
+                                    .map(|(net_obj, _)| *net_obj)
+                                    .collect();
+                                for net_obj in controlled {
+                                    let message =
+                                        ReliableMessageFromServer::ControlGranted(net_obj);
+                                    self.server.send_reliable_message(*client_id, message)?;
+                                }
+
+                                // Everyone else still has the player, so there's nothing to
+                                // announce.
+                                continue;
+                            }
+                        }
 
-                        self.player_spawn_requests.push((position, net_obj));
+                        let net_obj = self.instance.new_network_object();
+                        self.client_map.insert(*client_id, net_obj);
+
+                        let handoff = self.incoming_handoffs.remove(client_id);
+
+                        // Every character has the same footprint until character data says
+                        // otherwise.
+                        let radius = handoff.as_ref().map_or(PLAYER_RADIUS, |h| h.radius);
+
+                        let reserved: Vec<(Vec2, f32)> = self
+                            .player_spawn_requests
+                            .iter()
+                            .map(|request| (request.position, request.radius))
+                            .collect();
+                        let position = handoff
+                            .as_ref()
+                            .map(|handoff| Vec2::from(handoff.position))
+                            .filter(|position| self.instance.is_walkable(*position, radius))
+                            .unwrap_or_else(|| self.instance.find_spawn_point(radius, &reserved));
+                        let (name, abilities) = match &handoff {
+                            Some(handoff) => (handoff.name.clone(), handoff.get_abilities()),
+                            None => (
+                                self.server.character_name(*client_id).unwrap_or_default(),
+                                Abilities::default(),
+                            ),
+                        };
+
+                        self.player_spawn_requests.push(PlayerSpawnRequest {
+                            position,
+                            name: name.clone(),
+                            net_obj,
+                            radius,
+                            abilities,
+                        });
 
                         let message = ReliableMessageFromServer::PlayerInit(PlayerInit {
                             net_obj,
                             position: position.into(),
+                            name: name.clone(),
+                            radius,
                             tick: self.instance.get_tick(),
                         });
                         self.server.send_reliable_message(*client_id, message)?;
                         info!("Sent Player Init");
 
-                        let message = ReliableMessageFromServer::TickSync(TickSync {
-                            tick: self.instance.get_tick().get(),
-                            unix_millis: get_unix_millis(),
-                        });
+                        let message = self.tick_sync();
                         self.server.send_reliable_message(*client_id, message)?;
                         info!("Sent tick sync");
+
+                        self.player_names.insert(net_obj, name.clone());
+
+                        let message = ReliableMessageFromServer::PlayerList(self.player_list());
+                        self.server.send_reliable_message(*client_id, message)?;
+
+                        let message = ReliableMessageFromServer::PlayerJoined(net_obj, name);
+                        self.server
+                            .broadcast_reliable_message_except(*client_id, message)?;
                     }
                     ReliableMessageFromClient::ReadyForUpdates => {
                         info!("Received ready for updates from {client_id}");
 
-                        let tick = self.instance.get_tick();
+                        // Players are only sent what is around them from here on. Spectators
+                        // have no position, so they keep getting everything.
+                        let visible = self.client_map.get_by_left(client_id).map(|viewer| {
+                            let visible = self.areas.visible_from(*viewer);
+                            self.areas.watch(*client_id, visible.clone());
+                            visible
+                        });
 
-                        for (_, (net_obj, position, _)) in self
+                        let entries = self
                             .instance
-                            .get_world_mut()
-                            .query_mut::<(&NetworkObject, &Position, &Player)>()
-                        {
-                            let net_spawn = NetworkSpawn::Player(position.0.into());
-                            let message = ReliableMessageFromServer::Spawn(Spawn {
-                                net_obj: *net_obj,
-                                net_spawn,
-                                tick,
-                            });
-                            self.server.send_reliable_message(*client_id, message)?;
-                        }
+                            .iter_players()
+                            .map(|(net_obj, _)| net_obj)
+                            .filter(|net_obj| {
+                                visible
+                                    .as_ref()
+                                    .is_none_or(|visible| visible.contains(net_obj))
+                            })
+                            .filter_map(|net_obj| {
+                                Some(SpawnEntry {
+                                    net_obj,
+                                    net_spawn: self.player_spawn(net_obj)?,
+                                })
+                            })
+                            .chain(self.instance.iter_broadcast().filter_map(|net_obj| {
+                                Some(SpawnEntry {
+                                    net_obj,
+                                    net_spawn: self.entity_spawn(net_obj)?,
+                                })
+                            }))
+                            .collect();
+
+                        // A full instance's snapshot can outgrow a single message.
+                        let message = ReliableMessageFromServer::Snapshot {
+                            tick: self.instance.get_tick(),
+                            entries,
+                        };
+                        self.server.send_large(*client_id, message)?;
                     }
                     _ => {}
                 }
@@ -304,50 +1567,238 @@ impl Game {
     }
 
     fn process_player_spawn_requests(&mut self) -> Result<()> {
-        for (pos, net_obj) in self.player_spawn_requests.drain(..) {
-            self.instance.spawn_player(false, pos, net_obj, None);
+        for request in std::mem::take(&mut self.player_spawn_requests) {
+            let entity = self.instance.spawn_player(
+                false,
+                request.position,
+                request.name,
+                request.net_obj,
+                request.radius,
+                None,
+            );
+            if let Ok(mut abilities) = self.instance.get_world().get::<&mut Abilities>(entity) {
+                *abilities = request.abilities;
+            }
+            self.send_event(GameEvent::PlayerSpawned(request.net_obj));
+
+            // Watching clients get it from the area update once it is in view.
+            let tick = self.instance.get_tick();
+            for client_id in self.server.client_ids() {
+                if self.areas.is_watching(client_id) {
+                    continue;
+                }
+
+                let Some(net_spawn) = self.player_spawn(request.net_obj) else {
+                    continue;
+                };
+                let message = ReliableMessageFromServer::Spawn(Spawn {
+                    net_obj: request.net_obj,
+                    net_spawn,
+                    tick,
+                });
+                self.server.send_reliable_message(client_id, message)?;
+            }
+        }
 
-            let net_spawn = NetworkSpawn::Player(pos.into());
-            let spawn = Spawn {
+        Ok(())
+    }
+
+    /// Sends every client the entities spawned with `broadcast` since the last update.
+    fn broadcast_entity_spawns(&mut self) -> Result<()> {
+        let tick = self.instance.get_tick();
+        for net_obj in self.instance.take_broadcast_spawns() {
+            // Already despawned again.
+            let Some(net_spawn) = self.entity_spawn(net_obj) else {
+                continue;
+            };
+            let message = ReliableMessageFromServer::Spawn(Spawn {
                 net_obj,
                 net_spawn,
-                tick: self.instance.get_tick(),
-            };
-            let message = ReliableMessageFromServer::Spawn(spawn);
+                tick,
+            });
             self.server.broadcast_reliable_message(message)?;
         }
 
         Ok(())
     }
 
+    /// How a client is told about a non-player entity `net_obj`.
+    fn entity_spawn(&self, net_obj: NetworkObject) -> Option<NetworkSpawn> {
+        let entity = self.instance.find_network_object(net_obj)?;
+        let mut query = self
+            .instance
+            .get_world()
+            .query_one::<(&EntityKind, &Position, &EntityCollider)>(entity)
+            .ok()?;
+        let (kind, position, collider) = query.get()?;
+
+        Some(NetworkSpawn::Entity {
+            kind: *kind,
+            position: position.0.into(),
+            collider: *collider,
+        })
+    }
+
+    /// How a client is told about the player `net_obj` when it comes into view.
+    fn player_spawn(&self, net_obj: NetworkObject) -> Option<NetworkSpawn> {
+        let entity = self.instance.find_network_object(net_obj)?;
+        let world = self.instance.get_world();
+
+        let position = world.get::<&Position>(entity).ok()?.0;
+        let name = world.get::<&DisplayName>(entity).ok()?.0.clone();
+        let radius = world.get::<&Player>(entity).ok()?.radius;
+        let interpolate = world
+            .get::<&Interpolate>(entity)
+            .map_or(true, |interpolate| interpolate.0);
+
+        Some(NetworkSpawn::Player {
+            position: position.into(),
+            name,
+            radius,
+            interpolate,
+        })
+    }
+
+    /// Recomputes which area every player is in, and sends watching clients spawns and
+    /// despawns for the players that came into or went out of view.
+    #[profiling::function]
+    fn update_areas(&mut self) -> Result<()> {
+        self.areas.set_positions(self.instance.iter_players());
+
+        let tick = self.instance.get_tick();
+        for client_id in self.server.client_ids() {
+            let Some(&viewer) = self.client_map.get_by_left(&client_id) else {
+                continue;
+            };
+            let Some(change) = self.areas.update_client(client_id, viewer) else {
+                continue;
+            };
+
+            for net_obj in change.left {
+                let message = ReliableMessageFromServer::Despawn(net_obj);
+                self.server.send_reliable_message(client_id, message)?;
+            }
+
+            for net_obj in change.entered {
+                let Some(net_spawn) = self.player_spawn(net_obj) else {
+                    continue;
+                };
+                let message = ReliableMessageFromServer::Spawn(Spawn {
+                    net_obj,
+                    net_spawn,
+                    tick,
+                });
+                self.server.send_reliable_message(client_id, message)?;
+            }
+        }
+
+        Ok(())
+    }
+
     #[instrument]
+    #[profiling::function]
     fn broadcast_data(&mut self) -> Result<()> {
-        for (_, (obj, position, input_tracker)) in
-            &mut self
-                .instance
-                .get_world()
-                .query::<(&NetworkObject, &Position, &LastInputTracker)>()
+        let tick = self.instance.get_tick();
+        let unix_millis = Some(get_unix_millis());
+        let mut position_syncs = Vec::new();
+        let mut positions = HashMap::new();
+        // Bytes of owned syncs each client was sent, which count against its budget.
+        let mut used = HashMap::new();
+
+        for (_, (obj, position, input_tracker, abilities)) in &mut self
+            .instance
+            .get_world()
+            .query::<(&NetworkObject, &Position, &LastInputTracker, &Abilities)>()
         {
-            let Some(client_id) = self.client_map.net_obj_to_client.get(obj) else {
-                warn!("No client id for player obj");
+            let owner = self.controlling_player(*obj);
+            let Some(client_id) = self.client_map.get_by_right(&owner) else {
+                // Held players wait for their client to come back without being synced.
+                if !self.is_player_held(owner) {
+                    warn!("No client id for player obj");
+                }
                 continue;
             };
 
-            let message = UnreliableMessageFromServer::PlayerPositionSync(PlayerPositionSync {
-                net_obj: *obj,
-                position: position.0.into(),
-                tick: self.instance.get_tick(),
-            });
-            self.server
-                .broadcast_unreliable_message_except(*client_id, message)?;
+            positions.insert(*obj, position.0);
+
+            // Stillness only throttles the broadcast. The owner's syncs follow its acknowledged
+            // inputs instead, which keep advancing even while it pushes against a wall.
+            // Encoded once here, however many clients it goes to.
+            if self.idle.should_broadcast(*obj, position.0) {
+                let sync = PlayerPositionSync {
+                    net_obj: *obj,
+                    position: position.0.into(),
+                    tick,
+                    unix_millis,
+                };
+                position_syncs.push((*obj, encode(&sync)?));
+            }
+
+            let resync = self.inputs.take_resync(*obj);
+            if !self
+                .owned_syncs
+                .should_send(*obj, input_tracker.order, resync)
+            {
+                continue;
+            }
 
-            let message = UnreliableMessageFromServer::OwnedPlayerSync(OwnedPlayerSync {
+            let sync = OwnedPlayerSync {
                 net_obj: *obj,
                 position: position.0.into(),
-                tick: self.instance.get_tick(),
+                tick,
                 last_input_order: input_tracker.order,
-            });
-            self.server.send_unreliable_message(*client_id, message)?;
+                inputs_applied: abilities.inputs_applied,
+                cooldowns: abilities.get_running(abilities.get_tick()),
+                unix_millis,
+            };
+            if resync {
+                let message = ReliableMessageFromServer::ForcedPlayerSync(sync);
+                self.server.send_reliable_message(*client_id, message)?;
+                continue;
+            }
+
+            let bytes = encode(&UnreliableMessageFromServer::OwnedPlayerSync(sync))?;
+            *used.entry(*client_id).or_default() += bytes.len();
+            self.server.send_unreliable_bytes(*client_id, bytes);
+        }
+
+        if !position_syncs.is_empty() {
+            for client_id in self.server.client_ids() {
+                let candidates: Vec<_> = position_syncs
+                    .iter()
+                    .filter(|(net_obj, _)| self.areas.sees(client_id, *net_obj))
+                    .map(|(net_obj, sync)| (*net_obj, positions[net_obj], sync.as_slice()))
+                    .collect();
+                if candidates.is_empty() {
+                    continue;
+                }
+
+                let viewer = self
+                    .client_map
+                    .get_by_left(&client_id)
+                    .and_then(|net_obj| positions.get(net_obj))
+                    .copied();
+                let was_over = self.budget.is_over(client_id);
+                let count = candidates.len();
+                let used = used.get(&client_id).copied().unwrap_or_default();
+                let syncs = self.budget.select(client_id, viewer, used, candidates);
+
+                match (was_over, self.budget.is_over(client_id)) {
+                    (false, true) => info!(
+                        "Client {client_id} is over its sync budget, sending {} of {count} players",
+                        syncs.len()
+                    ),
+                    (true, false) => info!("Client {client_id} is back within its sync budget"),
+                    _ => {}
+                }
+
+                if syncs.is_empty() {
+                    continue;
+                }
+
+                self.server
+                    .send_unreliable_bytes(client_id, encode_position_syncs(&syncs)?);
+            }
         }
 
         Ok(())
@@ -365,35 +1816,223 @@ impl Game {
     //     rapier_link: &'static RapierContextEntityLink,
     // }
 
+    #[profiling::function]
     fn apply_inputs(&mut self, dt: f32) {
         let net_obj_inputs = self.inputs.pop_inputs();
 
         self.instance.apply_inputs(dt, &net_obj_inputs);
+
+        if let Some(input_log) = &mut self.input_log {
+            input_log.record(
+                self.instance.get_tick().get(),
+                &net_obj_inputs,
+                self.instance.iter_players(),
+            );
+        }
     }
 
     #[instrument]
+    #[profiling::function]
     fn update(&mut self, dt: Duration) -> Result<()> {
-        tick(self, dt)?;
-
         self.receive_messages()?;
 
+        // Held players expire even while paused, so a dropped solo player is still cleaned up.
+        self.expire_held_players()?;
+
+        // Before the pause check, so the owner can still ask for the status while paused.
+        self.handle_admin_commands()?;
+
+        self.handle_leave_requests()?;
+
+        self.handle_pause_requests()?;
+
+        if self.paused {
+            self.clear_messages();
+            return Ok(());
+        }
+
+        tick(self, dt)?;
+
         self.read_inputs()?;
 
         self.handle_connections()?;
 
         self.process_player_spawn_requests()?;
 
+        self.broadcast_entity_spawns()?;
+
+        self.update_areas()?;
+
         self.broadcast_data()?;
 
-        self.instance.update(dt)?;
+        {
+            profiling::scope!("instance.update");
+            self.instance.update(dt)?;
+        }
 
         self.apply_inputs(dt.as_secs_f32());
 
+        self.handle_events()?;
+
         self.clear_messages();
 
         Ok(())
     }
 
+    /// Handles the events sent up to now in order. Events sent while handling them wait for
+    /// the next tick, so a chain of reactions can't hold up a tick.
+    fn handle_events(&mut self) -> Result<()> {
+        self.handled_events.clear();
+
+        for event in std::mem::take(&mut self.events) {
+            self.handle_event(event)?;
+            self.handled_events.push(event);
+        }
+
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: GameEvent) -> Result<()> {
+        match event {
+            GameEvent::PlayerSpawned(net_obj) => debug!("Player {net_obj:?} spawned"),
+            GameEvent::PlayerRemoved(net_obj) => debug!("Player {net_obj:?} removed"),
+        }
+
+        Ok(())
+    }
+
+    /// Removes the players of clients that said they are leaving and closes their connections.
+    fn handle_leave_requests(&mut self) -> Result<()> {
+        let leaving: Vec<_> = self
+            .message_queues
+            .iter()
+            .filter(|(_, queue)| {
+                queue
+                    .reliable
+                    .iter()
+                    .any(|msg| matches!(msg, ReliableMessageFromClient::Leave))
+            })
+            .map(|(client_id, _)| *client_id)
+            .collect();
+
+        for client_id in leaving {
+            info!("Client {client_id} is leaving");
+            self.remove_client(client_id)?;
+            self.server.disconnect(client_id);
+        }
+
+        Ok(())
+    }
+
+    fn handle_admin_commands(&mut self) -> Result<()> {
+        let mut commands = Vec::new();
+        for (client_id, message_queue) in &self.message_queues {
+            for msg in &message_queue.reliable {
+                if let ReliableMessageFromClient::Admin(command) = msg {
+                    commands.push((*client_id, command.clone()));
+                }
+            }
+        }
+
+        for (client_id, command) in commands {
+            if self.owner != Some(client_id) {
+                warn!("Rejecting {command:?} from client {client_id}, which isn't the owner");
+                let message = ReliableMessageFromServer::AdminRejected;
+                self.server.send_reliable_message(client_id, message)?;
+                continue;
+            }
+
+            info!("Owner sent {command:?}");
+
+            let reply = match command {
+                AdminCommand::Status => self.get_status(),
+                AdminCommand::Kick { name, reason } => match self.find_player_by_name(&name) {
+                    Some(net_obj) => {
+                        self.kick_player(net_obj, &reason)?;
+                        format!("Kicked {name}")
+                    }
+                    None => format!("No player named {name}"),
+                },
+                AdminCommand::SetTickRate(0) => "Tick rate must be above 0".to_string(),
+                AdminCommand::SetTickRate(tick_rate) => {
+                    self.change_tick_rate(tick_rate)?;
+                    format!("Tick rate set to {tick_rate}")
+                }
+                _ => "Unsupported command".to_string(),
+            };
+
+            let message = ReliableMessageFromServer::AdminReply(reply);
+            self.server.send_reliable_message(client_id, message)?;
+        }
+
+        Ok(())
+    }
+
+    fn handle_pause_requests(&mut self) -> Result<()> {
+        let mut requested = None;
+        let mut joining = false;
+
+        for (client_id, message_queue) in &self.message_queues {
+            for msg in &message_queue.reliable {
+                match msg {
+                    ReliableMessageFromClient::Pause => requested = Some((*client_id, true)),
+                    ReliableMessageFromClient::Resume => requested = Some((*client_id, false)),
+                    // Paused, these would be cleared before they were handled.
+                    ReliableMessageFromClient::Connected { .. }
+                    | ReliableMessageFromClient::Spectate { .. }
+                    | ReliableMessageFromClient::ReadyForUpdates => joining = true,
+                    _ => {}
+                }
+            }
+        }
+
+        // Joining and watching are handled on the next tick, and a client still loading needs
+        // its updates.
+        if self.paused && joining {
+            return self.set_paused(false);
+        }
+
+        match requested {
+            Some((client_id, _)) if self.spectators.contains(&client_id) => {
+                warn!("Ignoring pause request from spectator {client_id}");
+                Ok(())
+            }
+            // Spectators don't play, but a held player's client may be back any moment.
+            Some((client_id, true)) if self.client_map.len() + self.held_players.len() > 1 => {
+                warn!("Ignoring pause from {client_id}: instance is shared");
+                Ok(())
+            }
+            Some((_, paused)) => self.set_paused(paused),
+            None => Ok(()),
+        }
+    }
+
+    /// Freezes or resumes the simulation, including the tick.
+    ///
+    /// Clients stop advancing their own tick while paused. On resume the server sends a fresh
+    /// [`TickSync`] on a new timeline, stamped with the current time, so the client's
+    /// [`TickClock::estimate`](common::tick::TickClock::estimate) extrapolates from the resume
+    /// rather than jumping ahead by the paused duration.
+    pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+        if self.paused == paused {
+            return Ok(());
+        }
+
+        self.paused = paused;
+        info!("Instance {}", if paused { "paused" } else { "resumed" });
+
+        let message = ReliableMessageFromServer::PauseState { paused };
+        self.server.broadcast_reliable_message(message)?;
+
+        if !paused {
+            self.tick.break_timeline();
+            let message = self.tick_sync();
+            self.server.broadcast_reliable_message(message)?;
+        }
+
+        Ok(())
+    }
+
     fn clear_messages(&mut self) {
         for message_queue in self.message_queues.values_mut() {
             message_queue.reliable.clear();
@@ -1,11 +1,20 @@
 use std::{os::fd::FromRawFd, str::FromStr};
 
-use common::{Error, Result, ResultExt};
-use instance::{backend::BackendCommunication, run};
+use common::{Error, Result, ResultExt, TICK_RATE, game::instance::LevelDef};
+use instance::{
+    backend::{BackendCommunication, parse_pipe_handles},
+    run,
+};
+use tracing::{info, warn};
 use uuid::Uuid;
 
 fn main() -> Result<()> {
-    tracing_subscriber::fmt::init();
+    // Logs go to stderr so stdout never carries anything but what the instance means to
+    // print. The READY handshake goes over the manager's pipe, or stdout without one.
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_max_level(log_level())
+        .init();
 
     let mut args = std::env::args();
 
@@ -23,13 +32,17 @@ fn main() -> Result<()> {
         None => renet_netcode::generate_random_bytes(),
     };
 
-    let comm = match args.next() {
+    // The client id the manager started the instance for, allowed to send admin commands.
+    let owner = match args.next().filter(|owner| owner != "-") {
+        Some(owner) => Some(owner.parse().context("Invalid Owner Id")?),
+        None => None,
+    };
+
+    // Usage: instance [id] [key] [owner | -] [tx;rx | -] [level.json | -] [tick rate | -]
+    //     [input log ticks | -] [puffin port]
+    let comm = match args.next().filter(|comm| comm != "-") {
         Some(comm) => {
-            let mut handles = comm.split(';');
-            let tx_handle = handles.next().unwrap();
-            let tx_handle = tx_handle.parse().context("Invalid Pipe Handle")?;
-            let rx_handle = handles.next().unwrap();
-            let rx_handle = rx_handle.parse().context("Invalid Pipe Handle")?;
+            let (tx_handle, rx_handle) = parse_pipe_handles(&comm)?;
 
             let tx = unsafe { interprocess::unnamed_pipe::Sender::from_raw_fd(tx_handle) };
             let rx = unsafe { interprocess::unnamed_pipe::Recver::from_raw_fd(rx_handle) };
@@ -39,5 +52,52 @@ fn main() -> Result<()> {
         None => BackendCommunication::None,
     };
 
-    run(id, key, comm)
+    let level = match args.next().filter(|path| path != "-") {
+        Some(path) => LevelDef::load(path)?,
+        None => LevelDef::default(),
+    };
+
+    let tick_rate = match args.next().filter(|tick_rate| tick_rate != "-") {
+        Some(tick_rate) => tick_rate.parse().context("Invalid Tick Rate")?,
+        None => TICK_RATE,
+    };
+
+    if tick_rate == 0 {
+        return Err(Error::InvalidTickRate);
+    }
+
+    // Recording inputs for debugging desyncs is opt-in.
+    let input_log_ticks = match args.next().filter(|ticks| ticks != "-") {
+        Some(ticks) => Some(ticks.parse().context("Invalid Input Log Length")?),
+        None => None,
+    };
+
+    // Profiling is opt-in, since every local instance would otherwise fight over one port.
+    let _puffin_server = match args.next() {
+        Some(port) => {
+            let port: u16 = port.parse().context("Invalid Puffin Port")?;
+            match puffin_http::Server::new(&format!("0.0.0.0:{port}")) {
+                Ok(server) => {
+                    info!("Puffin profiling running on port {port}.");
+                    puffin::set_scopes_on(true);
+                    Some(server)
+                }
+                Err(err) => {
+                    warn!("Puffin profiling disabled: {err}");
+                    None
+                }
+            }
+        }
+        None => None,
+    };
+
+    run(id, key, owner, level, tick_rate, input_log_ticks, comm)
+}
+
+/// The most verbose level to log, from `RUST_LOG` if it names a plain level.
+fn log_level() -> tracing::Level {
+    std::env::var("RUST_LOG")
+        .ok()
+        .and_then(|level| tracing::Level::from_str(&level).ok())
+        .unwrap_or(tracing::Level::INFO)
 }
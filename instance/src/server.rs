@@ -1,19 +1,50 @@
 use std::{
+    collections::HashMap,
     net::{Ipv4Addr, SocketAddr, UdpSocket},
     time::{Duration, SystemTime},
 };
 
-use common::message::{ReliableMessageFromClient, UnreliableMessageFromClient};
-use renet::{ConnectionConfig, DefaultChannel, RenetServer};
-use renet_netcode::{NetcodeServerTransport, ServerAuthentication, ServerConfig};
+use common::{
+    chunk::{PayloadAssembler, decode_payload, split_message},
+    game::character::{name_from_user_data, name_to_user_data},
+    message::{ReliableMessageFromClient, UnreliableMessageFromClient, decode, encode},
+};
+use renet::{ConnectionConfig, DefaultChannel, RenetClient, RenetServer};
+use renet_netcode::{
+    NETCODE_USER_DATA_BYTES, NetcodeServerTransport, ServerAuthentication, ServerConfig,
+};
 
 use crate::Result;
 
+/// Players and spectators an instance admits by default. Clients past this are told the
+/// server is full. See [`crate::Game::set_max_clients`].
+pub const MAX_CLIENTS: usize = 256;
+
+/// Transport slots on top of [`MAX_CLIENTS`], so clients over the limit still get far enough
+/// to be told why they are turned away. Past these, netcode denies the connection outright.
+pub const FULL_HEADROOM: usize = 8;
+
 #[derive(Debug)]
 pub struct Server {
     server: RenetServer,
-    transport: NetcodeServerTransport,
+    transport: Transport,
     socket_addr: SocketAddr,
+    assemblers: HashMap<u64, PayloadAssembler>,
+}
+
+#[derive(Debug)]
+enum Transport {
+    Netcode(Box<NetcodeServerTransport>),
+    /// No sockets. Clients live in the same process and are connected with
+    /// [`Server::connect_local`], then exchange packets through [`Server::process_local`].
+    Memory(MemoryTransport),
+}
+
+#[derive(Debug, Default)]
+struct MemoryTransport {
+    /// Packets flushed by [`Server::send_packets`] that the local client hasn't picked up.
+    outgoing: HashMap<u64, Vec<Vec<u8>>>,
+    user_data: HashMap<u64, [u8; NETCODE_USER_DATA_BYTES]>,
 }
 
 impl Server {
@@ -25,7 +56,7 @@ impl Server {
         let socket_addr = socket.local_addr()?;
         let server_config = ServerConfig {
             current_time: SystemTime::now().duration_since(SystemTime::UNIX_EPOCH)?,
-            max_clients: 256,
+            max_clients: MAX_CLIENTS + FULL_HEADROOM,
             protocol_id: 0,
             public_addresses: vec![socket_addr],
             authentication: ServerAuthentication::Secure { private_key },
@@ -35,33 +66,120 @@ impl Server {
 
         Ok(Server {
             server,
-            transport,
+            transport: Transport::Netcode(Box::new(transport)),
             socket_addr,
+            assemblers: HashMap::new(),
         })
     }
 
+    /// A server without sockets or authentication, for tests that want messages to move
+    /// deterministically. Its address is a placeholder nothing listens on.
+    pub fn in_memory() -> Server {
+        Server {
+            server: RenetServer::new(ConnectionConfig::default()),
+            transport: Transport::Memory(MemoryTransport::default()),
+            socket_addr: SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0),
+            assemblers: HashMap::new(),
+        }
+    }
+
+    pub fn is_in_memory(&self) -> bool {
+        matches!(self.transport, Transport::Memory(_))
+    }
+
+    /// Connects an in-process client playing the character `name`. Only for servers made
+    /// with [`Server::in_memory`].
+    pub fn connect_local(&mut self, client_id: u64, name: &str) -> Option<RenetClient> {
+        let Transport::Memory(memory) = &mut self.transport else {
+            return None;
+        };
+
+        memory.user_data.insert(client_id, name_to_user_data(name));
+
+        Some(self.server.new_local_client(client_id))
+    }
+
+    /// Hands a local client the packets flushed for it and takes the ones it has queued,
+    /// which the server reads on its next update. A client the server has dropped is
+    /// disconnected, and a client that disconnected itself is dropped.
+    pub fn process_local(&mut self, client_id: u64, client: &mut RenetClient) {
+        let Transport::Memory(memory) = &mut self.transport else {
+            return;
+        };
+
+        let packets = memory.outgoing.remove(&client_id).unwrap_or_default();
+        let delivered = !packets.is_empty();
+        for packet in packets {
+            client.process_packet(&packet);
+        }
+
+        if client.is_disconnected() {
+            self.server.remove_connection(client_id);
+            return;
+        }
+
+        let known = self.server.is_connected(client_id)
+            || self.server.disconnections_id().contains(&client_id);
+        if !known {
+            // Leave a step for the client to read what was sent just before it was dropped.
+            if !delivered {
+                client.disconnect_due_to_transport();
+            }
+            return;
+        }
+
+        for packet in client.get_packets_to_send() {
+            let _ = self.server.process_packet_from(&packet, client_id);
+        }
+    }
+
     pub fn local_address(&self) -> SocketAddr {
         self.socket_addr
     }
 
     pub fn update(&mut self, delta: Duration) -> Result<()> {
         self.server.update(delta);
-        self.transport.update(delta, &mut self.server)?;
+
+        match &mut self.transport {
+            Transport::Netcode(transport) => transport.update(delta, &mut self.server)?,
+            Transport::Memory(memory) => {
+                for client_id in self.server.disconnections_id() {
+                    memory.user_data.remove(&client_id);
+                    self.server.remove_connection(client_id);
+                }
+            }
+        }
 
         Ok(())
     }
 
     pub fn get_event(&mut self) -> Option<renet::ServerEvent> {
-        self.server.get_event()
+        let event = self.server.get_event();
+
+        if let Some(renet::ServerEvent::ClientDisconnected { client_id, .. }) = &event {
+            self.assemblers.remove(client_id);
+        }
+
+        event
+    }
+
+    /// Name of the character the client's connect token was issued for.
+    pub fn character_name(&self, client_id: u64) -> Option<String> {
+        let data = match &self.transport {
+            Transport::Netcode(transport) => transport.user_data(client_id)?,
+            Transport::Memory(memory) => *memory.user_data.get(&client_id)?,
+        };
+
+        Some(name_from_user_data(&data))
     }
 
     pub fn client_ids(&self) -> Vec<u64> {
         self.server.clients_id()
     }
 
-    fn decode<T: bincode::Decode<()>>(data: &[u8]) -> Result<T> {
-        let (message, _) = bincode::decode_from_slice(data, bincode::config::standard())?;
-        Ok(message)
+    /// Smoothed round trip to the client, zero if it isn't connected.
+    pub fn rtt(&self, client_id: u64) -> Duration {
+        Duration::from_secs_f64(self.server.rtt(client_id))
     }
 
     pub fn receive_reliable_message(
@@ -71,7 +189,7 @@ impl Server {
         self.server
             .receive_message(client_id, DefaultChannel::ReliableUnordered)
             .as_deref()
-            .map(Self::decode)
+            .map(decode)
     }
 
     pub fn receive_unreliable_message(
@@ -81,12 +199,7 @@ impl Server {
         self.server
             .receive_message(client_id, DefaultChannel::Unreliable)
             .as_deref()
-            .map(Self::decode)
-    }
-
-    fn encode<T: bincode::Encode>(message: T) -> Result<Vec<u8>> {
-        let bytes = bincode::encode_to_vec(message, bincode::config::standard())?;
-        Ok(bytes)
+            .map(decode)
     }
 
     pub fn broadcast_reliable_message(
@@ -94,7 +207,7 @@ impl Server {
         message: common::message::ReliableMessageFromServer,
     ) -> Result<()> {
         self.server
-            .broadcast_message(DefaultChannel::ReliableUnordered, Self::encode(message)?);
+            .broadcast_message(DefaultChannel::ReliableUnordered, encode(&message)?);
 
         Ok(())
     }
@@ -107,7 +220,7 @@ impl Server {
         self.server.broadcast_message_except(
             except_id,
             DefaultChannel::ReliableUnordered,
-            Self::encode(message)?,
+            encode(&message)?,
         );
 
         Ok(())
@@ -121,7 +234,7 @@ impl Server {
         self.server.send_message(
             client_id,
             DefaultChannel::ReliableUnordered,
-            Self::encode(message)?,
+            encode(&message)?,
         );
 
         Ok(())
@@ -132,7 +245,7 @@ impl Server {
         message: common::message::UnreliableMessageFromServer,
     ) -> Result<()> {
         self.server
-            .broadcast_message(DefaultChannel::Unreliable, Self::encode(message)?);
+            .broadcast_message(DefaultChannel::Unreliable, encode(&message)?);
 
         Ok(())
     }
@@ -145,7 +258,7 @@ impl Server {
         self.server.broadcast_message_except(
             except_id,
             DefaultChannel::Unreliable,
-            Self::encode(message)?,
+            encode(&message)?,
         );
 
         Ok(())
@@ -159,13 +272,109 @@ impl Server {
         self.server.send_message(
             client_id,
             DefaultChannel::Unreliable,
-            Self::encode(message)?,
+            encode(&message)?,
         );
 
         Ok(())
     }
 
+    /// Sends an unreliable message encoded ahead of time, such as one shared by many clients.
+    pub fn send_unreliable_bytes(&mut self, client_id: u64, bytes: Vec<u8>) {
+        self.server
+            .send_message(client_id, DefaultChannel::Unreliable, bytes);
+    }
+
+    /// Sends a message too big for a single network message, as chunks on the ordered
+    /// reliable channel.
+    pub fn send_large(
+        &mut self,
+        client_id: u64,
+        message: common::message::ReliableMessageFromServer,
+    ) -> Result<()> {
+        for chunk in split_message(&message)? {
+            self.server
+                .send_message(client_id, DefaultChannel::ReliableOrdered, chunk);
+        }
+
+        Ok(())
+    }
+
+    /// Returns the next message sent with the client's `send_large` once all of its chunks
+    /// have arrived.
+    pub fn poll_large(&mut self, client_id: u64) -> Option<Result<ReliableMessageFromClient>> {
+        while let Some(bytes) = self
+            .server
+            .receive_message(client_id, DefaultChannel::ReliableOrdered)
+        {
+            let assembler = self.assemblers.entry(client_id).or_default();
+
+            match decode(&bytes).and_then(|chunk| assembler.push(chunk)) {
+                Ok(Some(payload)) => return Some(decode_payload(&payload)),
+                Ok(None) => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+
+        None
+    }
+
+    pub fn disconnect(&mut self, client_id: u64) {
+        self.server.disconnect(client_id);
+    }
+
+    /// Disconnects every client. Over netcode the disconnect packets go out straight away.
+    pub fn disconnect_all(&mut self) {
+        match &mut self.transport {
+            Transport::Netcode(transport) => transport.disconnect_all(&mut self.server),
+            Transport::Memory(_) => self.server.disconnect_all(),
+        }
+    }
+
+    /// Whether every connected client has acknowledged all reliable messages sent to it.
+    pub fn is_reliable_flushed(&self) -> bool {
+        self.server
+            .clients_id_iter()
+            .all(|client_id| self.is_client_flushed(client_id))
+    }
+
+    /// Whether the client has acknowledged all reliable messages sent to it. A client that
+    /// isn't connected has nothing left to acknowledge.
+    pub fn is_client_flushed(&self, client_id: u64) -> bool {
+        if !self.server.is_connected(client_id) {
+            return true;
+        }
+
+        let config = ConnectionConfig::default();
+        let reliable: [u8; 2] = [
+            DefaultChannel::ReliableOrdered.into(),
+            DefaultChannel::ReliableUnordered.into(),
+        ];
+
+        reliable.iter().all(|&channel_id| {
+            let capacity = config
+                .server_channels_config
+                .iter()
+                .find(|config| config.channel_id == channel_id)
+                .map_or(0, |config| config.max_memory_usage_bytes);
+
+            self.server.channel_available_memory(client_id, channel_id) == capacity
+        })
+    }
+
     pub fn send_packets(&mut self) {
-        self.transport.send_packets(&mut self.server);
+        match &mut self.transport {
+            Transport::Netcode(transport) => transport.send_packets(&mut self.server),
+            Transport::Memory(memory) => {
+                for client_id in self.server.clients_id() {
+                    if let Ok(packets) = self.server.get_packets_to_send(client_id) {
+                        memory
+                            .outgoing
+                            .entry(client_id)
+                            .or_default()
+                            .extend(packets);
+                    }
+                }
+            }
+        }
     }
 }
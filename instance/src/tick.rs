@@ -1,41 +1,69 @@
 use std::time::Duration;
 
-use common::{
-    Result,
-    message::{ReliableMessageFromServer, TickSync},
-    tick::get_unix_millis,
-};
+use common::Result;
 
 use crate::Game;
 
 #[derive(Debug)]
 pub struct TickData {
     broadcast_timer: Duration,
+    broadcast_interval: Duration,
+    /// See [`common::message::TickSync::timeline`].
+    timeline: u32,
+}
+
+impl Default for TickData {
+    fn default() -> Self {
+        TickData::new()
+    }
 }
 
 impl TickData {
     pub fn new() -> TickData {
         TickData {
             broadcast_timer: Duration::ZERO,
+            broadcast_interval: TICK_BROADCAST_INTERVAL,
+            timeline: 0,
         }
     }
+
+    pub fn get_broadcast_interval(&self) -> Duration {
+        self.broadcast_interval
+    }
+
+    pub fn set_broadcast_interval(&mut self, interval: Duration) {
+        self.broadcast_interval = interval;
+    }
+
+    pub fn get_timeline(&self) -> u32 {
+        self.timeline
+    }
+
+    /// Starts a new timeline, for when the tick stops following the wall-clock.
+    pub fn break_timeline(&mut self) {
+        self.timeline = self.timeline.wrapping_add(1);
+    }
 }
 
-const TICK_BROADCAST_INTERVAL: Duration = Duration::from_secs(10);
+/// Default time between tick syncs broadcast to every client. Clients measure the tick rate
+/// from them, so more frequent syncs correct drift sooner.
+pub const TICK_BROADCAST_INTERVAL: Duration = Duration::from_secs(10);
 
 pub fn tick(game: &mut Game, duration: Duration) -> Result<()> {
     game.instance.update_tick();
 
     game.tick.broadcast_timer += duration;
 
-    while game.tick.broadcast_timer >= TICK_BROADCAST_INTERVAL {
-        game.tick.broadcast_timer -= TICK_BROADCAST_INTERVAL;
+    let interval = game.tick.broadcast_interval;
+    if interval.is_zero() {
+        return Ok(());
+    }
+
+    while game.tick.broadcast_timer >= interval {
+        game.tick.broadcast_timer -= interval;
 
-        game.server
-            .broadcast_reliable_message(ReliableMessageFromServer::TickSync(TickSync {
-                tick: game.instance.get_tick().get(),
-                unix_millis: get_unix_millis(),
-            }))?;
+        let message = game.tick_sync();
+        game.server.broadcast_reliable_message(message)?;
     }
 
     Ok(())
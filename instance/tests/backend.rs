@@ -0,0 +1,155 @@
+use std::{
+    io::Write as _,
+    time::{Duration, Instant},
+};
+
+use common::{Error, Result, ability::AbilityId, handoff::PlayerHandoff};
+use instance::backend::{BackendCommunication, Message, parse_message, parse_pipe_handles};
+
+#[test]
+fn admin_commands_parse() {
+    assert!(matches!(parse_message("status\n"), Some(Message::Status)));
+    assert!(matches!(parse_message("report"), Some(Message::Report)));
+    assert!(matches!(parse_message("pause"), Some(Message::Pause)));
+    assert!(matches!(parse_message("resume"), Some(Message::Resume)));
+    assert!(matches!(
+        parse_message("dump-positions"),
+        Some(Message::DumpPositions)
+    ));
+    assert!(matches!(
+        parse_message("set-tickrate 30"),
+        Some(Message::SetTickRate(30))
+    ));
+    assert!(matches!(
+        parse_message("set-tick-broadcast 2000"),
+        Some(Message::SetTickBroadcastInterval(interval)) if interval == Duration::from_secs(2)
+    ));
+    assert!(matches!(parse_message("shutdown"), Some(Message::Shutdown)));
+}
+
+#[test]
+fn kick_keeps_spaces_in_the_name() {
+    let Some(Message::Kick { name, reason }) = parse_message("kick Bob the Brave\tbeing rude\n")
+    else {
+        panic!("kick did not parse");
+    };
+
+    assert_eq!(name, "Bob the Brave");
+    assert_eq!(reason, "being rude");
+}
+
+#[test]
+fn switch_character_keeps_spaces_in_the_name() {
+    let Some(Message::SwitchCharacter { client_id, name }) =
+        parse_message("switch-character 42 Bob the Brave\n")
+    else {
+        panic!("switch-character did not parse");
+    };
+
+    assert_eq!(client_id, 42);
+    assert_eq!(name, "Bob the Brave");
+}
+
+#[test]
+fn handoff_commands_parse() -> Result<()> {
+    assert!(matches!(
+        parse_message("handoff 42 7\n"),
+        Some(Message::BeginHandoff {
+            client_id: 42,
+            character_id: 7
+        })
+    ));
+    assert!(matches!(
+        parse_message("handoff-done 42"),
+        Some(Message::FinishHandoff(42))
+    ));
+    assert!(matches!(
+        parse_message("handoff-failed 42"),
+        Some(Message::CancelHandoff(42))
+    ));
+
+    let handoff = PlayerHandoff {
+        client_id: 42,
+        character_id: 7,
+        name: "Bob the Brave".to_string(),
+        radius: 16.0,
+        position: [1.0, -2.0],
+        cooldowns: vec![(AbilityId::Dash, 12)],
+    };
+    let Some(Message::AcceptHandoff(accepted)) =
+        parse_message(&format!("accept-handoff {}\n", handoff.to_hex()?))
+    else {
+        panic!("accept-handoff did not parse");
+    };
+    assert_eq!(accepted, handoff);
+
+    Ok(())
+}
+
+#[test]
+fn malformed_commands_are_rejected() {
+    assert!(parse_message("set-tickrate").is_none());
+    assert!(parse_message("switch-character bob").is_none());
+    assert!(parse_message("switch-character bob Bob").is_none());
+    assert!(parse_message("set-tickrate 0").is_none());
+    assert!(parse_message("set-tick-broadcast soon").is_none());
+    assert!(parse_message("set-tickrate fast").is_none());
+    assert!(parse_message("dump-inputs").is_none());
+    assert!(parse_message("handoff 42").is_none());
+    assert!(parse_message("handoff-done bob").is_none());
+    assert!(parse_message("accept-handoff nothex").is_none());
+    assert!(parse_message("accept-handoff 00").is_none());
+    assert!(parse_message("reticulate").is_none());
+}
+
+#[test]
+fn pipe_handles_parse() -> Result<()> {
+    assert_eq!(parse_pipe_handles("3;4")?, (3, 4));
+
+    Ok(())
+}
+
+#[test]
+fn malformed_pipe_handles_say_what_was_wrong() {
+    let err = parse_pipe_handles("3").unwrap_err();
+    assert!(matches!(err, Error::InvalidPipeHandles(_)));
+    assert_eq!(err.to_string(), "Expected tx;rx pipe handles, got \"3\"");
+
+    let err = parse_pipe_handles("3;x").unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Invalid rx pipe handle in \"3;x\"")
+    );
+
+    let err = parse_pipe_handles(";4").unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("Invalid tx pipe handle in \";4\"")
+    );
+}
+
+#[test]
+fn closing_the_pipe_shuts_the_instance_down() -> Result<()> {
+    let (tx, _parent_rx) = interprocess::unnamed_pipe::pipe()?;
+    let (mut parent_tx, rx) = interprocess::unnamed_pipe::pipe()?;
+    let mut comm = BackendCommunication::pipe(tx, rx);
+
+    parent_tx.write_all(b"status\n")?;
+    drop(parent_tx);
+
+    let mut messages = Vec::new();
+    let deadline = Instant::now() + Duration::from_secs(5);
+    while Instant::now() < deadline && !matches!(messages.last(), Some(Message::Shutdown)) {
+        match comm.message() {
+            Some(message) => messages.push(message),
+            None => std::thread::sleep(Duration::from_millis(1)),
+        }
+    }
+
+    assert!(
+        matches!(messages.as_slice(), [Message::Status, Message::Shutdown]),
+        "{messages:?}"
+    );
+
+    Ok(())
+}
@@ -0,0 +1,122 @@
+use common::{
+    Vec2,
+    message::{PlayerPositionSync, decode, encode},
+    net_obj::NetworkObject,
+    tick::Tick,
+};
+use instance::budget::SyncBudget;
+
+const CLIENT_ID: u64 = 1;
+
+/// Encoded syncs of `count` players standing in a line, 100 apart.
+fn encoded(count: u64) -> Vec<(NetworkObject, Vec2, Vec<u8>)> {
+    (0..count)
+        .map(|i| {
+            let net_obj = NetworkObject::new_static(i);
+            let position = Vec2::new(i as f32 * 100.0, 0.0);
+            let sync = PlayerPositionSync {
+                net_obj,
+                position: position.into(),
+                tick: Tick::new(1),
+                unix_millis: None,
+            };
+            (net_obj, position, encode(&sync).unwrap())
+        })
+        .collect()
+}
+
+fn candidates(encoded: &[(NetworkObject, Vec2, Vec<u8>)]) -> Vec<(NetworkObject, Vec2, &[u8])> {
+    encoded
+        .iter()
+        .map(|(net_obj, position, sync)| (*net_obj, *position, sync.as_slice()))
+        .collect()
+}
+
+fn ids(syncs: &[&[u8]]) -> Vec<NetworkObject> {
+    syncs
+        .iter()
+        .map(|sync| decode::<PlayerPositionSync>(sync).unwrap().net_obj)
+        .collect()
+}
+
+#[test]
+fn everything_is_sent_within_budget() {
+    let players = encoded(10);
+    let mut budget = SyncBudget::default();
+
+    let syncs = budget.select(CLIENT_ID, Some(Vec2::zeros()), 0, candidates(&players));
+
+    assert_eq!(syncs.len(), 10);
+    assert!(!budget.is_over(CLIENT_ID));
+}
+
+#[test]
+fn nearest_players_go_first_when_over_budget() {
+    let players = encoded(10);
+    let size = players[0].2.len();
+    let mut budget = SyncBudget::new(size * 3);
+
+    // Standing by the far end of the line.
+    let syncs = budget.select(
+        CLIENT_ID,
+        Some(Vec2::new(900.0, 0.0)),
+        0,
+        candidates(&players),
+    );
+
+    assert_eq!(
+        ids(&syncs),
+        [9, 8, 7].map(NetworkObject::new_static).to_vec()
+    );
+    assert!(budget.is_over(CLIENT_ID));
+}
+
+#[test]
+fn bytes_already_sent_count_against_the_budget() {
+    let players = encoded(10);
+    let size = players[0].2.len();
+    let mut budget = SyncBudget::new(size * 3);
+
+    let syncs = budget.select(
+        CLIENT_ID,
+        Some(Vec2::zeros()),
+        size * 2,
+        candidates(&players),
+    );
+
+    assert_eq!(ids(&syncs), vec![NetworkObject::new_static(0)]);
+}
+
+#[test]
+fn skipped_players_are_sent_in_turns() {
+    let players = encoded(10);
+    let size = players[0].2.len();
+    let mut budget = SyncBudget::new(size * 4);
+    let mut sent = Vec::new();
+
+    for _ in 0..3 {
+        let syncs = budget.select(CLIENT_ID, Some(Vec2::zeros()), 0, candidates(&players));
+        assert_eq!(syncs.len(), 4);
+        sent.extend(ids(&syncs));
+    }
+
+    for i in 0..10 {
+        assert!(
+            sent.contains(&NetworkObject::new_static(i)),
+            "{i} never sent"
+        );
+    }
+}
+
+#[test]
+fn removed_clients_start_fresh() {
+    let players = encoded(2);
+    let size = players[0].2.len();
+    let mut budget = SyncBudget::new(size);
+
+    budget.select(CLIENT_ID, Some(Vec2::zeros()), 0, candidates(&players));
+    assert!(budget.is_over(CLIENT_ID));
+
+    budget.remove_client(CLIENT_ID);
+    assert!(!budget.is_over(CLIENT_ID));
+}
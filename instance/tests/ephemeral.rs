@@ -0,0 +1,43 @@
+use std::{
+    collections::HashSet,
+    io::BufReader,
+    process::{Child, Command, Stdio},
+};
+
+use common::{Result, ready::read_ready};
+
+/// Kills the instance when dropped, so a failed assertion doesn't leave it running.
+struct Launched(Child);
+
+impl Drop for Launched {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Starts an instance without a manager pipe, which announces itself on stdout.
+fn launch() -> Result<(Launched, std::net::SocketAddr)> {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_instance"))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let launched = Launched(child);
+    let addr = read_ready(&mut BufReader::new(stdout))?;
+
+    Ok((launched, addr))
+}
+
+#[test]
+fn instances_launched_side_by_side_get_their_own_port() -> Result<()> {
+    let launched: Vec<_> = (0..2).map(|_| launch()).collect::<Result<_>>()?;
+
+    let ports: HashSet<_> = launched.iter().map(|(_, addr)| addr.port()).collect();
+    assert_eq!(ports.len(), launched.len());
+    assert!(!ports.contains(&0));
+
+    Ok(())
+}
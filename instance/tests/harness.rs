@@ -0,0 +1,1915 @@
+mod support;
+
+use std::{collections::HashSet, time::Duration};
+
+use common::{
+    DT, PROTOCOL_VERSION, Result, TICK_RATE, Vec2,
+    ability::{Abilities, AbilityId, DASH_COOLDOWN_INPUTS},
+    game::character::MAX_NAME_CHARS,
+    handoff::PlayerHandoff,
+    instance::{EntityCollider, EntityKind, PLAYER_RADIUS, Position},
+    message::{
+        AdminCommand, NetworkSpawn, OrderedInput, ReliableMessageFromClient,
+        ReliableMessageFromServer, Spawn, UnreliableMessageFromClient, UnreliableMessageFromServer,
+    },
+    net_obj::NetworkObject,
+    player::{DASH_DISTANCE, PLAYER_SPEED, PlayerAction, PlayerInput},
+    tick::Tick,
+};
+use instance::{
+    IDLE_AFTER_TICKS, IDLE_KEEPALIVE_TICKS, MAX_QUEUED_RELIABLE_MESSAGES,
+    MAX_QUEUED_UNRELIABLE_MESSAGES, OWNED_SYNC_INTERVAL, OWNED_SYNC_KEEPALIVE_TICKS,
+    RECONNECT_GRACE_TICKS,
+    area::{AREA_HYSTERESIS, AREA_SIZE, Area},
+    event::GameEvent,
+    tick::TICK_BROADCAST_INTERVAL,
+};
+use support::{Harness, MAX_STEPS, TestClient};
+
+#[test]
+fn input_moves_player_and_syncs_to_other_clients() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let mover = harness.add_client("mover")?;
+    let observer = harness.add_client("observer")?;
+
+    let mover_obj = harness.join(mover)?;
+    harness.join(observer)?;
+
+    for order in 1..=MAX_STEPS as u64 {
+        harness.clients[mover].send_unreliable(UnreliableMessageFromClient::Input(
+            OrderedInput {
+                input: PlayerInput {
+                    move_direction: [1.0, 0.0],
+                    action: None,
+                },
+                order,
+                target: None,
+            },
+        ))?;
+
+        harness.step()?;
+
+        let synced = harness.clients[observer]
+            .unreliable
+            .iter()
+            .find_map(|msg| match msg {
+                UnreliableMessageFromServer::PlayerPositionSyncs(syncs) => syncs
+                    .iter()
+                    .find(|sync| sync.net_obj == mover_obj && sync.position[0] > 0.0)
+                    .map(|sync| sync.position),
+                _ => None,
+            });
+
+        if let Some(position) = synced {
+            assert_eq!(position[1], 0.0, "movement along x must not drift in y");
+
+            // Owned syncs only go out every few ticks, so the ack may trail the broadcast.
+            let acked = harness.step_until(|h| {
+                h.clients[mover].unreliable.iter().any(|msg| {
+                    matches!(
+                        msg,
+                        UnreliableMessageFromServer::OwnedPlayerSync(sync)
+                            if sync.net_obj == mover_obj && sync.last_input_order > 0
+                    )
+                })
+            })?;
+            assert!(
+                acked,
+                "mover never received an OwnedPlayerSync acking its input"
+            );
+
+            return Ok(());
+        }
+    }
+
+    panic!("observer never received a position sync with the moved position");
+}
+
+#[test]
+fn mismatched_protocol_version_is_rejected() -> Result<()> {
+    let mut harness = Harness::new()?;
+    let client = harness.add_client("stale")?;
+
+    assert!(harness.step_until(|h| h.clients[client].client.is_connected())?);
+
+    harness.clients[client].send_reliable(ReliableMessageFromClient::Connected {
+        protocol_version: PROTOCOL_VERSION + 1,
+    })?;
+
+    assert!(
+        harness.step_until(|h| h.clients[client].client.is_disconnected())?,
+        "client with a stale protocol version stayed connected"
+    );
+
+    assert!(harness.clients[client].reliable.iter().any(|msg| matches!(
+        msg,
+        ReliableMessageFromServer::VersionMismatch { server_version }
+            if *server_version == PROTOCOL_VERSION
+    )));
+    assert!(harness.clients[client].player_init().is_none());
+
+    Ok(())
+}
+
+#[test]
+fn client_past_the_limit_is_told_the_server_is_full() -> Result<()> {
+    let mut harness = Harness::new()?;
+    harness.game.set_max_clients(2);
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+    let carol = harness.add_client("carol")?;
+
+    harness.join(alice)?;
+    harness.join(bob)?;
+
+    assert!(harness.step_until(|h| h.clients[carol].client.is_connected())?);
+    harness.clients[carol].send_reliable(ReliableMessageFromClient::Connected {
+        protocol_version: PROTOCOL_VERSION,
+    })?;
+
+    assert!(
+        harness.step_until(|h| h.clients[carol].client.is_disconnected())?,
+        "client past the limit stayed connected"
+    );
+    assert!(
+        harness.clients[carol]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::ServerFull))
+    );
+    assert!(harness.clients[carol].player_init().is_none());
+    assert!(harness.game.find_player_by_name("carol").is_none());
+
+    assert!(harness.clients[alice].client.is_connected());
+    assert!(harness.clients[bob].client.is_connected());
+
+    Ok(())
+}
+
+#[test]
+fn player_list_tracks_joins_and_leaves() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("Bøb 名前 with a name far too long to display")?;
+
+    let alice_obj = harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    let bob_name: String = "Bøb 名前 with a name far too long to display"
+        .chars()
+        .take(MAX_NAME_CHARS)
+        .collect();
+
+    assert!(harness.step_until(|h| {
+        h.clients[alice].reliable.iter().any(|msg| {
+            matches!(
+                msg,
+                ReliableMessageFromServer::PlayerJoined(obj, name)
+                    if *obj == bob_obj && *name == bob_name
+            )
+        })
+    })?);
+
+    let mut list = harness.clients[bob]
+        .reliable
+        .iter()
+        .find_map(|msg| match msg {
+            ReliableMessageFromServer::PlayerList(list) => Some(list.clone()),
+            _ => None,
+        })
+        .expect("bob never received the player list");
+    list.sort_by_key(|(_, name)| name.clone());
+    assert_eq!(
+        list,
+        vec![
+            (bob_obj, bob_name.clone()),
+            (alice_obj, "alice".to_string())
+        ]
+    );
+
+    assert!(harness.step_until(|h| {
+        h.clients[alice].reliable.iter().any(|msg| {
+            matches!(
+                msg,
+                ReliableMessageFromServer::Spawn(Spawn {
+                    net_obj,
+                    net_spawn: NetworkSpawn::Player { name, .. },
+                    ..
+                }) if *net_obj == bob_obj && *name == bob_name
+            )
+        })
+    })?);
+
+    harness.clients[bob].client.disconnect();
+
+    assert!(harness.step_until(|h| {
+        h.clients[alice]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::PlayerLeft(obj) if *obj == bob_obj))
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn disconnecting_already_despawned_player_does_not_panic() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    let instance = harness.game.get_instance_mut();
+    let entity = instance
+        .find_network_object(bob_obj)
+        .expect("bob was never spawned");
+    instance.despawn(entity);
+
+    harness.clients[bob].client.disconnect();
+
+    assert!(harness.step_until(|h| {
+        h.clients[alice]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::PlayerLeft(obj) if *obj == bob_obj))
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn large_input_gap_forces_resync() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let client = harness.add_client("lossy")?;
+    let obj = harness.join(client)?;
+
+    let send_input = |client: &mut TestClient, order| {
+        client.send_unreliable(UnreliableMessageFromClient::Input(OrderedInput {
+            input: PlayerInput {
+                move_direction: [1.0, 0.0],
+                action: None,
+            },
+            order,
+            target: None,
+        }))
+    };
+
+    send_input(&mut harness.clients[client], 1)?;
+    harness.step()?;
+    assert!(
+        !harness.clients[client]
+            .reliable
+            .iter()
+            .any(|msg| { matches!(msg, ReliableMessageFromServer::ForcedPlayerSync(_)) })
+    );
+
+    // Inputs 2..=49 were "lost" on the way.
+    send_input(&mut harness.clients[client], 50)?;
+
+    assert!(harness.step_until(|h| {
+        h.clients[client].reliable.iter().any(|msg| {
+            matches!(
+                msg,
+                ReliableMessageFromServer::ForcedPlayerSync(sync) if sync.net_obj == obj
+            )
+        })
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn flooding_inputs_does_not_exceed_speed_cap() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let client = harness.add_client("flooder")?;
+    let obj = harness.join(client)?;
+
+    let position = |harness: &Harness| {
+        let instance = harness.game.get_instance();
+        let entity = instance.find_network_object(obj).unwrap();
+        instance.get_world().get::<&Position>(entity).unwrap().0
+    };
+
+    let start = position(&harness);
+    let start_tick = harness.game.get_instance().get_tick().get();
+
+    let mut order = 0;
+    for _ in 0..20 {
+        for _ in 0..20 {
+            order += 1;
+            harness.clients[client].send_unreliable(UnreliableMessageFromClient::Input(
+                OrderedInput {
+                    input: PlayerInput {
+                        move_direction: [1.0, 0.0],
+                        action: None,
+                    },
+                    order,
+                    target: None,
+                },
+            ))?;
+        }
+        harness.step()?;
+    }
+
+    let ticks = harness.game.get_instance().get_tick().get() - start_tick;
+    let moved = position(&harness).metric_distance(&start);
+
+    assert!(moved > 0.0, "the flooded inputs were never applied");
+    assert!(
+        moved <= ticks as f32 * PLAYER_SPEED * DT.as_secs_f32() + 1.0e-3,
+        "moved {moved} in {ticks} ticks"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn movement_past_the_speed_cap_is_clamped() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    // The server allows less than inputs move players, so every input oversteps.
+    let cap = PLAYER_SPEED * 0.5;
+    harness.game.get_instance_mut().set_speed_cap(cap);
+
+    let client = harness.add_client("speeder")?;
+    let obj = harness.join(client)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("speeder").is_some())?);
+
+    let start = player_position(&harness, obj);
+    harness.clients[client].send_unreliable(UnreliableMessageFromClient::Input(OrderedInput {
+        input: PlayerInput {
+            move_direction: [1000.0, 0.0],
+            action: None,
+        },
+        order: 1,
+        target: None,
+    }))?;
+    assert!(harness.step_until(|h| player_position(h, obj) != start)?);
+
+    let moved = player_position(&harness, obj).x - start.x;
+    let expected = cap * DT.as_secs_f32();
+    assert!(
+        (moved - expected).abs() < 1.0e-3,
+        "moved {moved}, expected {expected}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn inputs_held_up_by_latency_are_all_applied() -> Result<()> {
+    let mut harness = Harness::new()?;
+    harness.game.set_input_queue_length(60);
+
+    let client = harness.add_client("laggy")?;
+    let obj = harness.join(client)?;
+
+    let position = |harness: &Harness| {
+        let instance = harness.game.get_instance();
+        let entity = instance.find_network_object(obj).unwrap();
+        instance.get_world().get::<&Position>(entity).unwrap().0
+    };
+    let start = position(&harness);
+
+    // A second of inputs arriving at once after a latency spike.
+    for order in 1..=60 {
+        harness.clients[client].send_unreliable(UnreliableMessageFromClient::Input(
+            OrderedInput {
+                input: PlayerInput {
+                    move_direction: [1.0, 0.0],
+                    action: None,
+                },
+                order,
+                target: None,
+            },
+        ))?;
+    }
+    for _ in 0..90 {
+        harness.step()?;
+    }
+
+    let moved = position(&harness).metric_distance(&start);
+    let expected = 60.0 * PLAYER_SPEED * DT.as_secs_f32();
+    assert!(
+        (moved - expected).abs() < 1.0e-2,
+        "moved {moved}, expected {expected}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn broadcast_props_reach_current_and_later_clients() -> Result<()> {
+    let mut harness = Harness::in_memory();
+
+    let early = harness.add_client("early")?;
+    harness.join(early)?;
+
+    let instance = harness.game.get_instance_mut();
+    let prop = instance.new_network_object();
+    instance.spawn_entity(
+        EntityKind::Static,
+        Vec2::new(300.0, 0.0),
+        EntityCollider::Cuboid {
+            half_extents: [20.0, 10.0],
+        },
+        prop,
+        true,
+    );
+
+    let is_prop = |net_obj: NetworkObject, net_spawn: &NetworkSpawn| {
+        net_obj == prop
+            && matches!(
+                net_spawn,
+                NetworkSpawn::Entity {
+                    kind: EntityKind::Static,
+                    position: [300.0, 0.0],
+                    collider: EntityCollider::Cuboid {
+                        half_extents: [20.0, 10.0]
+                    },
+                }
+            )
+    };
+
+    assert!(
+        harness.step_until(|h| {
+            h.clients[early].reliable.iter().any(|msg| match msg {
+                ReliableMessageFromServer::Spawn(spawn) => is_prop(spawn.net_obj, &spawn.net_spawn),
+                _ => false,
+            })
+        })?,
+        "the prop's spawn never arrived"
+    );
+
+    let late = harness.add_client("late")?;
+    harness.join(late)?;
+    assert!(
+        harness.step_until(|h| {
+            h.clients[late].reliable.iter().any(|msg| match msg {
+                ReliableMessageFromServer::Snapshot { entries, .. } => entries
+                    .iter()
+                    .any(|entry| is_prop(entry.net_obj, &entry.net_spawn)),
+                _ => false,
+            })
+        })?,
+        "the prop is missing from the late joiner's snapshot"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn client_drives_entities_it_was_given_control_of() -> Result<()> {
+    let mut harness = Harness::in_memory();
+
+    let owner = harness.add_client("owner")?;
+    let owner_obj = harness.join(owner)?;
+    let other = harness.add_client("other")?;
+    harness.join(other)?;
+
+    let instance = harness.game.get_instance_mut();
+    let pets = [Vec2::new(300.0, 0.0), Vec2::new(-300.0, 0.0)].map(|position| {
+        let net_obj = instance.new_network_object();
+        instance.spawn_player(false, position, "pet".to_string(), net_obj, 20.0, None);
+        (net_obj, position)
+    });
+    for (pet, _) in pets {
+        harness.game.grant_control(owner_obj, pet)?;
+    }
+
+    assert!(harness.step_until(|h| {
+        pets.iter().all(|(pet, _)| {
+            h.clients[owner].reliable.iter().any(
+                |msg| matches!(msg, ReliableMessageFromServer::ControlGranted(obj) if obj == pet),
+            )
+        })
+    })?);
+
+    let input = |order, target, move_direction| {
+        UnreliableMessageFromClient::Input(OrderedInput {
+            input: PlayerInput {
+                move_direction,
+                action: None,
+            },
+            order,
+            target: Some(target),
+        })
+    };
+
+    let ticks = 10;
+    for order in 1..=ticks {
+        harness.clients[owner].send_unreliable(input(order, pets[0].0, [1.0, 0.0]))?;
+        harness.clients[owner].send_unreliable(input(order, pets[1].0, [0.0, 1.0]))?;
+        // Not this client's to move.
+        harness.clients[other].send_unreliable(input(order, pets[0].0, [-1.0, 0.0]))?;
+        harness.step()?;
+    }
+    for _ in 0..10 {
+        harness.step()?;
+    }
+
+    let step = ticks as f32 * PLAYER_SPEED * DT.as_secs_f32();
+    let position = |harness: &Harness, net_obj| {
+        let instance = harness.game.get_instance();
+        let entity = instance.find_network_object(net_obj).unwrap();
+        instance.get_world().get::<&Position>(entity).unwrap().0
+    };
+    assert!((position(&harness, pets[0].0) - (pets[0].1 + Vec2::new(step, 0.0))).norm() < 1.0e-2);
+    assert!((position(&harness, pets[1].0) - (pets[1].1 + Vec2::new(0.0, step))).norm() < 1.0e-2);
+
+    // Each pet's inputs are acknowledged to its controller on their own.
+    for (pet, _) in pets {
+        assert!(harness.clients[owner].unreliable.iter().any(|msg| matches!(
+            msg,
+            UnreliableMessageFromServer::OwnedPlayerSync(sync)
+                if sync.net_obj == pet && sync.last_input_order == ticks
+        )));
+    }
+
+    harness.game.revoke_control(pets[0].0)?;
+    assert!(harness.step_until(|h| {
+        h.clients[owner].reliable.iter().any(|msg| match msg {
+            ReliableMessageFromServer::ControlRevoked(net_obj) => *net_obj == pets[0].0,
+            _ => false,
+        })
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn late_joiner_gets_one_snapshot_of_existing_players() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let mut existing = Vec::new();
+    for i in 0..30 {
+        let index = harness.add_client(&format!("player{i}"))?;
+        existing.push(harness.join(index)?);
+    }
+
+    let late = harness.add_client("late")?;
+    let late_obj = harness.join(late)?;
+
+    assert!(harness.step_until(|h| {
+        h.clients[late]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::Snapshot { .. }))
+    })?);
+
+    let snapshots: Vec<_> = harness.clients[late]
+        .reliable
+        .iter()
+        .filter_map(|msg| match msg {
+            ReliableMessageFromServer::Snapshot { entries, .. } => Some(entries),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(snapshots.len(), 1);
+
+    let snapshot_objs: HashSet<_> = snapshots[0].iter().map(|entry| entry.net_obj).collect();
+    let expected: HashSet<_> = existing.iter().copied().chain([late_obj]).collect();
+    assert_eq!(snapshots[0].len(), expected.len());
+    assert_eq!(snapshot_objs, expected);
+
+    let individual_spawns = harness.clients[late]
+        .reliable
+        .iter()
+        .filter(|msg| {
+            matches!(
+                msg,
+                ReliableMessageFromServer::Spawn(spawn) if existing.contains(&spawn.net_obj)
+            )
+        })
+        .count();
+    assert_eq!(individual_spawns, 0);
+
+    Ok(())
+}
+
+fn pause_state(client: &TestClient) -> Option<bool> {
+    client.reliable.iter().rev().find_map(|msg| match msg {
+        ReliableMessageFromServer::PauseState { paused } => Some(*paused),
+        _ => None,
+    })
+}
+
+#[test]
+fn solo_instance_pauses_and_shared_instance_does_not() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let solo = harness.add_client("solo")?;
+    harness.join(solo)?;
+
+    harness.clients[solo].send_reliable(ReliableMessageFromClient::Pause)?;
+    assert!(harness.step_until(|h| pause_state(&h.clients[solo]) == Some(true))?);
+
+    // Nothing is simulated or synced while paused.
+    harness.step()?;
+    harness.clients[solo].unreliable.clear();
+    for _ in 0..30 {
+        harness.step()?;
+    }
+    assert!(harness.clients[solo].unreliable.is_empty());
+
+    harness.clients[solo].reliable.clear();
+    harness.clients[solo].send_reliable(ReliableMessageFromClient::Resume)?;
+    assert!(harness.step_until(|h| {
+        // The tick stood still while paused, so it starts a new timeline.
+        pause_state(&h.clients[solo]) == Some(false)
+            && h.clients[solo].reliable.iter().any(
+                |msg| matches!(msg, ReliableMessageFromServer::TickSync(sync) if sync.timeline == 1),
+            )
+    })?);
+
+    let other = harness.add_client("other")?;
+    harness.join(other)?;
+
+    harness.clients[solo].reliable.clear();
+    harness.clients[solo].send_reliable(ReliableMessageFromClient::Pause)?;
+    for _ in 0..30 {
+        harness.step()?;
+    }
+    assert_eq!(pause_state(&harness.clients[solo]), None);
+
+    Ok(())
+}
+
+#[test]
+fn spectators_do_not_keep_a_solo_player_from_pausing() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let solo = harness.add_client("solo")?;
+    let spectator = harness.add_client("spectator")?;
+    harness.join(solo)?;
+
+    assert!(harness.step_until(|h| h.clients[spectator].client.is_connected())?);
+    harness.clients[spectator].send_reliable(ReliableMessageFromClient::Spectate {
+        protocol_version: PROTOCOL_VERSION,
+    })?;
+    assert!(harness.step_until(|h| {
+        h.clients[spectator]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::TickSync(_)))
+    })?);
+
+    harness.clients[solo].send_reliable(ReliableMessageFromClient::Pause)?;
+    assert!(harness.step_until(|h| pause_state(&h.clients[solo]) == Some(true))?);
+
+    Ok(())
+}
+
+#[test]
+fn held_player_keeps_the_instance_from_pausing() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+    harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    harness.clients[bob].client.disconnect();
+    assert!(harness.step_until(|h| h.game.is_player_held(bob_obj))?);
+
+    harness.clients[alice].reliable.clear();
+    harness.clients[alice].send_reliable(ReliableMessageFromClient::Pause)?;
+    for _ in 0..30 {
+        harness.step()?;
+    }
+    assert_eq!(pause_state(&harness.clients[alice]), None);
+
+    Ok(())
+}
+
+#[test]
+fn spectating_a_paused_instance_resumes_it() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let solo = harness.add_client("solo")?;
+    let spectator = harness.add_client("spectator")?;
+    harness.join(solo)?;
+
+    harness.clients[solo].send_reliable(ReliableMessageFromClient::Pause)?;
+    assert!(harness.step_until(|h| pause_state(&h.clients[solo]) == Some(true))?);
+
+    assert!(harness.step_until(|h| h.clients[spectator].client.is_connected())?);
+    harness.clients[spectator].send_reliable(ReliableMessageFromClient::Spectate {
+        protocol_version: PROTOCOL_VERSION,
+    })?;
+
+    assert!(harness.step_until(|h| {
+        pause_state(&h.clients[solo]) == Some(false)
+            && h.clients[spectator]
+                .reliable
+                .iter()
+                .any(|msg| matches!(msg, ReliableMessageFromServer::TickSync(_)))
+    })?);
+    harness.clients[spectator].send_reliable(ReliableMessageFromClient::ReadyForUpdates)?;
+
+    assert!(
+        harness.step_until(|h| {
+            h.clients[spectator]
+                .reliable
+                .iter()
+                .any(|msg| matches!(msg, ReliableMessageFromServer::Snapshot { .. }))
+        })?,
+        "spectator never finished loading"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn joining_players_get_distinct_spawn_points() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let first = harness.add_client("first")?;
+    let second = harness.add_client("second")?;
+
+    harness.join(first)?;
+    harness.join(second)?;
+
+    let position = |client: &TestClient| {
+        client.reliable.iter().find_map(|msg| match msg {
+            ReliableMessageFromServer::PlayerInit(init) => Some(Vec2::from(init.position)),
+            _ => None,
+        })
+    };
+
+    let first = position(&harness.clients[first]).unwrap();
+    let second = position(&harness.clients[second]).unwrap();
+
+    assert!(
+        first.metric_distance(&second) >= 2.0 * PLAYER_RADIUS,
+        "players spawned overlapping at {first:?} and {second:?}"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn tick_sync_announces_custom_tick_rate() -> Result<()> {
+    let mut harness = Harness::new()?;
+    harness.game.set_tick_rate(30);
+    harness.game.set_tick_rate(0);
+    assert_eq!(harness.game.get_tick_rate(), 30);
+
+    let client = harness.add_client("client")?;
+    harness.join(client)?;
+
+    let sync = harness.clients[client]
+        .reliable
+        .iter()
+        .find_map(|msg| match msg {
+            ReliableMessageFromServer::TickSync(sync) => Some(sync.tick_rate),
+            _ => None,
+        });
+    assert_eq!(sync, Some(30));
+
+    Ok(())
+}
+
+#[test]
+fn kicked_player_is_told_why_despawned_and_disconnected() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    assert!(harness.step_until(|h| h.game.find_player_by_name("bob").is_some())?);
+    assert_eq!(harness.game.find_player_by_name("bob"), Some(bob_obj));
+
+    harness.game.kick_player(bob_obj, "Spamming")?;
+
+    assert!(
+        harness.step_until(|h| h.clients[bob].client.is_disconnected())?,
+        "kicked client stayed connected"
+    );
+
+    assert!(harness.clients[bob].reliable.iter().any(|msg| matches!(
+        msg,
+        ReliableMessageFromServer::Kicked(reason) if reason == "Spamming"
+    )));
+    assert!(
+        harness
+            .game
+            .get_instance()
+            .find_network_object(bob_obj)
+            .is_none()
+    );
+
+    assert!(harness.step_until(|h| {
+        let reliable = &h.clients[alice].reliable;
+        reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::Despawn(obj) if *obj == bob_obj))
+            && reliable.iter().any(
+                |msg| matches!(msg, ReliableMessageFromServer::PlayerLeft(obj) if *obj == bob_obj),
+            )
+    })?);
+    assert!(harness.clients[alice].client.is_connected());
+
+    Ok(())
+}
+
+#[test]
+fn motionless_player_is_synced_at_keepalive_rate_until_it_moves() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let mover = harness.add_client("mover")?;
+    let observer = harness.add_client("observer")?;
+
+    let mover_obj = harness.join(mover)?;
+    harness.join(observer)?;
+
+    let synced_ticks = |harness: &Harness| {
+        harness.clients[observer]
+            .unreliable
+            .iter()
+            .filter_map(|msg| match msg {
+                UnreliableMessageFromServer::PlayerPositionSyncs(syncs) => {
+                    syncs.iter().find(|sync| sync.net_obj == mover_obj)
+                }
+                _ => None,
+            })
+            .map(|sync| sync.tick)
+            .collect::<HashSet<_>>()
+            .len()
+    };
+
+    for _ in 0..IDLE_AFTER_TICKS * 2 {
+        harness.step()?;
+    }
+
+    harness.clients[observer].unreliable.clear();
+    let window = IDLE_KEEPALIVE_TICKS * 4;
+    for _ in 0..window {
+        harness.step()?;
+    }
+
+    let idle_syncs = synced_ticks(&harness);
+    assert!(idle_syncs >= 1, "idle player was not kept alive");
+    assert!(
+        idle_syncs <= (window / IDLE_KEEPALIVE_TICKS + 1) as usize,
+        "idle player was synced {idle_syncs} times in {window} steps"
+    );
+
+    harness.clients[observer].unreliable.clear();
+    for order in 1..=window as u64 {
+        harness.clients[mover].send_unreliable(UnreliableMessageFromClient::Input(
+            OrderedInput {
+                input: PlayerInput {
+                    move_direction: [1.0, 0.0],
+                    action: None,
+                },
+                order,
+                target: None,
+            },
+        ))?;
+        harness.step()?;
+    }
+
+    let moving_syncs = synced_ticks(&harness);
+    assert!(
+        moving_syncs > window as usize / 2,
+        "moving player was only synced {moving_syncs} times in {window} steps"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn spectator_sees_players_without_owning_one_and_can_join() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let player = harness.add_client("player")?;
+    let spectator = harness.add_client("spectator")?;
+
+    let player_obj = harness.join(player)?;
+
+    assert!(harness.step_until(|h| h.clients[spectator].client.is_connected())?);
+    harness.clients[spectator].send_reliable(ReliableMessageFromClient::Spectate {
+        protocol_version: PROTOCOL_VERSION,
+    })?;
+
+    assert!(harness.step_until(|h| {
+        h.clients[spectator]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::TickSync(_)))
+    })?);
+    harness.clients[spectator].send_reliable(ReliableMessageFromClient::ReadyForUpdates)?;
+
+    assert!(
+        harness.step_until(|h| {
+            h.clients[spectator].reliable.iter().any(|msg| {
+                matches!(
+                    msg,
+                    ReliableMessageFromServer::Snapshot { entries, .. }
+                        if entries.iter().any(|entry| entry.net_obj == player_obj)
+                )
+            })
+        })?,
+        "spectator never received a snapshot with the player"
+    );
+    assert!(harness.step_until(|h| {
+        h.clients[spectator].unreliable.iter().any(|msg| {
+            matches!(
+                msg,
+                UnreliableMessageFromServer::PlayerPositionSyncs(syncs)
+                    if syncs.iter().any(|sync| sync.net_obj == player_obj)
+            )
+        })
+    })?);
+
+    assert!(harness.clients[spectator].player_init().is_none());
+    assert_eq!(harness.game.get_instance().player_count(), 1);
+
+    harness.clients[spectator].send_reliable(ReliableMessageFromClient::Connected {
+        protocol_version: PROTOCOL_VERSION,
+    })?;
+
+    assert!(
+        harness.step_until(|h| h.clients[spectator].player_init().is_some())?,
+        "spectator never got a player after joining"
+    );
+    let spectator_obj = harness.clients[spectator].player_init().unwrap();
+
+    assert!(harness.step_until(|h| {
+        h.clients[player].reliable.iter().any(|msg| {
+            matches!(
+                msg,
+                ReliableMessageFromServer::PlayerJoined(obj, _) if *obj == spectator_obj
+            )
+        })
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn owned_syncs_follow_acked_inputs_at_a_lower_cadence() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let client = harness.add_client("mover")?;
+    let obj = harness.join(client)?;
+
+    let owned_syncs = |harness: &Harness| {
+        harness.clients[client]
+            .unreliable
+            .iter()
+            .filter_map(|msg| match msg {
+                UnreliableMessageFromServer::OwnedPlayerSync(sync) if sync.net_obj == obj => {
+                    Some(sync.last_input_order)
+                }
+                _ => None,
+            })
+            .collect::<Vec<_>>()
+    };
+
+    let steps = 120;
+    harness.clients[client].unreliable.clear();
+    for order in 1..=steps {
+        harness.clients[client].send_unreliable(UnreliableMessageFromClient::Input(
+            OrderedInput {
+                input: PlayerInput {
+                    move_direction: [1.0, 0.0],
+                    action: None,
+                },
+                order,
+                target: None,
+            },
+        ))?;
+        harness.step()?;
+    }
+
+    // Before, every one of the `steps` ticks carried an owned sync.
+    let moving = owned_syncs(&harness);
+    assert!(!moving.is_empty(), "moving player was never acknowledged");
+    assert!(
+        moving.len() <= steps as usize / OWNED_SYNC_INTERVAL as usize + 2,
+        "{} owned syncs in {steps} steps",
+        moving.len()
+    );
+    assert!(
+        moving.windows(2).all(|orders| orders[0] < orders[1]),
+        "every owned sync should acknowledge a newer input: {moving:?}"
+    );
+
+    harness.clients[client].unreliable.clear();
+    for _ in 0..steps {
+        harness.step()?;
+    }
+
+    let idle = owned_syncs(&harness);
+    assert!(!idle.is_empty(), "idle player got no keepalive sync");
+    assert!(
+        idle.len() <= steps as usize / OWNED_SYNC_KEEPALIVE_TICKS as usize + 2,
+        "{} owned syncs in {steps} idle steps",
+        idle.len()
+    );
+
+    Ok(())
+}
+
+#[test]
+fn flooded_message_queues_stay_bounded() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let client = harness.add_client("flooder")?;
+    let obj = harness.join(client)?;
+
+    let last_order = 4 * MAX_QUEUED_UNRELIABLE_MESSAGES as u64;
+    for order in 1..=last_order {
+        harness.clients[client].send_unreliable(UnreliableMessageFromClient::Input(
+            OrderedInput {
+                input: PlayerInput {
+                    move_direction: [1.0, 0.0],
+                    action: None,
+                },
+                order,
+                target: None,
+            },
+        ))?;
+    }
+    for _ in 0..4 * MAX_QUEUED_RELIABLE_MESSAGES {
+        harness.clients[client].send_reliable(ReliableMessageFromClient::Resume)?;
+    }
+
+    assert!(
+        harness.step_until(|h| {
+            h.game
+                .get_dropped_messages(client as u64)
+                .is_some_and(|dropped| dropped.reliable > 0 && dropped.unreliable > 0)
+        })?,
+        "flooding never overflowed the queues"
+    );
+
+    // The oldest inputs are the ones dropped, so the newest one is still acknowledged.
+    assert!(
+        harness.step_until(|h| {
+            h.clients[client].unreliable.iter().any(|msg| {
+                matches!(
+                    msg,
+                    UnreliableMessageFromServer::OwnedPlayerSync(sync)
+                        if sync.net_obj == obj && sync.last_input_order == last_order
+                )
+            })
+        })?,
+        "the newest input was dropped"
+    );
+
+    let dropped = harness.game.get_dropped_messages(client as u64).unwrap();
+    assert!(dropped.unreliable <= last_order - MAX_QUEUED_UNRELIABLE_MESSAGES as u64);
+    assert!(dropped.reliable < 4 * MAX_QUEUED_RELIABLE_MESSAGES as u64);
+
+    Ok(())
+}
+
+fn player_left(client: &TestClient, net_obj: NetworkObject) -> bool {
+    client.reliable.iter().any(|msg| {
+        matches!(
+            msg,
+            ReliableMessageFromServer::PlayerLeft(obj) | ReliableMessageFromServer::Despawn(obj)
+                if *obj == net_obj
+        )
+    })
+}
+
+#[test]
+fn reconnecting_within_grace_period_reclaims_player() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    harness.clients[bob].client.disconnect();
+    assert!(harness.step_until(|h| h.game.is_player_held(bob_obj))?);
+
+    // The same client id comes back with a fresh connection.
+    harness.clients[bob] = TestClient::connect(
+        harness.game.local_address(),
+        bob as u64,
+        &harness.key,
+        "bob",
+    )?;
+    assert_eq!(harness.join(bob)?, bob_obj);
+    assert!(!harness.game.is_player_held(bob_obj));
+
+    for _ in 0..RECONNECT_GRACE_TICKS + 10 {
+        harness.step()?;
+    }
+
+    assert!(
+        harness
+            .game
+            .get_instance()
+            .find_network_object(bob_obj)
+            .is_some()
+    );
+    assert!(
+        !player_left(&harness.clients[alice], bob_obj),
+        "alice was told bob left although bob came back"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn reconnecting_to_a_vanished_player_spawns_a_new_one() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    harness.clients[bob].client.disconnect();
+    assert!(harness.step_until(|h| h.game.is_player_held(bob_obj))?);
+
+    let instance = harness.game.get_instance_mut();
+    let entity = instance.find_network_object(bob_obj).unwrap();
+    instance.despawn(entity);
+
+    harness.clients[bob] = TestClient::connect(
+        harness.game.local_address(),
+        bob as u64,
+        &harness.key,
+        "bob",
+    )?;
+    let new_obj = harness.join(bob)?;
+    assert_ne!(new_obj, bob_obj);
+    assert!(!harness.game.is_player_held(bob_obj));
+
+    assert!(harness.step_until(|h| player_left(&h.clients[alice], bob_obj))?);
+    let listed = harness.clients[bob]
+        .reliable
+        .iter()
+        .find_map(|msg| match msg {
+            ReliableMessageFromServer::PlayerList(players) => Some(players.clone()),
+            _ => None,
+        });
+    assert!(
+        listed.is_some_and(|players| players.iter().all(|(net_obj, _)| *net_obj != bob_obj)),
+        "bob was listed the player that vanished"
+    );
+
+    Ok(())
+}
+
+#[test]
+fn leaving_removes_the_player_without_holding_it() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    harness.clients[bob].send_reliable(ReliableMessageFromClient::Leave)?;
+    assert!(harness.step_until(|h| player_left(&h.clients[alice], bob_obj))?);
+
+    assert!(!harness.game.is_player_held(bob_obj));
+    assert!(harness.game.find_player_by_name("bob").is_none());
+    assert!(harness.step_until(|h| h.clients[bob].client.is_disconnected())?);
+
+    Ok(())
+}
+
+#[test]
+fn held_player_is_removed_after_grace_period() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    harness.clients[bob].client.disconnect();
+    assert!(harness.step_until(|h| h.game.is_player_held(bob_obj))?);
+
+    // The tick that noticed the disconnect already counted towards the grace period.
+    for _ in 0..RECONNECT_GRACE_TICKS - 2 {
+        harness.step()?;
+    }
+    assert!(
+        harness
+            .game
+            .get_instance()
+            .find_network_object(bob_obj)
+            .is_some()
+    );
+    assert!(!player_left(&harness.clients[alice], bob_obj));
+
+    assert!(harness.step_until(|h| {
+        h.game.get_instance().find_network_object(bob_obj).is_none()
+            && player_left(&h.clients[alice], bob_obj)
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn input_log_keeps_the_last_ticks_of_applied_inputs() -> Result<()> {
+    let mut harness = Harness::new()?;
+    harness.game.enable_input_log(8);
+
+    let client = harness.add_client("logged")?;
+    let obj = harness.join(client)?;
+
+    for order in 1..=20 {
+        harness.clients[client].send_unreliable(UnreliableMessageFromClient::Input(
+            OrderedInput {
+                input: PlayerInput {
+                    move_direction: [1.0, 0.0],
+                    action: None,
+                },
+                order,
+                target: None,
+            },
+        ))?;
+        harness.step()?;
+    }
+
+    let log = harness.game.get_input_log().unwrap();
+    assert_eq!(log.len(), 8);
+
+    let ticks: Vec<u64> = log.iter().map(|record| record.tick).collect();
+    assert!(ticks.windows(2).all(|pair| pair[1] == pair[0] + 1));
+    assert_eq!(
+        *ticks.last().unwrap(),
+        harness.game.get_instance().get_tick().get()
+    );
+
+    let latest = log.iter().last().unwrap();
+    let player = latest
+        .players
+        .iter()
+        .find(|player| player.net_obj == obj)
+        .unwrap();
+    let entity = harness
+        .game
+        .get_instance()
+        .find_network_object(obj)
+        .unwrap();
+    let position = harness
+        .game
+        .get_instance()
+        .get_world()
+        .get::<&Position>(entity)
+        .unwrap()
+        .0;
+    assert_eq!(player.position, position);
+    assert!(
+        log.iter()
+            .flat_map(|record| &record.players)
+            .any(|player| player.input.is_some()),
+        "no applied input was recorded"
+    );
+
+    let mut dump = Vec::new();
+    log.write(&mut dump)?;
+    assert_eq!(String::from_utf8(dump).unwrap().lines().count(), 8);
+
+    Ok(())
+}
+
+#[test]
+fn changing_tick_rate_tells_connected_clients() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let client = harness.add_client("client")?;
+    harness.join(client)?;
+
+    harness.game.change_tick_rate(30)?;
+
+    assert!(harness.step_until(|h| {
+        h.clients[client].reliable.iter().any(
+            |msg| matches!(msg, ReliableMessageFromServer::TickSync(sync) if sync.tick_rate == 30),
+        )
+    })?);
+    assert!(harness.game.get_status().contains("30 ticks/s"));
+
+    // Ticks before and after the change don't measure one rate.
+    let timelines: Vec<_> = harness.clients[client]
+        .reliable
+        .iter()
+        .filter_map(|msg| match msg {
+            ReliableMessageFromServer::TickSync(sync) => Some((sync.tick_rate, sync.timeline)),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(timelines.first(), Some(&(TICK_RATE, 0)));
+    assert_eq!(timelines.last(), Some(&(30, 1)));
+
+    Ok(())
+}
+
+#[test]
+fn tick_syncs_are_broadcast_at_the_configured_interval() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    assert_eq!(
+        harness.game.get_tick_broadcast_interval(),
+        TICK_BROADCAST_INTERVAL
+    );
+
+    let client = harness.add_client("client")?;
+    harness.join(client)?;
+
+    let count_syncs = |harness: &Harness| {
+        harness.clients[client]
+            .reliable
+            .iter()
+            .filter(|msg| matches!(msg, ReliableMessageFromServer::TickSync(_)))
+            .count()
+    };
+
+    harness.game.set_tick_broadcast_interval(DT * 10);
+    harness.step()?;
+    harness.clients[client].reliable.clear();
+    for _ in 0..100 {
+        harness.step()?;
+    }
+    let syncs = count_syncs(&harness);
+    assert!((9..=10).contains(&syncs), "{syncs} syncs");
+
+    let stamps: Vec<_> = harness.clients[client]
+        .reliable
+        .iter()
+        .filter_map(|msg| match msg {
+            ReliableMessageFromServer::TickSync(sync) => Some((sync.tick, sync.timeline)),
+            _ => None,
+        })
+        .collect();
+    assert!(stamps.windows(2).all(|pair| pair[1].0 == pair[0].0 + 10));
+    assert!(stamps.iter().all(|(_, timeline)| *timeline == 0));
+
+    harness.game.set_tick_broadcast_interval(Duration::ZERO);
+    harness.clients[client].reliable.clear();
+    for _ in 0..100 {
+        harness.step()?;
+    }
+    assert_eq!(count_syncs(&harness), 0);
+
+    Ok(())
+}
+
+fn admin_reply(client: &TestClient) -> Option<&ReliableMessageFromServer> {
+    client.reliable.iter().find(|msg| {
+        matches!(
+            msg,
+            ReliableMessageFromServer::AdminReply(_) | ReliableMessageFromServer::AdminRejected
+        )
+    })
+}
+
+#[test]
+fn only_the_owner_may_send_admin_commands() -> Result<()> {
+    let mut harness = Harness::in_memory();
+
+    let owner = harness.add_client("owner")?;
+    let guest = harness.add_client("guest")?;
+    harness.game.set_owner(Some(owner as u64));
+    harness.join(owner)?;
+    harness.join(guest)?;
+
+    harness.clients[guest].send_reliable(ReliableMessageFromClient::Admin(
+        AdminCommand::SetTickRate(30),
+    ))?;
+    assert!(harness.step_until(|h| admin_reply(&h.clients[guest]).is_some())?);
+    assert!(matches!(
+        admin_reply(&harness.clients[guest]),
+        Some(ReliableMessageFromServer::AdminRejected)
+    ));
+    assert_eq!(harness.game.get_tick_rate(), TICK_RATE);
+
+    harness.clients[owner].send_reliable(ReliableMessageFromClient::Admin(
+        AdminCommand::SetTickRate(30),
+    ))?;
+    assert!(harness.step_until(|h| admin_reply(&h.clients[owner]).is_some())?);
+    assert!(matches!(
+        admin_reply(&harness.clients[owner]),
+        Some(ReliableMessageFromServer::AdminReply(_))
+    ));
+    assert_eq!(harness.game.get_tick_rate(), 30);
+
+    Ok(())
+}
+
+#[test]
+fn switching_characters_keeps_the_connection() -> Result<()> {
+    let mut harness = Harness::in_memory();
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+    harness.join(alice)?;
+    let old_obj = harness.join(bob)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("bob").is_some())?);
+
+    let new_obj = harness
+        .game
+        .switch_character(bob as u64, "robert", PLAYER_RADIUS * 2.0)?
+        .expect("bob has a player to switch from");
+    assert_ne!(new_obj, old_obj);
+
+    assert!(harness.step_until(|h| {
+        h.clients[bob].reliable.iter().any(|msg| matches!(
+            msg,
+            ReliableMessageFromServer::PlayerInit(init)
+                if init.net_obj == new_obj && init.name == "robert" && init.radius == PLAYER_RADIUS * 2.0
+        ))
+    })?);
+    assert!(harness.clients[bob].client.is_connected());
+    assert_eq!(harness.game.find_player_by_name("bob"), None);
+    assert_eq!(harness.game.find_player_by_name("robert"), Some(new_obj));
+    assert!(harness.step_until(|h| {
+        let reliable = &h.clients[alice].reliable;
+        reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::Despawn(obj) if *obj == old_obj))
+            && reliable.iter().any(|msg| {
+                matches!(msg, ReliableMessageFromServer::PlayerJoined(obj, name) if *obj == new_obj && name == "robert")
+            })
+    })?);
+
+    // The new player counts its inputs from the start.
+    let start = player_position(&harness, new_obj);
+    harness.clients[bob].send_unreliable(UnreliableMessageFromClient::Input(OrderedInput {
+        input: PlayerInput {
+            move_direction: [1.0, 0.0],
+            action: None,
+        },
+        order: 1,
+        target: None,
+    }))?;
+    assert!(harness.step_until(|h| player_position(h, new_obj).x > start.x)?);
+    assert!(harness.clients[bob].client.is_connected());
+
+    Ok(())
+}
+
+#[test]
+fn game_events_are_handled_in_the_order_they_were_sent() -> Result<()> {
+    let mut harness = Harness::in_memory();
+
+    let alice = harness.add_client("alice")?;
+    let old_obj = harness.join(alice)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("alice").is_some())?);
+
+    let earlier = NetworkObject::new_static(1);
+    harness.game.send_event(GameEvent::PlayerSpawned(earlier));
+    let new_obj = harness
+        .game
+        .switch_character(alice as u64, "alicia", PLAYER_RADIUS)?
+        .unwrap();
+
+    // Nothing is handled until the tick ends.
+    assert!(
+        !harness
+            .game
+            .get_handled_events()
+            .contains(&GameEvent::PlayerRemoved(old_obj))
+    );
+
+    harness.step()?;
+    assert_eq!(
+        harness.game.get_handled_events(),
+        [
+            GameEvent::PlayerSpawned(earlier),
+            GameEvent::PlayerRemoved(old_obj),
+            GameEvent::PlayerSpawned(new_obj),
+        ]
+    );
+
+    harness.step()?;
+    assert!(harness.game.get_handled_events().is_empty());
+
+    Ok(())
+}
+
+#[test]
+fn server_applies_dashes_and_their_cooldown() -> Result<()> {
+    let mut harness = Harness::in_memory();
+
+    let dasher = harness.add_client("dasher")?;
+    let dasher_obj = harness.join(dasher)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("dasher").is_some())?);
+
+    let dash = |order| {
+        UnreliableMessageFromClient::Input(OrderedInput {
+            input: PlayerInput {
+                move_direction: [-1.0, 0.0],
+                action: Some(PlayerAction::Dash),
+            },
+            order,
+            target: None,
+        })
+    };
+
+    for order in 1..=2 {
+        let start = player_position(&harness, dasher_obj);
+        harness.clients[dasher].send_unreliable(dash(order))?;
+        assert!(harness.step_until(|h| player_position(h, dasher_obj) != start)?);
+
+        // Only the first dash is allowed, the second comes during its cooldown.
+        let expected = if order == 1 {
+            PLAYER_SPEED * DT.as_secs_f32() + DASH_DISTANCE
+        } else {
+            PLAYER_SPEED * DT.as_secs_f32()
+        };
+        let moved = start.x - player_position(&harness, dasher_obj).x;
+        assert!(
+            (moved - expected).abs() < 1.0e-2,
+            "moved {moved}, expected {expected}"
+        );
+    }
+
+    assert!(harness.step_until(|h| {
+        h.clients[dasher].unreliable.iter().any(|msg| {
+            matches!(
+                msg,
+                UnreliableMessageFromServer::OwnedPlayerSync(sync)
+                    if sync.last_input_order == 2
+                        && sync.cooldowns == [(AbilityId::Dash, Tick::new(1 + DASH_COOLDOWN_INPUTS))]
+            )
+        })
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn skipping_input_orders_does_not_skip_cooldowns() -> Result<()> {
+    let mut harness = Harness::in_memory();
+
+    let cheater = harness.add_client("cheater")?;
+    let cheater_obj = harness.join(cheater)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("cheater").is_some())?);
+
+    // Each dash claims to come a whole cooldown after the last one.
+    for dash in 0..3 {
+        let start = player_position(&harness, cheater_obj);
+        harness.clients[cheater].send_unreliable(UnreliableMessageFromClient::Input(
+            OrderedInput {
+                input: PlayerInput {
+                    move_direction: [1.0, 0.0],
+                    action: Some(PlayerAction::Dash),
+                },
+                order: 1 + dash * DASH_COOLDOWN_INPUTS,
+                target: None,
+            },
+        ))?;
+        assert!(harness.step_until(|h| player_position(h, cheater_obj) != start)?);
+
+        let expected = if dash == 0 {
+            PLAYER_SPEED * DT.as_secs_f32() + DASH_DISTANCE
+        } else {
+            PLAYER_SPEED * DT.as_secs_f32()
+        };
+        let moved = player_position(&harness, cheater_obj).x - start.x;
+        assert!(
+            (moved - expected).abs() < 1.0e-2,
+            "dash {dash} moved {moved}, expected {expected}"
+        );
+    }
+
+    let instance = harness.game.get_instance();
+    let entity = instance.find_network_object(cheater_obj).unwrap();
+    let abilities = instance.get_world().get::<&Abilities>(entity).unwrap();
+    assert_eq!(abilities.inputs_applied, 3);
+    assert!(!abilities.is_ready_next(AbilityId::Dash));
+
+    Ok(())
+}
+
+#[test]
+fn handed_off_player_arrives_with_its_state() -> Result<()> {
+    let mut source = Harness::in_memory();
+    let traveller = source.add_client("traveller")?;
+    let source_obj = source.join(traveller)?;
+    assert!(source.step_until(|h| h.game.find_player_by_name("traveller").is_some())?);
+
+    source.clients[traveller].send_unreliable(UnreliableMessageFromClient::Input(
+        OrderedInput {
+            input: PlayerInput {
+                move_direction: [1.0, 0.0],
+                action: Some(PlayerAction::Dash),
+            },
+            order: 1,
+            target: None,
+        },
+    ))?;
+    assert!(source.step_until(|h| {
+        let instance = h.game.get_instance();
+        let entity = instance.find_network_object(source_obj).unwrap();
+        let abilities = instance.get_world().get::<&Abilities>(entity).unwrap();
+        !abilities.is_ready(AbilityId::Dash, Tick::new(2))
+    })?);
+
+    let position = Vec2::new(AREA_SIZE * 0.5, 0.0);
+    teleport(&mut source, source_obj, position);
+
+    let mut handoff = source
+        .game
+        .begin_handoff(traveller as u64, 7)
+        .expect("the traveller has a player to hand off");
+    assert_eq!(handoff.character_id, 7);
+    assert_eq!(handoff.name, "traveller");
+    assert_eq!(handoff.cooldowns, [(AbilityId::Dash, DASH_COOLDOWN_INPUTS)]);
+    assert!(source.game.is_handing_off(traveller as u64));
+
+    // The player stays put until the destination has it.
+    source.step()?;
+    assert_eq!(
+        source.game.find_player_by_name("traveller"),
+        Some(source_obj)
+    );
+
+    let mut destination = Harness::in_memory();
+    let arriving = destination.add_client("someone else")?;
+    handoff.client_id = arriving as u64;
+    destination.game.accept_handoff(handoff)?;
+    let destination_obj = destination.join(arriving)?;
+
+    assert!(destination.step_until(|h| h.game.find_player_by_name("traveller").is_some())?);
+    assert_eq!(player_position(&destination, destination_obj), position);
+    let instance = destination.game.get_instance();
+    let entity = instance.find_network_object(destination_obj).unwrap();
+    let abilities = instance.get_world().get::<&Abilities>(entity).unwrap();
+    assert!(!abilities.is_ready(AbilityId::Dash, Tick::new(DASH_COOLDOWN_INPUTS - 1)));
+    assert!(abilities.is_ready(AbilityId::Dash, Tick::new(DASH_COOLDOWN_INPUTS)));
+    drop(abilities);
+
+    source.game.finish_handoff(traveller as u64)?;
+    assert!(!source.game.is_handing_off(traveller as u64));
+    assert_eq!(source.game.find_player_by_name("traveller"), None);
+    assert!(source.step_until(|h| {
+        h.clients[traveller]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::Despawn(obj) if *obj == source_obj))
+    })?);
+    assert!(source.clients[traveller].client.is_connected());
+
+    Ok(())
+}
+
+#[test]
+fn failed_handoff_keeps_the_player() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let traveller = harness.add_client("traveller")?;
+    let traveller_obj = harness.join(traveller)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("traveller").is_some())?);
+
+    assert!(harness.game.begin_handoff(traveller as u64, 0).is_some());
+    harness.game.cancel_handoff(traveller as u64);
+    assert!(!harness.game.is_handing_off(traveller as u64));
+
+    // A confirmation arriving after the handoff was called off changes nothing.
+    harness.game.finish_handoff(traveller as u64)?;
+    harness.step()?;
+    assert_eq!(
+        harness.game.find_player_by_name("traveller"),
+        Some(traveller_obj)
+    );
+
+    let start = player_position(&harness, traveller_obj);
+    harness.clients[traveller].send_unreliable(UnreliableMessageFromClient::Input(
+        OrderedInput {
+            input: PlayerInput {
+                move_direction: [1.0, 0.0],
+                action: None,
+            },
+            order: 1,
+            target: None,
+        },
+    ))?;
+    assert!(harness.step_until(|h| player_position(h, traveller_obj).x > start.x)?);
+
+    Ok(())
+}
+
+#[test]
+fn dropping_mid_handoff_calls_it_off() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let traveller = harness.add_client("traveller")?;
+    let traveller_obj = harness.join(traveller)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("traveller").is_some())?);
+
+    assert!(harness.game.begin_handoff(traveller as u64, 0).is_some());
+    harness.clients[traveller].client.disconnect();
+    assert!(harness.step_until(|h| h.game.is_player_held(traveller_obj))?);
+    assert!(!harness.game.is_handing_off(traveller as u64));
+
+    // The held player stays for its client to reclaim.
+    harness.game.finish_handoff(traveller as u64)?;
+    harness.step()?;
+    assert!(harness.game.is_player_held(traveller_obj));
+
+    Ok(())
+}
+
+#[test]
+fn handoff_to_a_connected_player_replaces_it() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let alice = harness.add_client("alice")?;
+    let old_obj = harness.join(alice)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("alice").is_some())?);
+
+    harness.game.accept_handoff(PlayerHandoff {
+        client_id: alice as u64,
+        character_id: 3,
+        name: "traveller".to_string(),
+        radius: PLAYER_RADIUS,
+        position: [AREA_SIZE * 0.5, 0.0],
+        cooldowns: Vec::new(),
+    })?;
+
+    assert!(harness.step_until(|h| {
+        h.game
+            .find_player_by_name("traveller")
+            .and_then(|net_obj| h.game.get_instance().find_network_object(net_obj))
+            .is_some()
+    })?);
+    assert_eq!(harness.game.find_player_by_name("alice"), None);
+    let new_obj = harness.game.find_player_by_name("traveller").unwrap();
+    assert_ne!(new_obj, old_obj);
+    assert_eq!(
+        player_position(&harness, new_obj),
+        Vec2::new(AREA_SIZE * 0.5, 0.0)
+    );
+
+    Ok(())
+}
+
+fn player_position(harness: &Harness, net_obj: NetworkObject) -> Vec2 {
+    let instance = harness.game.get_instance();
+    let entity = instance.find_network_object(net_obj).unwrap();
+    instance.get_world().get::<&Position>(entity).unwrap().0
+}
+
+fn teleport(harness: &mut Harness, net_obj: NetworkObject, position: Vec2) {
+    let instance = harness.game.get_instance_mut();
+    let entity = instance.find_network_object(net_obj).unwrap();
+    instance
+        .get_world_mut()
+        .get::<&mut Position>(entity)
+        .unwrap()
+        .0 = position;
+}
+
+fn position_synced(client: &TestClient, net_obj: NetworkObject) -> bool {
+    client.unreliable.iter().any(|msg| {
+        matches!(
+            msg,
+            UnreliableMessageFromServer::PlayerPositionSyncs(syncs)
+                if syncs.iter().any(|sync| sync.net_obj == net_obj)
+        )
+    })
+}
+
+#[test]
+fn players_out_of_each_others_areas_are_despawned_until_they_return() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+    assert!(harness.step_until(|h| position_synced(&h.clients[alice], bob_obj))?);
+
+    let far = Vec2::new(AREA_SIZE * 5.0, 0.0);
+    teleport(&mut harness, bob_obj, far);
+
+    assert!(harness.step_until(|h| {
+        h.clients[alice]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::Despawn(obj) if *obj == bob_obj))
+    })?);
+    assert_eq!(harness.game.get_area(bob_obj), Some(Area::of(far)));
+
+    // Motionless players are still synced at the keepalive rate, just not to alice.
+    harness.clients[alice].reliable.clear();
+    harness.clients[alice].unreliable.clear();
+    for _ in 0..IDLE_KEEPALIVE_TICKS * 2 {
+        harness.step()?;
+    }
+    assert!(!position_synced(&harness.clients[alice], bob_obj));
+
+    teleport(&mut harness, bob_obj, Vec2::new(AREA_SIZE * 0.5, 0.0));
+
+    assert!(harness.step_until(|h| {
+        h.clients[alice].reliable.iter().any(|msg| {
+            matches!(
+                msg,
+                ReliableMessageFromServer::Spawn(Spawn { net_obj, .. }) if *net_obj == bob_obj
+            )
+        })
+    })?);
+    assert!(harness.step_until(|h| position_synced(&h.clients[alice], bob_obj))?);
+
+    Ok(())
+}
+
+fn despawned(client: &TestClient, net_obj: NetworkObject) -> bool {
+    client
+        .reliable
+        .iter()
+        .any(|msg| matches!(msg, ReliableMessageFromServer::Despawn(obj) if *obj == net_obj))
+}
+
+#[test]
+fn player_pacing_along_an_area_edge_is_not_respawned() -> Result<()> {
+    let mut harness = Harness::new()?;
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    let alice_obj = harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    // Alice's view ends at the far side of the area next to hers.
+    teleport(
+        &mut harness,
+        alice_obj,
+        Vec2::new(AREA_SIZE * 0.5, AREA_SIZE * 0.5),
+    );
+    let edge = AREA_SIZE * 2.0;
+    let inside = Vec2::new(edge - 50.0, AREA_SIZE * 0.5);
+    let outside = Vec2::new(edge + 50.0, AREA_SIZE * 0.5);
+    teleport(&mut harness, bob_obj, inside);
+    assert!(harness.step_until(|h| position_synced(&h.clients[alice], bob_obj))?);
+
+    harness.clients[alice].reliable.clear();
+    for _ in 0..5 {
+        for position in [outside, inside] {
+            teleport(&mut harness, bob_obj, position);
+            for _ in 0..3 {
+                harness.step()?;
+            }
+        }
+    }
+    assert!(!despawned(&harness.clients[alice], bob_obj));
+
+    // Going well past the edge still takes bob away.
+    teleport(
+        &mut harness,
+        bob_obj,
+        Vec2::new(edge + AREA_HYSTERESIS * 2.0, AREA_SIZE * 0.5),
+    );
+    assert!(harness.step_until(|h| despawned(&h.clients[alice], bob_obj))?);
+
+    Ok(())
+}
+
+#[test]
+fn without_hysteresis_the_edge_is_exact() -> Result<()> {
+    let mut harness = Harness::new()?;
+    harness.game.set_area_hysteresis(0.0);
+
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    let alice_obj = harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    teleport(
+        &mut harness,
+        alice_obj,
+        Vec2::new(AREA_SIZE * 0.5, AREA_SIZE * 0.5),
+    );
+    teleport(
+        &mut harness,
+        bob_obj,
+        Vec2::new(AREA_SIZE * 2.0 - 50.0, AREA_SIZE * 0.5),
+    );
+    assert!(harness.step_until(|h| position_synced(&h.clients[alice], bob_obj))?);
+
+    teleport(
+        &mut harness,
+        bob_obj,
+        Vec2::new(AREA_SIZE * 2.0 + 50.0, AREA_SIZE * 0.5),
+    );
+    assert!(harness.step_until(|h| despawned(&h.clients[alice], bob_obj))?);
+
+    Ok(())
+}
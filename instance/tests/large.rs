@@ -0,0 +1,76 @@
+mod support;
+
+use std::time::Duration;
+
+use common::{
+    DT, Result,
+    message::{MAX_MESSAGE_SIZE, ReliableMessageFromClient, ReliableMessageFromServer, encode},
+    net_obj::NetworkObject,
+};
+use instance::server::Server;
+use support::{MAX_STEPS, TestClient};
+
+const CLIENT_ID: u64 = 1;
+
+fn step(server: &mut Server, client: &mut TestClient) -> Result<()> {
+    client.send_packets()?;
+    std::thread::sleep(Duration::from_millis(1));
+
+    server.update(DT)?;
+    server.send_packets();
+    std::thread::sleep(Duration::from_millis(1));
+
+    client.update(DT)
+}
+
+#[test]
+fn large_messages_arrive_whole_in_both_directions() -> Result<()> {
+    let key = [9; 32];
+    let mut server = Server::new(key)?;
+    let mut client = TestClient::connect(server.local_address(), CLIENT_ID, &key, "big")?;
+
+    for _ in 0..MAX_STEPS {
+        if client.client.is_connected() {
+            break;
+        }
+        step(&mut server, &mut client)?;
+    }
+    assert!(client.client.is_connected());
+
+    let list: Vec<_> = (0..6000)
+        .map(|i| (NetworkObject::new_static(i), format!("player{i}")))
+        .collect();
+    let message = ReliableMessageFromServer::PlayerList(list.clone());
+    // Far past the MTU, and past what a single message may even be.
+    assert!(encode(&message)?.len() > MAX_MESSAGE_SIZE);
+
+    server.send_large(CLIENT_ID, message)?;
+
+    let mut received = None;
+    for _ in 0..MAX_STEPS {
+        received = client.reliable.iter().find_map(|msg| match msg {
+            ReliableMessageFromServer::PlayerList(list) => Some(list.clone()),
+            _ => None,
+        });
+        if received.is_some() {
+            break;
+        }
+        step(&mut server, &mut client)?;
+    }
+    assert_eq!(received, Some(list));
+
+    client.send_large(ReliableMessageFromClient::ReadyForUpdates)?;
+
+    for _ in 0..MAX_STEPS {
+        if let Some(message) = server.poll_large(CLIENT_ID) {
+            assert!(matches!(
+                message?,
+                ReliableMessageFromClient::ReadyForUpdates
+            ));
+            return Ok(());
+        }
+        step(&mut server, &mut client)?;
+    }
+
+    panic!("server never received the large message");
+}
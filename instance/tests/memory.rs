@@ -0,0 +1,271 @@
+mod support;
+
+use std::time::Duration;
+
+use common::{
+    DT, PROTOCOL_VERSION, Result,
+    message::{
+        NetworkSpawn, OrderedInput, ReliableMessageFromClient, ReliableMessageFromServer, Spawn,
+        UnreliableMessageFromClient, UnreliableMessageFromServer,
+    },
+    player::PlayerInput,
+};
+use instance::DISCONNECT_DRAIN;
+use support::Harness;
+
+#[test]
+fn in_memory_clients_connect_straight_away() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let client = harness.add_client("alice")?;
+
+    assert!(harness.clients[client].client.is_connected());
+    assert!(harness.clients[client].transport.is_none());
+
+    let net_obj = harness.join(client)?;
+    harness.step()?;
+
+    // The name comes from the in-memory connection instead of a connect token.
+    assert_eq!(harness.game.find_player_by_name("alice"), Some(net_obj));
+
+    Ok(())
+}
+
+#[test]
+fn spawns_reach_other_in_memory_clients() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let alice = harness.add_client("alice")?;
+    let bob = harness.add_client("bob")?;
+
+    harness.join(alice)?;
+    let bob_obj = harness.join(bob)?;
+
+    assert!(harness.step_until(|h| {
+        h.clients[alice].reliable.iter().any(|msg| {
+            matches!(
+                msg,
+                ReliableMessageFromServer::Spawn(Spawn {
+                    net_obj,
+                    net_spawn: NetworkSpawn::Player { .. },
+                    ..
+                }) if *net_obj == bob_obj
+            )
+        })
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn inputs_are_acknowledged_in_memory() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let client = harness.add_client("mover")?;
+    let net_obj = harness.join(client)?;
+
+    harness.clients[client].send_unreliable(UnreliableMessageFromClient::Input(OrderedInput {
+        input: PlayerInput {
+            move_direction: [1.0, 0.0],
+            action: None,
+        },
+        order: 1,
+        target: None,
+    }))?;
+
+    assert!(harness.step_until(|h| {
+        h.clients[client].unreliable.iter().any(|msg| {
+            matches!(
+                msg,
+                UnreliableMessageFromServer::OwnedPlayerSync(sync)
+                    if sync.net_obj == net_obj && sync.last_input_order == 1
+            )
+        })
+    })?);
+
+    Ok(())
+}
+
+#[test]
+fn rejected_in_memory_client_is_told_why_and_disconnected() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let client = harness.add_client("stale")?;
+
+    harness.clients[client].send_reliable(ReliableMessageFromClient::Connected {
+        protocol_version: PROTOCOL_VERSION + 1,
+    })?;
+
+    assert!(harness.step_until(|h| h.clients[client].client.is_disconnected())?);
+    assert!(
+        harness.clients[client]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::VersionMismatch { .. }))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn kicked_client_stays_connected_until_it_acknowledges_why() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let client = harness.add_client("spammer")?;
+    let net_obj = harness.join(client)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("spammer").is_some())?);
+
+    harness.game.kick_player(net_obj, "Spamming")?;
+
+    // The client doesn't read its packets here, so nothing acknowledges the reason.
+    for _ in 0..3 {
+        harness.game.step(DT)?;
+    }
+    assert!(
+        harness
+            .game
+            .get_server_mut()
+            .client_ids()
+            .contains(&(client as u64))
+    );
+
+    assert!(harness.step_until(|h| h.clients[client].client.is_disconnected())?);
+    assert!(harness.clients[client].reliable.iter().any(|msg| matches!(
+        msg,
+        ReliableMessageFromServer::Kicked(reason) if reason == "Spamming"
+    )));
+
+    Ok(())
+}
+
+#[test]
+fn kicked_client_that_never_acknowledges_is_disconnected_anyway() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let client = harness.add_client("silent")?;
+    let net_obj = harness.join(client)?;
+    assert!(harness.step_until(|h| h.game.find_player_by_name("silent").is_some())?);
+
+    harness.game.kick_player(net_obj, "Spamming")?;
+
+    let mut waited = Duration::ZERO;
+    while waited <= DISCONNECT_DRAIN + DT {
+        harness.game.step(DT)?;
+        waited += DT;
+    }
+    assert!(
+        !harness
+            .game
+            .get_server_mut()
+            .client_ids()
+            .contains(&(client as u64))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn full_instance_keeps_the_client_connected_until_it_acknowledges() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    harness.game.set_max_clients(1);
+
+    let alice = harness.add_client("alice")?;
+    let carol = harness.add_client("carol")?;
+    harness.join(alice)?;
+
+    harness.clients[carol].send_reliable(ReliableMessageFromClient::Connected {
+        protocol_version: PROTOCOL_VERSION,
+    })?;
+    // Hand the server carol's message, then keep her from reading the answer.
+    harness.step()?;
+    for _ in 0..3 {
+        harness.game.step(DT)?;
+    }
+    assert!(
+        harness
+            .game
+            .get_server_mut()
+            .client_ids()
+            .contains(&(carol as u64))
+    );
+
+    assert!(harness.step_until(|h| h.clients[carol].client.is_disconnected())?);
+    assert!(
+        harness.clients[carol]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::ServerFull))
+    );
+
+    Ok(())
+}
+
+#[test]
+fn player_list_is_in_join_order() -> Result<()> {
+    let mut harness = Harness::in_memory();
+
+    let names: Vec<_> = (0..8).map(|i| format!("player{i}")).collect();
+    let mut players = Vec::new();
+    for name in &names {
+        let client = harness.add_client(name)?;
+        players.push(harness.join(client)?);
+    }
+    harness.step()?;
+
+    // Leaving takes a player out without reordering the rest.
+    harness.game.kick_player(players[3], "Leaving")?;
+    let expected: Vec<_> = names
+        .iter()
+        .enumerate()
+        .filter(|(i, _)| *i != 3)
+        .map(|(_, name)| name.clone())
+        .collect();
+
+    let late = harness.add_client("late")?;
+    harness.join(late)?;
+
+    let listed: Vec<_> = harness.clients[late]
+        .reliable
+        .iter()
+        .find_map(|msg| match msg {
+            ReliableMessageFromServer::PlayerList(players) => Some(players.clone()),
+            _ => None,
+        })
+        .unwrap()
+        .into_iter()
+        .map(|(_, name)| name)
+        .collect();
+    assert_eq!(listed[..expected.len()], expected);
+
+    Ok(())
+}
+
+#[test]
+fn in_memory_client_disconnecting_holds_its_player() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let client = harness.add_client("leaver")?;
+    let net_obj = harness.join(client)?;
+
+    harness.clients[client].client.disconnect();
+
+    assert!(harness.step_until(|h| h.game.is_player_held(net_obj))?);
+
+    Ok(())
+}
+
+#[test]
+fn shutdown_tells_clients_before_disconnecting_them() -> Result<()> {
+    let mut harness = Harness::in_memory();
+    let client = harness.add_client("alice")?;
+    harness.join(client)?;
+    harness.step()?;
+
+    harness.game.shutdown(Duration::ZERO)?;
+
+    assert!(
+        harness.step_until(|h| h.clients[client].client.is_disconnected())?,
+        "client stayed connected after shutdown"
+    );
+    assert!(
+        harness.clients[client]
+            .reliable
+            .iter()
+            .any(|msg| matches!(msg, ReliableMessageFromServer::ServerShutdown))
+    );
+
+    Ok(())
+}
@@ -0,0 +1,273 @@
+//! In-process server and clients shared by the integration tests and benchmarks.
+
+#![allow(dead_code)]
+
+use std::{
+    net::{Ipv4Addr, SocketAddr, UdpSocket},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use common::{
+    DT, PROTOCOL_VERSION, Result,
+    chunk::{PayloadAssembler, decode_payload, split_message},
+    game::character::name_to_user_data,
+    message::{
+        ReliableMessageFromClient, ReliableMessageFromServer, UnreliableMessageFromClient,
+        UnreliableMessageFromServer, decode, encode,
+    },
+    net_obj::NetworkObject,
+};
+use instance::{Game, server::Server};
+use renet::{ConnectionConfig, DefaultChannel, RenetClient};
+use renet_netcode::{ClientAuthentication, ConnectToken, NetcodeClientTransport};
+use uuid::Uuid;
+
+/// Upper bound on fixed steps any phase of a test may take before it is
+/// considered hung.
+pub const MAX_STEPS: usize = 600;
+
+pub struct TestClient {
+    pub client: RenetClient,
+    /// `None` for clients of an in-memory server, which trade packets through
+    /// [`Server::process_local`] instead.
+    pub transport: Option<NetcodeClientTransport>,
+    pub reliable: Vec<ReliableMessageFromServer>,
+    pub unreliable: Vec<UnreliableMessageFromServer>,
+    assembler: PayloadAssembler,
+}
+
+impl TestClient {
+    pub fn connect(
+        server_addr: SocketAddr,
+        client_id: u64,
+        key: &[u8; 32],
+        name: &str,
+    ) -> Result<TestClient> {
+        let current_time = SystemTime::now().duration_since(UNIX_EPOCH)?;
+
+        let connect_token = ConnectToken::generate(
+            current_time,
+            0,
+            30 * 60,
+            client_id,
+            30 * 60,
+            vec![server_addr],
+            Some(&name_to_user_data(name)),
+            key,
+        )?;
+
+        let socket = UdpSocket::bind(SocketAddr::new(Ipv4Addr::LOCALHOST.into(), 0))?;
+
+        let transport = NetcodeClientTransport::new(
+            current_time,
+            ClientAuthentication::Secure { connect_token },
+            socket,
+        )?;
+
+        Ok(TestClient::with_transport(
+            RenetClient::new(ConnectionConfig::default()),
+            Some(transport),
+        ))
+    }
+
+    /// Wraps a client from [`Server::connect_local`].
+    pub fn local(client: RenetClient) -> TestClient {
+        TestClient::with_transport(client, None)
+    }
+
+    fn with_transport(
+        client: RenetClient,
+        transport: Option<NetcodeClientTransport>,
+    ) -> TestClient {
+        TestClient {
+            client,
+            transport,
+            reliable: Vec::new(),
+            unreliable: Vec::new(),
+            assembler: PayloadAssembler::new(),
+        }
+    }
+
+    pub fn update(&mut self, dt: Duration) -> Result<()> {
+        self.client.update(dt);
+        if let Some(transport) = &mut self.transport
+            && let Err(err) = transport.update(dt, &mut self.client)
+        {
+            // Being disconnected by the server is an outcome tests assert on, not a failure.
+            if transport.disconnect_reason().is_none() {
+                return Err(err.into());
+            }
+        }
+
+        while let Some(bytes) = self
+            .client
+            .receive_message(DefaultChannel::ReliableUnordered)
+        {
+            self.reliable.push(decode(&bytes)?);
+        }
+
+        while let Some(bytes) = self.client.receive_message(DefaultChannel::ReliableOrdered) {
+            if let Some(payload) = self.assembler.push(decode(&bytes)?)? {
+                self.reliable.push(decode_payload(&payload)?);
+            }
+        }
+
+        while let Some(bytes) = self.client.receive_message(DefaultChannel::Unreliable) {
+            self.unreliable.push(decode(&bytes)?);
+        }
+
+        Ok(())
+    }
+
+    pub fn send_reliable(&mut self, message: ReliableMessageFromClient) -> Result<()> {
+        self.client
+            .send_message(DefaultChannel::ReliableUnordered, encode(&message)?);
+        Ok(())
+    }
+
+    pub fn send_large(&mut self, message: ReliableMessageFromClient) -> Result<()> {
+        for chunk in split_message(&message)? {
+            self.client
+                .send_message(DefaultChannel::ReliableOrdered, chunk);
+        }
+        Ok(())
+    }
+
+    pub fn send_unreliable(&mut self, message: UnreliableMessageFromClient) -> Result<()> {
+        self.client
+            .send_message(DefaultChannel::Unreliable, encode(&message)?);
+        Ok(())
+    }
+
+    pub fn send_packets(&mut self) -> Result<()> {
+        let Some(transport) = &mut self.transport else {
+            return Ok(());
+        };
+
+        if transport.disconnect_reason().is_some() {
+            return Ok(());
+        }
+
+        transport.send_packets(&mut self.client)?;
+        Ok(())
+    }
+
+    pub fn player_init(&self) -> Option<NetworkObject> {
+        self.reliable.iter().find_map(|msg| match msg {
+            ReliableMessageFromServer::PlayerInit(init) => Some(init.net_obj),
+            _ => None,
+        })
+    }
+}
+
+/// A server and any number of clients talking over loopback in one process, or without
+/// sockets at all when made with [`Harness::in_memory`].
+///
+/// Every [`Harness::step`] advances all participants by exactly one [`DT`], so
+/// the simulation is driven by the step count rather than wall-clock time.
+pub struct Harness {
+    pub game: Game,
+    pub key: [u8; 32],
+    pub clients: Vec<TestClient>,
+}
+
+impl Harness {
+    pub fn new() -> Result<Harness> {
+        let key = [7; 32];
+        let server = Server::new(key)?;
+
+        Ok(Harness {
+            game: Game::new(Uuid::now_v7(), server),
+            key,
+            clients: Vec::new(),
+        })
+    }
+
+    /// Server and clients pass packets in memory, so nothing depends on sockets or timing.
+    pub fn in_memory() -> Harness {
+        Harness {
+            game: Game::new(Uuid::now_v7(), Server::in_memory()),
+            key: [7; 32],
+            clients: Vec::new(),
+        }
+    }
+
+    pub fn add_client(&mut self, name: &str) -> Result<usize> {
+        let client_id = self.clients.len() as u64;
+        let client = match self.game.get_server_mut().connect_local(client_id, name) {
+            Some(client) => TestClient::local(client),
+            None => TestClient::connect(self.game.local_address(), client_id, &self.key, name)?,
+        };
+        self.clients.push(client);
+        Ok(client_id as usize)
+    }
+
+    pub fn step(&mut self) -> Result<()> {
+        if self.game.get_server_mut().is_in_memory() {
+            self.game.step(DT)?;
+
+            for (client_id, client) in self.clients.iter_mut().enumerate() {
+                self.game
+                    .get_server_mut()
+                    .process_local(client_id as u64, &mut client.client);
+                client.update(DT)?;
+            }
+
+            return Ok(());
+        }
+
+        for client in &mut self.clients {
+            client.send_packets()?;
+        }
+
+        // Give the loopback socket a moment to deliver what was just sent.
+        std::thread::sleep(Duration::from_millis(1));
+
+        self.game.step(DT)?;
+
+        std::thread::sleep(Duration::from_millis(1));
+
+        for client in &mut self.clients {
+            client.update(DT)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn step_until<F>(&mut self, mut condition: F) -> Result<bool>
+    where
+        F: FnMut(&Harness) -> bool,
+    {
+        for _ in 0..MAX_STEPS {
+            if condition(self) {
+                return Ok(true);
+            }
+
+            self.step()?;
+        }
+
+        Ok(condition(self))
+    }
+
+    /// Runs the `Connected` → `PlayerInit` → `ReadyForUpdates` handshake for a
+    /// client and returns the network object the server assigned to it.
+    pub fn join(&mut self, index: usize) -> Result<NetworkObject> {
+        assert!(
+            self.step_until(|h| h.clients[index].client.is_connected())?,
+            "client {index} never connected"
+        );
+
+        self.clients[index].send_reliable(ReliableMessageFromClient::Connected {
+            protocol_version: PROTOCOL_VERSION,
+        })?;
+
+        assert!(
+            self.step_until(|h| h.clients[index].player_init().is_some())?,
+            "client {index} never received PlayerInit"
+        );
+
+        self.clients[index].send_reliable(ReliableMessageFromClient::ReadyForUpdates)?;
+
+        Ok(self.clients[index].player_init().unwrap())
+    }
+}